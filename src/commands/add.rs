@@ -3,9 +3,13 @@ use std::io::{self, BufRead};
 use crate::{
     config::Config,
     error::Result,
-    habitica::{HabiticaClient, StatsCache},
-    sync::ConflictResolver,
-    taskwarrior::{Task, TaskwarriorClient},
+    habitica::{HabiticaApi, HabiticaClient, StatsCache},
+    say_err,
+    sync::{
+        spool::{self, Intent},
+        ConflictResolver,
+    },
+    taskwarrior::{backend::sync_backend, Task},
 };
 
 /// Handle the 'add' hook command
@@ -19,7 +23,7 @@ pub fn handle_add(config: &Config) -> Result<()> {
 
     // Debug: log the raw input if verbose mode
     if config.verbose {
-        eprintln!(
+        say_err!(
             "DEBUG: Received JSON (length {}): {}",
             task_json.len(),
             task_json
@@ -35,16 +39,26 @@ pub fn handle_add(config: &Config) -> Result<()> {
         ))
     })?;
 
+    // In queue mode, the exit hook pushes the task with `flush`. So it does
+    // with a filter or sync scope, which only see the task once Taskwarrior
+    // saved it.
+    if config.queue_hooks || config.push_filter.is_some() || config.sync_scope.is_some() {
+        spool::append(&config.spool_path(), &Intent::Add { task: task.clone() })?;
+        let output_json = serde_json::to_string(&task)?;
+        println!("{output_json}");
+        return Ok(());
+    }
+
     // Initialize clients
-    let tw_client = TaskwarriorClient::new();
+    let tw_client = sync_backend(config)?;
     let h_client = HabiticaClient::new(config)?;
 
     // Create resolver
-    let resolver = ConflictResolver::new(config, &tw_client, &h_client);
+    let resolver = ConflictResolver::new(config, tw_client.as_ref(), &h_client);
 
     // Initialize stats cache if task is completed
     let mut stats_cache = if task.status.is_completed() {
-        let stats = h_client.get_user_stats()?;
+        let stats = h_client.get_user_stats_cached()?;
         Some(StatsCache::new(stats))
     } else {
         None
@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+
+use crate::{
+    config::{age_cutoff, Config},
+    error::Result,
+    say,
+    sync::{archive::Archive, merge::MergeBase},
+    taskwarrior::{backend::sync_backend, NotesManager, Task, TaskStatus, TaskType},
+};
+
+/// Handle the 'archive' command
+///
+/// Unlinks todos completed before `older_than` (e.g. `180d`) so later syncs
+/// no longer reconcile them. The Habitica todos are kept but excluded from
+/// future syncs, and with `habitica.archive.delete_notes` the note files are
+/// removed as well.
+pub fn handle_archive(config: &Config, older_than: &str) -> Result<()> {
+    let cutoff = age_cutoff(older_than, Utc::now())?;
+    let tw_client = sync_backend(config)?;
+    let notes_manager = NotesManager::new(config);
+
+    let stale: Vec<Task> = tw_client
+        .get_tasks_with_habitica()?
+        .into_iter()
+        .filter(|task| is_archivable(task, cutoff))
+        .collect();
+
+    let archive_path = config.archive_path();
+    let mut archive = Archive::load(&archive_path)?;
+    let mut merge_base = MergeBase::load(&config.merge_base_path())?;
+
+    for task in &stale {
+        if let Some(h_id) = task.habitica_uuid {
+            archive.insert(h_id);
+        }
+        if config.archive_delete_notes {
+            notes_manager.delete_note(task)?;
+        }
+
+        let mut unlinked = task.clone();
+        unlinked.habitica_uuid = None;
+        tw_client.import(&unlinked)?;
+        merge_base.record(&unlinked);
+
+        // Save as we go so an interrupted run never re-imports archived todos
+        archive.save(&archive_path)?;
+        say!("Archived: {}", task.description);
+    }
+
+    merge_base.save(&config.merge_base_path())?;
+
+    say!(
+        "Archived {} todos completed more than {} ago",
+        stale.len(),
+        older_than
+    );
+    Ok(())
+}
+
+/// Check whether a linked todo was completed before `cutoff`
+fn is_archivable(task: &Task, cutoff: DateTime<Utc>) -> bool {
+    task.habitica_uuid.is_some()
+        && task.task_type() == TaskType::Todo
+        && task.status == TaskStatus::Completed
+        && task.date_attribute("end").is_some_and(|end| end < cutoff)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn test_is_archivable() {
+        let now = Utc::now();
+        let mut task = Task {
+            status: TaskStatus::Completed,
+            habitica_uuid: Some(Uuid::new_v4()),
+            ..Default::default()
+        };
+        task.extra.insert(
+            "end".to_string(),
+            serde_json::Value::String("20250101T120000Z".to_string()),
+        );
+
+        assert!(is_archivable(&task, now - Duration::days(90)));
+        assert!(!is_archivable(&task, now - Duration::days(5000)));
+
+        // Deleted todos are pruned, not archived
+        task.status = TaskStatus::Deleted;
+        assert!(!is_archivable(&task, now));
+
+        task.status = TaskStatus::Completed;
+        task.habitica_uuid = None;
+        assert!(!is_archivable(&task, now));
+    }
+}
@@ -0,0 +1,38 @@
+use crate::{
+    config::Config,
+    error::Result,
+    habitica::{rate_limit::REQUESTS_PER_MINUTE, HabiticaClient},
+    say,
+};
+
+/// Handle the 'auth verify' command
+///
+/// Logs in with the configured credentials and shows whose account they
+/// belong to, so wrong keys show up before a sync runs into them.
+pub fn handle_auth_verify(config: &Config) -> Result<()> {
+    let h_client = HabiticaClient::new(config)?;
+    let (account, rate_limit) = h_client.verify_credentials()?;
+
+    say!("Logged in as {} (@{})", account.name, account.username);
+    match account.class {
+        Some(class) => say!("Level {} {}", account.level, class),
+        None => say!("Level {}", account.level),
+    }
+    match rate_limit {
+        Some(status) => {
+            say!(
+                "Rate limit: {} of {} requests left",
+                status.remaining,
+                status.limit
+            );
+            if let Some(reset) = status.reset {
+                say!("    Resets {}", reset);
+            }
+        }
+        None => say!(
+            "Rate limit: not reported, requests are kept to {} per minute",
+            REQUESTS_PER_MINUTE
+        ),
+    }
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    commands::prune::delete_and_unlink,
+    config::{age_cutoff, Config},
+    error::Result,
+    habitica::{HabiticaApi, HabiticaClient, HabiticaTask},
+    say,
+    taskwarrior::{backend::sync_backend, Task, TaskStatus},
+};
+
+/// Handle the 'clear-completed' command
+///
+/// Deletes completed Habitica todos whose Taskwarrior task is completed as
+/// well, optionally only those completed before `older_than` (e.g. `30d`).
+/// The Taskwarrior tasks keep their status and dates and are only unlinked.
+pub fn handle_clear_completed(config: &Config, older_than: Option<&str>) -> Result<()> {
+    let cutoff = older_than
+        .map(|age| age_cutoff(age, Utc::now()))
+        .transpose()?;
+    let tw_client = sync_backend(config)?;
+    let h_client = HabiticaClient::new(config)?;
+
+    let cleared: HashSet<Uuid> = h_client
+        .get_tasks(Some("_allCompletedTodos"))?
+        .iter()
+        .filter(|h_task| is_clearable(h_task, cutoff))
+        .filter_map(|h_task| h_task.id)
+        .collect();
+    let tasks: Vec<Task> = tw_client
+        .get_tasks_with_habitica()?
+        .into_iter()
+        .filter(|task| task.status == TaskStatus::Completed)
+        .filter(|task| task.habitica_uuid.is_some_and(|id| cleared.contains(&id)))
+        .collect();
+
+    delete_and_unlink(config, tw_client.as_ref(), &h_client, &tasks, "Cleared")?;
+
+    say!("Cleared {} completed Habitica todos", tasks.len());
+    Ok(())
+}
+
+/// Check whether a completed Habitica todo was done before `cutoff`
+///
+/// Without a cutoff, every completed todo qualifies.
+fn is_clearable(h_task: &HabiticaTask, cutoff: Option<DateTime<Utc>>) -> bool {
+    h_task.completed
+        && cutoff.map_or(true, |cutoff| {
+            h_task
+                .date_completed
+                .or(h_task.updated_at)
+                .is_some_and(|done| done < cutoff)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_is_clearable() {
+        let now = Utc::now();
+        let h_task = HabiticaTask {
+            completed: true,
+            date_completed: Some(now - Duration::days(40)),
+            ..Default::default()
+        };
+
+        assert!(is_clearable(&h_task, None));
+        assert!(is_clearable(&h_task, Some(now - Duration::days(30))));
+        assert!(!is_clearable(&h_task, Some(now - Duration::days(60))));
+
+        let open = HabiticaTask::default();
+        assert!(!is_clearable(&open, None));
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    fs,
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+use chrono::{DateTime, Utc};
+use indicatif::ProgressBar;
+use serde::Serialize;
+
+#[cfg(feature = "dbus")]
+use crate::commands::dbus::DaemonBus;
+use crate::{
+    commands::sync::{level_up_messages, print_event, run_sync, SyncMode, SyncOptions},
+    config::Config,
+    error::Result,
+    notify::Notification,
+    say, say_err,
+    sync::SyncSummary,
+    taskwarrior::HookGuard,
+    tr,
+};
+
+/// Minutes between syncs unless given on the command line
+pub const DEFAULT_INTERVAL_MINUTES: u64 = 30;
+
+/// Files of the task database that change whenever a task does, including
+/// the undo journals of Taskwarrior 2 and 3
+const DATA_FILES: &[&str] = &[
+    "pending.data",
+    "completed.data",
+    "undo.data",
+    "taskchampion.sqlite3",
+    "taskchampion.sqlite3-wal",
+];
+
+/// Size and modification time of each task database file
+type Fingerprint = Vec<Option<(u64, SystemTime)>>;
+
+/// State of the daemon, as reported by `GetStatus` over D-Bus
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DaemonStatus {
+    pub syncing: bool,
+    pub last_sync: Option<DateTime<Utc>>,
+    pub last_summary: Option<SyncSummary>,
+    pub last_error: Option<String>,
+    pub next_sync: Option<DateTime<Utc>>,
+}
+
+impl DaemonStatus {
+    /// Record the outcome of a sync run
+    fn finish(&mut self, result: &Result<SyncSummary>, now: DateTime<Utc>, next: DateTime<Utc>) {
+        self.syncing = false;
+        self.last_sync = Some(now);
+        self.next_sync = Some(next);
+        match result {
+            Ok(summary) => {
+                self.last_summary = Some(*summary);
+                self.last_error = None;
+            }
+            Err(err) => self.last_error = Some(err.to_string()),
+        }
+    }
+}
+
+/// Lock the shared status, even if a previous holder panicked
+pub(crate) fn lock(status: &Mutex<DaemonStatus>) -> MutexGuard<'_, DaemonStatus> {
+    status.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Handle the 'daemon' command
+///
+/// Syncs every `interval_minutes` until killed. With the `dbus` feature, the
+/// daemon also serves [`DaemonBus`] so a sync can be triggered early. With
+/// `poll`, the task database is checked that often and a change syncs right
+/// away, so hooks aren't needed to get local changes to Habitica.
+pub fn handle_daemon(config: &Config, interval_minutes: u64, poll: Option<Duration>) -> Result<()> {
+    let interval = Duration::from_secs(interval_minutes.max(1) * 60);
+    let status = Arc::new(Mutex::new(DaemonStatus::default()));
+    let (trigger, triggered) = mpsc::channel();
+    let bus = connect_bus(&status, &trigger);
+
+    say!(
+        "Syncing every {} minutes, press Ctrl-C to stop.\n",
+        interval.as_secs() / 60
+    );
+
+    loop {
+        lock(&status).syncing = true;
+        say!("{}\n", tr!("sync-start"));
+
+        // A marker that can't be written fails this cycle like a failed sync
+        let result = HookGuard::acquire(&config.hook_guard_path()).and_then(|_guard| {
+            run_sync(
+                config,
+                "sync",
+                SyncMode::Incremental,
+                SyncOptions::default(),
+                &ProgressBar::hidden(),
+                &mut |event| {
+                    print_event(config, event);
+                    if let Some(bus) = &bus {
+                        for msg in level_up_messages(event) {
+                            bus.level_up(msg);
+                        }
+                    }
+                },
+            )
+        });
+        // Taken after the sync so its own writes don't count as changes
+        let synced = fingerprint(config);
+
+        let now = Utc::now();
+        let next = chrono::Duration::from_std(interval).map_or(now, |delay| now + delay);
+        lock(&status).finish(&result, now, next);
+
+        let message = match &result {
+            Ok(summary) => {
+                say!("{}", tr!("sync-complete"));
+                Notification::SyncCompleted(summary).message()
+            }
+            Err(err) => {
+                say_err!("{}", err.localized());
+                err.to_string()
+            }
+        };
+        if let Some(bus) = &bus {
+            bus.sync_finished(result.is_ok(), &message);
+        }
+
+        // Sleep until the next interval, an early trigger or a local change.
+        // `trigger` lives as long as the loop, so the channel never
+        // disconnects.
+        let deadline = Instant::now() + interval;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let wait = poll.map_or(left, |poll| poll.max(Duration::from_secs(1)).min(left));
+            if triggered.recv_timeout(wait).is_ok()
+                || Instant::now() >= deadline
+                || (poll.is_some() && fingerprint(config) != synced)
+            {
+                break;
+            }
+        }
+        while triggered.try_recv().is_ok() {}
+        say!();
+    }
+}
+
+/// Fingerprint the task database, to notice changes without hooks
+fn fingerprint(config: &Config) -> Fingerprint {
+    DATA_FILES
+        .iter()
+        .map(|name| {
+            let metadata = fs::metadata(config.data_location.join(name)).ok()?;
+            Some((metadata.len(), metadata.modified().ok()?))
+        })
+        .collect()
+}
+
+/// Serve the D-Bus interface, warning if there is no session bus
+#[cfg(feature = "dbus")]
+fn connect_bus(status: &Arc<Mutex<DaemonStatus>>, trigger: &Sender<()>) -> Option<DaemonBus> {
+    match DaemonBus::connect(Arc::clone(status), trigger.clone()) {
+        Ok(bus) => Some(bus),
+        Err(err) => {
+            say_err!("D-Bus service unavailable, continuing without it: {}", err);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "dbus"))]
+const fn connect_bus(
+    _status: &Arc<Mutex<DaemonStatus>>,
+    _trigger: &Sender<()>,
+) -> Option<DaemonBus> {
+    None
+}
+
+/// Stand-in for the D-Bus service when built without the `dbus` feature
+#[cfg(not(feature = "dbus"))]
+#[derive(Clone, Copy)]
+enum DaemonBus {}
+
+#[cfg(not(feature = "dbus"))]
+impl DaemonBus {
+    const fn level_up(self, _message: &str) {
+        match self {}
+    }
+
+    const fn sync_finished(self, _success: bool, _message: &str) {
+        match self {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    #[test]
+    fn test_status_records_outcome() {
+        let mut status = DaemonStatus {
+            syncing: true,
+            ..Default::default()
+        };
+        let now = Utc::now();
+
+        let summary = SyncSummary {
+            pushed: 1,
+            ..Default::default()
+        };
+        status.finish(&Ok(summary), now, now);
+        assert!(!status.syncing);
+        assert_eq!(status.last_summary, Some(summary));
+        assert!(status.last_error.is_none());
+
+        status.finish(&Err(Error::custom("offline")), now, now);
+        assert_eq!(status.last_error.as_deref(), Some("offline"));
+        assert_eq!(status.last_summary, Some(summary));
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_fingerprint() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::for_tests();
+        config.data_location = dir.path().to_path_buf();
+
+        let empty = fingerprint(&config);
+        fs::write(
+            dir.path().join("pending.data"),
+            "[description:\"Buy milk\"]\n",
+        )
+        .unwrap();
+        let added = fingerprint(&config);
+        assert_ne!(added, empty);
+
+        // Files of our own don't count
+        fs::write(dir.path().join("habitica_journal.jsonl"), "{}\n").unwrap();
+        assert_eq!(fingerprint(&config), added);
+    }
+}
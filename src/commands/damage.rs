@@ -0,0 +1,113 @@
+use crate::{
+    config::Config,
+    error::Result,
+    habitica::{HabiticaApi, HabiticaClient, HabiticaTask, HabiticaTaskType},
+    say,
+};
+
+/// Handle the 'damage' command
+///
+/// Lists today's unfinished dailies with the HP each would cost at the next
+/// cron, using Habitica's scoring formula. Checklists, gear and buffs are not
+/// taken into account, so the numbers are an estimate.
+pub fn handle_damage(config: &Config) -> Result<()> {
+    let h_client = HabiticaClient::new(config)?;
+
+    let missed: Vec<HabiticaTask> = h_client
+        .get_tasks(Some("dailys"))?
+        .into_iter()
+        .filter(is_missed)
+        .collect();
+
+    if missed.is_empty() {
+        say!("All dailies due today are done, no damage at cron.");
+        return Ok(());
+    }
+
+    let stats = h_client.get_user_stats()?;
+    let mut dailies: Vec<(HabiticaTask, f64)> = missed
+        .into_iter()
+        .map(|task| {
+            let damage = daily_damage(&task, stats.con);
+            (task, damage)
+        })
+        .collect();
+    dailies.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    say!("Unfinished dailies due today:\n");
+    for (task, damage) in &dailies {
+        say!("  {:>5.1} HP  {}", damage, task.text);
+    }
+
+    let total: f64 = dailies.iter().map(|(_, damage)| damage).sum();
+    say!(
+        "\nEstimated damage at cron: {:.1} HP ({:.1} of {} HP left)",
+        total,
+        (stats.hp - total).max(0.0),
+        stats.max_hp.unwrap_or(50)
+    );
+
+    if h_client.is_sleeping()? {
+        say!("You're resting in the inn, so none of it applies tonight.");
+    }
+    Ok(())
+}
+
+/// Check whether a daily is due today and not done yet
+fn is_missed(task: &HabiticaTask) -> bool {
+    task.task_type == HabiticaTaskType::Daily && task.is_due && !task.completed
+}
+
+/// HP lost at cron for one missed daily
+///
+/// Mirrors Habitica's cron: the task value sets the base delta, constitution
+/// reduces it by up to 90% and harder tasks hurt more.
+fn daily_damage(task: &HabiticaTask, con: f64) -> f64 {
+    let value = task.value.unwrap_or(0.0).clamp(-47.27, 21.27);
+    let delta = 0.9747_f64.powf(value);
+    let con_bonus = (1.0 - con / 250.0).max(0.1);
+    let damage = delta * con_bonus * task.priority * 2.0;
+    (damage * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn daily(priority: f64, value: f64) -> HabiticaTask {
+        HabiticaTask {
+            text: "Stretch".to_string(),
+            task_type: HabiticaTaskType::Daily,
+            priority,
+            is_due: true,
+            value: Some(value),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_daily_damage() {
+        // A fresh easy daily costs 2 HP
+        assert_eq!(daily_damage(&daily(1.0, 0.0), 0.0), 2.0);
+        // Hard dailies hurt more, well-kept (high value) ones less
+        assert_eq!(daily_damage(&daily(2.0, 0.0), 0.0), 4.0);
+        assert!(daily_damage(&daily(1.0, 10.0), 0.0) < 2.0);
+        assert!(daily_damage(&daily(1.0, -10.0), 0.0) > 2.0);
+        // Constitution softens the blow, down to a tenth
+        assert_eq!(daily_damage(&daily(1.0, 0.0), 125.0), 1.0);
+        assert_eq!(daily_damage(&daily(1.0, 0.0), 1000.0), 0.2);
+    }
+
+    #[test]
+    fn test_is_missed() {
+        let mut task = daily(1.0, 0.0);
+        assert!(is_missed(&task));
+
+        task.completed = true;
+        assert!(!is_missed(&task));
+
+        task.completed = false;
+        task.is_due = false;
+        assert!(!is_missed(&task));
+    }
+}
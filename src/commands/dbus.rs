@@ -0,0 +1,100 @@
+use std::sync::{mpsc::Sender, Arc, Mutex};
+
+use zbus::{
+    blocking::{connection::Builder, Connection},
+    fdo, interface,
+    names::BusName,
+    SignalContext,
+};
+
+use crate::{
+    commands::daemon::{lock, DaemonStatus},
+    error::{Error, Result},
+    say_err,
+};
+
+/// Well-known name claimed on the session bus
+pub const BUS_NAME: &str = "org.task2habitica.Daemon";
+/// Path of the daemon object
+pub const OBJECT_PATH: &str = "/org/task2habitica/Daemon";
+/// Interface served at [`OBJECT_PATH`]
+pub const INTERFACE: &str = "org.task2habitica.Daemon1";
+
+/// Methods and signals of the daemon object
+struct DaemonInterface {
+    status: Arc<Mutex<DaemonStatus>>,
+    trigger: Mutex<Sender<()>>,
+}
+
+#[interface(name = "org.task2habitica.Daemon1")]
+impl DaemonInterface {
+    /// Start a sync now instead of waiting for the next interval
+    fn trigger_sync(&self) -> bool {
+        self.trigger
+            .lock()
+            .is_ok_and(|trigger| trigger.send(()).is_ok())
+    }
+
+    /// Daemon state as JSON: `syncing`, `last_sync`, `last_summary`,
+    /// `last_error` and `next_sync`
+    fn get_status(&self) -> fdo::Result<String> {
+        serde_json::to_string(&*lock(&self.status)).map_err(|e| fdo::Error::Failed(e.to_string()))
+    }
+
+    /// A sync step gained a level
+    #[zbus(signal)]
+    async fn level_up(ctxt: &SignalContext<'_>, message: &str) -> zbus::Result<()>;
+
+    /// A sync run ended, with its summary or error
+    #[zbus(signal)]
+    async fn sync_finished(
+        ctxt: &SignalContext<'_>,
+        success: bool,
+        message: &str,
+    ) -> zbus::Result<()>;
+}
+
+/// Session bus connection serving the daemon object
+pub struct DaemonBus {
+    connection: Connection,
+}
+
+impl DaemonBus {
+    /// Claim [`BUS_NAME`] and serve the daemon object
+    ///
+    /// `TriggerSync` sends on `trigger`, `GetStatus` reads `status`.
+    pub fn connect(status: Arc<Mutex<DaemonStatus>>, trigger: Sender<()>) -> Result<Self> {
+        let iface = DaemonInterface {
+            status,
+            trigger: Mutex::new(trigger),
+        };
+        let connection = Builder::session()
+            .and_then(|builder| builder.name(BUS_NAME))
+            .and_then(|builder| builder.serve_at(OBJECT_PATH, iface))
+            .and_then(Builder::build)
+            .map_err(|e| Error::custom(format!("Failed to connect to D-Bus: {}", e)))?;
+        Ok(DaemonBus { connection })
+    }
+
+    /// Emit `LevelUp`
+    pub fn level_up(&self, message: &str) {
+        self.emit("LevelUp", &(message,));
+    }
+
+    /// Emit `SyncFinished`
+    pub fn sync_finished(&self, success: bool, message: &str) {
+        self.emit("SyncFinished", &(success, message));
+    }
+
+    fn emit<B>(&self, signal: &str, body: &B)
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+    {
+        if let Err(err) =
+            self.connection
+                .emit_signal(None::<BusName<'_>>, OBJECT_PATH, INTERFACE, signal, body)
+        {
+            say_err!("Failed to emit D-Bus signal {}: {}", signal, err);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{config::Config, error::Result, habitica::StatsCache};
+use crate::{commands::sync::paint_stat, config::Config, error::Result, habitica::StatsCache, say};
 
 /// Handle the 'exit' hook command
 pub fn handle_exit(config: &Config) -> Result<()> {
@@ -9,7 +9,7 @@ pub fn handle_exit(config: &Config) -> Result<()> {
         // Get and display stat diffs
         let messages = cache.get_diff_messages();
         for message in messages {
-            println!("{}", message);
+            say!("{}", paint_stat(&message));
         }
 
         // Delete the cache file
@@ -0,0 +1,242 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    habitica::{HabiticaApi, HabiticaClient, HabiticaTask, HabiticaTaskType},
+    output, say,
+    sync::{converter, merge::MergeBase, resolver::skips_import},
+    taskwarrior::{
+        backend::sync_backend, NotesManager, Task, TaskDifficulty, TaskStatus, TaskType,
+    },
+};
+
+/// What the export command writes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Habitica tasks as Taskwarrior import JSON
+    #[default]
+    Taskwarrior,
+    /// A JSON array with one object per linked pair
+    Json,
+    /// A CSV table with one row per linked pair
+    Csv,
+}
+
+/// A linked pair, both sides side by side
+#[derive(Debug, Clone, Serialize)]
+struct PairRow {
+    uuid: Uuid,
+    description: String,
+    status: TaskStatus,
+    project: Option<String>,
+    tags: String,
+    due: Option<DateTime<Utc>>,
+    modified: Option<DateTime<Utc>>,
+    difficulty: TaskDifficulty,
+    task_type: TaskType,
+    habitica_id: Uuid,
+    habitica_type: Option<HabiticaTaskType>,
+    habitica_text: Option<String>,
+    habitica_completed: Option<bool>,
+    habitica_date: Option<DateTime<Utc>>,
+    habitica_updated: Option<DateTime<Utc>>,
+    habitica_value: Option<f64>,
+    habitica_streak: Option<u32>,
+    /// `in-sync`, `out-of-sync` or `missing-on-habitica`
+    link: &'static str,
+    /// When the pair was last synced, if recorded
+    last_sync: Option<DateTime<Utc>>,
+}
+
+/// Handle the 'export' command
+///
+/// With [`ExportFormat::Taskwarrior`], converts every Habitica task into
+/// Taskwarrior import JSON with the Habitica UDAs filled in, so
+/// `task import <file>` can bootstrap a fresh database. Habitica notes are
+/// written to the task note directory. Habitica's own and challenge tasks
+/// are left out unless allowed.
+///
+/// The other formats write each linked pair as one record, for
+/// spreadsheets and scripts. Without a path, they go to stdout.
+pub fn handle_export(config: &Config, path: Option<&Path>, format: ExportFormat) -> Result<()> {
+    if format == ExportFormat::Taskwarrior {
+        let path = path.ok_or_else(|| {
+            Error::custom("Name the file to write the Taskwarrior import JSON to")
+        })?;
+        return export_taskwarrior(config, path);
+    }
+    output::reserve_stdout(path.is_none());
+
+    let tw_client = sync_backend(config)?;
+    let h_client = HabiticaClient::new(config)?;
+    let linked = tw_client.get_tasks_with_habitica()?;
+    let h_tasks = h_client.get_all_tasks()?;
+    let merge_base = MergeBase::load(&config.merge_base_path())?;
+    let rows = pair_rows(config, &linked, &h_tasks, &merge_base);
+
+    let mut out: Box<dyn Write> = match path {
+        Some(path) => Box::new(fs::File::create(path)?),
+        None => Box::new(io::stdout().lock()),
+    };
+    match format {
+        ExportFormat::Csv => write_csv(&mut out, &rows)?,
+        _ => writeln!(out, "{}", serde_json::to_string_pretty(&rows)?)?,
+    }
+    out.flush()?;
+
+    if let Some(path) = path {
+        say!("Exported {} linked tasks to {}", rows.len(), path.display());
+    }
+    Ok(())
+}
+
+/// Write Habitica tasks as Taskwarrior import JSON
+fn export_taskwarrior(config: &Config, path: &Path) -> Result<()> {
+    let h_client = HabiticaClient::new(config)?;
+    let notes_manager = NotesManager::new(config);
+
+    let mut h_tasks = h_client.get_all_tasks()?;
+    h_tasks.retain(|h_task| !skips_import(config, h_task));
+
+    let day_start = h_client.get_day_start()?;
+    let mut tw_tasks: Vec<Task> = Vec::with_capacity(h_tasks.len());
+    for h_task in &h_tasks {
+        let mut tw_task = converter::habitica_to_taskwarrior(config, h_task, None, day_start)?;
+        notes_manager.import_note_from_habitica(&mut tw_task, &h_task.notes)?;
+        tw_tasks.push(tw_task);
+    }
+
+    fs::write(path, serde_json::to_string_pretty(&tw_tasks)?)?;
+
+    say!(
+        "Exported {} Habitica tasks to {}. Import them with: task import {}",
+        tw_tasks.len(),
+        path.display(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Join each linked Taskwarrior task with its Habitica task
+fn pair_rows(
+    config: &Config,
+    linked: &[Task],
+    h_tasks: &[HabiticaTask],
+    merge_base: &MergeBase,
+) -> Vec<PairRow> {
+    let by_id: HashMap<Uuid, &HabiticaTask> = h_tasks
+        .iter()
+        .filter_map(|h_task| h_task.id.map(|id| (id, h_task)))
+        .collect();
+
+    linked
+        .iter()
+        .filter_map(|task| {
+            let habitica_id = task.habitica_uuid?;
+            let h_task = by_id.get(&habitica_id).copied();
+            let link = match h_task {
+                None => "missing-on-habitica",
+                Some(h_task) if converter::tasks_are_equivalent(config, task, h_task) => "in-sync",
+                Some(_) => "out-of-sync",
+            };
+            Some(PairRow {
+                uuid: task.uuid,
+                description: task.description.clone(),
+                status: task.status,
+                project: task.project().map(str::to_string),
+                tags: task.tags().join(" "),
+                due: task.due,
+                modified: task.modified,
+                difficulty: task.difficulty(),
+                task_type: task.task_type(),
+                habitica_id,
+                habitica_type: h_task.map(|h_task| h_task.task_type),
+                habitica_text: h_task.map(|h_task| h_task.text.clone()),
+                habitica_completed: h_task.map(|h_task| h_task.completed),
+                habitica_date: h_task.and_then(|h_task| h_task.date),
+                habitica_updated: h_task.and_then(|h_task| h_task.updated_at),
+                habitica_value: h_task.and_then(|h_task| h_task.value),
+                habitica_streak: h_task.and_then(|h_task| h_task.streak),
+                link,
+                last_sync: merge_base.synced_at(habitica_id),
+            })
+        })
+        .collect()
+}
+
+/// Write rows as CSV with a header line
+fn write_csv(out: &mut dyn Write, rows: &[PairRow]) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(out);
+    for row in rows {
+        writer
+            .serialize(row)
+            .map_err(|e| Error::custom(format!("Failed to write CSV: {}", e)))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_command_exists() {}
+
+    #[test]
+    fn test_pair_rows() {
+        let config = Config::for_tests();
+        let h_task = HabiticaTask {
+            id: Some(Uuid::new_v4()),
+            text: "Read".to_string(),
+            priority: 1.0,
+            ..Default::default()
+        };
+        let in_sync = Task {
+            uuid: Uuid::new_v4(),
+            description: "Read".to_string(),
+            habitica_uuid: h_task.id,
+            ..Default::default()
+        };
+        let renamed = Task {
+            uuid: Uuid::new_v4(),
+            description: "Read more".to_string(),
+            habitica_uuid: h_task.id,
+            ..Default::default()
+        };
+        let orphan = Task {
+            uuid: Uuid::new_v4(),
+            description: "Gone".to_string(),
+            habitica_uuid: Some(Uuid::new_v4()),
+            ..Default::default()
+        };
+        let mut merge_base = MergeBase::default();
+        let now = Utc::now();
+        merge_base.record_at(&in_sync, now);
+
+        let rows = pair_rows(&config, &[in_sync, renamed, orphan], &[h_task], &merge_base);
+        let links: Vec<_> = rows.iter().map(|row| row.link).collect();
+        assert_eq!(links, ["in-sync", "out-of-sync", "missing-on-habitica"]);
+        assert_eq!(rows[0].last_sync, Some(now));
+        assert_eq!(rows[2].last_sync, None);
+        assert_eq!(rows[2].habitica_text, None);
+
+        let mut out = Vec::new();
+        write_csv(&mut out, &rows).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        assert!(csv.starts_with("uuid,description,status,"));
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.contains(",pending,"));
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::{
+    commands::modify::{push_changes, Changes},
+    config::Config,
+    error::Result,
+    habitica::{HabiticaApi, HabiticaClient, StatsCache},
+    say,
+    sync::{
+        engine::in_scope,
+        spool::{self, Intent, Pending},
+        ConflictResolver, SyncEngine, SyncEvent,
+    },
+    taskwarrior::{backend::sync_backend, Task, TaskBackend, TaskSnapshot},
+};
+
+/// Handle the 'flush' command
+///
+/// Pushes the changes spooled by the hooks in queue mode, or with
+/// `habitica.filter`, the tasks added since the last flush. New tasks are
+/// created in one batch, modifications follow one by one. Whatever could not
+/// be pushed goes back to the spool for the next flush.
+pub fn handle_flush(config: &Config) -> Result<()> {
+    let spool_path = config.spool_path();
+    let pending = spool::coalesce(spool::take(&spool_path)?);
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let tw_client = sync_backend(config)?;
+    let h_client = HabiticaClient::new(config)?;
+    SyncEngine::new(config, tw_client.as_ref(), &h_client).resume()?;
+
+    // The hooks saw the tasks before later hooks changed them again
+    let uuids: Vec<String> = pending.iter().map(|p| p.new.uuid.to_string()).collect();
+    let filters: Vec<&str> = uuids.iter().map(String::as_str).collect();
+    let current: HashMap<Uuid, Task> = tw_client
+        .export(&filters)?
+        .into_iter()
+        .map(|task| (task.uuid, task))
+        .collect();
+
+    let (added, modified): (Vec<Pending>, Vec<Pending>) =
+        pending.into_iter().partition(|p| p.old.is_none());
+
+    // Linked tasks were pushed by an earlier flush that failed afterwards
+    if let Err(e) = push_added(config, tw_client.as_ref(), &h_client, &added, &current) {
+        respool(&spool_path, added.into_iter().chain(modified))?;
+        return Err(e);
+    }
+
+    let resolver = ConflictResolver::new(config, tw_client.as_ref(), &h_client);
+    let mut stats_cache = h_client.get_user_stats_cached().ok().map(StatsCache::new);
+
+    let mut modified = modified.into_iter();
+    while let Some(entry) = modified.next() {
+        let result = push_modified(
+            config,
+            tw_client.as_ref(),
+            &h_client,
+            &resolver,
+            &entry,
+            &current,
+            &mut stats_cache,
+        );
+        if let Err(e) = result {
+            respool(&spool_path, std::iter::once(entry).chain(modified))?;
+            return Err(e);
+        }
+    }
+
+    // Shown by the exit hook
+    if let Some(cache) = &stats_cache {
+        cache.save(&config.stats_cache_path())?;
+    }
+
+    Ok(())
+}
+
+/// Push the added tasks that are still unlinked and match `habitica.filter`
+fn push_added(
+    config: &Config,
+    tw_client: &dyn TaskBackend,
+    h_client: &dyn HabiticaApi,
+    added: &[Pending],
+    current: &HashMap<Uuid, Task>,
+) -> Result<()> {
+    let tasks: Vec<Task> = added
+        .iter()
+        .map(|p| current.get(&p.new.uuid).unwrap_or(&p.new).clone())
+        .filter(|task| task.habitica_uuid.is_none())
+        .collect();
+    if tasks.is_empty() {
+        return Ok(());
+    }
+
+    let engine = SyncEngine::new(config, tw_client, h_client).source("flush");
+    let plan = engine.plan_new(TaskSnapshot {
+        unlinked: tasks,
+        linked: Vec::new(),
+    })?;
+    engine.apply(plan, &mut |event| {
+        if let SyncEvent::Pushed { stat_messages, .. } = event {
+            for message in stat_messages {
+                say!("{}", message);
+            }
+        }
+    })?;
+    Ok(())
+}
+
+/// Push the modification of a task and save what Habitica changed about it
+fn push_modified(
+    config: &Config,
+    tw_client: &dyn TaskBackend,
+    h_client: &dyn HabiticaApi,
+    resolver: &ConflictResolver,
+    entry: &Pending,
+    current: &HashMap<Uuid, Task>,
+    stats_cache: &mut Option<StatsCache>,
+) -> Result<()> {
+    let Some(old_task) = &entry.old else {
+        return Ok(());
+    };
+    let new_task = current.get(&entry.new.uuid).unwrap_or(&entry.new);
+    if !in_scope(config, tw_client, new_task)? {
+        return Ok(());
+    }
+
+    let changes = Changes::detect(config, old_task, new_task)?;
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let updated_task = push_changes(
+        config,
+        h_client,
+        resolver,
+        old_task,
+        new_task,
+        &changes,
+        stats_cache,
+    )?;
+    if serde_json::to_value(&updated_task)? != serde_json::to_value(new_task)? {
+        tw_client.import(&updated_task)?;
+    }
+    Ok(())
+}
+
+/// Put entries back into the spool
+fn respool(path: &std::path::Path, entries: impl Iterator<Item = Pending>) -> Result<()> {
+    for entry in entries {
+        spool::append(path, &Intent::from(entry))?;
+    }
+    Ok(())
+}
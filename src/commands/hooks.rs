@@ -0,0 +1,244 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::Config,
+    error::Result,
+    output, say,
+    taskwarrior::{TaskBackend, TaskwarriorClient},
+};
+
+/// Hook scripts shipped with task2habitica
+const HOOKS: &[(&str, &str)] = &[
+    (
+        "on-add.task2habitica",
+        include_str!("../../hooks/on-add.task2habitica"),
+    ),
+    (
+        "on-modify.task2habitica",
+        include_str!("../../hooks/on-modify.task2habitica"),
+    ),
+    (
+        "on-exit.task2habitica",
+        include_str!("../../hooks/on-exit.task2habitica"),
+    ),
+];
+
+/// Second line of installed hooks, telling them apart from hand-written ones
+const MARKER: &str = "# Installed by task2habitica, removed by `task2habitica uninstall-hooks`";
+
+/// What [`install_hook`] did with a hook
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Installed {
+    New,
+    Updated,
+    Kept,
+}
+
+/// Handle the 'install-hooks' command
+pub fn handle_install_hooks() -> Result<()> {
+    add_hooks(&TaskwarriorClient::new())
+}
+
+/// Handle the 'uninstall-hooks' command
+///
+/// Removes the hooks installed by task2habitica. Other scripts with the same
+/// names are only removed after asking.
+pub fn handle_uninstall_hooks() -> Result<()> {
+    let hooks_dir = hooks_dir(&TaskwarriorClient::new())?;
+    for (name, script) in HOOKS {
+        let path = hooks_dir.join(name);
+        let Ok(existing) = fs::read_to_string(&path) else {
+            say!("No hook at {}", path.display());
+            continue;
+        };
+        let ours = existing.contains(MARKER) || existing == *script;
+        if ours
+            || output::confirm(&format!(
+                "Remove {}, not installed by task2habitica?",
+                path.display()
+            ))
+        {
+            fs::remove_file(&path)?;
+            say!("Hook removed: {}", path.display());
+        } else {
+            say!("Hook kept: {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Install the hook scripts into Taskwarrior's hooks directory
+///
+/// The scripts run this binary by its full path, so they keep working when
+/// it isn't on the `PATH` Taskwarrior is started with.
+pub(crate) fn add_hooks(tw_client: &TaskwarriorClient) -> Result<()> {
+    let hooks_dir = hooks_dir(tw_client)?;
+    fs::create_dir_all(&hooks_dir)?;
+    let binary = env::current_exe()?;
+    for (name, script) in HOOKS {
+        let path = hooks_dir.join(name);
+        match install_hook(&path, &render(script, &binary))? {
+            Installed::New => say!("Hook installed: {}", path.display()),
+            Installed::Updated => say!("Hook updated: {}", path.display()),
+            Installed::Kept => say!("Hook kept: {}", path.display()),
+        }
+    }
+
+    let others = other_hooks(&hooks_dir)?;
+    if !others.is_empty() {
+        say!("Other hooks Taskwarrior also runs:");
+        for path in others {
+            say!("    {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Paths of the task2habitica hooks, whether installed or not
+pub(crate) fn hook_paths(tw_client: &TaskwarriorClient) -> Result<Vec<PathBuf>> {
+    let hooks_dir = hooks_dir(tw_client)?;
+    Ok(HOOKS.iter().map(|(name, _)| hooks_dir.join(name)).collect())
+}
+
+/// Directory Taskwarrior runs hooks from
+fn hooks_dir(tw_client: &TaskwarriorClient) -> Result<PathBuf> {
+    let location = tw_client.get_config("rc.hooks.location")?;
+    if !location.is_empty() {
+        return Config::expand_path(&location);
+    }
+    let data_location = tw_client.get_config("rc.data.location")?;
+    let data_location = if data_location.is_empty() {
+        "~/.task"
+    } else {
+        data_location.as_str()
+    };
+    Ok(Config::expand_path(data_location)?.join("hooks"))
+}
+
+/// Hook script calling `binary` instead of looking it up on the `PATH`
+fn render(script: &str, binary: &Path) -> String {
+    let binary = shell_quote(&binary.to_string_lossy());
+    let mut lines = script.lines();
+    let mut rendered = format!("{}\n{}\n", lines.next().unwrap_or_default(), MARKER);
+    for line in lines {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let line = match line.trim_start() {
+            "if command -v task2habitica >/dev/null; then" => {
+                format!("{}if [ -x {} ]; then", indent, binary)
+            }
+            command => match command.strip_prefix("task2habitica ") {
+                Some(args) => format!("{}{} {}", indent, binary, args),
+                None => line.to_string(),
+            },
+        };
+        rendered.push_str(&line);
+        rendered.push('\n');
+    }
+    rendered
+}
+
+/// Quote a string for a POSIX shell
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Write an executable hook script
+///
+/// Earlier versions of a task2habitica hook are replaced, other scripts
+/// already at the path only after asking.
+fn install_hook(path: &Path, script: &str) -> Result<Installed> {
+    let installed = match fs::read_to_string(path) {
+        Ok(existing) if existing == script => return Ok(Installed::Kept),
+        Ok(existing)
+            if !existing.contains(MARKER)
+                && !output::confirm(&format!("Replace {}?", path.display())) =>
+        {
+            return Ok(Installed::Kept)
+        }
+        Ok(_) => Installed::Updated,
+        Err(_) => Installed::New,
+    };
+
+    fs::write(path, script)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(installed)
+}
+
+/// Hooks in the directory besides task2habitica's
+///
+/// Taskwarrior runs every executable whose name starts with the event.
+fn other_hooks(hooks_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut others: Vec<_> = fs::read_dir(hooks_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    ["on-add", "on-modify", "on-exit", "on-launch"]
+                        .iter()
+                        .any(|event| name.starts_with(event))
+                        && !HOOKS.iter().any(|(hook, _)| *hook == name)
+                })
+        })
+        .collect();
+    others.sort();
+    Ok(others)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_hook() {
+        let dir = tempfile::tempdir().unwrap();
+        let (name, script) = HOOKS[0];
+        let path = dir.path().join(name);
+        let script = render(script, Path::new("/usr/local/bin/task2habitica"));
+
+        assert_eq!(install_hook(&path, &script).unwrap(), Installed::New);
+        assert_eq!(fs::read_to_string(&path).unwrap(), script);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o111, 0o111);
+        }
+
+        // Rerunning keeps the installed script
+        assert_eq!(install_hook(&path, &script).unwrap(), Installed::Kept);
+
+        // A moved binary updates it without asking
+        let moved = render(HOOKS[0].1, Path::new("/opt/task2habitica"));
+        assert_eq!(install_hook(&path, &moved).unwrap(), Installed::Updated);
+        assert_eq!(fs::read_to_string(&path).unwrap(), moved);
+
+        fs::write(dir.path().join("on-add.other"), "").unwrap();
+        assert_eq!(
+            other_hooks(dir.path()).unwrap(),
+            vec![dir.path().join("on-add.other")]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let script = render(HOOKS[1].1, Path::new("/home/me/it's/task2habitica"));
+        let quoted = r"'/home/me/it'\''s/task2habitica'";
+
+        assert!(script.starts_with("#!/usr/bin/env bash\n"));
+        assert!(script.contains(MARKER));
+        assert!(script.contains(&format!("if [ -x {} ]; then", quoted)));
+        assert!(script.contains(&format!("    {} modify\n", quoted)));
+        assert!(!script.contains("command -v"));
+        // Messages keep the program's name
+        assert!(script.contains("task2habitica is not installed"));
+    }
+}
@@ -0,0 +1,218 @@
+use std::{fmt::Write as _, fs, path::Path};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    config::Config,
+    error::Result,
+    habitica::{Frequency, HabiticaApi, HabiticaClient, HabiticaTask, HabiticaTaskType},
+    say,
+};
+
+/// iCalendar weekday codes in Monday-first order
+const ICAL_WEEKDAYS: [&str; 7] = ["MO", "TU", "WE", "TH", "FR", "SA", "SU"];
+
+/// Handle the 'ical' command
+pub fn handle_ical(config: &Config, path: &Path) -> Result<()> {
+    let h_client = HabiticaClient::new(config)?;
+
+    let mut tasks = h_client.get_tasks(Some("dailys"))?;
+    tasks.extend(h_client.get_tasks(Some("todos"))?);
+
+    let calendar = render_calendar(&tasks, Utc::now());
+    fs::write(path, calendar)?;
+
+    say!("Wrote calendar to {}", path.display());
+    Ok(())
+}
+
+/// Render dailies and dated todos as an iCalendar feed
+pub fn render_calendar(tasks: &[HabiticaTask], now: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//task2habitica//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+
+    for task in tasks {
+        let Some(id) = task.id else { continue };
+
+        let (start, rrule) = match task.task_type {
+            HabiticaTaskType::Daily => {
+                (task.start_date.unwrap_or(now), Some(recurrence_rule(task)))
+            }
+            HabiticaTaskType::Todo if !task.completed => match task.date {
+                Some(date) => (date, None),
+                None => continue,
+            },
+            _ => continue,
+        };
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}@task2habitica", id));
+        lines.push(format!("DTSTAMP:{}", now.format("%Y%m%dT%H%M%SZ")));
+        lines.push(format!("DTSTART;VALUE=DATE:{}", start.format("%Y%m%d")));
+        lines.push(format!("SUMMARY:{}", escape_text(&task.text)));
+        if !task.notes.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape_text(&task.notes)));
+        }
+        if let Some(rrule) = rrule {
+            lines.push(format!("RRULE:{}", rrule));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().fold(String::new(), |mut out, line| {
+        let _ = write!(out, "{}\r\n", fold_line(line));
+        out
+    })
+}
+
+/// Build an RRULE from a daily's repeat schedule
+fn recurrence_rule(task: &HabiticaTask) -> String {
+    let interval = task.every_x.unwrap_or(1).max(1);
+    let repeat = task.repeat.unwrap_or_default();
+    let weekdays: Vec<&str> = repeat
+        .days()
+        .iter()
+        .zip(ICAL_WEEKDAYS)
+        .filter(|(active, _)| **active)
+        .map(|(_, code)| code)
+        .collect();
+
+    match task.frequency.unwrap_or(Frequency::Weekly) {
+        Frequency::Daily => format!("FREQ=DAILY;INTERVAL={}", interval),
+        Frequency::Weekly => format!(
+            "FREQ=WEEKLY;INTERVAL={};BYDAY={}",
+            interval,
+            weekdays.join(",")
+        ),
+        Frequency::Monthly if !task.weeks_of_month.is_empty() => {
+            // Habitica counts weeks from zero, iCalendar from one
+            let by_day: Vec<String> = task
+                .weeks_of_month
+                .iter()
+                .flat_map(|week| {
+                    weekdays
+                        .iter()
+                        .map(move |day| format!("{}{}", week + 1, day))
+                })
+                .collect();
+            format!(
+                "FREQ=MONTHLY;INTERVAL={};BYDAY={}",
+                interval,
+                by_day.join(",")
+            )
+        }
+        Frequency::Monthly if !task.days_of_month.is_empty() => {
+            let days: Vec<String> = task.days_of_month.iter().map(u32::to_string).collect();
+            format!(
+                "FREQ=MONTHLY;INTERVAL={};BYMONTHDAY={}",
+                interval,
+                days.join(",")
+            )
+        }
+        Frequency::Monthly => format!("FREQ=MONTHLY;INTERVAL={}", interval),
+        Frequency::Yearly => format!("FREQ=YEARLY;INTERVAL={}", interval),
+    }
+}
+
+/// Escape a text value per RFC 5545
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Fold lines longer than 75 octets
+fn fold_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for ch in line.chars() {
+        if width + ch.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(ch);
+        width += ch.len_utf8();
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::habitica::WeekdayRepeat;
+
+    fn test_daily() -> HabiticaTask {
+        HabiticaTask {
+            id: Some(Uuid::new_v4()),
+            text: "Stretch, then run".to_string(),
+            task_type: HabiticaTaskType::Daily,
+            priority: 1.0,
+            frequency: Some(Frequency::Weekly),
+            every_x: Some(1),
+            repeat: Some(WeekdayRepeat {
+                m: true,
+                t: false,
+                w: true,
+                th: false,
+                f: true,
+                s: false,
+                su: false,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_weekly_rule() {
+        assert_eq!(
+            recurrence_rule(&test_daily()),
+            "FREQ=WEEKLY;INTERVAL=1;BYDAY=MO,WE,FR"
+        );
+    }
+
+    #[test]
+    fn test_monthly_weeks_rule() {
+        let mut daily = test_daily();
+        daily.frequency = Some(Frequency::Monthly);
+        daily.weeks_of_month = vec![1];
+        daily.repeat = Some(WeekdayRepeat {
+            m: false,
+            t: true,
+            w: false,
+            th: false,
+            f: false,
+            s: false,
+            su: false,
+        });
+        assert_eq!(recurrence_rule(&daily), "FREQ=MONTHLY;INTERVAL=1;BYDAY=2TU");
+    }
+
+    #[test]
+    fn test_render_calendar() {
+        let mut todo = test_daily();
+        todo.task_type = HabiticaTaskType::Todo;
+        todo.text = "Undated".to_string();
+
+        let calendar = render_calendar(&[test_daily(), todo], Utc::now());
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(calendar.contains("SUMMARY:Stretch\\, then run\r\n"));
+        assert!(!calendar.contains("Undated"));
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 1);
+    }
+
+    #[test]
+    fn test_fold_line() {
+        let folded = fold_line(&"x".repeat(100));
+        assert!(folded.lines().all(|line| line.len() <= 75));
+    }
+}
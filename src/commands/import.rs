@@ -0,0 +1,258 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    habitica::{HabiticaApi, HabiticaClient},
+    say,
+    sync::{resolver::skips_import, ConflictResolver},
+    taskwarrior::{backend::sync_backend, date_format, Task, TaskDifficulty, TaskStatus, TaskType},
+};
+
+/// A task row from a CSV or JSON import file
+#[derive(Debug, Deserialize)]
+struct ImportRecord {
+    description: String,
+    #[serde(default)]
+    due: Option<String>,
+    #[serde(default)]
+    difficulty: Option<String>,
+    #[serde(default, rename = "type")]
+    task_type: Option<String>,
+}
+
+impl ImportRecord {
+    /// Build a new pending Taskwarrior task from the record
+    fn into_task(self) -> Result<Task> {
+        let description = self.description.trim().to_string();
+        if description.is_empty() {
+            return Err(Error::custom("Task description is empty"));
+        }
+
+        let due = match non_empty(self.due.as_deref()) {
+            Some(due) => Some(
+                date_format::parse_input(due)
+                    .ok_or_else(|| Error::custom(format!("Invalid due date: {}", due)))?,
+            ),
+            None => None,
+        };
+
+        let habitica_difficulty = non_empty(self.difficulty.as_deref())
+            .map(str::parse::<TaskDifficulty>)
+            .transpose()?;
+        let habitica_task_type = non_empty(self.task_type.as_deref())
+            .map(str::parse::<TaskType>)
+            .transpose()?;
+
+        Ok(Task {
+            uuid: Uuid::new_v4(),
+            description,
+            status: TaskStatus::Pending,
+            due,
+            habitica_difficulty,
+            habitica_task_type,
+            ..Default::default()
+        })
+    }
+}
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// Read import records from a CSV or JSON file, chosen by extension
+fn read_records(path: &Path) -> Result<Vec<ImportRecord>> {
+    let is_csv = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+
+    if is_csv {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_path(path)
+            .map_err(|e| Error::custom(format!("Failed to read CSV: {}", e)))?;
+        reader
+            .deserialize()
+            .map(|record| record.map_err(|e| Error::custom(format!("Invalid CSV row: {}", e))))
+            .collect()
+    } else {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+/// Handle the 'import' command
+///
+/// Creates every task from the file on Habitica first, then imports it into
+/// Taskwarrior with the Habitica UUID already set.
+pub fn handle_import(config: &Config, path: &Path) -> Result<()> {
+    let tasks = read_records(path)?
+        .into_iter()
+        .map(ImportRecord::into_task)
+        .collect::<Result<Vec<_>>>()?;
+
+    let tw_client = sync_backend(config)?;
+    let h_client = HabiticaClient::new(config)?;
+    let resolver = ConflictResolver::new(config, tw_client.as_ref(), &h_client);
+
+    for task in &tasks {
+        let linked = resolver.push_to_habitica(task, &mut None)?;
+        tw_client.import(&linked)?;
+        say!("Imported: {}", task.description);
+    }
+
+    say!(
+        "Imported {} tasks into Taskwarrior and Habitica.",
+        tasks.len()
+    );
+    Ok(())
+}
+
+/// Handle the 'import' command without a file
+///
+/// Pulls the Habitica tasks of the given types (`todos` or `dailys`) that
+/// aren't linked yet into Taskwarrior in one pass, for
+/// moving over from Habitica. New tasks land in `project`, or in the project
+/// the sync would pick, and get `tags` on top.
+pub fn handle_import_habitica(
+    config: &Config,
+    types: &[&str],
+    project: Option<&str>,
+    tags: &[String],
+) -> Result<()> {
+    let tw_client = sync_backend(config)?;
+    let h_client = HabiticaClient::new(config)?;
+    let resolver = ConflictResolver::new(config, tw_client.as_ref(), &h_client);
+
+    let linked: HashSet<_> = tw_client
+        .get_tasks_with_habitica()?
+        .iter()
+        .filter_map(|task| task.habitica_uuid)
+        .collect();
+
+    let mut tasks = Vec::new();
+    let mut already_linked = 0;
+    for task_type in types {
+        for h_task in h_client.get_tasks(Some(task_type))? {
+            if h_task.id.is_some_and(|id| linked.contains(&id)) {
+                already_linked += 1;
+                continue;
+            }
+            if skips_import(config, &h_task) {
+                continue;
+            }
+            let mut task = resolver.pull_from_habitica(&h_task, None)?;
+            assign(&mut task, project, tags);
+            tasks.push(task);
+        }
+    }
+
+    tw_client.import_many(&tasks)?;
+    for task in &tasks {
+        resolver.record_synced(task);
+        say!("Imported: {}", task.description);
+    }
+    resolver.save_merge_base()?;
+
+    say!(
+        "Imported {} Habitica tasks into Taskwarrior, {} were already linked.",
+        tasks.len(),
+        already_linked
+    );
+    Ok(())
+}
+
+/// Put a task in `project` and add `tags` to its own
+fn assign(task: &mut Task, project: Option<&str>, tags: &[String]) {
+    if let Some(project) = project {
+        task.extra.insert(
+            "project".to_string(),
+            serde_json::Value::String(project.to_string()),
+        );
+    }
+    if !tags.is_empty() {
+        let mut all: Vec<String> = task.tags().into_iter().map(str::to_string).collect();
+        for tag in tags {
+            if !all.contains(tag) {
+                all.push(tag.clone());
+            }
+        }
+        task.extra
+            .insert("tags".to_string(), serde_json::json!(all));
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn test_read_csv_records() {
+        let mut file = tempfile::Builder::new().suffix(".csv").tempfile().unwrap();
+        writeln!(file, "description,due,difficulty,type").unwrap();
+        writeln!(file, "Write report,2026-03-01,hard,todo").unwrap();
+        writeln!(file, "Stretch,,,daily").unwrap();
+
+        let tasks: Vec<Task> = read_records(file.path())
+            .unwrap()
+            .into_iter()
+            .map(|r| r.into_task().unwrap())
+            .collect();
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].habitica_difficulty, Some(TaskDifficulty::Hard));
+        assert!(tasks[0].due.is_some());
+        assert_eq!(tasks[1].habitica_task_type, Some(TaskType::Daily));
+        assert!(tasks[1].due.is_none());
+    }
+
+    #[test]
+    fn test_read_json_records() {
+        let mut file = tempfile::Builder::new().suffix(".json").tempfile().unwrap();
+        write!(
+            file,
+            r#"[{{"description": "Call mom", "difficulty": "trivial"}}]"#
+        )
+        .unwrap();
+
+        let records = read_records(file.path()).unwrap();
+        let task = records.into_iter().next().unwrap().into_task().unwrap();
+        assert_eq!(task.description, "Call mom");
+        assert_eq!(task.habitica_difficulty, Some(TaskDifficulty::Trivial));
+    }
+
+    #[test]
+    fn test_invalid_difficulty_is_rejected() {
+        let record = ImportRecord {
+            description: "Task".to_string(),
+            due: None,
+            difficulty: Some("legendary".to_string()),
+            task_type: None,
+        };
+        assert!(record.into_task().is_err());
+    }
+
+    #[test]
+    fn test_assign() {
+        let mut task = Task::default();
+        task.extra
+            .insert("tags".to_string(), serde_json::json!(["home"]));
+
+        assign(&mut task, None, &[]);
+        assert_eq!(task.project(), None);
+
+        assign(
+            &mut task,
+            Some("habitica"),
+            &["home".to_string(), "imported".to_string()],
+        );
+        assert_eq!(task.project(), Some("habitica"));
+        assert_eq!(task.tags(), ["home", "imported"]);
+    }
+}
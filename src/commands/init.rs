@@ -0,0 +1,80 @@
+use std::env;
+
+use crate::{
+    commands::hooks::add_hooks,
+    error::Result,
+    output, say, say_err,
+    taskwarrior::{TaskBackend, TaskwarriorClient},
+};
+
+/// UDA definitions the sync relies on
+const UDAS: &[(&str, &str)] = &[
+    ("uda.habitica_uuid.label", "Habitica UUID"),
+    ("uda.habitica_uuid.type", "string"),
+    ("uda.habitica_difficulty.label", "Habitica Difficulty"),
+    ("uda.habitica_difficulty.type", "string"),
+    ("uda.habitica_difficulty.values", "trivial,easy,medium,hard"),
+    ("uda.habitica_task_type.label", "Habitica Task Type"),
+    ("uda.habitica_task_type.type", "string"),
+    ("uda.habitica_task_type.values", "daily,todo,habit,reward"),
+    ("uda.habitica_cost.label", "Habitica Cost"),
+    ("uda.habitica_cost.type", "numeric"),
+    ("uda.habitica_counter_up.label", "Habit +"),
+    ("uda.habitica_counter_up.type", "numeric"),
+    ("uda.habitica_counter_down.label", "Habit -"),
+    ("uda.habitica_counter_down.type", "numeric"),
+    ("uda.habitica_attribute.label", "Habitica Attribute"),
+    ("uda.habitica_attribute.type", "string"),
+    ("uda.habitica_attribute.values", "str,int,con,per"),
+    ("uda.habitica_streak.label", "Streak"),
+    ("uda.habitica_streak.type", "numeric"),
+];
+
+/// Credentials asked for when neither the environment nor the taskrc has them
+pub(crate) const CREDENTIALS: &[(&str, &str, &str)] = &[
+    ("HABITICA_USER_ID", "habitica.user_id", "Habitica user ID"),
+    ("HABITICA_API_KEY", "habitica.api_key", "Habitica API token"),
+];
+
+/// Handle the 'init' command
+///
+/// Sets up a fresh installation: adds the missing UDA definitions to the
+/// taskrc, installs the hook scripts and asks for the Habitica credentials.
+/// Settings that are already there are left alone, so it is safe to rerun.
+/// Without `install_hooks`, syncing is left to `sync` or `daemon --poll`.
+pub fn handle_init(install_hooks: bool) -> Result<()> {
+    let tw_client = TaskwarriorClient::new();
+
+    add_udas(&tw_client)?;
+    if install_hooks {
+        add_hooks(&tw_client)?;
+    } else {
+        say!("Hooks skipped, run `task2habitica daemon --poll` to keep both sides in sync");
+    }
+
+    for (var, key, label) in CREDENTIALS {
+        let from_env = env::var(var).is_ok_and(|value| !value.is_empty());
+        if from_env || !tw_client.get_config(&format!("rc.{}", key))?.is_empty() {
+            continue;
+        }
+        match output::ask(label) {
+            Some(value) => tw_client.set_config(key, &value)?,
+            None => say_err!("Set {} or {} before syncing", key, var),
+        }
+    }
+
+    Ok(())
+}
+
+/// Add the UDA definitions missing from the taskrc
+pub(crate) fn add_udas(tw_client: &TaskwarriorClient) -> Result<()> {
+    let mut added = 0;
+    for (key, value) in UDAS {
+        if tw_client.get_config(&format!("rc.{}", key))?.is_empty() {
+            tw_client.set_config(key, value)?;
+            added += 1;
+        }
+    }
+    say!("UDAs: {} of {} definitions added", added, UDAS.len());
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use crate::{
+    config::Config,
+    error::Result,
+    habitica::{HabiticaApi, HabiticaClient},
+    say, tr,
+};
+
+/// Handle the 'inn' command
+pub fn handle_inn(config: &Config, rest: bool) -> Result<()> {
+    let h_client = HabiticaClient::new(config)?;
+
+    if h_client.is_sleeping()? == rest {
+        say!("{}", inn_status(rest));
+        return Ok(());
+    }
+
+    let sleeping = h_client.set_sleeping(rest)?;
+    say!("{}", inn_status(sleeping));
+    Ok(())
+}
+
+/// Describe the inn state
+pub fn inn_status(sleeping: bool) -> String {
+    if sleeping {
+        tr!("inn-resting")
+    } else {
+        tr!("inn-not-resting")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inn_status() {
+        assert!(inn_status(true).starts_with("Resting"));
+        assert!(inn_status(false).starts_with("Not resting"));
+    }
+}
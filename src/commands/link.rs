@@ -0,0 +1,88 @@
+use uuid::Uuid;
+
+use crate::{
+    commands::touch::handle_touch,
+    config::Config,
+    error::{Error, Result},
+    habitica::{HabiticaApi, HabiticaClient},
+    say,
+    sync::{converter, merge::MergeBase},
+    taskwarrior::{backend::sync_backend, Task, TaskBackend},
+};
+
+/// Handle the 'link' command
+///
+/// Pairs a Taskwarrior task with a Habitica task by hand, for when relinking
+/// or a sync matched the wrong ones. With `sync`, the next sync runs for the
+/// task right away.
+pub fn handle_link(config: &Config, id: &str, habitica_id: Uuid, sync: bool) -> Result<()> {
+    let task = link_task(
+        config,
+        sync_backend(config)?.as_ref(),
+        &HabiticaClient::new(config)?,
+        id,
+        habitica_id,
+    )?;
+
+    if sync {
+        handle_touch(config, &task.uuid.to_string())?;
+    }
+    Ok(())
+}
+
+/// Link a Taskwarrior task to a Habitica task
+///
+/// Both must exist. A task previously paired with the Habitica task is
+/// unlinked. Habitica's version is recorded as the last synced one, so the
+/// next sync sends the Taskwarrior task's fields over. Returns the linked
+/// task.
+pub(crate) fn link_task(
+    config: &Config,
+    tw_client: &dyn TaskBackend,
+    h_client: &HabiticaClient,
+    id: &str,
+    habitica_id: Uuid,
+) -> Result<Task> {
+    let mut task = tw_client.get_task(id)?;
+    let h_task = h_client
+        .get_task(habitica_id)?
+        .ok_or_else(|| Error::custom(format!("No Habitica task {}", habitica_id)))?;
+    if task.habitica_uuid == Some(habitica_id) {
+        say!("Already linked: {}", task.description);
+        return Ok(task);
+    }
+
+    let path = config.merge_base_path();
+    let mut merge_base = MergeBase::load(&path)?;
+    for mut other in tw_client.export(&[&format!("habitica_uuid:{}", habitica_id)])? {
+        if other.uuid == task.uuid {
+            continue;
+        }
+        other.habitica_uuid = None;
+        tw_client.import(&other)?;
+        merge_base.record(&other);
+        say!("Unlinked: {}", other.description);
+    }
+
+    if let Some(previous) = task.habitica_uuid {
+        say!(
+            "Habitica task {} is no longer linked, the next sync imports it",
+            previous
+        );
+        // Forget the base of the old pair
+        task.habitica_uuid = None;
+        merge_base.record(&task);
+    }
+    task.habitica_uuid = Some(habitica_id);
+    tw_client.import(&task)?;
+    let base = converter::habitica_to_taskwarrior(
+        config,
+        &h_task,
+        Some(&task),
+        h_client.get_day_start()?,
+    )?;
+    merge_base.record(&base);
+    merge_base.save(&path)?;
+    say!("Linked: {} <-> {}", task.description, h_task.text);
+    Ok(task)
+}
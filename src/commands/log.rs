@@ -0,0 +1,74 @@
+use chrono::Local;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::Result,
+    say,
+    sync::journal::{self, JournalEntry},
+};
+
+/// Handle the 'log' command
+///
+/// Prints the last `limit` journal entries, oldest first, optionally only
+/// those about the task with the given Taskwarrior UUID or Habitica ID. With
+/// `details`, the fields each action changed are listed below it.
+pub fn handle_log(config: &Config, limit: usize, task: Option<Uuid>, details: bool) -> Result<()> {
+    let entries: Vec<JournalEntry> = journal::read(&config.journal_path())?
+        .into_iter()
+        .filter(|entry| task.map_or(true, |id| entry.concerns(id)))
+        .collect();
+    if entries.is_empty() {
+        say!("No sync actions recorded");
+        return Ok(());
+    }
+
+    for entry in &entries[entries.len().saturating_sub(limit)..] {
+        say!("{}", format_entry(entry));
+        if details {
+            for (field, before, after) in entry.changes() {
+                say!(
+                    "    {}: {} -> {}",
+                    field,
+                    show(before.as_ref()),
+                    show(after.as_ref())
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Describe a journal entry in a single line
+fn format_entry(entry: &JournalEntry) -> String {
+    format!(
+        "{}  {:<6} {:<22} {}",
+        entry.time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+        entry.source,
+        entry.action.as_str(),
+        entry.description
+    )
+}
+
+/// Show a field value without the quotes around strings
+fn show(value: Option<&Value>) -> String {
+    match value {
+        None => "(unset)".to_string(),
+        Some(Value::String(text)) => text.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_show() {
+        assert_eq!(show(None), "(unset)");
+        assert_eq!(show(Some(&Value::from("pending"))), "pending");
+        assert_eq!(show(Some(&Value::from(2.5))), "2.5");
+        assert_eq!(show(Some(&serde_json::json!(["a", "b"]))), r#"["a","b"]"#);
+    }
+}
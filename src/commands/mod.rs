@@ -1,9 +1,69 @@
 pub mod add;
+pub mod archive;
+pub mod auth;
+pub mod clear;
+pub mod daemon;
+pub mod damage;
+#[cfg(feature = "dbus")]
+pub mod dbus;
 pub mod exit;
+pub mod export;
+pub mod flush;
+pub mod hooks;
+pub mod ical;
+pub mod import;
+pub mod init;
+pub mod inn;
+pub mod link;
+pub mod log;
 pub mod modify;
+pub mod pomodoro;
+pub mod prune;
+pub mod purge;
+pub mod relink;
+pub mod report;
+pub mod resolve;
+pub mod reward;
+pub mod rpc;
+pub mod score;
+pub mod setup;
+pub mod stats;
+pub mod status;
 pub mod sync;
+pub mod today;
+pub mod touch;
+pub mod webhook;
 
 pub use add::handle_add;
+pub use archive::handle_archive;
+pub use auth::handle_auth_verify;
+pub use clear::handle_clear_completed;
+pub use daemon::handle_daemon;
+pub use damage::handle_damage;
 pub use exit::handle_exit;
+pub use export::{handle_export, ExportFormat};
+pub use flush::handle_flush;
+pub use hooks::{handle_install_hooks, handle_uninstall_hooks};
+pub use ical::handle_ical;
+pub use import::{handle_import, handle_import_habitica};
+pub use init::handle_init;
+pub use inn::handle_inn;
+pub use link::handle_link;
+pub use log::handle_log;
 pub use modify::handle_modify;
-pub use sync::handle_sync;
+pub use pomodoro::handle_pomodoro;
+pub use prune::handle_prune;
+pub use purge::handle_purge;
+pub use relink::handle_relink;
+pub use report::handle_report;
+pub use resolve::handle_resolve;
+pub use reward::handle_reward_buy;
+pub use rpc::handle_rpc;
+pub use score::handle_score;
+pub use setup::handle_setup;
+pub use stats::handle_stats;
+pub use status::handle_status;
+pub use sync::{handle_sync, SyncFormat, SyncMode, SyncOptions};
+pub use today::handle_today;
+pub use touch::handle_touch;
+pub use webhook::{handle_webhook_add, handle_webhook_list, handle_webhook_remove};
@@ -1,11 +1,19 @@
 use std::io::{self, BufRead};
 
+use chrono::Utc;
+
 use crate::{
     config::Config,
     error::Result,
-    habitica::{HabiticaClient, StatsCache},
-    sync::{converter, ConflictResolver},
-    taskwarrior::{NotesManager, Task, TaskwarriorClient},
+    habitica::{HabiticaApi, HabiticaClient, ScoreDirection, StatsCache},
+    sync::{
+        converter, habits,
+        spool::{self, Intent},
+        timewarrior,
+        tombstones::{DeletedOn, Tombstones},
+        ConflictResolver,
+    },
+    taskwarrior::{backend::sync_backend, NotesManager, Task, TaskStatus},
 };
 
 /// Handle the 'modify' hook command
@@ -23,40 +31,48 @@ pub fn handle_modify(config: &Config) -> Result<()> {
 
     let old_task: Task = serde_json::from_str(&old_task_json)?;
     let new_task: Task = serde_json::from_str(&new_task_json)?;
+    record_deletion(config, &old_task, &new_task)?;
 
-    // Check if note was recently modified
-    let notes_manager = NotesManager::new(config);
-    let note_recently_changed = notes_manager.note_recently_modified(&new_task)?;
-
-    // Check if note annotations changed
-    let old_note_annos = old_task.filter_note_annotations(&config.task_note_prefix);
-    let new_note_annos = new_task.filter_note_annotations(&config.task_note_prefix);
-
-    // Read note content
-    let note_content = notes_manager.read_note(&new_task)?;
-
-    // Convert both to Habitica format to compare
-    let old_h_opt = converter::taskwarrior_to_habitica(&old_task, note_content.as_deref())?;
-    let new_h_opt = converter::taskwarrior_to_habitica(&new_task, note_content.as_deref())?;
+    // If nothing concerns Habitica, just output the new task
+    let changes = Changes::detect(config, &old_task, &new_task)?;
+    if changes.is_empty() {
+        let output_json = serde_json::to_string(&new_task)?;
+        println!("{}", output_json);
+        return Ok(());
+    }
 
-    // If tasks are equivalent and note hasn't changed, just output the new task
-    if old_h_opt == new_h_opt && !note_recently_changed && old_note_annos == new_note_annos {
+    // In queue mode, the exit hook pushes the change with `flush`. So it
+    // does with a sync scope, which only sees the change once saved.
+    if config.queue_hooks || config.sync_scope.is_some() {
+        spool::append(
+            &config.spool_path(),
+            &Intent::Modify {
+                old: old_task,
+                new: new_task.clone(),
+            },
+        )?;
         let output_json = serde_json::to_string(&new_task)?;
         println!("{}", output_json);
         return Ok(());
     }
 
-    // Tasks have changed, so we need to sync
-    let tw_client = TaskwarriorClient::new();
+    let tw_client = sync_backend(config)?;
     let h_client = HabiticaClient::new(config)?;
-    let resolver = ConflictResolver::new(config, &tw_client, &h_client);
+    let resolver = ConflictResolver::new(config, tw_client.as_ref(), &h_client);
 
     // Load or create stats cache
     let mut stats_cache = StatsCache::load(&config.stats_cache_path())?
-        .or_else(|| h_client.get_user_stats().ok().map(StatsCache::new));
-
-    // Modify task on Habitica
-    let updated_task = resolver.modify_on_habitica(&old_task, &new_task, &mut stats_cache)?;
+        .or_else(|| h_client.get_user_stats_cached().ok().map(StatsCache::new));
+
+    let updated_task = push_changes(
+        config,
+        &h_client,
+        &resolver,
+        &old_task,
+        &new_task,
+        &changes,
+        &mut stats_cache,
+    )?;
 
     // Save stats cache
     if let Some(cache) = &stats_cache {
@@ -70,6 +86,113 @@ pub fn handle_modify(config: &Config) -> Result<()> {
     Ok(())
 }
 
+/// Leave a tombstone for a linked task being deleted
+///
+/// Should the Habitica copy outlive this hook, say because Habitica can't be
+/// reached, the next sync deletes it instead of importing it again.
+fn record_deletion(config: &Config, old_task: &Task, new_task: &Task) -> Result<()> {
+    let Some(h_id) = old_task.habitica_uuid else {
+        return Ok(());
+    };
+    if old_task.status == TaskStatus::Deleted || new_task.status != TaskStatus::Deleted {
+        return Ok(());
+    }
+    let path = config.tombstones_path();
+    let mut tombstones = Tombstones::load(&path)?;
+    tombstones.record(new_task, h_id, DeletedOn::Taskwarrior, Utc::now());
+    tombstones.save(&path, Utc::now())
+}
+
+/// What a modification of a task means for Habitica
+pub(crate) struct Changes {
+    /// The Habitica version of the task or its note changed
+    details: bool,
+    /// Minutes tracked by Timewarrior, if the modification stopped the task
+    tracked_minutes: Option<i64>,
+    /// Scores due for a habit whose counter UDAs went up
+    habit_scores: Vec<(ScoreDirection, u32)>,
+    /// Scores due for the habit configured for starting tasks
+    start_scores: u32,
+}
+
+impl Changes {
+    /// Compare a task before and after a modification
+    pub(crate) fn detect(config: &Config, old_task: &Task, new_task: &Task) -> Result<Self> {
+        // Check if note was recently modified
+        let notes_manager = NotesManager::new(config);
+        let note_recently_changed = notes_manager.note_recently_modified(new_task)?;
+
+        // Check if note annotations changed
+        let old_note_annos = old_task.filter_note_annotations(&config.task_note_prefix);
+        let new_note_annos = new_task.filter_note_annotations(&config.task_note_prefix);
+
+        // Read note content
+        let note_content = notes_manager.read_note(new_task)?;
+
+        // Convert both to Habitica format to compare
+        let old_h_opt =
+            converter::taskwarrior_to_habitica(config, old_task, note_content.as_deref())?;
+        let new_h_opt =
+            converter::taskwarrior_to_habitica(config, new_task, note_content.as_deref())?;
+
+        Ok(Changes {
+            details: old_h_opt != new_h_opt
+                || note_recently_changed
+                || old_note_annos != new_note_annos,
+            // Check whether a Timewarrior interval just ended
+            tracked_minutes: config
+                .timew_habit
+                .as_ref()
+                .and_then(|_| timewarrior::stopped_minutes(old_task, new_task, Utc::now())),
+            // Check whether a habit's counter UDAs went up
+            habit_scores: habits::counter_increments(old_task, new_task),
+            // Check whether the task was started or stopped
+            start_scores: habits::start_scores(config, old_task, new_task),
+        })
+    }
+
+    /// Check whether Habitica is unaffected
+    pub(crate) fn is_empty(&self) -> bool {
+        !self.details
+            && self.tracked_minutes.is_none()
+            && self.habit_scores.is_empty()
+            && self.start_scores == 0
+    }
+}
+
+/// Push a modification to Habitica, returning the task to keep in Taskwarrior
+pub(crate) fn push_changes(
+    config: &Config,
+    h_client: &dyn HabiticaApi,
+    resolver: &ConflictResolver,
+    old_task: &Task,
+    new_task: &Task,
+    changes: &Changes,
+    stats_cache: &mut Option<StatsCache>,
+) -> Result<Task> {
+    // Modify task on Habitica
+    let updated_task = if changes.details {
+        resolver.modify_on_habitica(old_task, new_task, stats_cache)?
+    } else {
+        new_task.clone()
+    };
+
+    // Score the habit once per counter step
+    habits::score_counters(h_client, &updated_task, &changes.habit_scores, stats_cache)?;
+
+    // Score the habit configured for starting tasks
+    if let Some(habit) = &config.start_habit {
+        habits::score_habit(h_client, habit, changes.start_scores, stats_cache)?;
+    }
+
+    // Reward the tracked time on the configured habit
+    if let Some(minutes) = changes.tracked_minutes {
+        timewarrior::score_tracked_time(config, h_client, minutes, stats_cache)?;
+    }
+
+    Ok(updated_task)
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
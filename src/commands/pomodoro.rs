@@ -0,0 +1,102 @@
+use std::{
+    io::{self, Write},
+    thread,
+    time::Duration,
+};
+
+use chrono::Utc;
+
+use crate::{
+    config::Config,
+    error::Result,
+    habitica::{HabiticaApi, HabiticaClient, StatsCache},
+    output, say,
+    sync::habits::score_habit,
+    taskwarrior::{backend::sync_backend, date_format, Annotation, Task},
+};
+
+/// Handle the 'pomodoro' command
+pub fn handle_pomodoro(config: &Config, id: &str, minutes: Option<u32>) -> Result<()> {
+    let tw_client = sync_backend(config)?;
+    let h_client = HabiticaClient::new(config)?;
+
+    let task = tw_client.get_task(id)?;
+    let minutes = minutes.unwrap_or(config.pomodoro_minutes);
+
+    say!("Pomodoro: {} ({} min)", task.description, minutes);
+    run_timer(minutes)?;
+    say!("\nPomodoro complete!");
+
+    let mut stats_cache = Some(StatsCache::new(h_client.get_user_stats()?));
+    score_habit(&h_client, &config.pomodoro_habit, 1, &mut stats_cache)?;
+    tw_client.import(&annotate(&task, minutes))?;
+
+    if let Some(cache) = stats_cache {
+        for message in cache.get_diff_messages() {
+            say!("{}", message);
+        }
+    }
+
+    Ok(())
+}
+
+/// Count down, redrawing the remaining time once a second
+///
+/// Plain output has no countdown, only the start and finish lines.
+fn run_timer(minutes: u32) -> Result<()> {
+    if output::is_plain() {
+        thread::sleep(Duration::from_secs(u64::from(minutes) * 60));
+        return Ok(());
+    }
+
+    let mut stdout = io::stdout();
+    for remaining in (1..=u64::from(minutes) * 60).rev() {
+        write!(
+            stdout,
+            "\r{:02}:{:02} remaining",
+            remaining / 60,
+            remaining % 60
+        )?;
+        stdout.flush()?;
+        thread::sleep(Duration::from_secs(1));
+    }
+    Ok(())
+}
+
+/// Copy of the task with a note about the finished pomodoro
+fn annotate(task: &Task, minutes: u32) -> Task {
+    let mut annotated = task.clone();
+    annotated
+        .annotations
+        .get_or_insert_with(Vec::new)
+        .push(Annotation {
+            entry: date_format::format(&Utc::now()),
+            description: format!("Pomodoro completed ({} min)", minutes),
+        });
+    annotated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotate_appends() {
+        let task = Task {
+            description: "Write report".to_string(),
+            ..Default::default()
+        };
+
+        let annotated = annotate(&annotate(&task, 25), 15);
+        let descriptions: Vec<&str> = annotated
+            .annotations
+            .iter()
+            .flatten()
+            .map(|anno| anno.description.as_str())
+            .collect();
+        assert_eq!(
+            descriptions,
+            ["Pomodoro completed (25 min)", "Pomodoro completed (15 min)"]
+        );
+    }
+}
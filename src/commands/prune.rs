@@ -0,0 +1,105 @@
+use chrono::{DateTime, Utc};
+
+use crate::{
+    config::{age_cutoff, Config},
+    error::Result,
+    habitica::{HabiticaApi, HabiticaClient},
+    say,
+    sync::merge::MergeBase,
+    taskwarrior::{backend::sync_backend, Task, TaskBackend, TaskStatus, TaskType},
+};
+
+/// Handle the 'prune' command
+///
+/// Deletes the Habitica todos of tasks completed or deleted before
+/// `older_than` (e.g. `90d`) and unlinks them in Taskwarrior.
+pub fn handle_prune(config: &Config, older_than: &str) -> Result<()> {
+    let cutoff = age_cutoff(older_than, Utc::now())?;
+    let tw_client = sync_backend(config)?;
+    let h_client = HabiticaClient::new(config)?;
+
+    let stale: Vec<Task> = tw_client
+        .get_tasks_with_habitica()?
+        .into_iter()
+        .filter(|task| is_prunable(task, cutoff))
+        .collect();
+
+    delete_and_unlink(config, tw_client.as_ref(), &h_client, &stale, "Pruned")?;
+
+    say!(
+        "Pruned {} Habitica todos finished more than {} ago",
+        stale.len(),
+        older_than
+    );
+    Ok(())
+}
+
+/// Delete the Habitica copies of tasks and unlink them in Taskwarrior
+///
+/// Each task is reported as `<label>: <description>`.
+pub(crate) fn delete_and_unlink(
+    config: &Config,
+    tw_client: &dyn TaskBackend,
+    h_client: &dyn HabiticaApi,
+    tasks: &[Task],
+    label: &str,
+) -> Result<()> {
+    let mut merge_base = MergeBase::load(&config.merge_base_path())?;
+
+    for task in tasks {
+        if let Some(h_id) = task.habitica_uuid {
+            h_client.delete_task(h_id)?;
+        }
+
+        let mut unlinked = task.clone();
+        unlinked.habitica_uuid = None;
+        tw_client.import(&unlinked)?;
+        merge_base.record(&unlinked);
+
+        say!("{}: {}", label, task.description);
+    }
+
+    merge_base.save(&config.merge_base_path())?;
+    Ok(())
+}
+
+/// Check whether a linked todo was finished before `cutoff`
+fn is_prunable(task: &Task, cutoff: DateTime<Utc>) -> bool {
+    task.habitica_uuid.is_some()
+        && task.task_type() == TaskType::Todo
+        && matches!(task.status, TaskStatus::Completed | TaskStatus::Deleted)
+        && task.date_attribute("end").is_some_and(|end| end < cutoff)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use chrono::Duration;
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn test_is_prunable() {
+        let now = Utc::now();
+        let mut task = Task {
+            status: TaskStatus::Completed,
+            habitica_uuid: Some(Uuid::new_v4()),
+            ..Default::default()
+        };
+        task.extra.insert(
+            "end".to_string(),
+            serde_json::Value::String("20250101T120000Z".to_string()),
+        );
+
+        assert!(is_prunable(&task, now - Duration::days(90)));
+        assert!(!is_prunable(&task, Utc::now() - Duration::days(5000)));
+
+        task.status = TaskStatus::Pending;
+        assert!(!is_prunable(&task, now));
+
+        task.status = TaskStatus::Deleted;
+        task.habitica_task_type = Some(TaskType::Daily);
+        assert!(!is_prunable(&task, now));
+    }
+}
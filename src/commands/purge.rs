@@ -0,0 +1,106 @@
+use crate::{
+    config::Config,
+    error::Result,
+    habitica::{HabiticaApi, HabiticaClient, HabiticaTask},
+    say,
+    sync::merge::MergeBase,
+    taskwarrior::{
+        backend::sync_backend,
+        task::{HABIT_COUNTER_UDAS, STREAK_UDA},
+    },
+};
+
+/// Handle the 'purge' command
+///
+/// Deletes every Habitica task carrying a Taskwarrior UUID as alias, the
+/// mark task2habitica leaves on the tasks it writes, then unlinks all
+/// Taskwarrior tasks, dropping the UDAs the sync maintains. Challenge tasks
+/// can't be deleted and are left alone. Without `yes`, only tells what
+/// would go.
+pub fn handle_purge(config: &Config, yes: bool) -> Result<()> {
+    let tw_client = sync_backend(config)?;
+    let h_client = HabiticaClient::new(config)?;
+
+    let h_tasks = h_client.get_all_tasks()?;
+    let purged = purgeable(&h_tasks);
+    let linked = tw_client.get_tasks_with_habitica()?;
+
+    if !yes {
+        say!(
+            "Purging would delete {} Habitica tasks and unlink {} Taskwarrior tasks",
+            purged.len(),
+            linked.len()
+        );
+        say!("Nothing deleted, rerun with --yes to purge");
+        return Ok(());
+    }
+
+    let path = config.merge_base_path();
+    let mut merge_base = MergeBase::load(&path)?;
+    for h_task in &purged {
+        let Some(h_id) = h_task.id else {
+            continue;
+        };
+        h_client.delete_task(h_id)?;
+        say!("Deleted: {}", h_task.text);
+    }
+
+    // Every link goes, including those of tasks Habitica kept
+    for mut task in linked.iter().cloned() {
+        task.habitica_uuid = None;
+        for uda in [STREAK_UDA, HABIT_COUNTER_UDAS.0, HABIT_COUNTER_UDAS.1] {
+            task.extra.remove(uda);
+        }
+        tw_client.import(&task)?;
+        merge_base.record(&task);
+    }
+    merge_base.save(&path)?;
+
+    say!(
+        "Purged {} Habitica tasks and unlinked {} Taskwarrior tasks",
+        purged.len(),
+        linked.len()
+    );
+    say!("Run `task2habitica uninstall-hooks` to stop syncing new tasks");
+    Ok(())
+}
+
+/// Habitica tasks written by task2habitica that can be deleted
+fn purgeable(h_tasks: &[HabiticaTask]) -> Vec<&HabiticaTask> {
+    h_tasks
+        .iter()
+        .filter(|h_task| {
+            h_task.id.is_some() && h_task.alias_uuid().is_some() && h_task.challenge_id().is_none()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn test_purgeable() {
+        let written = HabiticaTask {
+            id: Some(Uuid::new_v4()),
+            alias: Some(Uuid::new_v4().to_string()),
+            ..Default::default()
+        };
+        let own = HabiticaTask {
+            id: Some(Uuid::new_v4()),
+            alias: Some("gym".to_string()),
+            ..Default::default()
+        };
+        let unmarked = HabiticaTask {
+            id: Some(Uuid::new_v4()),
+            ..Default::default()
+        };
+
+        let h_tasks = [written.clone(), own, unmarked];
+        let purged = purgeable(&h_tasks);
+        assert_eq!(purged.len(), 1);
+        assert_eq!(purged[0].id, written.id);
+    }
+}
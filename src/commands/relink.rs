@@ -0,0 +1,161 @@
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::Result,
+    habitica::{HabiticaApi, HabiticaClient, HabiticaTask},
+    say,
+    sync::merge::MergeBase,
+    taskwarrior::{backend::sync_backend, Task},
+};
+
+/// Handle the 'relink' command
+///
+/// Restores the `habitica_uuid` UDA of unlinked Taskwarrior tasks whose
+/// Habitica copy is still around, so the next sync doesn't push duplicates.
+/// Habitica tasks are matched by alias first, then by exact description.
+pub fn handle_relink(config: &Config) -> Result<()> {
+    let tw_client = sync_backend(config)?;
+    let h_client = HabiticaClient::new(config)?;
+
+    let unlinked = tw_client.export(&["status.not:deleted", "habitica_uuid.none:"])?;
+    let linked: HashSet<Uuid> = tw_client
+        .get_tasks_with_habitica()?
+        .into_iter()
+        .filter_map(|task| task.habitica_uuid)
+        .collect();
+    let h_tasks: Vec<HabiticaTask> = h_client
+        .get_all_tasks()?
+        .into_iter()
+        .filter(|h_task| h_task.id.is_some_and(|id| !linked.contains(&id)))
+        .collect();
+
+    let links = find_links(&unlinked, &h_tasks);
+
+    let mut merge_base = MergeBase::load(&config.merge_base_path())?;
+
+    for task in &unlinked {
+        let Some(&h_id) = links.get(&task.uuid) else {
+            continue;
+        };
+
+        let mut relinked = task.clone();
+        relinked.habitica_uuid = Some(h_id);
+        tw_client.import(&relinked)?;
+        merge_base.record(&relinked);
+
+        say!("Relinked: {}", task.description);
+    }
+
+    merge_base.save(&config.merge_base_path())?;
+
+    say!(
+        "Relinked {} of {} unlinked tasks",
+        links.len(),
+        unlinked.len()
+    );
+    Ok(())
+}
+
+/// Pair unlinked Taskwarrior tasks with unlinked Habitica tasks
+///
+/// Returns Habitica IDs keyed by Taskwarrior UUID. An alias holding the task
+/// UUID always wins; a description only counts when it is unique on both
+/// sides, so same-named tasks are never guessed at.
+fn find_links(unlinked: &[Task], h_tasks: &[HabiticaTask]) -> HashMap<Uuid, Uuid> {
+    let mut links = HashMap::new();
+    let mut claimed = HashSet::new();
+
+    for h_task in h_tasks {
+        let (Some(h_id), Some(alias)) = (h_task.id, h_task.alias_uuid()) else {
+            continue;
+        };
+        if unlinked.iter().any(|task| task.uuid == alias) {
+            links.insert(alias, h_id);
+            claimed.insert(h_id);
+        }
+    }
+
+    let mut by_text: HashMap<&str, Vec<Uuid>> = HashMap::new();
+    for h_task in h_tasks {
+        if let Some(h_id) = h_task.id.filter(|id| !claimed.contains(id)) {
+            by_text.entry(h_task.text.as_str()).or_default().push(h_id);
+        }
+    }
+    let mut descriptions: HashMap<&str, usize> = HashMap::new();
+    for task in unlinked
+        .iter()
+        .filter(|task| !links.contains_key(&task.uuid))
+    {
+        *descriptions.entry(task.description.as_str()).or_default() += 1;
+    }
+
+    for task in unlinked {
+        if links.contains_key(&task.uuid) || descriptions.get(task.description.as_str()) != Some(&1)
+        {
+            continue;
+        }
+        if let Some([h_id]) = by_text.get(task.description.as_str()).map(Vec::as_slice) {
+            links.insert(task.uuid, *h_id);
+        }
+    }
+
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn h_task(text: &str, alias: Option<Uuid>) -> HabiticaTask {
+        HabiticaTask {
+            id: Some(Uuid::new_v4()),
+            text: text.to_string(),
+            alias: alias.map(|alias| alias.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn tw_task(description: &str) -> Task {
+        Task {
+            uuid: Uuid::new_v4(),
+            description: description.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_links() {
+        let renamed = tw_task("Renamed locally");
+        let exact = tw_task("Water plants");
+        let twin = tw_task("Call mom");
+        let other_twin = tw_task("Call mom");
+        let unmatched = tw_task("Nowhere on Habitica");
+
+        let by_alias = h_task("Old name", Some(renamed.uuid));
+        let by_text = h_task("Water plants", None);
+        let h_tasks = vec![
+            by_alias.clone(),
+            by_text.clone(),
+            h_task("Call mom", None),
+            h_task("Stranger", Some(Uuid::new_v4())),
+        ];
+
+        let links = find_links(
+            &[
+                renamed.clone(),
+                exact.clone(),
+                twin.clone(),
+                other_twin,
+                unmatched,
+            ],
+            &h_tasks,
+        );
+        assert_eq!(links.len(), 2);
+        assert_eq!(links.get(&renamed.uuid).copied(), by_alias.id);
+        assert_eq!(links.get(&exact.uuid).copied(), by_text.id);
+        assert!(!links.contains_key(&twin.uuid));
+    }
+}
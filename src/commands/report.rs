@@ -0,0 +1,238 @@
+use std::fmt::Write as _;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    habitica::{HabiticaApi, HabiticaClient, StatsCache},
+    say,
+    sync::{ResolutionAction, SyncEngine, SyncOperation},
+    taskwarrior::{backend::sync_backend, Task},
+    tr,
+};
+
+/// Handle the 'report' command
+pub fn handle_report(config: &Config, email: bool) -> Result<()> {
+    let tw_client = sync_backend(config)?;
+    let h_client = HabiticaClient::new(config)?;
+    let now = Utc::now();
+
+    let completed = completed_since(
+        tw_client.export(&["status:completed"])?,
+        now - Duration::days(1),
+    );
+
+    // Compare against the stats saved by the previous report
+    let stats_path = config.report_stats_path();
+    let current_stats = h_client.get_user_stats()?;
+    let stat_messages = match StatsCache::load(&stats_path)? {
+        Some(mut cache) => {
+            cache.update(Some(current_stats.clone()), None);
+            cache.get_diff_messages()
+        }
+        None => Vec::new(),
+    };
+
+    let engine = SyncEngine::new(config, tw_client.as_ref(), &h_client);
+    let pending: Vec<String> = engine
+        .plan(tw_client.snapshot()?)?
+        .operations
+        .iter()
+        .filter_map(describe_pending)
+        .collect();
+
+    let digest = render_digest(&completed, &stat_messages, &pending);
+
+    if email {
+        let email_config = config.report_email.as_ref().ok_or_else(|| {
+            Error::config("Set habitica.report.email to send the report by email")
+        })?;
+        let subject = tr!("report-subject", date = now.format("%Y-%m-%d").to_string());
+        send_email(email_config, &subject, digest)?;
+        say!("{}", tr!("report-sent", to = email_config.to.as_str()));
+    } else {
+        say!("{}", digest.trim_end());
+    }
+
+    StatsCache::new(current_stats).save(&stats_path)?;
+    Ok(())
+}
+
+/// Keep tasks completed at or after `since`, oldest first
+fn completed_since(tasks: Vec<Task>, since: DateTime<Utc>) -> Vec<Task> {
+    let mut completed: Vec<Task> = tasks
+        .into_iter()
+        .filter(|task| task.date_attribute("end").is_some_and(|end| end >= since))
+        .collect();
+    completed.sort_by_key(|task| task.date_attribute("end"));
+    completed
+}
+
+/// Describe a planned operation that still needs a sync
+fn describe_pending(operation: &SyncOperation) -> Option<String> {
+    let (id, task) = match operation {
+        SyncOperation::PushNew(task) => ("report-push", &task.description),
+        SyncOperation::ImportFromHabitica(h_task) => ("report-import", &h_task.text),
+        SyncOperation::DeletedOnHabitica(task) => ("report-deleted-habitica", &task.description),
+        SyncOperation::DeletedInTaskwarrior(h_task) => ("report-deleted-taskwarrior", &h_task.text),
+        SyncOperation::Expired(task) => ("report-expired", &task.description),
+        SyncOperation::Filtered(task) => ("report-filtered", &task.description),
+        SyncOperation::Superseded(task) => ("report-superseded", &task.description),
+        SyncOperation::Reconcile {
+            tw_task, action, ..
+        } => match action {
+            ResolutionAction::NoChange => return None,
+            ResolutionAction::UseHabitica => ("report-changed-habitica", &tw_task.description),
+            ResolutionAction::UseTaskwarrior => {
+                ("report-changed-taskwarrior", &tw_task.description)
+            }
+            ResolutionAction::Merge
+            | ResolutionAction::Combine(_)
+            | ResolutionAction::Skip
+            | ResolutionAction::Defer => ("report-changed-both", &tw_task.description),
+        },
+    };
+    Some(tr!(id, task = task.as_str()))
+}
+
+/// Render the plain-text digest
+fn render_digest(completed: &[Task], stat_messages: &[String], pending: &[String]) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(out, "{}", tr!("report-completed", count = completed.len()));
+    for task in completed {
+        let _ = writeln!(out, "  - {}", task.description);
+    }
+
+    let _ = writeln!(out, "\n{}", tr!("report-stats"));
+    if stat_messages.is_empty() {
+        let _ = writeln!(out, "  {}", tr!("report-stats-none"));
+    }
+    for msg in stat_messages {
+        let _ = writeln!(out, "  {}", msg);
+    }
+
+    let _ = writeln!(out, "\n{}", tr!("report-waiting", count = pending.len()));
+    for item in pending {
+        let _ = writeln!(out, "  - {}", item);
+    }
+
+    out
+}
+
+#[cfg(feature = "email")]
+fn send_email(email: &crate::config::EmailConfig, subject: &str, body: String) -> Result<()> {
+    use lettre::{
+        transport::smtp::authentication::Credentials, Message, SendmailTransport, SmtpTransport,
+        Transport,
+    };
+
+    use crate::config::EmailTransport;
+
+    let message = Message::builder()
+        .from(
+            email
+                .from
+                .parse()
+                .map_err(|e| Error::config(format!("Invalid sender address: {}", e)))?,
+        )
+        .to(email
+            .to
+            .parse()
+            .map_err(|e| Error::config(format!("Invalid recipient address: {}", e)))?)
+        .subject(subject)
+        .body(body)
+        .map_err(|e| Error::custom(format!("Failed to build email: {}", e)))?;
+
+    match &email.transport {
+        EmailTransport::Sendmail(command) => SendmailTransport::new_with_command(command)
+            .send(&message)
+            .map_err(|e| Error::custom(format!("Failed to send email: {}", e))),
+        EmailTransport::Smtp {
+            host,
+            port,
+            user,
+            password,
+        } => {
+            let mut builder = SmtpTransport::starttls_relay(host)
+                .map_err(|e| Error::config(format!("Invalid SMTP host {}: {}", host, e)))?
+                .port(*port);
+            if let (Some(user), Some(password)) = (user, password) {
+                builder = builder.credentials(Credentials::new(user.clone(), password.clone()));
+            }
+            builder
+                .build()
+                .send(&message)
+                .map(|_| ())
+                .map_err(|e| Error::custom(format!("Failed to send email: {}", e)))
+        }
+    }
+}
+
+#[cfg(not(feature = "email"))]
+fn send_email(_email: &crate::config::EmailConfig, _subject: &str, _body: String) -> Result<()> {
+    Err(Error::config(
+        "Emailing reports requires building with the `email` feature",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::taskwarrior::date_format;
+
+    fn completed_task(description: &str, end: DateTime<Utc>) -> Task {
+        let mut task = Task {
+            description: description.to_string(),
+            ..Default::default()
+        };
+        task.extra
+            .insert("end".to_string(), Value::String(date_format::format(&end)));
+        task
+    }
+
+    #[test]
+    fn test_completed_since() {
+        let now = Utc::now();
+        let tasks = vec![
+            completed_task("Today", now - Duration::hours(2)),
+            completed_task("Last week", now - Duration::days(7)),
+            completed_task("This morning", now - Duration::hours(5)),
+        ];
+
+        let recent = completed_since(tasks, now - Duration::days(1));
+        let names: Vec<&str> = recent.iter().map(|t| t.description.as_str()).collect();
+        assert_eq!(names, ["This morning", "Today"]);
+    }
+
+    #[test]
+    fn test_render_digest() {
+        let digest = render_digest(
+            &[completed_task("Write report", Utc::now())],
+            &["Gold: +2 (40)".to_string()],
+            &["Changed on Habitica: Stretch".to_string()],
+        );
+
+        assert!(digest.starts_with("Completed in the last 24 hours: 1\n  - Write report\n"));
+        assert!(digest.contains("  Gold: +2 (40)\n"));
+        assert!(digest.contains("Waiting for sync: 1\n  - Changed on Habitica: Stretch\n"));
+    }
+
+    #[test]
+    fn test_describe_pending() {
+        let task = completed_task("Stretch", Utc::now());
+        assert_eq!(
+            describe_pending(&SyncOperation::Expired(task.clone())).as_deref(),
+            Some("Expired: Stretch")
+        );
+        let unchanged = SyncOperation::Reconcile {
+            tw_task: task,
+            h_task: crate::habitica::HabiticaTask::default(),
+            action: ResolutionAction::NoChange,
+        };
+        assert_eq!(describe_pending(&unchanged), None);
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{
+    commands::touch::summarize_event,
+    config::Config,
+    error::Result,
+    habitica::HabiticaClient,
+    say,
+    sync::{conflicts::ConflictQueue, SyncEngine, SyncEvent},
+    taskwarrior::backend::sync_backend,
+};
+
+/// Handle the 'resolve' command
+///
+/// Goes through the conflicts deferred by syncs, showing how each task
+/// differs and asking which side to keep. Skipped tasks stay queued, and
+/// tasks that were deleted or have come back in sync are dropped.
+pub fn handle_resolve(config: &Config) -> Result<()> {
+    let path = config.conflicts_path();
+    let mut queue = ConflictQueue::load(&path)?;
+    if queue.is_empty() {
+        say!("No deferred conflicts");
+        return Ok(());
+    }
+    say!("{} deferred conflicts\n", queue.len());
+
+    let tw_client = sync_backend(config)?;
+    let h_client = HabiticaClient::new(config)?;
+    let engine = SyncEngine::new(config, tw_client.as_ref(), &h_client)
+        .source("resolve")
+        .interactive(true);
+    engine.resume()?;
+
+    let queued: Vec<_> = queue
+        .entries()
+        .map(|(uuid, conflict)| (uuid, conflict.description.clone()))
+        .collect();
+    for (uuid, description) in queued {
+        let Ok(task) = tw_client.get_task(&uuid.to_string()) else {
+            say!("No longer in Taskwarrior: {}", description);
+            queue.remove(uuid);
+            queue.save(&path)?;
+            continue;
+        };
+
+        let plan = engine.plan_task(task)?;
+        let mut skipped = false;
+        if plan.operations.is_empty() {
+            say!("Nothing to resolve: {}", description);
+        } else {
+            engine.apply(plan, &mut |event| {
+                skipped |= matches!(event, SyncEvent::Skipped { .. });
+                say!("{}", summarize_event(event));
+            })?;
+        }
+        if !skipped {
+            queue.remove(uuid);
+            queue.save(&path)?;
+        }
+        say!();
+    }
+
+    if !queue.is_empty() {
+        say!("{} conflicts left for later", queue.len());
+    }
+    Ok(())
+}
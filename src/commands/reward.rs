@@ -0,0 +1,64 @@
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    habitica::{HabiticaApi, HabiticaClient, ScoreDirection, StatsCache},
+    say,
+    sync::resolver::purchase_message,
+    taskwarrior::task::DEFAULT_REWARD_COST,
+};
+
+/// Handle the 'reward buy' command
+///
+/// Buys a Habitica reward, given by name or ID, and reports the gold spent
+/// along with any other stat changes.
+pub fn handle_reward_buy(config: &Config, name: &str) -> Result<()> {
+    let h_client = HabiticaClient::new(config)?;
+
+    let reward = h_client
+        .find_reward(name)?
+        .ok_or_else(|| Error::custom(format!("Reward not found on Habitica: {}", name)))?;
+    let reward_id = reward
+        .id
+        .ok_or_else(|| Error::HabiticaApiError("Reward has no ID".to_string()))?;
+    let cost = reward.value.unwrap_or(DEFAULT_REWARD_COST);
+
+    let stats = h_client.get_user_stats()?;
+    if !can_afford(stats.gp, cost) {
+        return Err(Error::custom(format!(
+            "Not enough gold for {}: it costs {} gold, you have {:.2}",
+            reward.text, cost, stats.gp
+        )));
+    }
+
+    let mut stats_cache = StatsCache::new(stats);
+    let (new_stats, drop_msg) = h_client.score_task(reward_id, ScoreDirection::Up)?;
+    stats_cache.update(new_stats, drop_msg);
+
+    let gold_left = stats_cache
+        .current
+        .as_ref()
+        .map_or(stats_cache.old.gp - cost, |stats| stats.gp);
+    say!("{}", purchase_message(&reward.text, cost, gold_left));
+    for message in stats_cache.get_diff_messages() {
+        say!("{}", message);
+    }
+
+    Ok(())
+}
+
+/// Check whether the gold covers a price
+fn can_afford(gold: f64, cost: f64) -> bool {
+    gold >= cost
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_can_afford() {
+        assert!(can_afford(25.0, 25.0));
+        assert!(can_afford(30.5, 10.0));
+        assert!(!can_afford(9.99, 10.0));
+    }
+}
@@ -0,0 +1,466 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    path::Path,
+};
+
+use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    commands::{
+        link::link_task,
+        sync::{run_sync, SyncMode, SyncOptions},
+    },
+    config::{Config, ConflictStrategy},
+    error::{Error, Result},
+    habitica::{HabiticaApi, HabiticaClient, ScoreDirection},
+    say_err,
+    taskwarrior::{backend::sync_backend, HookGuard, TaskBackend},
+};
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+const UNAUTHORIZED: i64 = -32001;
+
+/// A JSON-RPC 2.0 request
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+    /// Needed on TCP, where any local process or web page could connect
+    #[serde(default)]
+    token: Option<String>,
+}
+
+/// A JSON-RPC 2.0 error object
+#[derive(Debug, Serialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+/// A JSON-RPC 2.0 response
+#[derive(Debug, Serialize)]
+struct RpcResponse {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    const fn success(id: Value, result: Value) -> Self {
+        RpcResponse {
+            jsonrpc: "2.0",
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    fn failure(id: Value, code: i64, message: impl Into<String>) -> Self {
+        RpcResponse {
+            jsonrpc: "2.0",
+            id,
+            result: None,
+            error: Some(RpcError {
+                code,
+                message: message.into(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListParams {
+    #[serde(default)]
+    filter: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LinkParams {
+    task: String,
+    habitica_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScoreParams {
+    task: String,
+    #[serde(default)]
+    direction: Option<String>,
+}
+
+/// Line-delimited JSON-RPC server driving the sync operations
+struct RpcServer<'a> {
+    config: &'a Config,
+    tw_client: Box<dyn TaskBackend>,
+    h_client: HabiticaClient,
+    /// Token every request must carry, `None` on the Unix socket
+    token: Option<String>,
+}
+
+impl RpcServer<'_> {
+    /// Handle one raw request line and build the response
+    fn handle_line(&self, line: &str) -> RpcResponse {
+        let request: RpcRequest = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => return RpcResponse::failure(Value::Null, PARSE_ERROR, e.to_string()),
+        };
+
+        let id = request.id.clone();
+        if let Some(token) = &self.token {
+            if !request
+                .token
+                .as_deref()
+                .is_some_and(|given| same_token(given, token))
+            {
+                return RpcResponse::failure(id, UNAUTHORIZED, "Missing or wrong token");
+            }
+        }
+        match self.dispatch(&request) {
+            Ok(result) => RpcResponse::success(id, result),
+            Err(RpcFailure::UnknownMethod) => RpcResponse::failure(
+                id,
+                METHOD_NOT_FOUND,
+                format!("Unknown method: {}", request.method),
+            ),
+            Err(RpcFailure::InvalidParams(msg)) => RpcResponse::failure(id, INVALID_PARAMS, msg),
+            Err(RpcFailure::Failed(err)) => RpcResponse::failure(id, SERVER_ERROR, err.to_string()),
+        }
+    }
+
+    fn dispatch(&self, request: &RpcRequest) -> std::result::Result<Value, RpcFailure> {
+        match request.method.as_str() {
+            "sync" => {
+                // Hooks of other task processes must not push what the sync
+                // is writing
+                let _guard = self.pause_hooks()?;
+                let summary = run_sync(
+                    self.config,
+                    "rpc",
+                    SyncMode::Full,
+                    SyncOptions::default(),
+                    &ProgressBar::hidden(),
+                    &mut |_| {},
+                )?;
+                Ok(serde_json::to_value(summary).map_err(Error::from)?)
+            }
+
+            "list" => {
+                let params: ListParams = parse_params(&request.params)?;
+                let mut filters: Vec<&str> = params.filter.iter().map(String::as_str).collect();
+                if filters.is_empty() {
+                    filters.push("status:pending");
+                }
+                let tasks = self.tw_client.export(&filters)?;
+                Ok(serde_json::to_value(tasks).map_err(Error::from)?)
+            }
+
+            "link" => {
+                let params: LinkParams = parse_params(&request.params)?;
+                let _guard = self.pause_hooks()?;
+                let task = link_task(
+                    self.config,
+                    self.tw_client.as_ref(),
+                    &self.h_client,
+                    &params.task,
+                    params.habitica_id,
+                )?;
+                Ok(serde_json::to_value(task).map_err(Error::from)?)
+            }
+
+            "score" => {
+                let params: ScoreParams = parse_params(&request.params)?;
+                let direction = match params.direction.as_deref() {
+                    None | Some("up") => ScoreDirection::Up,
+                    Some("down") => ScoreDirection::Down,
+                    Some(other) => {
+                        return Err(RpcFailure::InvalidParams(format!(
+                            "Invalid direction: {}",
+                            other
+                        )))
+                    }
+                };
+                let _guard = self.pause_hooks()?;
+                let task = self.tw_client.get_task(&params.task)?;
+                let h_id = task
+                    .habitica_uuid
+                    .ok_or_else(|| Error::custom("Task is not linked to Habitica"))?;
+                let (stats, drop) = self.h_client.score_task(h_id, direction)?;
+                Ok(json!({ "stats": stats, "drop": drop }))
+            }
+
+            _ => Err(RpcFailure::UnknownMethod),
+        }
+    }
+
+    /// Keep the hooks quiet while a request writes to Taskwarrior
+    fn pause_hooks(&self) -> Result<HookGuard> {
+        HookGuard::acquire(&self.config.hook_guard_path())
+    }
+
+    /// Serve requests from one connection until it closes
+    fn serve_connection(&self, reader: impl BufRead, mut writer: impl Write) -> Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let response = self.handle_line(&line);
+            writeln!(writer, "{}", serde_json::to_string(&response)?)?;
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reasons a request could not produce a result
+enum RpcFailure {
+    UnknownMethod,
+    InvalidParams(String),
+    Failed(Error),
+}
+
+impl From<Error> for RpcFailure {
+    fn from(err: Error) -> Self {
+        RpcFailure::Failed(err)
+    }
+}
+
+fn parse_params<T: for<'de> Deserialize<'de>>(
+    params: &Value,
+) -> std::result::Result<T, RpcFailure> {
+    let params = if params.is_null() {
+        json!({})
+    } else {
+        params.clone()
+    };
+    serde_json::from_value(params).map_err(|e| RpcFailure::InvalidParams(e.to_string()))
+}
+
+/// Compare tokens without stopping at the first differing byte
+fn same_token(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Handle the 'rpc' command
+///
+/// Listens on a Unix socket only the user can connect to, at `socket` or
+/// next to the other data files. With `listen`, it takes TCP connections
+/// instead, which needs `habitica.rpc.token` set.
+pub fn handle_rpc(config: &Config, socket: Option<&Path>, listen: Option<&str>) -> Result<()> {
+    let config = &unattended(config);
+    let mut server = RpcServer {
+        config,
+        tw_client: sync_backend(config)?,
+        h_client: HabiticaClient::new(config)?,
+        token: None,
+    };
+
+    if let Some(address) = listen {
+        server.token = Some(
+            config
+                .rpc_token
+                .clone()
+                .ok_or_else(|| Error::config("Set habitica.rpc.token before listening on TCP"))?,
+        );
+        let listener = TcpListener::bind(address)?;
+        say_err!(
+            "Listening for JSON-RPC requests on {}",
+            listener.local_addr()?
+        );
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let reader = BufReader::new(stream.try_clone()?);
+            if let Err(err) = server.serve_connection(reader, stream) {
+                say_err!("RPC connection error: {}", err);
+            }
+        }
+        return Ok(());
+    }
+
+    let path = socket.map_or_else(|| config.rpc_socket_path(), Path::to_path_buf);
+    serve_socket(&server, &path)
+}
+
+/// The configuration for syncs nobody watches
+///
+/// Clients can't answer a prompt or wait on the merge tool, so conflicts of
+/// the `ask` and `external` strategies are queued for `resolve` instead.
+fn unattended(config: &Config) -> Config {
+    let mut config = config.clone();
+    if matches!(
+        config.conflict_strategy,
+        ConflictStrategy::Ask | ConflictStrategy::External
+    ) {
+        config.conflict_strategy = ConflictStrategy::Defer;
+    }
+    config
+}
+
+/// Serve requests on a Unix socket
+#[cfg(unix)]
+fn serve_socket(server: &RpcServer, path: &Path) -> Result<()> {
+    use std::os::unix::net::UnixStream;
+
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(Error::custom(format!(
+                "Another server is listening on {}",
+                path.display()
+            )));
+        }
+        fs::remove_file(path)?;
+    }
+    let listener = bind_private(path)?;
+    say_err!("Listening for JSON-RPC requests on {}", path.display());
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let reader = BufReader::new(stream.try_clone()?);
+        if let Err(err) = server.serve_connection(reader, stream) {
+            say_err!("RPC connection error: {}", err);
+        }
+    }
+    Ok(())
+}
+
+/// Serve requests on a Unix socket
+#[cfg(not(unix))]
+fn serve_socket(_server: &RpcServer, _path: &Path) -> Result<()> {
+    Err(Error::custom(
+        "Unix sockets aren't available here, use --listen with habitica.rpc.token",
+    ))
+}
+
+/// Bind a socket only its owner can connect to
+///
+/// The socket is made in a directory only the owner can enter, then moved
+/// into place, so nobody can connect before its permissions are set.
+#[cfg(unix)]
+fn bind_private(path: &Path) -> Result<std::os::unix::net::UnixListener> {
+    use std::os::unix::{
+        fs::{DirBuilderExt, PermissionsExt},
+        net::UnixListener,
+    };
+
+    let dir = path.with_extension(format!("{}.tmp", std::process::id()));
+    fs::DirBuilder::new().mode(0o700).create(&dir)?;
+    let staged = dir.join("socket");
+    let bound = UnixListener::bind(&staged).and_then(|listener| {
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o600))?;
+        fs::rename(&staged, path)?;
+        Ok(listener)
+    });
+    let _ = fs::remove_file(&staged);
+    fs::remove_dir(&dir)?;
+    Ok(bound?)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::taskwarrior::TaskwarriorClient;
+
+    fn test_server(config: &Config) -> RpcServer<'_> {
+        RpcServer {
+            config,
+            tw_client: Box::new(TaskwarriorClient::new()),
+            h_client: HabiticaClient::new(config).unwrap(),
+            token: None,
+        }
+    }
+
+    #[test]
+    fn test_unattended_defers_conflicts() {
+        let mut config = Config::for_tests();
+        config.conflict_strategy = ConflictStrategy::Ask;
+        assert_eq!(
+            unattended(&config).conflict_strategy,
+            ConflictStrategy::Defer
+        );
+        config.conflict_strategy = ConflictStrategy::External;
+        assert_eq!(
+            unattended(&config).conflict_strategy,
+            ConflictStrategy::Defer
+        );
+        config.conflict_strategy = ConflictStrategy::HabiticaWins;
+        assert_eq!(
+            unattended(&config).conflict_strategy,
+            ConflictStrategy::HabiticaWins
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let config = Config::for_tests();
+        let response = test_server(&config).handle_line("not json");
+        assert_eq!(response.error.unwrap().code, PARSE_ERROR);
+    }
+
+    #[test]
+    fn test_unknown_method() {
+        let config = Config::for_tests();
+        let response =
+            test_server(&config).handle_line(r#"{"jsonrpc":"2.0","id":1,"method":"explode"}"#);
+        assert_eq!(response.id, json!(1));
+        assert_eq!(response.error.unwrap().code, METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let config = Config::for_tests();
+        let response = test_server(&config)
+            .handle_line(r#"{"jsonrpc":"2.0","id":2,"method":"link","params":{}}"#);
+        assert_eq!(response.error.unwrap().code, INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_token_required() {
+        let config = Config::for_tests();
+        let mut server = test_server(&config);
+        server.token = Some("secret".to_string());
+
+        let response = server.handle_line(r#"{"jsonrpc":"2.0","id":3,"method":"explode"}"#);
+        assert_eq!(response.error.unwrap().code, UNAUTHORIZED);
+        let response =
+            server.handle_line(r#"{"jsonrpc":"2.0","id":3,"method":"explode","token":"secreT"}"#);
+        assert_eq!(response.error.unwrap().code, UNAUTHORIZED);
+        let response =
+            server.handle_line(r#"{"jsonrpc":"2.0","id":3,"method":"explode","token":"secret"}"#);
+        assert_eq!(response.error.unwrap().code, METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_socket_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("habitica_rpc.sock");
+        let _listener = bind_private(&path).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
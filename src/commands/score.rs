@@ -0,0 +1,35 @@
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    habitica::{HabiticaApi, HabiticaClient, ScoreDirection, StatsCache},
+    say,
+    taskwarrior::backend::sync_backend,
+};
+
+/// Handle the 'score' command
+///
+/// Scores the Habitica task linked to a Taskwarrior task and reports the
+/// stat changes, for when a hook failed before a completion reached
+/// Habitica. Nothing changes in Taskwarrior.
+pub fn handle_score(config: &Config, id: &str, direction: ScoreDirection) -> Result<()> {
+    let tw_client = sync_backend(config)?;
+    let h_client = HabiticaClient::new(config)?;
+
+    let task = tw_client.get_task(id)?;
+    let h_id = task.habitica_uuid.ok_or_else(|| {
+        Error::custom(format!(
+            "Not linked to Habitica: {}, run `task2habitica touch {}` to push it",
+            task.description, id
+        ))
+    })?;
+
+    let mut stats_cache = StatsCache::new(h_client.get_user_stats()?);
+    let (new_stats, drop_msg) = h_client.score_task(h_id, direction)?;
+    stats_cache.update(new_stats, drop_msg);
+
+    say!("Scored {}: {}", direction.as_str(), task.description);
+    for message in stats_cache.get_diff_messages() {
+        say!("{}", message);
+    }
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use std::{
+    env, fs,
+    io::{self, IsTerminal},
+};
+
+use crate::{
+    commands::{
+        hooks::add_hooks,
+        init::{add_udas, CREDENTIALS},
+    },
+    config::{Config, ConflictStrategy},
+    error::{Error, Result},
+    habitica::{Account, HabiticaClient},
+    output, say, say_err,
+    taskwarrior::{TaskBackend, TaskwarriorClient},
+};
+
+/// Handle the 'setup' command
+///
+/// Walks a new user through the configuration: the Habitica credentials,
+/// checked against the API before going on, the task note directory and the
+/// main sync preferences, then offers to add the UDAs and install the hooks.
+/// Empty answers keep the current value, so it also works for changing an
+/// existing setup.
+pub fn handle_setup() -> Result<()> {
+    if output::is_plain() || !io::stdin().is_terminal() {
+        return Err(Error::custom(
+            "setup asks its questions on a terminal, use `task2habitica init` in scripts",
+        ));
+    }
+    let tw_client = TaskwarriorClient::new();
+
+    say!("Habitica credentials, found under Settings > Site Data on habitica.com");
+    let account = loop {
+        ask_credentials(&tw_client)?;
+        match check_credentials() {
+            Ok(account) => break account,
+            Err(e) => {
+                say_err!("Habitica didn't accept the credentials: {}", e);
+                if !output::confirm("Enter them again?") {
+                    return Err(e);
+                }
+            }
+        }
+    };
+    say!(
+        "Logged in as {} (@{}), level {}\n",
+        account.name,
+        account.username,
+        account.level
+    );
+
+    let notes = ask_setting(
+        &tw_client,
+        "tasknote.location",
+        "Task note directory",
+        "~/.task/notes/",
+        &|_| Ok(()),
+    )?;
+    fs::create_dir_all(Config::expand_path(&notes)?)?;
+    ask_setting(
+        &tw_client,
+        "habitica.conflict.strategy",
+        "Conflict strategy (newest, taskwarrior-wins, habitica-wins, ask or defer)",
+        "newest",
+        &|value| value.parse::<ConflictStrategy>().map(|_| ()),
+    )?;
+    ask_setting(
+        &tw_client,
+        "habitica.sync.project",
+        "Project for tasks imported from Habitica",
+        "none",
+        &|_| Ok(()),
+    )?;
+    say!();
+
+    if output::confirm("Add the task2habitica UDAs to the taskrc?") {
+        add_udas(&tw_client)?;
+    }
+    if output::confirm("Install the hooks, syncing each change as it's made?") {
+        add_hooks(&tw_client)?;
+    } else {
+        say!("Hooks skipped, run `task2habitica daemon --poll` to keep both sides in sync");
+    }
+
+    say!("\nAll set, run `task2habitica sync` for the first sync");
+    Ok(())
+}
+
+/// Ask for the credentials not taken from the environment
+///
+/// The user ID is offered as the default, the API token is not shown.
+fn ask_credentials(tw_client: &TaskwarriorClient) -> Result<()> {
+    for (var, key, label) in CREDENTIALS {
+        if env::var(var).is_ok_and(|value| !value.is_empty()) {
+            say!("{} taken from {}", label, var);
+            continue;
+        }
+        let current = tw_client.get_config(&format!("rc.{}", key))?;
+        let question = match (current.is_empty(), *var == "HABITICA_API_KEY") {
+            (true, _) => (*label).to_string(),
+            (false, true) => format!("{} [keep current]", label),
+            (false, false) => format!("{} [{}]", label, current),
+        };
+        if let Some(value) = output::ask(&question) {
+            tw_client.set_config(key, &value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Load the configuration and log in with it
+fn check_credentials() -> Result<Account> {
+    let config = Config::load(false)?;
+    let (account, _) = HabiticaClient::new(&config)?.verify_credentials()?;
+    Ok(account)
+}
+
+/// Ask for a taskrc setting, writing the answer if it changed
+///
+/// An empty answer keeps the current value, or the default when unset.
+/// Answers that `check` rejects are asked again.
+fn ask_setting(
+    tw_client: &TaskwarriorClient,
+    key: &str,
+    label: &str,
+    default: &str,
+    check: &dyn Fn(&str) -> Result<()>,
+) -> Result<String> {
+    let current = tw_client.get_config(&format!("rc.{}", key))?;
+    let shown = if current.is_empty() {
+        default
+    } else {
+        current.as_str()
+    };
+    loop {
+        let Some(value) = output::ask(&format!("{} [{}]", label, shown)) else {
+            return Ok(shown.to_string());
+        };
+        if let Err(e) = check(&value) {
+            say_err!("{}", e);
+            continue;
+        }
+        if value != current {
+            tw_client.set_config(key, &value)?;
+        }
+        return Ok(value);
+    }
+}
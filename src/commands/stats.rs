@@ -0,0 +1,153 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    thread,
+    time::Duration,
+};
+
+use chrono::Local;
+
+use crate::{
+    config::Config,
+    error::Result,
+    habitica::{HabiticaApi, HabiticaClient, StatsCache, UserStats},
+    output, say, tr,
+};
+
+/// Shortest time between two polls of `stats --watch`
+pub const MIN_WATCH_SECONDS: u64 = 10;
+
+/// Width of the HP, MP and experience bars
+const BAR_WIDTH: u32 = 20;
+
+/// Handle the 'stats' command
+///
+/// Shows the character's level, class, health, mana, experience and gold.
+/// With `watch`, fetches them again every that many seconds until
+/// interrupted, listing what changed since the previous poll. Intervals
+/// under [`MIN_WATCH_SECONDS`] are raised to it.
+pub fn handle_stats(config: &Config, watch: Option<u64>) -> Result<()> {
+    let h_client = HabiticaClient::new(config)?;
+    let mut stats = h_client.get_user_stats()?;
+    print_stats(&stats);
+
+    let Some(seconds) = watch else {
+        return Ok(());
+    };
+    // Redraw in place on a terminal, keep a running log otherwise
+    let redraw = !output::is_plain() && io::stdout().is_terminal();
+    loop {
+        thread::sleep(Duration::from_secs(seconds.max(MIN_WATCH_SECONDS)));
+        let mut cache = StatsCache::new(stats);
+        let current = h_client.get_user_stats()?;
+        cache.update(Some(current.clone()), None);
+        let changes = cache.get_diff_messages();
+        stats = current;
+
+        if redraw {
+            print!("\x1b[2J\x1b[H");
+            io::stdout().flush()?;
+            print_stats(&stats);
+        } else if changes.is_empty() {
+            continue;
+        } else {
+            say!();
+            print_stats(&stats);
+        }
+        if !changes.is_empty() {
+            say!(
+                "\n{}",
+                tr!(
+                    "stats-changed",
+                    time = Local::now().format("%H:%M").to_string(),
+                    changes = changes.join(", ")
+                )
+            );
+        }
+    }
+}
+
+fn print_stats(stats: &UserStats) {
+    for line in render(stats) {
+        say!("{}", line);
+    }
+}
+
+/// Lines showing the stats, with bars for the values that have a maximum
+fn render(stats: &UserStats) -> Vec<String> {
+    let level = match &stats.class {
+        Some(class) if stats.lvl >= 10 => {
+            tr!(
+                "stats-level-class",
+                level = stats.lvl,
+                class = class.as_str()
+            )
+        }
+        _ => tr!("stats-level", level = stats.lvl),
+    };
+    let meter = |label: String, value: f64, max: Option<i32>| match max {
+        Some(max) => format!(
+            "{:<5} {:>5} / {:<5} {}",
+            label,
+            value.floor(),
+            max,
+            bar(value, f64::from(max))
+        ),
+        None => format!("{:<5} {:>5}", label, value.floor()),
+    };
+    vec![
+        level,
+        meter(tr!("stats-hp"), stats.hp, stats.max_hp),
+        meter(tr!("stats-mp"), stats.mp, stats.max_mp),
+        meter(tr!("stats-exp"), stats.exp, stats.to_next_level),
+        format!("{:<5} {:>8.2}", tr!("stats-gold"), stats.gp),
+    ]
+}
+
+/// Bar filled in proportion to `value` out of `max`
+fn bar(value: f64, max: f64) -> String {
+    let ratio = if max > 0.0 {
+        (value / max).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let width = f64::from(BAR_WIDTH);
+    // Cells whose middle the value reaches
+    let filled = (0..BAR_WIDTH)
+        .take_while(|cell| f64::from(*cell) + 0.5 <= ratio * width)
+        .count();
+    let empty = BAR_WIDTH as usize - filled;
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(empty))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let stats = UserStats {
+            hp: 42.6,
+            max_hp: Some(50),
+            mp: 0.0,
+            max_mp: Some(30),
+            exp: 120.0,
+            to_next_level: Some(240),
+            gp: 85.304,
+            lvl: 12,
+            con: 0.0,
+            class: Some("wizard".to_string()),
+        };
+        let lines = render(&stats);
+        assert_eq!(lines[0], "Level 12 wizard");
+        assert_eq!(
+            lines[1],
+            format!("HP       42 / 50    [{}---]", "#".repeat(17))
+        );
+        assert_eq!(
+            lines[2],
+            format!("MP        0 / 30    [{}]", "-".repeat(20))
+        );
+        assert!(lines[3].ends_with(&format!("[{}{}]", "#".repeat(10), "-".repeat(10))));
+        assert_eq!(lines[4], "Gold     85.30");
+    }
+}
@@ -0,0 +1,113 @@
+use chrono::{DateTime, Duration, Local, Utc};
+
+use crate::{
+    commands::hooks::hook_paths,
+    config::Config,
+    error::Result,
+    say,
+    sync::{conflicts::ConflictQueue, progress, resolver::is_excluded, spool, watermark},
+    taskwarrior::{backend::sync_backend, TaskwarriorClient},
+};
+
+/// Handle the 'status' command
+///
+/// Reports how the sync is doing from what is stored locally, without
+/// asking Habitica: when the last complete sync ran, how many pending tasks
+/// are linked, what is still waiting to be sent or resolved, and whether the
+/// hooks are installed.
+pub fn handle_status(config: &Config) -> Result<()> {
+    let now = Utc::now();
+    match watermark::load(&config.watermark_path())? {
+        Some(time) => say!(
+            "Last sync:          {} ({})",
+            time.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            since(now, time)
+        ),
+        None => say!("Last sync:          never"),
+    }
+
+    let pending = sync_backend(config)?.export(&["status:pending"])?;
+    let linked = pending
+        .iter()
+        .filter(|task| task.habitica_uuid.is_some())
+        .count();
+    let excluded = pending
+        .iter()
+        .filter(|task| task.habitica_uuid.is_none() && is_excluded(config, task))
+        .count();
+    let unlinked = pending.len() - linked - excluded;
+    say!(
+        "Pending tasks:      {} linked, {} not on Habitica yet, {} kept local",
+        linked,
+        unlinked,
+        excluded
+    );
+
+    let queued = spool::coalesce(spool::load(&config.spool_path())?).len();
+    if queued > 0 {
+        say!(
+            "Queued changes:     {} tasks, sent by `task2habitica flush`",
+            queued
+        );
+    } else {
+        say!("Queued changes:     none");
+    }
+    let staged = progress::load(&config.progress_path())?.len();
+    if staged > 0 {
+        say!(
+            "Interrupted sync:   {} tasks to import on the next run",
+            staged
+        );
+    }
+
+    let conflicts = ConflictQueue::load(&config.conflicts_path())?.len();
+    if conflicts > 0 {
+        say!(
+            "Deferred conflicts: {}, settled by `task2habitica resolve`",
+            conflicts
+        );
+    } else {
+        say!("Deferred conflicts: none");
+    }
+
+    let hooks = hook_paths(&TaskwarriorClient::new())?;
+    let missing: Vec<_> = hooks.iter().filter(|path| !path.exists()).collect();
+    if missing.is_empty() {
+        say!("Hooks:              installed");
+    } else if missing.len() == hooks.len() {
+        say!("Hooks:              not installed");
+    } else {
+        for path in missing {
+            say!("Hooks:              missing {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// How long ago a moment was, roughly
+fn since(now: DateTime<Utc>, time: DateTime<Utc>) -> String {
+    let elapsed = now - time;
+    if elapsed < Duration::minutes(1) {
+        "just now".to_string()
+    } else if elapsed < Duration::hours(1) {
+        format!("{} minutes ago", elapsed.num_minutes())
+    } else if elapsed < Duration::days(1) {
+        format!("{} hours ago", elapsed.num_hours())
+    } else {
+        format!("{} days ago", elapsed.num_days())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_since() {
+        let now = Utc::now();
+        assert_eq!(since(now, now - Duration::seconds(20)), "just now");
+        assert_eq!(since(now, now - Duration::minutes(5)), "5 minutes ago");
+        assert_eq!(since(now, now - Duration::minutes(150)), "2 hours ago");
+        assert_eq!(since(now, now - Duration::days(3)), "3 days ago");
+    }
+}
@@ -1,168 +1,660 @@
-use std::collections::HashMap;
+use std::{
+    io::{self, IsTerminal},
+    time::Duration,
+};
 
-use uuid::Uuid;
+use chrono::Utc;
+use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
+use serde_json::{json, Value};
 
 use crate::{
-    config::Config,
+    commands::inn::inn_status,
+    config::{Config, ConflictStrategy},
     error::Result,
-    habitica::{HabiticaClient, StatsCache},
-    sync::{ConflictResolver, ResolutionAction},
-    taskwarrior::{TaskStatus, TaskwarriorClient},
+    habitica::{HabiticaApi, HabiticaClient, HabiticaTask, StatsCache},
+    notify::{Notification, Notifier},
+    output::{self, Tone},
+    say, say_err,
+    sync::{
+        converter::FieldChange, order::mirror_urgency, party::post_party_milestone, watermark,
+        SyncEngine, SyncEvent, SyncSummary,
+    },
+    taskwarrior::{backend::sync_backend, Task},
+    tr,
 };
 
-pub fn handle_sync(config: &Config) -> Result<()> {
-    let tw_client = TaskwarriorClient::new();
-    let h_client = HabiticaClient::new(config)?;
-    let resolver = ConflictResolver::new(config, &tw_client, &h_client);
+/// Layout of the progress bar once the number of steps is known
+const PROGRESS_TEMPLATE: &str = "{spinner} [{bar:30}] {pos}/{len} {wide_msg}";
 
-    println!("Syncing tasks between Taskwarrior and Habitica...\n");
+/// Which tasks a sync looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Linked tasks modified on neither side since the last sync are skipped
+    Incremental,
+    /// Every task is reconciled
+    Full,
+    /// Only unlinked Taskwarrior tasks are pushed
+    OnlyNew,
+}
 
-    // Get tasks from both sides
-    let tw_only = tw_client.get_pending_without_habitica()?;
-    let tw_synced = tw_client.get_tasks_with_habitica()?;
-    let h_tasks = h_client.get_all_tasks()?;
+/// How a sync treats tasks it would otherwise leave alone or copy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncOptions {
+    /// Uncheck dailies even under streak protection
+    pub force: bool,
+    /// Show tasks changed on both sides field by field and let the user pick
+    /// a side or skip them
+    pub interactive: bool,
+    /// Link unlinked tasks that already exist on both sides instead of
+    /// copying them
+    pub link_existing: bool,
+}
 
-    // Get current user stats
-    let mut current_stats = h_client.get_user_stats()?;
+/// How the sync command reports what it did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyncFormat {
+    /// Descriptions of each step for people
+    #[default]
+    Text,
+    /// One JSON object per line and step on stdout, then a summary or
+    /// error object
+    Json,
+}
 
-    // Handle tasks that only exist in Taskwarrior
-    for tw_task in tw_only {
-        println!("Task: {}", tw_task.description);
-        println!("    Status: Created in Taskwarrior.");
-        println!("    Action: Pushing to Habitica and updating Habitica ID in Taskwarrior.");
-        println!();
+/// Handle the 'sync' command
+pub fn handle_sync(
+    config: &Config,
+    mode: SyncMode,
+    options: SyncOptions,
+    format: SyncFormat,
+) -> Result<()> {
+    let notifier = Notifier::new(config)?;
+    let json = format == SyncFormat::Json;
+    output::reserve_stdout(json);
 
-        let mut stats_cache = Some(StatsCache::new(current_stats.clone()));
-        let updated_task = resolver.push_to_habitica(&tw_task, &mut stats_cache)?;
-        tw_client.import(&updated_task)?;
+    say!("{}\n", tr!("sync-start"));
 
-        if let Some(cache) = stats_cache {
-            if let Some(new_stats) = cache.current.clone() {
-                current_stats = new_stats;
+    let progress = progress_bar(config, options, format);
+    let result = run_sync(config, "sync", mode, options, &progress, &mut |event| {
+        if json {
+            if let Some(value) = event_json(config, event) {
+                output::emit(&value);
             }
-            for msg in cache.get_diff_messages() {
-                println!("    {}", msg);
+        } else {
+            print_event(config, event);
+        }
+        if let Some(notifier) = &notifier {
+            for msg in level_up_messages(event) {
+                notifier.send_or_warn(&Notification::LevelUp(msg));
             }
         }
+    });
+    progress.finish_and_clear();
+
+    match result {
+        Ok(summary) => {
+            if let Some(notifier) = &notifier {
+                notifier.send_or_warn(&Notification::SyncCompleted(&summary));
+            }
+            if json {
+                let mut value = json!({ "type": "summary" });
+                if let (Value::Object(fields), Ok(Value::Object(counts))) =
+                    (&mut value, serde_json::to_value(summary))
+                {
+                    fields.extend(counts);
+                }
+                output::emit(&value);
+            }
+            say!("{}", tr!("sync-complete"));
+            Ok(())
+        }
+        Err(err) => {
+            if let Some(notifier) = &notifier {
+                notifier.send_or_warn(&Notification::SyncFailed(&err.to_string()));
+            }
+            if json {
+                output::emit(&json!({ "type": "error", "message": err.to_string() }));
+            }
+            Err(err)
+        }
     }
+}
 
-    // Create maps for efficient lookup
-    let h_tasks_map: HashMap<Uuid, _> = h_tasks
-        .iter()
-        .filter_map(|t| t.id.map(|id| (id, t)))
-        .collect();
+/// Progress bar for a sync, hidden unless stdout is a terminal showing text
+///
+/// Syncs that may ask questions get no bar either, as it would be drawn over
+/// the prompts.
+fn progress_bar(config: &Config, options: SyncOptions, format: SyncFormat) -> ProgressBar {
+    let shown = format == SyncFormat::Text
+        && !options.interactive
+        && config.conflict_strategy != ConflictStrategy::Ask
+        && !output::is_plain()
+        && io::stdout().is_terminal();
+    if !shown {
+        return ProgressBar::hidden();
+    }
+
+    let progress = ProgressBar::new_spinner()
+        .with_message(tr!("sync-progress-planning"))
+        .with_finish(ProgressFinish::AndClear);
+    progress.enable_steady_tick(Duration::from_millis(100));
+    progress
+}
 
-    let tw_synced_map: HashMap<Uuid, _> = tw_synced
+/// Run a sync, reporting each step through `on_event` and `progress`
+///
+/// `source` names the command running it in the journal.
+pub(crate) fn run_sync(
+    config: &Config,
+    source: &'static str,
+    mode: SyncMode,
+    options: SyncOptions,
+    progress: &ProgressBar,
+    on_event: &mut dyn FnMut(&SyncEvent),
+) -> Result<SyncSummary> {
+    let started = Utc::now();
+    let since = match mode {
+        SyncMode::Incremental => watermark::load(&config.watermark_path())?,
+        SyncMode::Full | SyncMode::OnlyNew => None,
+    };
+
+    let tw_client = sync_backend(config)?;
+    let h_client = HabiticaClient::new(config)?;
+    let engine = SyncEngine::new(config, tw_client.as_ref(), &h_client)
+        .allow_streak_loss(options.force)
+        .interactive(options.interactive)
+        .link_existing(options.link_existing)
+        .changed_since(since)
+        .source(source);
+
+    if h_client.is_sleeping()? {
+        progress.suspend(|| say!("{}\n", inn_status(true)));
+    }
+
+    let resumed = engine.resume()?;
+    if resumed > 0 {
+        progress.suspend(|| say!("{}\n", tr!("sync-resumed", count = resumed)));
+    }
+
+    // Both lists come from one export, so they agree with each other
+    let snapshot = tw_client.snapshot()?;
+    let plan = if mode == SyncMode::OnlyNew {
+        engine.plan_new(snapshot)?
+    } else {
+        engine.plan(snapshot)?
+    };
+    if let Ok(style) = ProgressStyle::with_template(PROGRESS_TEMPLATE) {
+        progress.set_style(style.progress_chars("=> "));
+    }
+    progress.set_length(plan.operations.len() as u64);
+    let summary = engine.apply(plan, &mut |event| {
+        progress.inc(1);
+        progress.set_message(event_description(event).to_string());
+        progress.suspend(|| on_event(event));
+    })?;
+    progress.finish_and_clear();
+    if mode == SyncMode::OnlyNew {
+        return Ok(summary);
+    }
+    // Changes made while syncing are looked at again by the next sync, and
+    // so are skipped tasks, as long as the watermark stays put
+    if summary.skipped == 0 {
+        watermark::save(&config.watermark_path(), started)?;
+    }
+
+    let moved = mirror_urgency(config, tw_client.as_ref(), &h_client)?;
+    if moved > 0 {
+        say!("{}\n", tr!("sync-reordered", count = moved));
+    }
+
+    match post_party_milestone(config, tw_client.as_ref(), &h_client) {
+        Ok(Some(message)) => say!("{}\n", tr!("sync-party-posted", message = message)),
+        Ok(None) => {}
+        Err(err) => say_err!("{}", tr!("sync-party-failed", error = err.to_string())),
+    }
+
+    Ok(summary)
+}
+
+/// Level-up messages among the stat changes of a sync step
+pub(crate) fn level_up_messages<'e>(event: &'e SyncEvent) -> Vec<&'e str> {
+    let stat_messages = match event {
+        SyncEvent::Pushed { stat_messages, .. }
+        | SyncEvent::UpdatedHabitica { stat_messages, .. }
+        | SyncEvent::Merged { stat_messages, .. }
+        | SyncEvent::Combined { stat_messages, .. } => stat_messages,
+        _ => return Vec::new(),
+    };
+
+    let level_up = tr!("stats-level-up-label");
+    stat_messages
         .iter()
-        .filter_map(|t| t.habitica_uuid.map(|id| (id, t)))
-        .collect();
-
-    // Get all unique Habitica UUIDs
-    let mut all_h_uuids: Vec<Uuid> = h_tasks_map.keys().copied().collect();
-    all_h_uuids.extend(tw_synced_map.keys().copied());
-    all_h_uuids.sort();
-    all_h_uuids.dedup();
-
-    // Process each task
-    for h_uuid in all_h_uuids {
-        let h_task_opt = h_tasks_map.get(&h_uuid);
-        let tw_task_opt = tw_synced_map.get(&h_uuid);
-
-        match (h_task_opt, tw_task_opt) {
-            (Some(h_task), None) => {
-                // Task only exists on Habitica
-                println!("Task: {}", h_task.text);
-                println!("    Status: Created on Habitica.");
-                println!("    Action: Importing into Taskwarrior.");
-                println!();
-
-                let tw_task = resolver.pull_from_habitica(h_task, None)?;
-                tw_client.import(&tw_task)?;
+        .map(String::as_str)
+        .filter(|msg| msg.starts_with(&level_up))
+        .collect()
+}
+
+/// Description of the task a sync event is about
+fn event_description<'e>(event: &'e SyncEvent) -> &'e str {
+    match event {
+        SyncEvent::Imported { h_task } | SyncEvent::DeletedInTaskwarrior { h_task } => &h_task.text,
+        SyncEvent::Pushed { task, .. }
+        | SyncEvent::DeletedOnHabitica { task, .. }
+        | SyncEvent::Expired { task }
+        | SyncEvent::Filtered { task }
+        | SyncEvent::Superseded { task } => &task.description,
+        SyncEvent::Unchanged { tw_task, .. }
+        | SyncEvent::Skipped { tw_task, .. }
+        | SyncEvent::Deferred { tw_task, .. }
+        | SyncEvent::UpdatedTaskwarrior { tw_task, .. }
+        | SyncEvent::UpdatedHabitica { tw_task, .. }
+        | SyncEvent::Merged { tw_task, .. }
+        | SyncEvent::Combined { tw_task, .. } => &tw_task.description,
+    }
+}
+
+/// Print a human-readable description of a sync event
+pub(crate) fn print_event(config: &Config, event: &SyncEvent) {
+    match event {
+        SyncEvent::Pushed {
+            task,
+            stat_messages,
+        } => {
+            say!("{}", tr!("sync-task", text = task.description.as_str()));
+            say!("    {}", tr!("sync-status-created-taskwarrior"));
+            say!(
+                "    {}",
+                output::paint(&tr!("sync-action-push"), Tone::Created)
+            );
+            say!();
+            for msg in stat_messages {
+                say!("    {}", paint_stat(msg));
             }
+        }
 
-            (None, Some(tw_task)) => {
-                // Task was deleted on Habitica
-                println!("Task: {}", tw_task.description);
-                println!("    Status: Deleted on Habitica.");
-
-                if tw_task.status == TaskStatus::Completed {
-                    println!("    Action: Already completed in Taskwarrior. Leaving status as Completed. Unsetting Habitica ID.");
-                    let mut updated = (*tw_task).clone();
-                    updated.habitica_uuid = None;
-                    tw_client.import(&updated)?;
-                } else {
-                    println!("    Action: Setting status to Deleted in Taskwarrior. Unsetting Habitica ID.");
-                    let mut updated = (*tw_task).clone();
-                    updated.status = TaskStatus::Deleted;
-                    updated.habitica_uuid = None;
-                    tw_client.import(&updated)?;
-                }
-                println!();
+        SyncEvent::Imported { h_task } => {
+            say!("{}", tr!("sync-task", text = h_task.text.as_str()));
+            say!("    {}", tr!("sync-status-created-habitica"));
+            say!(
+                "    {}",
+                output::paint(&tr!("sync-action-import"), Tone::Created)
+            );
+            say!();
+        }
+
+        SyncEvent::DeletedOnHabitica {
+            task,
+            kept_completed,
+        } => {
+            say!("{}", tr!("sync-task", text = task.description.as_str()));
+            say!("    {}", tr!("sync-status-deleted-habitica"));
+            if *kept_completed {
+                say!(
+                    "    {}",
+                    output::paint(&tr!("sync-action-keep-completed"), Tone::Updated)
+                );
+            } else {
+                say!(
+                    "    {}",
+                    output::paint(&tr!("sync-action-mark-deleted"), Tone::Deleted)
+                );
             }
+            say!();
+        }
 
-            (Some(h_task), Some(tw_task)) => {
-                // Task exists on both sides
-                match resolver.resolve(tw_task, h_task) {
-                    ResolutionAction::NoChange => {
-                        if config.verbose {
-                            println!("Habitica Task:    {}", h_task.text);
-                            println!("Taskwarrior Task: {}", tw_task.description);
-                            println!("    Status: Exists on both Habitica and Taskwarrior.");
-                            println!("    Action: Tasks are equal. Doing nothing.");
-                            println!();
-                        }
-                    }
-
-                    ResolutionAction::UseHabitica => {
-                        println!("Habitica Task:    {}", h_task.text);
-                        println!("Taskwarrior Task: {}", tw_task.description);
-                        println!("    Status: Exists on both Habitica and Taskwarrior.");
-                        println!("    Action: Habitica task is most recently modified. Updating in Taskwarrior.");
-                        println!();
-
-                        let updated_tw = resolver.pull_from_habitica(h_task, Some(tw_task))?;
-                        tw_client.import(&updated_tw)?;
-                    }
-
-                    ResolutionAction::UseTaskwarrior => {
-                        println!("Habitica Task:    {}", h_task.text);
-                        println!("Taskwarrior Task: {}", tw_task.description);
-                        println!("    Status: Exists on both Habitica and Taskwarrior.");
-                        println!("    Action: Taskwarrior task is most recently modified. Updating on Habitica.");
-
-                        let mut stats_cache = Some(StatsCache::new(current_stats.clone()));
-                        let old_tw = resolver.pull_from_habitica(h_task, Some(tw_task))?;
-                        let updated_tw =
-                            resolver.modify_on_habitica(&old_tw, tw_task, &mut stats_cache)?;
-                        tw_client.import(&updated_tw)?;
-
-                        if let Some(cache) = stats_cache {
-                            if let Some(new_stats) = cache.current.clone() {
-                                current_stats = new_stats;
-                            }
-                            for msg in cache.get_diff_messages() {
-                                println!("    {}", msg);
-                            }
-                        }
-                        println!();
-                    }
-                }
+        SyncEvent::DeletedInTaskwarrior { h_task } => {
+            say!("{}", tr!("sync-task", text = h_task.text.as_str()));
+            say!("    {}", tr!("sync-status-deleted-taskwarrior"));
+            say!(
+                "    {}",
+                output::paint(&tr!("sync-action-delete-habitica"), Tone::Deleted)
+            );
+            say!();
+        }
+
+        SyncEvent::Expired { task } => {
+            say!("{}", tr!("sync-task", text = task.description.as_str()));
+            say!("    {}", tr!("sync-status-expired"));
+            say!(
+                "    {}",
+                output::paint(&tr!("sync-action-expire"), Tone::Deleted)
+            );
+            say!();
+        }
+
+        SyncEvent::Filtered { task } => {
+            say!("{}", tr!("sync-task", text = task.description.as_str()));
+            say!("    {}", tr!("sync-status-filtered"));
+            say!(
+                "    {}",
+                output::paint(&tr!("sync-action-remove"), Tone::Deleted)
+            );
+            say!();
+        }
+
+        SyncEvent::Superseded { task } => {
+            say!("{}", tr!("sync-task", text = task.description.as_str()));
+            say!("    {}", tr!("sync-status-superseded"));
+            say!(
+                "    {}",
+                output::paint(&tr!("sync-action-hand-on"), Tone::Updated)
+            );
+            say!();
+        }
+
+        SyncEvent::Unchanged { tw_task, h_task } => {
+            if config.verbose {
+                say!("{}", tr!("sync-habitica-task", text = h_task.text.as_str()));
+                say!(
+                    "{}",
+                    tr!("sync-taskwarrior-task", text = tw_task.description.as_str())
+                );
+                say!("    {}", tr!("sync-status-both"));
+                say!("    {}", tr!("sync-action-equal"));
+                say!();
             }
+        }
+
+        SyncEvent::Skipped { tw_task, h_task } => {
+            say!("{}", tr!("sync-habitica-task", text = h_task.text.as_str()));
+            say!(
+                "{}",
+                tr!("sync-taskwarrior-task", text = tw_task.description.as_str())
+            );
+            say!("    {}", tr!("sync-status-conflict"));
+            say!("    {}", tr!("sync-action-skip"));
+            say!();
+        }
 
-            (None, None) => {
-                // This shouldn't happen since we only iterate over keys that
-                // exist in at least one map, but we need to
-                // handle it for completeness
+        SyncEvent::Deferred { tw_task, h_task } => {
+            say!("{}", tr!("sync-habitica-task", text = h_task.text.as_str()));
+            say!(
+                "{}",
+                tr!("sync-taskwarrior-task", text = tw_task.description.as_str())
+            );
+            say!("    {}", tr!("sync-status-conflict"));
+            say!("    {}", tr!("sync-action-defer"));
+            say!();
+        }
+
+        SyncEvent::UpdatedTaskwarrior {
+            tw_task,
+            h_task,
+            changes,
+        } => {
+            say!("{}", tr!("sync-habitica-task", text = h_task.text.as_str()));
+            say!(
+                "{}",
+                tr!("sync-taskwarrior-task", text = tw_task.description.as_str())
+            );
+            say!("    {}", tr!("sync-status-both"));
+            say!(
+                "    {}",
+                output::paint(&tr!("sync-action-use-habitica"), Tone::Updated)
+            );
+            print_changes(changes);
+            print_challenge_note(h_task);
+            say!();
+        }
+
+        SyncEvent::UpdatedHabitica {
+            tw_task,
+            h_task,
+            changes,
+            stat_messages,
+        } => {
+            say!("{}", tr!("sync-habitica-task", text = h_task.text.as_str()));
+            say!(
+                "{}",
+                tr!("sync-taskwarrior-task", text = tw_task.description.as_str())
+            );
+            say!("    {}", tr!("sync-status-both"));
+            say!(
+                "    {}",
+                output::paint(&tr!("sync-action-use-taskwarrior"), Tone::Updated)
+            );
+            print_changes(changes);
+            print_challenge_note(h_task);
+            for msg in stat_messages {
+                say!("    {}", paint_stat(msg));
+            }
+            say!();
+        }
+
+        SyncEvent::Merged {
+            tw_task,
+            h_task,
+            stat_messages,
+        } => {
+            say!("{}", tr!("sync-habitica-task", text = h_task.text.as_str()));
+            say!(
+                "{}",
+                tr!("sync-taskwarrior-task", text = tw_task.description.as_str())
+            );
+            say!("    {}", tr!("sync-status-conflict"));
+            say!(
+                "    {}",
+                output::paint(&tr!("sync-action-merge"), Tone::Updated)
+            );
+            for msg in stat_messages {
+                say!("    {}", paint_stat(msg));
+            }
+            say!();
+        }
+
+        SyncEvent::Combined {
+            tw_task,
+            h_task,
+            stat_messages,
+        } => {
+            say!("{}", tr!("sync-habitica-task", text = h_task.text.as_str()));
+            say!(
+                "{}",
+                tr!("sync-taskwarrior-task", text = tw_task.description.as_str())
+            );
+            say!("    {}", tr!("sync-status-conflict"));
+            say!(
+                "    {}",
+                output::paint(&tr!("sync-action-combine"), Tone::Updated)
+            );
+            for msg in stat_messages {
+                say!("    {}", paint_stat(msg));
             }
+            say!();
         }
     }
+}
 
-    println!("Sync complete!");
-    Ok(())
+/// Describe a sync event as a JSON object, `None` for unchanged tasks unless
+/// verbose
+///
+/// Every object has a `type` and the `description` of the task. Events about
+/// a Taskwarrior task carry its `task` UUID, and `habitica_id` is set once
+/// the task is linked.
+pub(crate) fn event_json(config: &Config, event: &SyncEvent) -> Option<Value> {
+    let (kind, tw_task, h_task, stat_messages) = match event {
+        SyncEvent::Pushed {
+            task,
+            stat_messages,
+        } => ("pushed", Some(*task), None, Some(stat_messages)),
+        SyncEvent::Imported { h_task } => ("imported", None, Some(*h_task), None),
+        SyncEvent::DeletedOnHabitica {
+            task,
+            kept_completed,
+        } => {
+            let mut value = task_json("deleted-on-habitica", task, None);
+            value["kept_completed"] = json!(kept_completed);
+            return Some(value);
+        }
+        SyncEvent::DeletedInTaskwarrior { h_task } => {
+            ("deleted-in-taskwarrior", None, Some(*h_task), None)
+        }
+        SyncEvent::Expired { task } => ("expired", Some(*task), None, None),
+        SyncEvent::Filtered { task } => ("filtered", Some(*task), None, None),
+        SyncEvent::Superseded { task } => ("superseded", Some(*task), None, None),
+        SyncEvent::Unchanged { tw_task, h_task } => {
+            if !config.verbose {
+                return None;
+            }
+            ("unchanged", Some(*tw_task), Some(*h_task), None)
+        }
+        SyncEvent::Skipped { tw_task, h_task } => ("skipped", Some(*tw_task), Some(*h_task), None),
+        SyncEvent::Deferred { tw_task, h_task } => {
+            ("deferred", Some(*tw_task), Some(*h_task), None)
+        }
+        SyncEvent::UpdatedTaskwarrior {
+            tw_task, h_task, ..
+        } => ("updated-taskwarrior", Some(*tw_task), Some(*h_task), None),
+        SyncEvent::UpdatedHabitica {
+            tw_task,
+            h_task,
+            stat_messages,
+            ..
+        } => (
+            "updated-habitica",
+            Some(*tw_task),
+            Some(*h_task),
+            Some(stat_messages),
+        ),
+        SyncEvent::Merged {
+            tw_task,
+            h_task,
+            stat_messages,
+        } => ("merged", Some(*tw_task), Some(*h_task), Some(stat_messages)),
+        SyncEvent::Combined {
+            tw_task,
+            h_task,
+            stat_messages,
+        } => (
+            "combined",
+            Some(*tw_task),
+            Some(*h_task),
+            Some(stat_messages),
+        ),
+    };
+
+    let mut value = match (tw_task, h_task) {
+        (Some(tw_task), h_task) => task_json(kind, tw_task, h_task),
+        (None, Some(h_task)) => json!({
+            "type": kind,
+            "habitica_id": h_task.id,
+            "description": h_task.text,
+        }),
+        (None, None) => json!({ "type": kind }),
+    };
+    if let Some(stat_messages) = stat_messages {
+        value["stat_messages"] = json!(stat_messages);
+    }
+    if let SyncEvent::UpdatedTaskwarrior { changes, .. }
+    | SyncEvent::UpdatedHabitica { changes, .. } = event
+    {
+        value["changes"] = json!(changes);
+    }
+    Some(value)
+}
+
+/// JSON object for an event about a Taskwarrior task
+fn task_json(kind: &str, tw_task: &Task, h_task: Option<&HabiticaTask>) -> Value {
+    json!({
+        "type": kind,
+        "task": tw_task.uuid,
+        "habitica_id": tw_task.habitica_uuid.or_else(|| h_task.and_then(|h_task| h_task.id)),
+        "description": tw_task.description,
+    })
+}
+
+/// Color a stat change message if it's a gain
+pub(crate) fn paint_stat(message: &str) -> String {
+    if StatsCache::is_gain(message) {
+        output::paint(message, Tone::Gain)
+    } else {
+        message.to_string()
+    }
+}
+
+/// List the fields an update changed, with their old and new values
+fn print_changes(changes: &[FieldChange]) {
+    for change in changes {
+        say!("        {}: {} → {}", change.field, change.old, change.new);
+    }
+}
+
+/// Point out that a challenge task only takes its status from Taskwarrior
+fn print_challenge_note(h_task: &HabiticaTask) {
+    if let Some(challenge) = h_task.challenge_name() {
+        say!(
+            "    {}",
+            tr!("sync-note-challenge", challenge = challenge.as_str())
+        );
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
     #[test]
     fn test_sync_command_exists() {}
+
+    #[test]
+    fn test_event_json() {
+        let config = Config::for_tests();
+        let tw_task = Task {
+            uuid: Uuid::new_v4(),
+            description: "Water plants".to_string(),
+            ..Default::default()
+        };
+        let h_task = HabiticaTask {
+            id: Some(Uuid::new_v4()),
+            text: "Water plants".to_string(),
+            ..Default::default()
+        };
+
+        let linked = Task {
+            habitica_uuid: h_task.id,
+            ..tw_task.clone()
+        };
+        let pushed = SyncEvent::Pushed {
+            task: &linked,
+            stat_messages: vec!["Exp: +5".to_string()],
+        };
+        assert_eq!(
+            event_json(&config, &pushed),
+            Some(json!({
+                "type": "pushed",
+                "task": tw_task.uuid,
+                "habitica_id": h_task.id,
+                "description": "Water plants",
+                "stat_messages": ["Exp: +5"],
+            }))
+        );
+
+        let imported = SyncEvent::Imported { h_task: &h_task };
+        assert_eq!(
+            event_json(&config, &imported),
+            Some(json!({
+                "type": "imported",
+                "habitica_id": h_task.id,
+                "description": "Water plants",
+            }))
+        );
+
+        let skipped = SyncEvent::Skipped {
+            tw_task: &tw_task,
+            h_task: &h_task,
+        };
+        let value = event_json(&config, &skipped);
+        assert_eq!(
+            value.as_ref().map(|value| &value["habitica_id"]),
+            Some(&json!(h_task.id))
+        );
+
+        let unchanged = SyncEvent::Unchanged {
+            tw_task: &tw_task,
+            h_task: &h_task,
+        };
+        assert_eq!(event_json(&config, &unchanged), None);
+    }
 }
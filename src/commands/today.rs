@@ -0,0 +1,227 @@
+use std::{cmp::Reverse, collections::HashSet};
+
+use chrono::{DateTime, Local, Utc};
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::Result,
+    habitica::{HabiticaApi, HabiticaClient, HabiticaTask, HabiticaTaskType},
+    say,
+    sync::converter::difficulty_of,
+    taskwarrior::{backend::sync_backend, Task, TaskDifficulty},
+};
+
+/// Where an item of the overview exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Both,
+    TaskwarriorOnly,
+    HabiticaOnly,
+}
+
+/// One line of the overview
+#[derive(Debug, Clone, PartialEq)]
+struct TodayItem {
+    description: String,
+    difficulty: TaskDifficulty,
+    /// Due or scheduled time, `None` for dailies due some time today
+    when: Option<DateTime<Utc>>,
+    side: Side,
+}
+
+/// Handle the 'today' command
+///
+/// Lists Taskwarrior tasks due or scheduled today (or overdue) together with
+/// the Habitica dailies still due today, earliest first and harder tasks
+/// ahead of easier ones, flagging anything that is not linked to the other
+/// side.
+pub fn handle_today(config: &Config) -> Result<()> {
+    let tw_client = sync_backend(config)?;
+    let h_client = HabiticaClient::new(config)?;
+
+    let tw_tasks = tw_client.export(&[
+        "status:pending",
+        "(",
+        "due.before:tomorrow",
+        "or",
+        "scheduled.before:tomorrow",
+        ")",
+    ])?;
+    let linked: HashSet<Uuid> = tw_client
+        .export(&["status:pending", "habitica_uuid.any:"])?
+        .into_iter()
+        .filter_map(|task| task.habitica_uuid)
+        .collect();
+    let dailies = h_client.get_tasks(Some("dailys"))?;
+
+    let items = build_items(config, &tw_tasks, &linked, &dailies);
+    if items.is_empty() {
+        say!("Nothing due today.");
+        return Ok(());
+    }
+
+    say!("Due today:\n");
+    for item in &items {
+        say!("{}", render_item(item));
+    }
+    Ok(())
+}
+
+/// Merge both sides into one list, most pressing first
+fn build_items(
+    config: &Config,
+    tw_tasks: &[Task],
+    linked: &HashSet<Uuid>,
+    dailies: &[HabiticaTask],
+) -> Vec<TodayItem> {
+    let mut items: Vec<TodayItem> = tw_tasks
+        .iter()
+        .map(|task| TodayItem {
+            description: task.description.clone(),
+            difficulty: difficulty_of(config, task),
+            when: task.due.or_else(|| task.date_attribute("scheduled")),
+            side: if task.habitica_uuid.is_some() {
+                Side::Both
+            } else {
+                Side::TaskwarriorOnly
+            },
+        })
+        .collect();
+
+    // Linked dailies already showing up through Taskwarrior are not repeated
+    let listed: HashSet<Uuid> = tw_tasks.iter().filter_map(|t| t.habitica_uuid).collect();
+    items.extend(
+        dailies
+            .iter()
+            .filter(|daily| {
+                daily.task_type == HabiticaTaskType::Daily && daily.is_due && !daily.completed
+            })
+            .filter(|daily| daily.id.map_or(true, |id| !listed.contains(&id)))
+            .map(|daily| TodayItem {
+                description: daily.text.clone(),
+                difficulty: TaskDifficulty::from_habitica_priority(daily.priority),
+                when: None,
+                side: if daily.id.is_some_and(|id| linked.contains(&id)) {
+                    Side::Both
+                } else {
+                    Side::HabiticaOnly
+                },
+            }),
+    );
+
+    // Timed items first by time, then the rest of the day, hardest first
+    items.sort_by_key(|item| (item.when.is_none(), item.when, Reverse(item.difficulty)));
+    items
+}
+
+fn render_item(item: &TodayItem) -> String {
+    let when = item.when.map_or_else(
+        || "today".to_string(),
+        |when| {
+            let local = when.with_timezone(&Local);
+            if local.date_naive() < Local::now().date_naive() {
+                "overdue".to_string()
+            } else {
+                local.format("%H:%M").to_string()
+            }
+        },
+    );
+    let flag = match item.side {
+        Side::Both => "",
+        Side::TaskwarriorOnly => "  (Taskwarrior only)",
+        Side::HabiticaOnly => "  (Habitica only)",
+    };
+    format!(
+        "  {:<7} {:<7} {}{}",
+        when,
+        difficulty_label(item.difficulty),
+        item.description,
+        flag
+    )
+}
+
+const fn difficulty_label(difficulty: TaskDifficulty) -> &'static str {
+    match difficulty {
+        TaskDifficulty::Trivial => "trivial",
+        TaskDifficulty::Easy => "easy",
+        TaskDifficulty::Medium => "medium",
+        TaskDifficulty::Hard => "hard",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn daily(text: &str, priority: f64) -> HabiticaTask {
+        HabiticaTask {
+            id: Some(Uuid::new_v4()),
+            text: text.to_string(),
+            task_type: HabiticaTaskType::Daily,
+            priority,
+            is_due: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build_items() {
+        let config = Config::for_tests();
+        let now = Utc::now();
+
+        let linked_daily = daily("Stretch", 1.0);
+        let lone_daily = daily("Water plants", 2.0);
+        let mut done_daily = daily("Meditate", 1.0);
+        done_daily.completed = true;
+
+        let local_task = Task {
+            description: "Call bank".to_string(),
+            due: Some(now + Duration::hours(2)),
+            ..Default::default()
+        };
+        let synced_task = Task {
+            description: "Stretch".to_string(),
+            due: Some(now + Duration::hours(1)),
+            habitica_uuid: linked_daily.id,
+            ..Default::default()
+        };
+
+        let linked = linked_daily.id.into_iter().collect();
+        let items = build_items(
+            &config,
+            &[local_task, synced_task],
+            &linked,
+            &[linked_daily, lone_daily, done_daily],
+        );
+
+        let summary: Vec<(&str, Side)> = items
+            .iter()
+            .map(|item| (item.description.as_str(), item.side))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Stretch", Side::Both),
+                ("Call bank", Side::TaskwarriorOnly),
+                ("Water plants", Side::HabiticaOnly),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_item() {
+        let item = TodayItem {
+            description: "Water plants".to_string(),
+            difficulty: TaskDifficulty::Hard,
+            when: None,
+            side: Side::HabiticaOnly,
+        };
+        assert_eq!(
+            render_item(&item),
+            "  today   hard    Water plants  (Habitica only)"
+        );
+    }
+}
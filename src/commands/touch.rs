@@ -0,0 +1,141 @@
+use crate::{
+    config::Config,
+    error::Result,
+    habitica::HabiticaClient,
+    say,
+    sync::{SyncEngine, SyncEvent},
+    taskwarrior::backend::sync_backend,
+};
+
+/// Handle the 'touch' command
+pub fn handle_touch(config: &Config, id: &str) -> Result<()> {
+    let tw_client = sync_backend(config)?;
+    let h_client = HabiticaClient::new(config)?;
+    let engine = SyncEngine::new(config, tw_client.as_ref(), &h_client).source("touch");
+    engine.resume()?;
+
+    let task = tw_client.get_task(id)?;
+    let description = task.description.clone();
+
+    let plan = engine.plan_task(task)?;
+    if plan.operations.is_empty() {
+        say!("Nothing to sync: {}", description);
+        return Ok(());
+    }
+
+    engine.apply(plan, &mut |event| say!("{}", summarize_event(event)))?;
+    Ok(())
+}
+
+/// Describe a sync event in a single line
+pub(crate) fn summarize_event(event: &SyncEvent) -> String {
+    let (line, stat_messages) = match event {
+        SyncEvent::Pushed {
+            task,
+            stat_messages,
+        } => (
+            format!("Pushed to Habitica: {}", task.description),
+            stat_messages.as_slice(),
+        ),
+        SyncEvent::Imported { h_task } => {
+            (format!("Imported from Habitica: {}", h_task.text), &[][..])
+        }
+        SyncEvent::DeletedOnHabitica { task, .. } => (
+            format!("Deleted on Habitica, unlinked: {}", task.description),
+            &[][..],
+        ),
+        SyncEvent::DeletedInTaskwarrior { h_task } => (
+            format!(
+                "Deleted in Taskwarrior, deleted on Habitica: {}",
+                h_task.text
+            ),
+            &[][..],
+        ),
+        SyncEvent::Expired { task } => (
+            format!("Expired, deleted on Habitica: {}", task.description),
+            &[][..],
+        ),
+        SyncEvent::Filtered { task } => (
+            format!(
+                "Below the filter, deleted on Habitica: {}",
+                task.description
+            ),
+            &[][..],
+        ),
+        SyncEvent::Superseded { task } => (
+            format!(
+                "Replaced by a newer instance, unlinked: {}",
+                task.description
+            ),
+            &[][..],
+        ),
+        SyncEvent::Unchanged { tw_task, .. } => {
+            (format!("Already in sync: {}", tw_task.description), &[][..])
+        }
+        SyncEvent::Skipped { tw_task, .. } => (
+            format!("Skipped, changed on both sides: {}", tw_task.description),
+            &[][..],
+        ),
+        SyncEvent::Deferred { tw_task, .. } => (
+            format!("Deferred, changed on both sides: {}", tw_task.description),
+            &[][..],
+        ),
+        SyncEvent::UpdatedTaskwarrior { tw_task, .. } => (
+            format!("Updated from Habitica: {}", tw_task.description),
+            &[][..],
+        ),
+        SyncEvent::UpdatedHabitica {
+            tw_task,
+            stat_messages,
+            ..
+        } => (
+            format!("Updated on Habitica: {}", tw_task.description),
+            stat_messages.as_slice(),
+        ),
+        SyncEvent::Merged {
+            tw_task,
+            stat_messages,
+            ..
+        } => (
+            format!("Merged both sides: {}", tw_task.description),
+            stat_messages.as_slice(),
+        ),
+        SyncEvent::Combined {
+            tw_task,
+            stat_messages,
+            ..
+        } => (
+            format!("Combined both sides: {}", tw_task.description),
+            stat_messages.as_slice(),
+        ),
+    };
+
+    if stat_messages.is_empty() {
+        line
+    } else {
+        format!("{} ({})", line, stat_messages.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::taskwarrior::Task;
+
+    #[test]
+    fn test_summarize_event_is_one_line() {
+        let task = Task {
+            description: "Write report".to_string(),
+            ..Default::default()
+        };
+        let event = SyncEvent::Pushed {
+            task: &task,
+            stat_messages: vec!["Exp: +10 (20/150)".to_string(), "Gold: +1 (5)".to_string()],
+        };
+
+        assert_eq!(
+            summarize_event(&event),
+            "Pushed to Habitica: Write report (Exp: +10 (20/150), Gold: +1 (5))"
+        );
+    }
+}
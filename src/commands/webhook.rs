@@ -0,0 +1,98 @@
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    habitica::{HabiticaApi, HabiticaClient, Webhook},
+    say,
+};
+
+/// Handle the 'webhook add' command
+///
+/// Registers a `taskActivity` webhook sent whenever a task is created,
+/// updated, deleted or scored.
+pub fn handle_webhook_add(config: &Config, url: &str, label: &str) -> Result<()> {
+    check_url(url)?;
+    let h_client = HabiticaClient::new(config)?;
+
+    let webhook = h_client.add_webhook(&Webhook::task_activity(url, label))?;
+    say!("Added webhook:\n");
+    say!("{}", render_webhook(&webhook));
+    Ok(())
+}
+
+/// Handle the 'webhook list' command
+pub fn handle_webhook_list(config: &Config) -> Result<()> {
+    let h_client = HabiticaClient::new(config)?;
+
+    let webhooks = h_client.get_webhooks()?;
+    if webhooks.is_empty() {
+        say!("No webhooks registered.");
+        return Ok(());
+    }
+    for webhook in &webhooks {
+        say!("{}", render_webhook(webhook));
+    }
+    Ok(())
+}
+
+/// Handle the 'webhook remove' command
+pub fn handle_webhook_remove(config: &Config, id: Uuid) -> Result<()> {
+    let h_client = HabiticaClient::new(config)?;
+
+    h_client.delete_webhook(id)?;
+    say!("Removed webhook {}", id);
+    Ok(())
+}
+
+/// Reject anything Habitica couldn't post to
+fn check_url(url: &str) -> Result<()> {
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|e| Error::custom(format!("Invalid webhook URL {}: {}", url, e)))?;
+    if matches!(parsed.scheme(), "http" | "https") {
+        Ok(())
+    } else {
+        Err(Error::custom(format!(
+            "Invalid webhook URL {}: expected an http or https URL",
+            url
+        )))
+    }
+}
+
+fn render_webhook(webhook: &Webhook) -> String {
+    let id = webhook.id.map_or_else(String::new, |id| id.to_string());
+    let state = if webhook.enabled { "on" } else { "off" };
+    let label = if webhook.label.is_empty() {
+        String::new()
+    } else {
+        format!("  ({})", webhook.label)
+    };
+    format!(
+        "  {:<36}  {:<3}  {:<17}  {}{}",
+        id, state, webhook.webhook_type, webhook.url, label
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_url() {
+        assert!(check_url("https://example.org/habitica").is_ok());
+        assert!(check_url("example.org/habitica").is_err());
+        assert!(check_url("ftp://example.org").is_err());
+    }
+
+    #[test]
+    fn test_render_webhook() {
+        let webhook = Webhook {
+            id: Some(Uuid::nil()),
+            ..Webhook::task_activity("https://example.org/hook", "sync")
+        };
+        assert_eq!(
+            render_webhook(&webhook),
+            "  00000000-0000-0000-0000-000000000000  on   taskActivity       https://example.org/hook  (sync)"
+        );
+    }
+}
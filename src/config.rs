@@ -1,17 +1,408 @@
-use std::{env, path::PathBuf, process::Command};
+use std::{collections::HashMap, env, path::PathBuf, process::Command, str::FromStr};
 
-use crate::error::{Error, Result};
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::{
+    error::{Error, Result},
+    habitica::client::DEFAULT_BASE_URL,
+    sync::sanitize::{SanitizeMode, SanitizeOptions},
+    taskwarrior::{Task, TaskDifficulty},
+};
+
+/// Parse an age like `90d`, `12w` or `36h`
+///
+/// The count has to be positive and small enough for a `Duration`.
+pub fn parse_age(age: &str) -> Result<Duration> {
+    let age = age.trim();
+    let invalid = || {
+        Error::custom(format!(
+            "Invalid age: {} (expected a positive number followed by h, d or w, e.g. 90d)",
+            age
+        ))
+    };
+
+    let split = age.len().saturating_sub(1);
+    let (count, unit) = (age.get(..split), age.get(split..));
+    let count: i64 = count
+        .and_then(|n| n.parse().ok())
+        .filter(|count| *count > 0)
+        .ok_or_else(invalid)?;
+
+    let duration = match unit {
+        Some("h") => Duration::try_hours(count),
+        Some("d") => Duration::try_days(count),
+        Some("w") => Duration::try_weeks(count),
+        _ => None,
+    };
+    duration.ok_or_else(invalid)
+}
+
+/// Parse an age like [`parse_age`] and go back that far from `now`
+pub fn age_cutoff(age: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    now.checked_sub_signed(parse_age(age)?)
+        .ok_or_else(|| Error::custom(format!("Invalid age: {} reaches too far back", age.trim())))
+}
+
+/// Validate a Habitica API URL like `https://habitica.example.org/api`
+///
+/// Only http and https are accepted. A trailing slash is dropped, since
+/// request paths are appended to the URL.
+pub fn parse_api_url(url: &str) -> Result<String> {
+    let url = url.trim();
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|e| Error::config(format!("Invalid habitica.api_url {}: {}", url, e)))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(Error::config(format!(
+            "Invalid habitica.api_url {}: expected an http or https URL",
+            url
+        )));
+    }
+    Ok(url.trim_end_matches('/').to_string())
+}
+
+/// Split a comma-separated list of Taskwarrior filter terms
+fn filter_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|term| !term.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Filter matching tasks that match any `include` term and no `exclude` term
+fn scope_filter(include: &[String], exclude: &[String]) -> Option<String> {
+    let mut terms = Vec::new();
+    if !include.is_empty() {
+        let any: Vec<String> = include.iter().map(|term| format!("({})", term)).collect();
+        terms.push(format!("({})", any.join(" or ")));
+    }
+    terms.extend(exclude.iter().map(|term| format!("!({})", term)));
+    (!terms.is_empty()).then(|| terms.join(" and "))
+}
+
+/// How tasks created by bugwarrior take part in the sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BugwarriorMode {
+    /// Sync them like any other task
+    #[default]
+    Sync,
+    /// Keep them out of Habitica
+    Skip,
+    /// Sync them and append the issue URL to the Habitica notes
+    Link,
+}
+
+impl FromStr for BugwarriorMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "sync" => Ok(BugwarriorMode::Sync),
+            "skip" => Ok(BugwarriorMode::Skip),
+            "link" => Ok(BugwarriorMode::Link),
+            other => Err(Error::config(format!(
+                "Invalid habitica.bugwarrior value: {} (expected sync, skip or link)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Taskwarrior date that mirrors the first Habitica reminder of a task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReminderMapping {
+    /// The `scheduled` date
+    #[default]
+    Scheduled,
+    /// The `wait` date, hiding the task until the reminder
+    Wait,
+    /// Reminders are left alone
+    Off,
+}
+
+impl ReminderMapping {
+    /// Taskwarrior attribute holding the reminder, `None` when off
+    pub const fn attribute(self) -> Option<&'static str> {
+        match self {
+            ReminderMapping::Scheduled => Some("scheduled"),
+            ReminderMapping::Wait => Some("wait"),
+            ReminderMapping::Off => None,
+        }
+    }
+}
+
+impl FromStr for ReminderMapping {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "scheduled" => Ok(ReminderMapping::Scheduled),
+            "wait" => Ok(ReminderMapping::Wait),
+            "off" | "none" => Ok(ReminderMapping::Off),
+            other => Err(Error::config(format!(
+                "Invalid habitica.reminders value: {} (expected scheduled, wait or off)",
+                other
+            ))),
+        }
+    }
+}
+
+/// How conflicting edits on both sides are resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictStrategy {
+    /// The most recently modified side wins
+    #[default]
+    Newest,
+    /// Taskwarrior's changes win
+    TaskwarriorWins,
+    /// Habitica's changes win
+    HabiticaWins,
+    /// The user picks a side on the terminal
+    Ask,
+    /// Conflicts are handed to `habitica.conflict.merge_command`
+    External,
+    /// Conflicts are left alone and queued for `resolve`
+    Defer,
+}
+
+impl FromStr for ConflictStrategy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "newest" => Ok(ConflictStrategy::Newest),
+            "taskwarrior-wins" => Ok(ConflictStrategy::TaskwarriorWins),
+            "habitica-wins" => Ok(ConflictStrategy::HabiticaWins),
+            "ask" => Ok(ConflictStrategy::Ask),
+            "external" => Ok(ConflictStrategy::External),
+            "defer" => Ok(ConflictStrategy::Defer),
+            other => Err(Error::config(format!(
+                "Invalid habitica.conflict.strategy value: {} (expected newest, \
+                 taskwarrior-wins, habitica-wins, ask, external or defer)",
+                other
+            ))),
+        }
+    }
+}
+
+/// When to color messages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color when stdout is a terminal and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            other => Err(Error::config(format!(
+                "Invalid habitica.color value: {} (expected auto, always or never)",
+                other
+            ))),
+        }
+    }
+}
+
+/// How report emails are delivered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmailTransport {
+    /// Pipe the message to a sendmail-compatible command
+    Sendmail(String),
+    /// Submit the message to an SMTP server over STARTTLS
+    Smtp {
+        host: String,
+        port: u16,
+        user: Option<String>,
+        password: Option<String>,
+    },
+}
+
+/// Where and how `report --email` sends the digest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailConfig {
+    pub to: String,
+    pub from: String,
+    pub transport: EmailTransport,
+}
+
+/// All Taskwarrior settings, read with a single `task _show`
+#[derive(Debug, Default)]
+struct TaskrcValues {
+    values: HashMap<String, String>,
+}
+
+impl TaskrcValues {
+    /// Read the effective configuration from Taskwarrior
+    fn load() -> Result<Self> {
+        let output = Command::new("task")
+            .args(["rc.hooks=off", "_show"])
+            .output()
+            .map_err(|e| Error::config(format!("Failed to run task command: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(Error::config("Failed to read Taskwarrior configuration"));
+        }
+
+        Ok(Self::parse(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    /// Parse `key=value` lines
+    fn parse(output: &str) -> Self {
+        let values = output
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect();
+        TaskrcValues { values }
+    }
+
+    /// Get a value, empty if unset
+    fn get(&self, key: &str) -> String {
+        self.values.get(key).cloned().unwrap_or_default()
+    }
+
+    /// Get a value, `None` if unset or empty
+    fn get_opt(&self, key: &str) -> Option<String> {
+        self.values.get(key).filter(|v| !v.is_empty()).cloned()
+    }
+
+    /// Get a value with a default fallback
+    fn get_or(&self, key: &str, default: &str) -> String {
+        self.get_opt(key).unwrap_or_else(|| default.to_string())
+    }
+
+    /// Iterate over non-empty values whose key starts with `prefix`, prefix
+    /// removed
+    fn with_prefix<'s>(&'s self, prefix: &'s str) -> impl Iterator<Item = (&'s str, &'s str)> {
+        self.values.iter().filter_map(move |(key, value)| {
+            let rest = key.strip_prefix(prefix)?;
+            (!value.is_empty()).then_some((rest, value.as_str()))
+        })
+    }
+
+    /// Parse a value, falling back to the default when unset
+    fn get_parsed<T: FromStr>(&self, key: &str, default: T) -> Result<T> {
+        self.get_opt(key).map_or(Ok(default), |value| {
+            value
+                .parse()
+                .map_err(|_| Error::config(format!("Invalid value for {}: {}", key, value)))
+        })
+    }
+
+    /// Parse a Taskwarrior-style boolean, falling back to the default when
+    /// unset
+    fn get_bool(&self, key: &str, default: bool) -> Result<bool> {
+        self.get_opt(key)
+            .map_or(Ok(default), |value| match value.to_lowercase().as_str() {
+                "yes" | "on" | "true" | "1" | "y" => Ok(true),
+                "no" | "off" | "false" | "0" | "n" => Ok(false),
+                _ => Err(Error::config(format!(
+                    "Invalid value for {}: {}",
+                    key, value
+                ))),
+            })
+    }
+}
 
 /// Configuration loaded from .taskrc and environment
 #[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Config {
     pub habitica_user_id: String,
     pub habitica_api_key: String,
+    /// Habitica API root, for self-hosted servers
+    pub api_url: String,
     pub task_note_dir: PathBuf,
     pub task_note_prefix: String,
     pub task_note_extension: String,
     pub data_location: PathBuf,
     pub verbose: bool,
+    /// Spool hook changes and push them from the exit hook
+    pub queue_hooks: bool,
+    /// Habit (name or ID) scored for time tracked with Timewarrior
+    pub timew_habit: Option<String>,
+    /// Tracked minutes per habit score
+    pub timew_minutes_per_score: u32,
+    /// Habit (name or ID) scored when a linked task is started
+    pub start_habit: Option<String>,
+    /// Also score the start habit when a started task is stopped
+    pub start_habit_on_stop: bool,
+    /// Tags whose completed tasks also score a habit of the same name
+    pub habit_tags: Vec<String>,
+    /// Difficulty of tasks with these tags and no `habitica_difficulty`
+    pub difficulty_tags: HashMap<String, TaskDifficulty>,
+    /// Habit (name or ID) scored for each finished pomodoro
+    pub pomodoro_habit: String,
+    /// Default pomodoro length in minutes
+    pub pomodoro_minutes: u32,
+    /// Handling of bugwarrior-managed tasks
+    pub bugwarrior: BugwarriorMode,
+    /// How conflicting edits are resolved
+    pub conflict_strategy: ConflictStrategy,
+    /// Merge tool run for conflicts under the external strategy
+    pub merge_command: Option<String>,
+    /// ntfy topic URL receiving sync notifications
+    pub notify_ntfy_url: Option<String>,
+    /// Webhook URL receiving sync notifications as JSON
+    pub notify_webhook_url: Option<String>,
+    /// Delivery settings for emailed reports
+    pub report_email: Option<EmailConfig>,
+    /// Completed tasks per day that trigger a party chat post
+    pub party_milestone: Option<u32>,
+    /// Taskwarrior projects shared through a Habitica group plan
+    pub group_plans: HashMap<String, Uuid>,
+    /// Output language, overriding the locale environment variables
+    pub locale: Option<String>,
+    /// Cleanup of Habitica markup in imported descriptions
+    pub sanitize: SanitizeOptions,
+    /// Ask before unchecking a daily, which resets its streak
+    pub streak_protection: bool,
+    /// Delete the note files of archived tasks
+    pub archive_delete_notes: bool,
+    /// Tasks completed longer than this before being linked earn no XP
+    pub link_score_max_age: Option<Duration>,
+    /// How long hooks reuse fetched user stats
+    pub stats_ttl: Duration,
+    /// Habitica-generated and challenge tasks imported anyway: `habitica`,
+    /// `challenge` or challenge IDs
+    pub import_allow: Vec<String>,
+    /// Taskwarrior date mirroring Habitica reminders
+    pub reminders: ReminderMapping,
+    /// Import group tasks of groups without a shared project
+    pub sync_group_tasks: bool,
+    /// Order Habitica todos by Taskwarrior urgency after each sync
+    pub sort_todos: bool,
+    /// Keep unlinked waiting tasks off Habitica until their wait date passes
+    pub hold_waiting: bool,
+    /// Tag keeping a task off Habitica, `None` when disabled
+    pub exclude_tag: Option<String>,
+    /// Taskwarrior filter pending tasks must match to be on Habitica
+    pub push_filter: Option<String>,
+    /// Taskwarrior filter tasks must match to take part in syncs at all,
+    /// built from `habitica.sync.include` and `habitica.sync.exclude`
+    pub sync_scope: Option<String>,
+    /// Project given to tasks imported from Habitica
+    pub import_project: Option<String>,
+    /// Threads sending a sync's Habitica writes
+    pub sync_workers: usize,
+    /// Read sync exports from `taskchampion.sqlite3` instead of `task export`
+    pub read_taskchampion: bool,
+    /// File every message is also written to, with timestamps
+    pub log_file: Option<PathBuf>,
+    /// Token RPC requests over TCP must carry
+    pub rpc_token: Option<String>,
+    /// When to color output without `--color`
+    pub color: ColorChoice,
 }
 
 impl Config {
@@ -26,48 +417,356 @@ impl Config {
         let version_str = String::from_utf8_lossy(&version_output.stdout);
         Self::check_version(&version_str)?;
 
+        // Read all Taskwarrior settings in one go
+        let taskrc = TaskrcValues::load()?;
+
         // Read Habitica credentials (env vars take precedence over .taskrc)
         let habitica_user_id =
-            Self::get_habitica_credential("HABITICA_USER_ID", "rc.habitica.user_id")?;
+            Self::get_habitica_credential(&taskrc, "HABITICA_USER_ID", "habitica.user_id");
         let habitica_api_key =
-            Self::get_habitica_credential("HABITICA_API_KEY", "rc.habitica.api_key")?;
+            Self::get_habitica_credential(&taskrc, "HABITICA_API_KEY", "habitica.api_key");
 
         // Validate credentials are present
         if habitica_user_id.is_empty() || habitica_api_key.is_empty() {
             return Err(Error::InvalidHabiticaCredentials);
         }
 
+        // Self-hosted servers replace the public API
+        let api_url =
+            Self::get_habitica_credential(&taskrc, "HABITICA_API_URL", "habitica.api_url");
+        let api_url = if api_url.is_empty() {
+            DEFAULT_BASE_URL.to_string()
+        } else {
+            parse_api_url(&api_url)?
+        };
+
         // Read task note configuration
-        let task_note_location =
-            Self::get_taskrc_value_or_default("rc.tasknote.location", "~/.task/notes/")?;
+        let task_note_location = taskrc.get_or("tasknote.location", "~/.task/notes/");
         let task_note_dir = Self::expand_path(&task_note_location)?;
 
-        let task_note_prefix =
-            Self::get_taskrc_value_or_default("rc.tasknote.prefix", "[tasknote]")?;
+        let task_note_prefix = taskrc.get_or("tasknote.prefix", "[tasknote]");
 
-        let task_note_extension =
-            Self::get_taskrc_value_or_default("rc.tasknote.extension", ".txt")?;
+        let task_note_extension = taskrc.get_or("tasknote.extension", ".txt");
 
         // Get data directory
-        let data_location_str = Self::get_taskrc_value("rc.data.location")?;
-        let data_location = Self::expand_path(&data_location_str)?;
+        let data_location = Self::expand_path(&taskrc.get_or("data.location", "~/.task"))?;
+
+        // Hooks spooling their changes for `flush`
+        let queue_hooks = taskrc.get_bool("habitica.hooks.queue", false)?;
+
+        // Timewarrior integration
+        let timew_habit = taskrc.get_opt("habitica.timew.habit");
+        let timew_minutes_per_score = taskrc.get_parsed("habitica.timew.minutes_per_score", 25)?;
+
+        // Habit scored by `task start`
+        let start_habit = taskrc.get_opt("habitica.start.habit");
+        let start_habit_on_stop = taskrc.get_bool("habitica.start.on_stop", false)?;
+
+        // Tags scoring habits on completion
+        let habit_tags = taskrc
+            .get("habitica.habit_tags")
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(String::from)
+            .collect();
+
+        // Pomodoro timer
+        let pomodoro_habit = taskrc.get_or("habitica.pomodoro.habit", "Pomodoro");
+        let pomodoro_minutes = taskrc.get_parsed("habitica.pomodoro.minutes", 25)?;
+
+        // Bugwarrior coexistence
+        let bugwarrior = taskrc.get_parsed("habitica.bugwarrior", BugwarriorMode::Sync)?;
+
+        // Conflict resolution
+        let conflict_strategy =
+            taskrc.get_parsed("habitica.conflict.strategy", ConflictStrategy::Newest)?;
+        let merge_command = taskrc.get_opt("habitica.conflict.merge_command");
+        if conflict_strategy == ConflictStrategy::External && merge_command.is_none() {
+            return Err(Error::config(
+                "habitica.conflict.strategy=external requires habitica.conflict.merge_command",
+            ));
+        }
+
+        // Notification targets
+        let notify_ntfy_url = taskrc.get_opt("habitica.notify.ntfy");
+        let notify_webhook_url = taskrc.get_opt("habitica.notify.webhook");
+
+        // Report email delivery
+        let report_email = Self::load_email_config(&taskrc)?;
+
+        // Difficulty implied by tags
+        let difficulty_tags = taskrc
+            .with_prefix("habitica.difficulty.")
+            .map(|(tag, difficulty)| {
+                let difficulty = difficulty.parse().map_err(|_| {
+                    Error::config(format!(
+                        "Invalid difficulty for tag {}: {}",
+                        tag, difficulty
+                    ))
+                })?;
+                Ok((tag.to_string(), difficulty))
+            })
+            .collect::<Result<_>>()?;
+
+        // Projects shared through group plans
+        let group_plans = taskrc
+            .with_prefix("habitica.group.")
+            .map(|(project, id)| {
+                let id = id.parse().map_err(|_| {
+                    Error::config(format!("Invalid group ID for project {}: {}", project, id))
+                })?;
+                Ok((project.to_string(), id))
+            })
+            .collect::<Result<_>>()?;
+
+        // Party chat milestone
+        let party_milestone = taskrc
+            .get_parsed("habitica.party.milestone", 0)
+            .map(|n: u32| Some(n).filter(|n| *n > 0))?;
+
+        let locale = taskrc.get_opt("habitica.locale");
+
+        // Markup cleanup for imported text
+        let sanitize = SanitizeOptions {
+            emoji: taskrc.get_parsed("habitica.sanitize.emoji", SanitizeMode::Keep)?,
+            links: taskrc.get_parsed("habitica.sanitize.links", SanitizeMode::Keep)?,
+            entities: taskrc.get_parsed("habitica.sanitize.entities", SanitizeMode::Keep)?,
+        };
+
+        let streak_protection = taskrc.get_bool("habitica.streak.protect", false)?;
+        let archive_delete_notes = taskrc.get_bool("habitica.archive.delete_notes", false)?;
+        let link_score_max_age = taskrc
+            .get_opt("habitica.link.score_max_age")
+            .map(|age| parse_age(&age))
+            .transpose()?;
+        let stats_ttl = Duration::seconds(taskrc.get_parsed("habitica.stats.ttl", 60)?);
+        let import_allow = taskrc
+            .get("habitica.import.allow")
+            .split(',')
+            .map(|entry| entry.trim().to_lowercase())
+            .filter(|entry| !entry.is_empty())
+            .collect();
+        let reminders = taskrc.get_parsed("habitica.reminders", ReminderMapping::Scheduled)?;
+        let sync_group_tasks = taskrc.get_bool("habitica.sync_group_tasks", true)?;
+        let sort_todos = taskrc.get_bool("habitica.sort_todos", false)?;
+        let hold_waiting = taskrc.get_bool("habitica.hold_waiting", false)?;
+        // An empty value turns the exclusion tag off
+        let exclude_tag = taskrc
+            .values
+            .get("habitica.exclude_tag")
+            .map_or("nohabitica", String::as_str)
+            .trim_start_matches('+');
+        let exclude_tag = (!exclude_tag.is_empty()).then(|| exclude_tag.to_string());
+        let push_filter = taskrc.get_opt("habitica.filter");
+        let sync_scope = scope_filter(
+            &filter_list(&taskrc.get("habitica.sync.include")),
+            &filter_list(&taskrc.get("habitica.sync.exclude")),
+        );
+        let import_project = taskrc.get_opt("habitica.sync.project");
+        let rpc_token = taskrc.get_opt("habitica.rpc.token");
+        let sync_workers = taskrc.get_parsed("habitica.sync.workers", 4_usize)?.max(1);
+        let read_taskchampion = taskrc.get_bool("habitica.taskchampion", false)?;
+        let log_file = taskrc
+            .get_opt("habitica.log.file")
+            .map(|path| Self::expand_path(&path))
+            .transpose()?;
+        let color = taskrc.get_parsed("habitica.color", ColorChoice::Auto)?;
 
         Ok(Config {
             habitica_user_id,
             habitica_api_key,
+            api_url,
             task_note_dir,
             task_note_prefix,
             task_note_extension,
             data_location,
             verbose,
+            queue_hooks,
+            timew_habit,
+            timew_minutes_per_score,
+            start_habit,
+            start_habit_on_stop,
+            habit_tags,
+            difficulty_tags,
+            pomodoro_habit,
+            pomodoro_minutes,
+            bugwarrior,
+            conflict_strategy,
+            merge_command,
+            notify_ntfy_url,
+            notify_webhook_url,
+            report_email,
+            party_milestone,
+            group_plans,
+            locale,
+            sanitize,
+            streak_protection,
+            archive_delete_notes,
+            link_score_max_age,
+            stats_ttl,
+            import_allow,
+            reminders,
+            sync_group_tasks,
+            sort_todos,
+            hold_waiting,
+            exclude_tag,
+            push_filter,
+            sync_scope,
+            import_project,
+            sync_workers,
+            read_taskchampion,
+            log_file,
+            rpc_token,
+            color,
         })
     }
 
+    /// Read report email settings, `None` if no recipient is configured
+    fn load_email_config(taskrc: &TaskrcValues) -> Result<Option<EmailConfig>> {
+        let Some(to) = taskrc.get_opt("habitica.report.email") else {
+            return Ok(None);
+        };
+        let from = taskrc.get_or("habitica.report.from", &to);
+
+        let transport = match taskrc.get_opt("habitica.report.smtp.host") {
+            Some(host) => {
+                let password = Self::get_habitica_credential(
+                    taskrc,
+                    "TASK2HABITICA_SMTP_PASSWORD",
+                    "habitica.report.smtp.password",
+                );
+                EmailTransport::Smtp {
+                    host,
+                    port: taskrc.get_parsed("habitica.report.smtp.port", 587)?,
+                    user: taskrc.get_opt("habitica.report.smtp.user"),
+                    password: Some(password).filter(|p| !p.is_empty()),
+                }
+            }
+            None => EmailTransport::Sendmail(taskrc.get_or("habitica.report.sendmail", "sendmail")),
+        };
+
+        Ok(Some(EmailConfig {
+            to,
+            from,
+            transport,
+        }))
+    }
+
+    /// Group plan for a project or its closest configured parent project
+    pub fn group_for_project(&self, project: &str) -> Option<Uuid> {
+        self.group_plans
+            .iter()
+            .filter(|(shared, _)| {
+                project == shared.as_str()
+                    || project
+                        .strip_prefix(shared.as_str())
+                        .is_some_and(|rest| rest.starts_with('.'))
+            })
+            .max_by_key(|(shared, _)| shared.len())
+            .map(|(_, id)| *id)
+    }
+
+    /// Project shared through a group plan
+    pub fn project_for_group(&self, group_id: Uuid) -> Option<&str> {
+        self.group_plans
+            .iter()
+            .find(|(_, id)| **id == group_id)
+            .map(|(project, _)| project.as_str())
+    }
+
+    /// Check whether a task carries the tag keeping it off Habitica
+    pub fn keeps_local(&self, task: &Task) -> bool {
+        self.exclude_tag
+            .as_deref()
+            .is_some_and(|tag| task.tags().contains(&tag))
+    }
+
+    /// Get the path to the cached Habitica content catalog
+    pub fn content_cache_path(&self) -> PathBuf {
+        self.data_location.join("habitica_content.json")
+    }
+
+    /// Get the path to the cached responses of Habitica's task lists
+    pub fn response_cache_path(&self) -> PathBuf {
+        self.data_location.join("habitica_etags.json")
+    }
+
     /// Get the path to the stats cache file
     pub fn stats_cache_path(&self) -> PathBuf {
         self.data_location.join("cached_habitica_stats.json")
     }
 
+    /// Get the path to the changes spooled by the hooks
+    pub fn spool_path(&self) -> PathBuf {
+        self.data_location.join("habitica_spool.jsonl")
+    }
+
+    /// Get the path to the marker pausing the hooks
+    pub fn hook_guard_path(&self) -> PathBuf {
+        self.data_location.join("habitica_hooks.lock")
+    }
+
+    /// Get the path to the socket the RPC server listens on
+    pub fn rpc_socket_path(&self) -> PathBuf {
+        self.data_location.join("habitica_rpc.sock")
+    }
+
+    /// Get the path to the user stats shared between hooks
+    pub fn stats_snapshot_path(&self) -> PathBuf {
+        self.data_location.join("habitica_user_stats.json")
+    }
+
+    /// Get the path to the stats snapshot taken by the last report
+    pub fn report_stats_path(&self) -> PathBuf {
+        self.data_location.join("report_habitica_stats.json")
+    }
+
+    /// Get the path to the last synced versions used as merge base
+    pub fn merge_base_path(&self) -> PathBuf {
+        self.data_location.join("habitica_merge_base.json")
+    }
+
+    /// Get the path to the start time of the last successful sync
+    pub fn watermark_path(&self) -> PathBuf {
+        self.data_location.join("habitica_last_sync")
+    }
+
+    /// Get the path to the deletions not yet carried over by a sync
+    pub fn tombstones_path(&self) -> PathBuf {
+        self.data_location.join("habitica_tombstones.json")
+    }
+
+    /// Get the path to the conflicts left for `resolve`
+    pub fn conflicts_path(&self) -> PathBuf {
+        self.data_location.join("habitica_conflicts.json")
+    }
+
+    /// Get the path to the journal of actions taken by syncs
+    pub fn journal_path(&self) -> PathBuf {
+        self.data_location.join("habitica_journal.jsonl")
+    }
+
+    /// Get the path to the tasks a running sync has yet to import
+    pub fn progress_path(&self) -> PathBuf {
+        self.data_location.join("habitica_progress.jsonl")
+    }
+
+    /// Get the path to the list of archived Habitica tasks
+    pub fn archive_path(&self) -> PathBuf {
+        self.data_location.join("habitica_archive.json")
+    }
+
+    /// Get the path to Taskwarrior 3's task database
+    pub fn taskchampion_path(&self) -> PathBuf {
+        self.data_location.join("taskchampion.sqlite3")
+    }
+
+    /// Get the path to the record of the last party milestone post
+    pub fn party_milestone_path(&self) -> PathBuf {
+        self.data_location.join("party_milestone.json")
+    }
+
     /// Check if Taskwarrior version is compatible
     fn check_version(version_str: &str) -> Result<()> {
         // Extract version number from output like "3.4.2" or "2.6.2"
@@ -95,51 +794,22 @@ impl Config {
         Ok(())
     }
 
-    /// Get a value from Taskwarrior config using `task _get`
-    fn get_taskrc_value(key: &str) -> Result<String> {
-        let output = Command::new("task")
-            .arg("rc.hooks=off")
-            .arg("_get")
-            .arg(key)
-            .output()
-            .map_err(|e| Error::config(format!("Failed to run task command: {}", e)))?;
-
-        if !output.status.success() {
-            return Err(Error::config(format!(
-                "Failed to get config value for {}",
-                key
-            )));
-        }
-
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
-    }
-
-    /// Get a value from Taskwarrior config with a default fallback
-    fn get_taskrc_value_or_default(key: &str, default: &str) -> Result<String> {
-        let value = Self::get_taskrc_value(key)?;
-        if value.is_empty() {
-            Ok(default.to_string())
-        } else {
-            Ok(value)
-        }
-    }
-
     /// Get Habitica credential from environment variable or .taskrc
     /// Environment variables take precedence over .taskrc values
-    fn get_habitica_credential(env_var: &str, taskrc_key: &str) -> Result<String> {
+    fn get_habitica_credential(taskrc: &TaskrcValues, env_var: &str, taskrc_key: &str) -> String {
         // Check environment variable first
         if let Ok(value) = env::var(env_var) {
             let value = value.trim().to_string();
             if !value.is_empty() {
-                return Ok(value);
+                return value;
             }
         }
 
-        Self::get_taskrc_value(taskrc_key)
+        taskrc.get(taskrc_key)
     }
 
     /// Expand ~ in paths to home directory
-    fn expand_path(path: &str) -> Result<PathBuf> {
+    pub(crate) fn expand_path(path: &str) -> Result<PathBuf> {
         if let Some(stripped) = path.strip_prefix('~') {
             let home = dirs::home_dir()
                 .ok_or_else(|| Error::config("Could not determine home directory"))?;
@@ -151,11 +821,79 @@ impl Config {
     }
 }
 
+#[cfg(test)]
+impl Config {
+    /// Configuration with dummy credentials and temporary directories
+    pub(crate) fn for_tests() -> Self {
+        Config {
+            habitica_user_id: "user".to_string(),
+            habitica_api_key: "key".to_string(),
+            api_url: DEFAULT_BASE_URL.to_string(),
+            task_note_dir: env::temp_dir().join("test_notes"),
+            task_note_prefix: "[tasknote]".to_string(),
+            task_note_extension: ".txt".to_string(),
+            data_location: env::temp_dir(),
+            verbose: false,
+            queue_hooks: false,
+            timew_habit: None,
+            timew_minutes_per_score: 25,
+            start_habit: None,
+            start_habit_on_stop: false,
+            habit_tags: Vec::new(),
+            difficulty_tags: HashMap::new(),
+            pomodoro_habit: "Pomodoro".to_string(),
+            pomodoro_minutes: 25,
+            bugwarrior: BugwarriorMode::Sync,
+            conflict_strategy: ConflictStrategy::Newest,
+            merge_command: None,
+            notify_ntfy_url: None,
+            notify_webhook_url: None,
+            report_email: None,
+            party_milestone: None,
+            group_plans: HashMap::new(),
+            locale: None,
+            sanitize: SanitizeOptions::default(),
+            streak_protection: false,
+            archive_delete_notes: false,
+            link_score_max_age: None,
+            stats_ttl: Duration::zero(),
+            import_allow: Vec::new(),
+            reminders: ReminderMapping::Scheduled,
+            sync_group_tasks: true,
+            sort_todos: false,
+            hold_waiting: false,
+            exclude_tag: Some("nohabitica".to_string()),
+            push_filter: None,
+            sync_scope: None,
+            import_project: None,
+            sync_workers: 1,
+            read_taskchampion: false,
+            log_file: None,
+            rpc_token: None,
+            color: ColorChoice::Auto,
+        }
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_api_url() {
+        assert_eq!(
+            parse_api_url(" https://habitica.example.org/api/ ").unwrap(),
+            "https://habitica.example.org/api"
+        );
+        assert_eq!(
+            parse_api_url("http://localhost:3000/api").unwrap(),
+            "http://localhost:3000/api"
+        );
+        assert!(parse_api_url("habitica.example.org").is_err());
+        assert!(parse_api_url("ftp://habitica.example.org/api").is_err());
+    }
+
     #[test]
     fn test_expand_path() {
         // This will only work if HOME is set
@@ -167,6 +905,129 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_taskrc_values_parse() {
+        let taskrc = TaskrcValues::parse(
+            "data.location=~/.task\nhabitica.timew.minutes_per_score=30\nempty=\n",
+        );
+        assert_eq!(taskrc.get("data.location"), "~/.task");
+        assert_eq!(taskrc.get_opt("empty"), None);
+        assert_eq!(taskrc.get_or("missing", "fallback"), "fallback");
+        assert_eq!(
+            taskrc
+                .get_parsed("habitica.timew.minutes_per_score", 25)
+                .unwrap(),
+            30
+        );
+        assert!(taskrc.get_parsed::<u32>("data.location", 0).is_err());
+    }
+
+    #[test]
+    fn test_scope_filter() {
+        assert_eq!(scope_filter(&[], &[]), None);
+        assert_eq!(
+            scope_filter(
+                &filter_list("project:work, +habitica"),
+                &filter_list("project:someday")
+            )
+            .as_deref(),
+            Some("((project:work) or (+habitica)) and !(project:someday)")
+        );
+        assert_eq!(
+            scope_filter(&[], &filter_list("+private")).as_deref(),
+            Some("!(+private)")
+        );
+    }
+
+    #[test]
+    fn test_parse_conflict_strategy() {
+        assert_eq!(
+            "habitica-wins".parse::<ConflictStrategy>().unwrap(),
+            ConflictStrategy::HabiticaWins
+        );
+        assert_eq!(
+            " Taskwarrior-Wins ".parse::<ConflictStrategy>().unwrap(),
+            ConflictStrategy::TaskwarriorWins
+        );
+        assert_eq!(
+            "ask".parse::<ConflictStrategy>().unwrap(),
+            ConflictStrategy::Ask
+        );
+        assert_eq!(
+            "defer".parse::<ConflictStrategy>().unwrap(),
+            ConflictStrategy::Defer
+        );
+        assert!("oldest".parse::<ConflictStrategy>().is_err());
+    }
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("90d").unwrap(), Duration::days(90));
+        assert_eq!(parse_age("2w").unwrap(), Duration::weeks(2));
+        assert_eq!(parse_age("36h").unwrap(), Duration::hours(36));
+        assert!(parse_age("0d").is_err());
+        assert!(parse_age("-3w").is_err());
+        assert!(parse_age("9223372036854775807w").is_err());
+        assert!(parse_age("90").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("").is_err());
+    }
+
+    #[test]
+    fn test_age_cutoff() {
+        let now = Utc::now();
+        assert_eq!(age_cutoff("1d", now).unwrap(), now - Duration::days(1));
+        assert!(age_cutoff("20000000w", now).is_err());
+    }
+
+    #[test]
+    fn test_taskrc_values_bool() {
+        let taskrc = TaskrcValues::parse("a=yes\nb=off\nc=maybe\n");
+        assert!(taskrc.get_bool("a", false).unwrap());
+        assert!(!taskrc.get_bool("b", true).unwrap());
+        assert!(taskrc.get_bool("missing", true).unwrap());
+        assert!(taskrc.get_bool("c", false).is_err());
+    }
+
+    #[test]
+    fn test_load_email_config() {
+        let taskrc = TaskrcValues::parse("habitica.report.email=me@example.com\n");
+        assert_eq!(
+            Config::load_email_config(&taskrc).unwrap(),
+            Some(EmailConfig {
+                to: "me@example.com".to_string(),
+                from: "me@example.com".to_string(),
+                transport: EmailTransport::Sendmail("sendmail".to_string()),
+            })
+        );
+
+        let taskrc = TaskrcValues::parse(
+            "habitica.report.email=me@example.com\nhabitica.report.smtp.host=smtp.example.com\n",
+        );
+        let email = Config::load_email_config(&taskrc).unwrap().unwrap();
+        assert!(matches!(
+            email.transport,
+            EmailTransport::Smtp { port: 587, .. }
+        ));
+
+        assert_eq!(
+            Config::load_email_config(&TaskrcValues::default()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_group_for_project() {
+        let group_id = Uuid::new_v4();
+        let mut config = Config::for_tests();
+        config.group_plans.insert("home".to_string(), group_id);
+
+        assert_eq!(config.group_for_project("home"), Some(group_id));
+        assert_eq!(config.group_for_project("home.kitchen"), Some(group_id));
+        assert_eq!(config.group_for_project("homework"), None);
+        assert_eq!(config.project_for_group(group_id), Some("home"));
+    }
+
     #[test]
     fn test_expand_path_no_tilde() {
         let path = "/tmp/test";
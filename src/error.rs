@@ -2,6 +2,8 @@ use std::fmt;
 
 use thiserror::Error;
 
+use crate::tr;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug, Error)]
@@ -65,6 +67,27 @@ impl Error {
         Error::ConfigError(msg.into())
     }
 
+    /// Error message in the selected output language
+    ///
+    /// Errors without a translation keep their English message.
+    pub fn localized(&self) -> String {
+        let message = match self {
+            Error::TaskwarriorNotFound => tr!("error-taskwarrior-not-found"),
+            Error::TaskwarriorVersionTooOld(version) => {
+                tr!("error-taskwarrior-too-old", version = version.as_str())
+            }
+            Error::InvalidHabiticaCredentials => tr!("error-invalid-credentials"),
+            Error::HabiticaApiError(details) => {
+                tr!("error-habitica-api", details = details.as_str())
+            }
+            Error::HttpError(err) => tr!("error-http", details = err.to_string()),
+            Error::ConfigError(details) => tr!("error-config", details = details.as_str()),
+            Error::TaskNotFound(details) => tr!("error-task-not-found", details = details.as_str()),
+            other => other.to_string(),
+        };
+        tr!("error", message = message)
+    }
+
     /// Check if this is a user-facing error that should be shown without
     /// backtrace
     pub const fn is_user_error(&self) -> bool {
@@ -0,0 +1,135 @@
+use uuid::Uuid;
+
+use crate::{
+    error::Result,
+    habitica::{
+        client::ScoreDirection,
+        task::{DayStart, HabiticaTask, UserStats, Webhook},
+    },
+};
+
+/// The Habitica API as used by the sync and the commands
+///
+/// [`HabiticaClient`](super::HabiticaClient) implements it against a live
+/// server. Code taking a `&dyn HabiticaApi` can be handed a double in tests,
+/// or a client pointed at a mock server with
+/// [`HabiticaClient::with_base_url`](super::HabiticaClient::with_base_url).
+/// Implementations are shared with the sync engine's workers.
+pub trait HabiticaApi: Sync {
+    /// Fetch Habitica's content catalog
+    fn get_content(&self) -> Result<serde_json::Value>;
+
+    /// Get all tasks of a specific type
+    ///
+    /// The list is revalidated against the last response, which is reused
+    /// when Habitica reports it unchanged.
+    fn get_tasks(&self, task_type: Option<&str>) -> Result<Vec<HabiticaTask>>;
+
+    /// Get all relevant tasks (todos, dailies, rewards, and completed todos)
+    fn get_all_tasks(&self) -> Result<Vec<HabiticaTask>> {
+        let mut tasks = Vec::new();
+
+        // Get todos
+        tasks.extend(self.get_tasks(Some("todos"))?);
+
+        // Get dailies
+        tasks.extend(self.get_tasks(Some("dailys"))?);
+
+        // Get rewards
+        tasks.extend(self.get_tasks(Some("rewards"))?);
+
+        // Get completed todos
+        tasks.extend(self.get_tasks(Some("_allCompletedTodos"))?);
+
+        Ok(tasks)
+    }
+
+    /// Get a single task by ID, `None` if it no longer exists
+    fn get_task(&self, task_id: Uuid) -> Result<Option<HabiticaTask>>;
+
+    /// Get the tasks of a group plan
+    fn get_group_tasks(&self, group_id: Uuid) -> Result<Vec<HabiticaTask>>;
+
+    /// Create a task on a group plan
+    fn create_group_task(&self, group_id: Uuid, task: &HabiticaTask) -> Result<HabiticaTask>;
+
+    /// Find a habit by its ID or exact text
+    fn find_habit(&self, name_or_id: &str) -> Result<Option<HabiticaTask>> {
+        let habits = self.get_tasks(Some("habits"))?;
+        Ok(habits.into_iter().find(|habit| {
+            habit.text == name_or_id || habit.id.is_some_and(|id| id.to_string() == name_or_id)
+        }))
+    }
+
+    /// Find a reward by its ID or exact text
+    fn find_reward(&self, name_or_id: &str) -> Result<Option<HabiticaTask>> {
+        let rewards = self.get_tasks(Some("rewards"))?;
+        Ok(rewards.into_iter().find(|reward| {
+            reward.text == name_or_id || reward.id.is_some_and(|id| id.to_string() == name_or_id)
+        }))
+    }
+
+    /// Create a new task on Habitica
+    fn create_task(
+        &self,
+        task: &HabiticaTask,
+    ) -> Result<(HabiticaTask, Option<UserStats>, Option<String>)>;
+
+    /// Create several tasks on Habitica in a single request
+    ///
+    /// The created tasks come back in the order they were given.
+    fn create_tasks(&self, tasks: &[HabiticaTask]) -> Result<Vec<HabiticaTask>>;
+
+    /// Update an existing task on Habitica
+    fn update_task(
+        &self,
+        task_id: Uuid,
+        task: &HabiticaTask,
+    ) -> Result<(HabiticaTask, Option<UserStats>, Option<String>)>;
+
+    /// Delete a task from Habitica
+    fn delete_task(&self, task_id: Uuid) -> Result<()>;
+
+    /// Move a task to a position in its list, 0 being the top
+    ///
+    /// Returns the task IDs of the list in their new order.
+    fn move_task(&self, task_id: Uuid, position: usize) -> Result<Vec<Uuid>>;
+
+    /// Score a task (mark as complete/incomplete)
+    fn score_task(
+        &self,
+        task_id: Uuid,
+        direction: ScoreDirection,
+    ) -> Result<(Option<UserStats>, Option<String>)>;
+
+    /// Post a message to the user's party chat
+    fn post_party_chat(&self, message: &str) -> Result<()>;
+
+    /// Get user stats
+    fn get_user_stats(&self) -> Result<UserStats>;
+
+    /// Get user stats, reusing ones fetched within `habitica.stats.ttl`
+    ///
+    /// Meant for hooks, which often run several times per `task` command.
+    fn get_user_stats_cached(&self) -> Result<UserStats> {
+        self.get_user_stats()
+    }
+
+    /// Check whether the user is resting in the inn
+    fn is_sleeping(&self) -> Result<bool>;
+
+    /// Fetch when the user's day rolls over
+    fn get_day_start(&self) -> Result<DayStart>;
+
+    /// Start or stop resting in the inn, returning the new state
+    fn set_sleeping(&self, sleep: bool) -> Result<bool>;
+
+    /// List the webhooks registered on the account
+    fn get_webhooks(&self) -> Result<Vec<Webhook>>;
+
+    /// Register a webhook, returning it with its new ID
+    fn add_webhook(&self, webhook: &Webhook) -> Result<Webhook>;
+
+    /// Remove a webhook
+    fn delete_webhook(&self, webhook_id: Uuid) -> Result<()>;
+}
@@ -0,0 +1,286 @@
+use std::time::Instant;
+
+use reqwest::{Client, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    habitica::{
+        client::{default_headers, ScoreDirection},
+        content::ContentCatalog,
+        rate_limit::RateLimiter,
+        task::{DayStart, HabiticaResponse, HabiticaTask, ResponseWithStats, UserStats},
+    },
+};
+
+/// Async client for interacting with the Habitica API
+///
+/// Mirrors [`HabiticaClient`](super::HabiticaClient) for use inside a tokio
+/// runtime.
+pub struct AsyncHabiticaClient {
+    client: Client,
+    base_url: String,
+    /// Cached content catalog for naming drops (fetched by the blocking client)
+    content: Option<ContentCatalog>,
+    limiter: RateLimiter,
+}
+
+impl AsyncHabiticaClient {
+    /// Create a new async Habitica client with credentials from config
+    pub fn new(config: &Config) -> Result<Self> {
+        let client = Client::builder()
+            .default_headers(default_headers(config)?)
+            .build()?;
+
+        Ok(AsyncHabiticaClient {
+            client,
+            base_url: config.api_url.clone(),
+            content: ContentCatalog::load(&config.content_cache_path())
+                .ok()
+                .flatten(),
+            limiter: RateLimiter::default(),
+        })
+    }
+
+    /// Wait for the rate limiter before sending a request
+    async fn rate_limit(&self) {
+        while let Some(wait) = self.limiter.reserve(Instant::now()) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Unwrap the Habitica response envelope
+    async fn into_data<T: DeserializeOwned>(response: Response) -> Result<Option<T>> {
+        if !response.status().is_success() {
+            let status = response.status();
+            return Err(Error::HabiticaApiError(format!(
+                "HTTP {}: {}",
+                status,
+                response.text().await.unwrap_or_default()
+            )));
+        }
+
+        let api_response: HabiticaResponse<T> = response.json().await?;
+
+        if !api_response.success {
+            return Err(Error::HabiticaApiError(
+                api_response
+                    .message
+                    .unwrap_or_else(|| "Unknown error".to_string()),
+            ));
+        }
+
+        Ok(api_response.data)
+    }
+
+    /// Get all tasks of a specific type
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn get_tasks(&self, task_type: Option<&str>) -> Result<Vec<HabiticaTask>> {
+        self.rate_limit().await;
+
+        let url = format!("{}/v3/tasks/user", self.base_url);
+        let mut request = self.client.get(&url);
+
+        if let Some(type_param) = task_type {
+            request = request.query(&[("type", type_param)]);
+        }
+
+        let data = Self::into_data(request.send().await?).await?;
+        Ok(data.unwrap_or_default())
+    }
+
+    /// Get all relevant tasks (todos, dailies, rewards, and completed todos)
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn get_all_tasks(&self) -> Result<Vec<HabiticaTask>> {
+        let mut tasks = Vec::new();
+        tasks.extend(self.get_tasks(Some("todos")).await?);
+        tasks.extend(self.get_tasks(Some("dailys")).await?);
+        tasks.extend(self.get_tasks(Some("rewards")).await?);
+        tasks.extend(self.get_tasks(Some("_allCompletedTodos")).await?);
+        Ok(tasks)
+    }
+
+    /// Find a habit by its ID or exact text
+    pub async fn find_habit(&self, name_or_id: &str) -> Result<Option<HabiticaTask>> {
+        let habits = self.get_tasks(Some("habits")).await?;
+        Ok(habits.into_iter().find(|habit| {
+            habit.text == name_or_id || habit.id.is_some_and(|id| id.to_string() == name_or_id)
+        }))
+    }
+
+    /// Get the tasks of a group plan
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn get_group_tasks(&self, group_id: Uuid) -> Result<Vec<HabiticaTask>> {
+        self.rate_limit().await;
+
+        let url = format!("{}/v3/tasks/group/{}", self.base_url, group_id);
+        let data = Self::into_data(self.client.get(&url).send().await?).await?;
+        Ok(data.unwrap_or_default())
+    }
+
+    /// Create a task on a group plan
+    #[instrument(level = "debug", skip(self, task), fields(text = %task.text), err)]
+    pub async fn create_group_task(
+        &self,
+        group_id: Uuid,
+        task: &HabiticaTask,
+    ) -> Result<HabiticaTask> {
+        self.rate_limit().await;
+
+        let url = format!("{}/v3/tasks/group/{}", self.base_url, group_id);
+        let response = self.client.post(&url).json(task).send().await?;
+        Self::into_data(response)
+            .await?
+            .ok_or_else(|| Error::HabiticaApiError("No data in response".to_string()))
+    }
+
+    /// Create a new task on Habitica
+    #[instrument(level = "debug", skip_all, fields(text = %task.text), err)]
+    pub async fn create_task(
+        &self,
+        task: &HabiticaTask,
+    ) -> Result<(HabiticaTask, Option<UserStats>, Option<String>)> {
+        self.rate_limit().await;
+
+        let url = format!("{}/v3/tasks/user", self.base_url);
+        let response = self.client.post(&url).json(task).send().await?;
+
+        let response_data: ResponseWithStats<HabiticaTask> = Self::into_data(response)
+            .await?
+            .ok_or_else(|| Error::HabiticaApiError("No data in response".to_string()))?;
+
+        let item_drop = response_data.item_drop_message(self.content.as_ref());
+        Ok((response_data.data, response_data.stats, item_drop))
+    }
+
+    /// Update an existing task on Habitica
+    #[instrument(level = "debug", skip(self, task), err)]
+    pub async fn update_task(
+        &self,
+        task_id: Uuid,
+        task: &HabiticaTask,
+    ) -> Result<(HabiticaTask, Option<UserStats>, Option<String>)> {
+        self.rate_limit().await;
+
+        let url = format!("{}/v3/tasks/{}", self.base_url, task_id);
+        let response = self.client.put(&url).json(task).send().await?;
+
+        let response_data: ResponseWithStats<HabiticaTask> = Self::into_data(response)
+            .await?
+            .ok_or_else(|| Error::HabiticaApiError("No data in response".to_string()))?;
+
+        let item_drop = response_data.item_drop_message(self.content.as_ref());
+        Ok((response_data.data, response_data.stats, item_drop))
+    }
+
+    /// Delete a task from Habitica
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn delete_task(&self, task_id: Uuid) -> Result<()> {
+        self.rate_limit().await;
+
+        let url = format!("{}/v3/tasks/{}", self.base_url, task_id);
+        let response = self.client.delete(&url).send().await?;
+
+        // Treat 404 as success - task already doesn't exist
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+
+        Self::into_data::<serde_json::Value>(response).await?;
+        Ok(())
+    }
+
+    /// Score a task (mark as complete/incomplete)
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn score_task(
+        &self,
+        task_id: Uuid,
+        direction: ScoreDirection,
+    ) -> Result<(Option<UserStats>, Option<String>)> {
+        self.rate_limit().await;
+
+        let url = format!(
+            "{}/v3/tasks/{}/score/{}",
+            self.base_url,
+            task_id,
+            direction.as_str()
+        );
+        let response = self.client.post(&url).body("").send().await?;
+
+        // Treat 404 as success with no stats update - task already doesn't exist
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok((None, None));
+        }
+
+        let response_data: ResponseWithStats<serde_json::Value> = Self::into_data(response)
+            .await?
+            .ok_or_else(|| Error::HabiticaApiError("No data in response".to_string()))?;
+
+        let item_drop = response_data.item_drop_message(self.content.as_ref());
+        Ok((response_data.stats, item_drop))
+    }
+
+    /// Get user stats
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn get_user_stats(&self) -> Result<UserStats> {
+        self.rate_limit().await;
+
+        #[derive(Debug, Deserialize)]
+        struct UserResponse {
+            stats: UserStats,
+        }
+
+        let url = format!("{}/v4/user", self.base_url);
+        let response = self.client.get(&url).send().await?;
+
+        let data: UserResponse = Self::into_data(response)
+            .await?
+            .ok_or_else(|| Error::HabiticaApiError("No data in response".to_string()))?;
+        Ok(data.stats)
+    }
+
+    /// Fetch when the user's day rolls over
+    #[instrument(level = "debug", skip(self), err)]
+    pub async fn get_day_start(&self) -> Result<DayStart> {
+        self.rate_limit().await;
+
+        #[derive(Debug, Deserialize)]
+        struct UserResponse {
+            preferences: DayStart,
+        }
+
+        let url = format!("{}/v3/user", self.base_url);
+        let response = self
+            .client
+            .get(&url)
+            .query(&[(
+                "userFields",
+                "preferences.dayStart,preferences.timezoneOffset",
+            )])
+            .send()
+            .await?;
+
+        let data: UserResponse = Self::into_data(response)
+            .await?
+            .ok_or_else(|| Error::HabiticaApiError("No data in response".to_string()))?;
+        Ok(data.preferences)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::habitica::client::DEFAULT_BASE_URL;
+
+    #[test]
+    fn test_async_client_uses_default_base_url() {
+        let config = Config::for_tests();
+
+        let client = AsyncHabiticaClient::new(&config).unwrap();
+        assert_eq!(client.base_url, DEFAULT_BASE_URL);
+    }
+}
@@ -1,27 +1,69 @@
-use std::{thread, time::Duration};
+use std::{path::PathBuf, sync::OnceLock};
 
+use chrono::Utc;
 use reqwest::{
     blocking::Client,
     header::{HeaderMap, HeaderValue},
+    StatusCode,
 };
 use serde::Deserialize;
+use tracing::instrument;
 use uuid::Uuid;
 
 use crate::{
     config::Config,
     error::{Error, Result},
-    habitica::task::{HabiticaResponse, HabiticaTask, ResponseWithStats, UserStats},
+    habitica::{
+        api::HabiticaApi,
+        content::ContentCatalog,
+        etag::{CachedResponse, ResponseCache},
+        rate_limit::{RateLimitStatus, RateLimiter},
+        stats::StatsSnapshot,
+        task::{
+            Account, DayStart, HabiticaResponse, HabiticaTask, ResponseWithStats, UserStats,
+            Webhook,
+        },
+    },
 };
 
+/// Habitica's public API root
+pub const DEFAULT_BASE_URL: &str = "https://habitica.com/api";
+
+/// Build the authentication and client identification headers
+pub(crate) fn default_headers(config: &Config) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+
+    headers.insert(
+        "x-api-user",
+        HeaderValue::from_str(&config.habitica_user_id)
+            .map_err(|_| Error::InvalidHabiticaCredentials)?,
+    );
+
+    headers.insert(
+        "x-api-key",
+        HeaderValue::from_str(&config.habitica_api_key)
+            .map_err(|_| Error::InvalidHabiticaCredentials)?,
+    );
+
+    headers.insert(
+        "x-client",
+        HeaderValue::from_static("cab16cfa-e951-4dc3-a468-1abadc1dd109-Task2HabiticaRust"),
+    );
+
+    headers.insert("Content-Type", HeaderValue::from_static("application/json"));
+
+    Ok(headers)
+}
+
 /// Direction for scoring a task
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ScoreDirection {
     Up,
     Down,
 }
 
 impl ScoreDirection {
-    const fn as_str(&self) -> &str {
+    pub(crate) const fn as_str(&self) -> &str {
         match self {
             ScoreDirection::Up => "up",
             ScoreDirection::Down => "down",
@@ -33,47 +75,207 @@ impl ScoreDirection {
 pub struct HabiticaClient {
     client: Client,
     base_url: String,
+    content_path: PathBuf,
+    content: OnceLock<Option<ContentCatalog>>,
+    stats_path: PathBuf,
+    stats_ttl: chrono::Duration,
+    response_cache_path: PathBuf,
+    limiter: RateLimiter,
 }
 
 impl HabiticaClient {
     /// Create a new Habitica client with credentials from config
     pub fn new(config: &Config) -> Result<Self> {
-        let mut headers = HeaderMap::new();
+        Self::with_base_url(config, &config.api_url)
+    }
 
-        headers.insert(
-            "x-api-user",
-            HeaderValue::from_str(&config.habitica_user_id)
-                .map_err(|_| Error::InvalidHabiticaCredentials)?,
-        );
+    /// Create a client talking to another API root than `habitica.api_url`,
+    /// such as a mock server
+    pub fn with_base_url(config: &Config, base_url: &str) -> Result<Self> {
+        let client = Client::builder()
+            .default_headers(default_headers(config)?)
+            .build()?;
 
-        headers.insert(
-            "x-api-key",
-            HeaderValue::from_str(&config.habitica_api_key)
-                .map_err(|_| Error::InvalidHabiticaCredentials)?,
-        );
+        Ok(HabiticaClient {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            content_path: config.content_cache_path(),
+            content: OnceLock::new(),
+            stats_path: config.stats_snapshot_path(),
+            stats_ttl: config.stats_ttl,
+            response_cache_path: config.response_cache_path(),
+            limiter: RateLimiter::default(),
+        })
+    }
 
-        headers.insert(
-            "x-client",
-            HeaderValue::from_static("cab16cfa-e951-4dc3-a468-1abadc1dd109-Task2HabiticaRust"),
-        );
+    /// Content catalog for naming drops, fetched on first use when stale
+    fn content_catalog(&self) -> Option<&ContentCatalog> {
+        self.content
+            .get_or_init(|| {
+                let cached = ContentCatalog::load(&self.content_path).ok().flatten();
+                if cached
+                    .as_ref()
+                    .is_some_and(|catalog| !catalog.is_stale(Utc::now()))
+                {
+                    return cached;
+                }
+
+                match self.get_content() {
+                    Ok(content) => {
+                        let catalog = ContentCatalog::from_content(&content, Utc::now());
+                        let _ = catalog.save(&self.content_path);
+                        Some(catalog)
+                    }
+                    // Fall back to an outdated catalog rather than none
+                    Err(_) => cached,
+                }
+            })
+            .as_ref()
+    }
+
+    /// Check the credentials with a small request for the account they
+    /// belong to, along with the rate limit Habitica reports
+    #[instrument(level = "debug", skip(self), err)]
+    pub fn verify_credentials(&self) -> Result<(Account, Option<RateLimitStatus>)> {
+        self.rate_limit();
+
+        let url = format!("{}/v3/user", self.base_url);
+        let response = self
+            .client
+            .get(&url)
+            .query(&[(
+                "userFields",
+                "auth.local.username,profile.name,stats.lvl,stats.class",
+            )])
+            .send()?;
+
+        if response.status() == StatusCode::UNAUTHORIZED {
+            return Err(Error::HabiticaApiError(format!(
+                "HTTP {}: the user ID or API token is wrong",
+                response.status()
+            )));
+        }
+        if !response.status().is_success() {
+            return Err(Error::HabiticaApiError(format!(
+                "HTTP {}: {}",
+                response.status(),
+                response.text().unwrap_or_default()
+            )));
+        }
+        let rate_limit = RateLimitStatus::from_headers(response.headers());
 
-        headers.insert("Content-Type", HeaderValue::from_static("application/json"));
+        #[derive(Debug, Default, Deserialize)]
+        struct Local {
+            #[serde(default)]
+            username: String,
+        }
 
-        let client = Client::builder().default_headers(headers).build()?;
+        #[derive(Debug, Default, Deserialize)]
+        struct Auth {
+            #[serde(default)]
+            local: Local,
+        }
 
-        Ok(HabiticaClient {
-            client,
-            base_url: "https://habitica.com/api".to_string(),
-        })
+        #[derive(Debug, Default, Deserialize)]
+        struct Profile {
+            #[serde(default)]
+            name: String,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Stats {
+            lvl: i32,
+            #[serde(default)]
+            class: Option<String>,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct UserResponse {
+            #[serde(default)]
+            auth: Auth,
+            #[serde(default)]
+            profile: Profile,
+            stats: Stats,
+        }
+
+        let api_response: HabiticaResponse<UserResponse> = response.json()?;
+
+        if !api_response.success {
+            return Err(Error::HabiticaApiError(
+                api_response
+                    .message
+                    .unwrap_or_else(|| "Unknown error".to_string()),
+            ));
+        }
+
+        let user = api_response
+            .data
+            .ok_or_else(|| Error::HabiticaApiError("No data in response".to_string()))?;
+        let account = Account {
+            username: user.auth.local.username,
+            name: user.profile.name,
+            level: user.stats.lvl,
+            // Characters are warriors until classes unlock at level 10
+            class: user.stats.class.filter(|_| user.stats.lvl >= 10),
+        };
+        Ok((account, rate_limit))
     }
 
-    /// Rate limiting: wait 1 second between requests
+    /// Wait for the rate limiter before sending a request
     fn rate_limit(&self) {
-        thread::sleep(Duration::from_secs(1));
+        self.limiter.acquire();
+    }
+
+    /// Save stats for [`get_user_stats_cached`](Self::get_user_stats_cached)
+    fn remember_stats(&self, stats: &UserStats) {
+        if self.stats_ttl <= chrono::Duration::zero() {
+            return;
+        }
+        let snapshot = StatsSnapshot {
+            fetched_at: Utc::now(),
+            stats: stats.clone(),
+        };
+        // Losing the snapshot only costs a fetch next time
+        let _ = snapshot.save(&self.stats_path);
+    }
+}
+
+impl HabiticaApi for HabiticaClient {
+    /// Fetch Habitica's content catalog
+    #[instrument(level = "debug", skip(self), err)]
+    fn get_content(&self) -> Result<serde_json::Value> {
+        self.rate_limit();
+
+        let url = format!("{}/v3/content", self.base_url);
+        let response = self.client.get(&url).send()?;
+
+        if !response.status().is_success() {
+            return Err(Error::HabiticaApiError(format!(
+                "HTTP {}: {}",
+                response.status(),
+                response.text().unwrap_or_default()
+            )));
+        }
+
+        let api_response: HabiticaResponse<serde_json::Value> = response.json()?;
+
+        if !api_response.success {
+            return Err(Error::HabiticaApiError(
+                api_response
+                    .message
+                    .unwrap_or_else(|| "Unknown error".to_string()),
+            ));
+        }
+
+        Ok(api_response.data.unwrap_or_default())
     }
 
     /// Get all tasks of a specific type
-    pub fn get_tasks(&self, task_type: Option<&str>) -> Result<Vec<HabiticaTask>> {
+    ///
+    /// The list is revalidated against the last response, which is reused
+    /// when Habitica reports it unchanged.
+    #[instrument(level = "debug", skip(self), err)]
+    fn get_tasks(&self, task_type: Option<&str>) -> Result<Vec<HabiticaTask>> {
         self.rate_limit();
 
         let url = format!("{}/v3/tasks/user", self.base_url);
@@ -83,8 +285,89 @@ impl HabiticaClient {
             request = request.query(&[("type", type_param)]);
         }
 
+        let key = format!("{}?type={}", url, task_type.unwrap_or_default());
+        let mut cache = ResponseCache::load(&self.response_cache_path);
+        if let Some(cached) = cache.get(&key) {
+            request = request.headers(cached.conditional_headers());
+        }
+
         let response = request.send()?;
 
+        let body = match (response.status(), cache.get(&key)) {
+            (StatusCode::NOT_MODIFIED, Some(cached)) => cached.body.clone(),
+            (status, _) if !status.is_success() => {
+                return Err(Error::HabiticaApiError(format!(
+                    "HTTP {}: {}",
+                    status,
+                    response.text().unwrap_or_default()
+                )));
+            }
+            _ => {
+                let headers = response.headers().clone();
+                let body = response.text()?;
+                if let Some(cached) = CachedResponse::from_response(&headers, body.clone()) {
+                    cache.insert(key, cached);
+                    // Losing the cache only costs a full download next time
+                    let _ = cache.save(&self.response_cache_path);
+                }
+                body
+            }
+        };
+
+        let api_response: HabiticaResponse<Vec<HabiticaTask>> = serde_json::from_str(&body)?;
+
+        if !api_response.success {
+            return Err(Error::HabiticaApiError(
+                api_response
+                    .message
+                    .unwrap_or_else(|| "Unknown error".to_string()),
+            ));
+        }
+
+        Ok(api_response.data.unwrap_or_default())
+    }
+
+    /// Get a single task by ID, `None` if it no longer exists
+    #[instrument(level = "debug", skip(self), err)]
+    fn get_task(&self, task_id: Uuid) -> Result<Option<HabiticaTask>> {
+        self.rate_limit();
+
+        let url = format!("{}/v3/tasks/{}", self.base_url, task_id);
+        let response = self.client.get(&url).send()?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            return Err(Error::HabiticaApiError(format!(
+                "HTTP {}: {}",
+                response.status(),
+                response.text().unwrap_or_default()
+            )));
+        }
+
+        let api_response: HabiticaResponse<HabiticaTask> = response.json()?;
+
+        if !api_response.success {
+            return Err(Error::HabiticaApiError(
+                api_response
+                    .message
+                    .unwrap_or_else(|| "Unknown error".to_string()),
+            ));
+        }
+
+        Ok(api_response.data)
+    }
+
+    /// Get the tasks of a group plan
+    #[instrument(level = "debug", skip(self), err)]
+    fn get_group_tasks(&self, group_id: Uuid) -> Result<Vec<HabiticaTask>> {
+        self.rate_limit();
+
+        let url = format!("{}/v3/tasks/group/{}", self.base_url, group_id);
+        let response = self.client.get(&url).send()?;
+
         if !response.status().is_success() {
             return Err(Error::HabiticaApiError(format!(
                 "HTTP {}: {}",
@@ -106,24 +389,40 @@ impl HabiticaClient {
         Ok(api_response.data.unwrap_or_default())
     }
 
-    /// Get all relevant tasks (todos, dailies, and completed todos)
-    pub fn get_all_tasks(&self) -> Result<Vec<HabiticaTask>> {
-        let mut tasks = Vec::new();
+    /// Create a task on a group plan
+    #[instrument(level = "debug", skip(self, task), fields(text = %task.text), err)]
+    fn create_group_task(&self, group_id: Uuid, task: &HabiticaTask) -> Result<HabiticaTask> {
+        self.rate_limit();
+
+        let url = format!("{}/v3/tasks/group/{}", self.base_url, group_id);
+        let response = self.client.post(&url).json(task).send()?;
 
-        // Get todos
-        tasks.extend(self.get_tasks(Some("todos"))?);
+        if !response.status().is_success() {
+            return Err(Error::HabiticaApiError(format!(
+                "HTTP {}: {}",
+                response.status(),
+                response.text().unwrap_or_default()
+            )));
+        }
 
-        // Get dailies
-        tasks.extend(self.get_tasks(Some("dailys"))?);
+        let api_response: HabiticaResponse<HabiticaTask> = response.json()?;
 
-        // Get completed todos
-        tasks.extend(self.get_tasks(Some("_allCompletedTodos"))?);
+        if !api_response.success {
+            return Err(Error::HabiticaApiError(
+                api_response
+                    .message
+                    .unwrap_or_else(|| "Unknown error".to_string()),
+            ));
+        }
 
-        Ok(tasks)
+        api_response
+            .data
+            .ok_or_else(|| Error::HabiticaApiError("No data in response".to_string()))
     }
 
     /// Create a new task on Habitica
-    pub fn create_task(
+    #[instrument(level = "debug", skip_all, fields(text = %task.text), err)]
+    fn create_task(
         &self,
         task: &HabiticaTask,
     ) -> Result<(HabiticaTask, Option<UserStats>, Option<String>)> {
@@ -154,12 +453,56 @@ impl HabiticaClient {
             .data
             .ok_or_else(|| Error::HabiticaApiError("No data in response".to_string()))?;
 
-        let item_drop = response_data.item_drop_message();
+        let item_drop = response_data
+            .item_drop()
+            .and_then(|drop| drop.describe(self.content_catalog()));
         Ok((response_data.data, response_data.stats, item_drop))
     }
 
+    /// Create several tasks on Habitica in a single request
+    ///
+    /// The created tasks come back in the order they were given.
+    #[instrument(level = "debug", skip_all, fields(count = tasks.len()), err)]
+    fn create_tasks(&self, tasks: &[HabiticaTask]) -> Result<Vec<HabiticaTask>> {
+        self.rate_limit();
+
+        let url = format!("{}/v3/tasks/user", self.base_url);
+        let response = self.client.post(&url).json(tasks).send()?;
+
+        if !response.status().is_success() {
+            return Err(Error::HabiticaApiError(format!(
+                "HTTP {}: {}",
+                response.status(),
+                response.text().unwrap_or_default()
+            )));
+        }
+
+        let api_response: HabiticaResponse<Vec<HabiticaTask>> = response.json()?;
+
+        if !api_response.success {
+            return Err(Error::HabiticaApiError(
+                api_response
+                    .message
+                    .unwrap_or_else(|| "Unknown error".to_string()),
+            ));
+        }
+
+        let created = api_response
+            .data
+            .ok_or_else(|| Error::HabiticaApiError("No data in response".to_string()))?;
+        if created.len() != tasks.len() {
+            return Err(Error::HabiticaApiError(format!(
+                "Created {} of {} tasks",
+                created.len(),
+                tasks.len()
+            )));
+        }
+        Ok(created)
+    }
+
     /// Update an existing task on Habitica
-    pub fn update_task(
+    #[instrument(level = "debug", skip(self, task), err)]
+    fn update_task(
         &self,
         task_id: Uuid,
         task: &HabiticaTask,
@@ -191,12 +534,15 @@ impl HabiticaClient {
             .data
             .ok_or_else(|| Error::HabiticaApiError("No data in response".to_string()))?;
 
-        let item_drop = response_data.item_drop_message();
+        let item_drop = response_data
+            .item_drop()
+            .and_then(|drop| drop.describe(self.content_catalog()));
         Ok((response_data.data, response_data.stats, item_drop))
     }
 
     /// Delete a task from Habitica
-    pub fn delete_task(&self, task_id: Uuid) -> Result<()> {
+    #[instrument(level = "debug", skip(self), err)]
+    fn delete_task(&self, task_id: Uuid) -> Result<()> {
         self.rate_limit();
 
         let url = format!("{}/v3/tasks/{}", self.base_url, task_id);
@@ -228,8 +574,43 @@ impl HabiticaClient {
         Ok(())
     }
 
+    /// Move a task to a position in its list, 0 being the top
+    ///
+    /// Returns the task IDs of the list in their new order.
+    #[instrument(level = "debug", skip(self), err)]
+    fn move_task(&self, task_id: Uuid, position: usize) -> Result<Vec<Uuid>> {
+        self.rate_limit();
+
+        let url = format!(
+            "{}/v3/tasks/{}/move/to/{}",
+            self.base_url, task_id, position
+        );
+        let response = self.client.post(&url).send()?;
+
+        if !response.status().is_success() {
+            return Err(Error::HabiticaApiError(format!(
+                "HTTP {}: {}",
+                response.status(),
+                response.text().unwrap_or_default()
+            )));
+        }
+
+        let api_response: HabiticaResponse<Vec<Uuid>> = response.json()?;
+
+        if !api_response.success {
+            return Err(Error::HabiticaApiError(
+                api_response
+                    .message
+                    .unwrap_or_else(|| "Unknown error".to_string()),
+            ));
+        }
+
+        Ok(api_response.data.unwrap_or_default())
+    }
+
     /// Score a task (mark as complete/incomplete)
-    pub fn score_task(
+    #[instrument(level = "debug", skip(self), err)]
+    fn score_task(
         &self,
         task_id: Uuid,
         direction: ScoreDirection,
@@ -272,12 +653,51 @@ impl HabiticaClient {
             .data
             .ok_or_else(|| Error::HabiticaApiError("No data in response".to_string()))?;
 
-        let item_drop = response_data.item_drop_message();
+        let item_drop = response_data
+            .item_drop()
+            .and_then(|drop| drop.describe(self.content_catalog()));
+        if let Some(stats) = &response_data.stats {
+            self.remember_stats(stats);
+        }
         Ok((response_data.stats, item_drop))
     }
 
+    /// Post a message to the user's party chat
+    #[instrument(level = "debug", skip(self, message), err)]
+    fn post_party_chat(&self, message: &str) -> Result<()> {
+        self.rate_limit();
+
+        let url = format!("{}/v3/groups/party/chat", self.base_url);
+        let response = self
+            .client
+            .post(&url)
+            .json(&serde_json::json!({ "message": message }))
+            .send()?;
+
+        if !response.status().is_success() {
+            return Err(Error::HabiticaApiError(format!(
+                "HTTP {}: {}",
+                response.status(),
+                response.text().unwrap_or_default()
+            )));
+        }
+
+        let api_response: HabiticaResponse<serde_json::Value> = response.json()?;
+
+        if !api_response.success {
+            return Err(Error::HabiticaApiError(
+                api_response
+                    .message
+                    .unwrap_or_else(|| "Unknown error".to_string()),
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Get user stats
-    pub fn get_user_stats(&self) -> Result<UserStats> {
+    #[instrument(level = "debug", skip(self), err)]
+    fn get_user_stats(&self) -> Result<UserStats> {
         self.rate_limit();
 
         let url = format!("{}/v4/user", self.base_url);
@@ -307,15 +727,245 @@ impl HabiticaClient {
             ));
         }
 
+        let stats = api_response
+            .data
+            .ok_or_else(|| Error::HabiticaApiError("No data in response".to_string()))?
+            .stats;
+        self.remember_stats(&stats);
+        Ok(stats)
+    }
+
+    /// Get user stats, reusing ones fetched within `habitica.stats.ttl`
+    ///
+    /// Meant for hooks, which often run several times per `task` command.
+    #[instrument(level = "debug", skip(self), err)]
+    fn get_user_stats_cached(&self) -> Result<UserStats> {
+        match StatsSnapshot::load(&self.stats_path) {
+            Some(snapshot) if snapshot.is_fresh(Utc::now(), self.stats_ttl) => Ok(snapshot.stats),
+            _ => self.get_user_stats(),
+        }
+    }
+
+    /// Check whether the user is resting in the inn
+    #[instrument(level = "debug", skip(self), err)]
+    fn is_sleeping(&self) -> Result<bool> {
+        self.rate_limit();
+
+        let url = format!("{}/v3/user", self.base_url);
+        let response = self
+            .client
+            .get(&url)
+            .query(&[("userFields", "preferences.sleep")])
+            .send()?;
+
+        if !response.status().is_success() {
+            return Err(Error::HabiticaApiError(format!(
+                "HTTP {}: {}",
+                response.status(),
+                response.text().unwrap_or_default()
+            )));
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Preferences {
+            #[serde(default)]
+            sleep: bool,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct UserResponse {
+            preferences: Preferences,
+        }
+
+        let api_response: HabiticaResponse<UserResponse> = response.json()?;
+
+        if !api_response.success {
+            return Err(Error::HabiticaApiError(
+                api_response
+                    .message
+                    .unwrap_or_else(|| "Unknown error".to_string()),
+            ));
+        }
+
         Ok(api_response
             .data
             .ok_or_else(|| Error::HabiticaApiError("No data in response".to_string()))?
-            .stats)
+            .preferences
+            .sleep)
+    }
+
+    /// Fetch when the user's day rolls over
+    #[instrument(level = "debug", skip(self), err)]
+    fn get_day_start(&self) -> Result<DayStart> {
+        self.rate_limit();
+
+        let url = format!("{}/v3/user", self.base_url);
+        let response = self
+            .client
+            .get(&url)
+            .query(&[(
+                "userFields",
+                "preferences.dayStart,preferences.timezoneOffset",
+            )])
+            .send()?;
+
+        if !response.status().is_success() {
+            return Err(Error::HabiticaApiError(format!(
+                "HTTP {}: {}",
+                response.status(),
+                response.text().unwrap_or_default()
+            )));
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct UserResponse {
+            preferences: DayStart,
+        }
+
+        let api_response: HabiticaResponse<UserResponse> = response.json()?;
+
+        if !api_response.success {
+            return Err(Error::HabiticaApiError(
+                api_response
+                    .message
+                    .unwrap_or_else(|| "Unknown error".to_string()),
+            ));
+        }
+
+        Ok(api_response
+            .data
+            .ok_or_else(|| Error::HabiticaApiError("No data in response".to_string()))?
+            .preferences)
+    }
+
+    /// Start or stop resting in the inn, returning the new state
+    #[instrument(level = "debug", skip(self), err)]
+    fn set_sleeping(&self, sleep: bool) -> Result<bool> {
+        self.rate_limit();
+
+        let url = format!("{}/v3/user/sleep", self.base_url);
+        let response = self
+            .client
+            .post(&url)
+            .json(&serde_json::json!({ "data": sleep }))
+            .send()?;
+
+        if !response.status().is_success() {
+            return Err(Error::HabiticaApiError(format!(
+                "HTTP {}: {}",
+                response.status(),
+                response.text().unwrap_or_default()
+            )));
+        }
+
+        let api_response: HabiticaResponse<bool> = response.json()?;
+
+        if !api_response.success {
+            return Err(Error::HabiticaApiError(
+                api_response
+                    .message
+                    .unwrap_or_else(|| "Unknown error".to_string()),
+            ));
+        }
+
+        Ok(api_response.data.unwrap_or(sleep))
+    }
+
+    /// List the webhooks registered on the account
+    #[instrument(level = "debug", skip(self), err)]
+    fn get_webhooks(&self) -> Result<Vec<Webhook>> {
+        self.rate_limit();
+
+        let url = format!("{}/v3/user/webhook", self.base_url);
+        let response = self.client.get(&url).send()?;
+
+        if !response.status().is_success() {
+            return Err(Error::HabiticaApiError(format!(
+                "HTTP {}: {}",
+                response.status(),
+                response.text().unwrap_or_default()
+            )));
+        }
+
+        let api_response: HabiticaResponse<Vec<Webhook>> = response.json()?;
+
+        if !api_response.success {
+            return Err(Error::HabiticaApiError(
+                api_response
+                    .message
+                    .unwrap_or_else(|| "Unknown error".to_string()),
+            ));
+        }
+
+        Ok(api_response.data.unwrap_or_default())
+    }
+
+    /// Register a webhook, returning it with its new ID
+    #[instrument(level = "debug", skip_all, fields(url = %webhook.url), err)]
+    fn add_webhook(&self, webhook: &Webhook) -> Result<Webhook> {
+        self.rate_limit();
+
+        let url = format!("{}/v3/user/webhook", self.base_url);
+        let response = self.client.post(&url).json(webhook).send()?;
+
+        if !response.status().is_success() {
+            return Err(Error::HabiticaApiError(format!(
+                "HTTP {}: {}",
+                response.status(),
+                response.text().unwrap_or_default()
+            )));
+        }
+
+        let api_response: HabiticaResponse<Webhook> = response.json()?;
+
+        if !api_response.success {
+            return Err(Error::HabiticaApiError(
+                api_response
+                    .message
+                    .unwrap_or_else(|| "Unknown error".to_string()),
+            ));
+        }
+
+        api_response
+            .data
+            .ok_or_else(|| Error::HabiticaApiError("No data in response".to_string()))
+    }
+
+    /// Remove a webhook
+    #[instrument(level = "debug", skip(self), err)]
+    fn delete_webhook(&self, webhook_id: Uuid) -> Result<()> {
+        self.rate_limit();
+
+        let url = format!("{}/v3/user/webhook/{}", self.base_url, webhook_id);
+        let response = self.client.delete(&url).send()?;
+
+        if !response.status().is_success() {
+            return Err(Error::HabiticaApiError(format!(
+                "HTTP {}: {}",
+                response.status(),
+                response.text().unwrap_or_default()
+            )));
+        }
+
+        let api_response: HabiticaResponse<serde_json::Value> = response.json()?;
+
+        if !api_response.success {
+            return Err(Error::HabiticaApiError(
+                api_response
+                    .message
+                    .unwrap_or_else(|| "Unknown error".to_string()),
+            ));
+        }
+
+        Ok(())
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
+    use mockito::Matcher;
+
     use super::*;
 
     #[test]
@@ -323,4 +973,73 @@ mod tests {
         assert_eq!(ScoreDirection::Up.as_str(), "up");
         assert_eq!(ScoreDirection::Down.as_str(), "down");
     }
+
+    #[test]
+    fn test_get_tasks_from_mock_server() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::for_tests();
+        config.data_location = dir.path().to_path_buf();
+
+        let mut server = mockito::Server::new();
+        let body = r#"{"success":true,"data":[{"text":"Buy milk","type":"todo","priority":1}]}"#;
+        let fresh = server
+            .mock("GET", "/v3/tasks/user")
+            .match_query(Matcher::UrlEncoded("type".into(), "todos".into()))
+            .match_header("if-none-match", Matcher::Missing)
+            .with_header("etag", "\"v1\"")
+            .with_body(body)
+            .create();
+        let unchanged = server
+            .mock("GET", "/v3/tasks/user")
+            .match_query(Matcher::UrlEncoded("type".into(), "todos".into()))
+            .match_header("if-none-match", "\"v1\"")
+            .with_status(304)
+            .create();
+
+        let h_client = HabiticaClient::with_base_url(&config, &server.url()).unwrap();
+        let api: &dyn HabiticaApi = &h_client;
+
+        // The second fetch is answered from the cache
+        for _ in 0..2 {
+            let tasks = api.get_tasks(Some("todos")).unwrap();
+            assert_eq!(tasks.len(), 1);
+            assert_eq!(tasks[0].text, "Buy milk");
+        }
+        fresh.assert();
+        unchanged.assert();
+    }
+
+    #[test]
+    fn test_verify_credentials() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::for_tests();
+        config.data_location = dir.path().to_path_buf();
+
+        let mut server = mockito::Server::new();
+        let body = r#"{"success":true,"data":{"auth":{"local":{"username":"hero"}},
+            "profile":{"name":"Hero"},"stats":{"lvl":12,"class":"wizard"}}}"#;
+        server
+            .mock("GET", "/v3/user")
+            .match_query(Matcher::Any)
+            .with_header("x-ratelimit-limit", "30")
+            .with_header("x-ratelimit-remaining", "29")
+            .with_body(body)
+            .create();
+
+        let h_client = HabiticaClient::with_base_url(&config, &server.url()).unwrap();
+        let (account, rate_limit) = h_client.verify_credentials().unwrap();
+        assert_eq!(account.username, "hero");
+        assert_eq!(account.name, "Hero");
+        assert_eq!(account.level, 12);
+        assert_eq!(account.class.as_deref(), Some("wizard"));
+        assert_eq!(rate_limit.unwrap().remaining, 29);
+
+        server.reset();
+        server
+            .mock("GET", "/v3/user")
+            .match_query(Matcher::Any)
+            .with_status(401)
+            .create();
+        assert!(h_client.verify_credentials().is_err());
+    }
 }
@@ -0,0 +1,123 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::Result;
+
+/// How long a cached catalog is trusted before it is fetched again
+const MAX_AGE_DAYS: i64 = 7;
+
+/// Content sections holding droppable items, with the suffix added to names
+const ITEM_SECTIONS: &[(&str, &str)] = &[
+    ("eggs", " Egg"),
+    ("hatchingPotions", " Hatching Potion"),
+    ("food", ""),
+    ("quests", ""),
+    ("special", ""),
+];
+
+/// Item display names from Habitica's content catalog
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentCatalog {
+    pub fetched_at: Option<DateTime<Utc>>,
+    /// Display names by content section and item key
+    pub items: HashMap<String, HashMap<String, String>>,
+}
+
+impl ContentCatalog {
+    /// Extract item names from the `/content` response
+    pub fn from_content(content: &Value, now: DateTime<Utc>) -> Self {
+        let items = ITEM_SECTIONS
+            .iter()
+            .filter_map(|(section, suffix)| {
+                let entries = content.get(section)?.as_object()?;
+                let names = entries
+                    .iter()
+                    .filter_map(|(key, item)| {
+                        let text = item.get("text")?.as_str()?;
+                        Some((key.clone(), format!("{}{}", text, suffix)))
+                    })
+                    .collect();
+                Some(((*section).to_string(), names))
+            })
+            .collect();
+
+        ContentCatalog {
+            fetched_at: Some(now),
+            items,
+        }
+    }
+
+    /// Load the cached catalog
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    /// Save the catalog to the cache
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Check whether the catalog should be fetched again
+    pub fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        self.fetched_at
+            .map_or(true, |fetched| now - fetched > Duration::days(MAX_AGE_DAYS))
+    }
+
+    /// Display name of a dropped item, given the drop's `type` and `key`
+    pub fn item_name(&self, drop_type: &str, key: &str) -> Option<&str> {
+        let section = match drop_type {
+            "Egg" => "eggs",
+            "HatchingPotion" => "hatchingPotions",
+            "Food" => "food",
+            "Quest" => "quests",
+            _ => "special",
+        };
+        self.items.get(section)?.get(key).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_item_names() {
+        let content = json!({
+            "eggs": { "Wolf": { "text": "Wolf", "key": "Wolf" } },
+            "hatchingPotions": { "Golden": { "text": "Golden" } },
+            "food": { "Meat": { "text": "Meat" } }
+        });
+        let catalog = ContentCatalog::from_content(&content, Utc::now());
+
+        assert_eq!(catalog.item_name("Egg", "Wolf"), Some("Wolf Egg"));
+        assert_eq!(
+            catalog.item_name("HatchingPotion", "Golden"),
+            Some("Golden Hatching Potion")
+        );
+        assert_eq!(catalog.item_name("Food", "Meat"), Some("Meat"));
+        assert_eq!(catalog.item_name("Food", "Cake"), None);
+    }
+
+    #[test]
+    fn test_is_stale() {
+        let now = Utc::now();
+        let catalog = ContentCatalog {
+            fetched_at: Some(now - Duration::days(8)),
+            ..Default::default()
+        };
+        assert!(catalog.is_stale(now));
+        assert!(!ContentCatalog::from_content(&json!({}), now).is_stale(now));
+        assert!(ContentCatalog::default().is_stale(now));
+    }
+}
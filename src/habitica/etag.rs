@@ -0,0 +1,129 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use reqwest::header::{HeaderMap, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+
+/// A response body along with the validators it was served with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub last_modified: Option<String>,
+    pub body: String,
+}
+
+impl CachedResponse {
+    /// Keep a response for later revalidation, `None` without validators
+    pub fn from_response(headers: &HeaderMap, body: String) -> Option<Self> {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        (etag.is_some() || last_modified.is_some()).then_some(CachedResponse {
+            etag,
+            last_modified,
+            body,
+        })
+    }
+
+    /// Conditional request headers, answered with 304 while unchanged
+    pub fn conditional_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = self.etag.as_deref().and_then(|v| v.parse().ok()) {
+            headers.insert(IF_NONE_MATCH, value);
+        }
+        if let Some(value) = self.last_modified.as_deref().and_then(|v| v.parse().ok()) {
+            headers.insert(IF_MODIFIED_SINCE, value);
+        }
+        headers
+    }
+}
+
+/// Responses of GET endpoints cached on disk, keyed by request URL
+///
+/// Hooks run one after the other, so revalidating the task lists spares
+/// downloading them again when nothing changed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResponseCache {
+    entries: HashMap<String, CachedResponse>,
+}
+
+impl ResponseCache {
+    /// Load the cache, empty if missing or unreadable
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the cache
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&CachedResponse> {
+        self.entries.get(key)
+    }
+
+    pub fn insert(&mut self, key: String, response: CachedResponse) {
+        self.entries.insert(key, response);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_revalidation_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            CachedResponse::from_response(&headers, "[]".to_string()),
+            None
+        );
+
+        headers.insert(ETAG, HeaderValue::from_static("W/\"2a-abc\""));
+        let cached = CachedResponse::from_response(&headers, "[]".to_string()).unwrap();
+        assert_eq!(cached.etag.as_deref(), Some("W/\"2a-abc\""));
+
+        let conditional = cached.conditional_headers();
+        assert_eq!(conditional.get(IF_NONE_MATCH).unwrap(), "W/\"2a-abc\"");
+        assert!(conditional.get(IF_MODIFIED_SINCE).is_none());
+    }
+
+    #[test]
+    fn test_cache_roundtrip() {
+        let path = std::env::temp_dir().join(format!("t2h-etags-{}.json", std::process::id()));
+        let mut cache = ResponseCache::default();
+        cache.insert(
+            "https://habitica.com/api/v3/tasks/user?type=todos".to_string(),
+            CachedResponse {
+                etag: Some("abc".to_string()),
+                last_modified: None,
+                body: "{}".to_string(),
+            },
+        );
+        cache.save(&path).unwrap();
+
+        let loaded = ResponseCache::load(&path);
+        assert_eq!(
+            loaded
+                .get("https://habitica.com/api/v3/tasks/user?type=todos")
+                .map(|cached| cached.body.as_str()),
+            Some("{}")
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
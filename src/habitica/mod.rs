@@ -1,7 +1,22 @@
+pub mod api;
+#[cfg(feature = "async")]
+pub mod async_client;
 pub mod client;
+pub mod content;
+pub mod etag;
+pub mod rate_limit;
 pub mod stats;
 pub mod task;
 
+pub use api::HabiticaApi;
+#[cfg(feature = "async")]
+pub use async_client::AsyncHabiticaClient;
 pub use client::{HabiticaClient, ScoreDirection};
-pub use stats::StatsCache;
-pub use task::{HabiticaTask, HabiticaTaskStatus, HabiticaTaskType, UserStats};
+pub use content::ContentCatalog;
+pub use rate_limit::RateLimitStatus;
+pub use stats::{StatsCache, StatsSnapshot};
+pub use task::{
+    Account, AssignedUser, ChallengeInfo, ChecklistItem, DayStart, Frequency, GroupInfo,
+    HabiticaAttribute, HabiticaTask, HabiticaTaskStatus, HabiticaTaskType, Reminder, UserStats,
+    Webhook, WeekdayRepeat,
+};
@@ -0,0 +1,146 @@
+use std::{
+    sync::{Mutex, PoisonError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use reqwest::header::HeaderMap;
+
+/// Requests Habitica allows per minute
+pub const REQUESTS_PER_MINUTE: u32 = 30;
+
+/// Requests that may go out back to back before the limiter spaces them
+const BURST: f64 = 5.0;
+
+/// Token bucket shared by everything sending requests with one client
+///
+/// Every request takes a token, and tokens come back at Habitica's rate of
+/// 30 per minute. Unlike a fixed pause before each request, the time spent
+/// waiting for a response counts towards the budget, so several workers can
+/// keep requests in flight without exceeding the limit.
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Time for one token to come back
+    interval: Duration,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl RateLimiter {
+    /// Limiter allowing `per_minute` requests, starting with a full bucket
+    pub fn new(per_minute: u32) -> Self {
+        RateLimiter {
+            interval: Duration::from_secs(60) / per_minute.max(1),
+            bucket: Mutex::new(Bucket {
+                tokens: BURST,
+                refilled: Instant::now(),
+            }),
+        }
+    }
+
+    /// Wait until a request may be sent
+    pub fn acquire(&self) {
+        while let Some(wait) = self.reserve(Instant::now()) {
+            thread::sleep(wait);
+        }
+    }
+
+    /// Take a token, or tell how long until the next one comes back
+    pub fn reserve(&self, now: Instant) -> Option<Duration> {
+        let mut bucket = self.bucket.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let elapsed = now.saturating_duration_since(bucket.refilled);
+        bucket.tokens =
+            (bucket.tokens + elapsed.as_secs_f64() / self.interval.as_secs_f64()).min(BURST);
+        bucket.refilled = bucket.refilled.max(now);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return None;
+        }
+        Some(self.interval.mul_f64(1.0 - bucket.tokens))
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(REQUESTS_PER_MINUTE)
+    }
+}
+
+/// Rate limit Habitica reports in the headers of a response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    /// Requests allowed in the current window
+    pub limit: u32,
+    /// Requests left in the current window
+    pub remaining: u32,
+    /// When the window ends, as Habitica formats it
+    pub reset: Option<String>,
+}
+
+impl RateLimitStatus {
+    /// Read the `X-RateLimit-*` headers, if the server sent them
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        Some(RateLimitStatus {
+            limit: header("x-ratelimit-limit")?.trim().parse().ok()?,
+            remaining: header("x-ratelimit-remaining")?.trim().parse().ok()?,
+            reset: header("x-ratelimit-reset").map(str::to_string),
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve() {
+        let limiter = RateLimiter::new(30);
+        let start = Instant::now();
+
+        // The burst goes out right away, then requests are two seconds apart
+        for _ in 0..5 {
+            assert_eq!(limiter.reserve(start), None);
+        }
+        assert_eq!(limiter.reserve(start), Some(Duration::from_secs(2)));
+
+        let later = start + Duration::from_millis(500);
+        assert_eq!(limiter.reserve(later), Some(Duration::from_millis(1500)));
+
+        let later = start + Duration::from_secs(2);
+        assert_eq!(limiter.reserve(later), None);
+        assert_eq!(limiter.reserve(later), Some(Duration::from_secs(2)));
+
+        // Idle time refills the bucket, but only up to the burst
+        let idle = later + Duration::from_secs(60);
+        for _ in 0..5 {
+            assert_eq!(limiter.reserve(idle), None);
+        }
+        assert!(limiter.reserve(idle).is_some());
+    }
+
+    #[test]
+    fn test_rate_limit_status() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(RateLimitStatus::from_headers(&headers), None);
+
+        headers.insert("x-ratelimit-limit", "30".parse().unwrap());
+        headers.insert("x-ratelimit-remaining", "28".parse().unwrap());
+        assert_eq!(
+            RateLimitStatus::from_headers(&headers),
+            Some(RateLimitStatus {
+                limit: 30,
+                remaining: 28,
+                reset: None,
+            })
+        );
+    }
+}
@@ -1,8 +1,38 @@
 use std::{fs, path::Path};
 
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::{error::Result, habitica::task::UserStats};
+use crate::{error::Result, habitica::task::UserStats, tr};
+
+/// User stats as last fetched, shared by hook invocations
+///
+/// A single `task` command can run several hooks in a row; each of them
+/// reuses a recent snapshot instead of fetching the stats again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsSnapshot {
+    pub fetched_at: DateTime<Utc>,
+    pub stats: UserStats,
+}
+
+impl StatsSnapshot {
+    /// Load the snapshot, `None` if missing or unreadable
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Save the snapshot
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Check whether the snapshot is recent enough to use
+    pub fn is_fresh(&self, now: DateTime<Utc>, ttl: Duration) -> bool {
+        now - self.fetched_at <= ttl
+    }
+}
 
 /// Cache of user stats for tracking changes
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,29 +100,35 @@ impl StatsCache {
         // Check for level changes
         let lvl_diff = new.lvl - self.old.lvl;
         if lvl_diff > 0 {
-            messages.push(format!("LEVEL UP! ({} -> {})", self.old.lvl, new.lvl));
+            messages.push(tr!("stats-level-up", old = self.old.lvl, new = new.lvl));
         } else if lvl_diff < 0 {
-            messages.push(format!("LEVEL LOST! ({} -> {})", self.old.lvl, new.lvl));
+            messages.push(tr!("stats-level-lost", old = self.old.lvl, new = new.lvl));
         }
 
         // HP changes
-        if let Some(msg) =
-            Self::format_stat_diff("HP", self.old.hp, new.hp, new.max_hp.map(|m| m as f64))
-        {
+        if let Some(msg) = Self::format_stat_diff(
+            &tr!("stats-hp"),
+            self.old.hp,
+            new.hp,
+            new.max_hp.map(|m| m as f64),
+        ) {
             messages.push(msg);
         }
 
         // MP changes
-        if let Some(msg) =
-            Self::format_stat_diff("MP", self.old.mp, new.mp, new.max_mp.map(|m| m as f64))
-        {
+        if let Some(msg) = Self::format_stat_diff(
+            &tr!("stats-mp"),
+            self.old.mp,
+            new.mp,
+            new.max_mp.map(|m| m as f64),
+        ) {
             messages.push(msg);
         }
 
         // Exp changes (only if level didn't change)
         if lvl_diff == 0 {
             if let Some(msg) = Self::format_stat_diff(
-                "Exp",
+                &tr!("stats-exp"),
                 self.old.exp,
                 new.exp,
                 new.to_next_level.map(|m| m as f64),
@@ -102,7 +138,7 @@ impl StatsCache {
         }
 
         // Gold changes
-        if let Some(msg) = Self::format_stat_diff("Gold", self.old.gp, new.gp, None) {
+        if let Some(msg) = Self::format_stat_diff(&tr!("stats-gold"), self.old.gp, new.gp, None) {
             messages.push(msg);
         }
 
@@ -112,6 +148,12 @@ impl StatsCache {
         messages
     }
 
+    /// Check whether a message of [`get_diff_messages`](Self::get_diff_messages)
+    /// reports a gain
+    pub fn is_gain(message: &str) -> bool {
+        message.contains(":+") || message.starts_with(&tr!("stats-level-up-label"))
+    }
+
     /// Format a stat difference message
     fn format_stat_diff(
         name: &str,
@@ -164,9 +206,23 @@ mod tests {
             to_next_level: Some(100),
             gp,
             lvl,
+            con: 0.0,
+            class: None,
         }
     }
 
+    #[test]
+    fn test_snapshot_freshness() {
+        let now = Utc::now();
+        let snapshot = StatsSnapshot {
+            fetched_at: now - Duration::seconds(30),
+            stats: test_stats(50.0, 50.0, 0.0, 100.0, 1),
+        };
+        assert!(snapshot.is_fresh(now, Duration::seconds(60)));
+        assert!(!snapshot.is_fresh(now, Duration::seconds(10)));
+        assert!(!snapshot.is_fresh(now, Duration::zero()));
+    }
+
     #[test]
     fn test_stats_diff_no_change() {
         let stats = test_stats(50.0, 50.0, 0.0, 100.0, 1);
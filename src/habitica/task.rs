@@ -1,7 +1,11 @@
-use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::habitica::content::ContentCatalog;
+
 /// Habitica task status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum HabiticaTaskStatus {
@@ -14,17 +18,158 @@ pub enum HabiticaTaskStatus {
 }
 
 /// Habitica task type
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum HabiticaTaskType {
+    #[default]
     Todo,
     Daily,
     Habit,
     Reward,
 }
 
+/// Character attribute a task trains
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HabiticaAttribute {
+    Str,
+    Int,
+    Con,
+    Per,
+}
+
+/// How often a daily repeats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// Weekdays a daily is active on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct WeekdayRepeat {
+    #[serde(default)]
+    pub m: bool,
+    #[serde(default)]
+    pub t: bool,
+    #[serde(default)]
+    pub w: bool,
+    #[serde(default)]
+    pub th: bool,
+    #[serde(default)]
+    pub f: bool,
+    #[serde(default)]
+    pub s: bool,
+    #[serde(default)]
+    pub su: bool,
+}
+
+impl WeekdayRepeat {
+    /// Active days in Monday-first order
+    pub const fn days(&self) -> [bool; 7] {
+        [self.m, self.t, self.w, self.th, self.f, self.s, self.su]
+    }
+}
+
+impl Default for WeekdayRepeat {
+    /// Habitica's default: every day of the week
+    fn default() -> Self {
+        WeekdayRepeat {
+            m: true,
+            t: true,
+            w: true,
+            th: true,
+            f: true,
+            s: true,
+            su: true,
+        }
+    }
+}
+
+/// Details of a user a group task is assigned to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct AssignedUser {
+    #[serde(rename = "assignedUsername", default)]
+    pub assigned_username: Option<String>,
+}
+
+/// Group plan information attached to a task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct GroupInfo {
+    /// Group the task belongs to, unset for personal tasks
+    #[serde(default)]
+    pub id: Option<Uuid>,
+
+    /// For a member's copy: the group task it was assigned from
+    #[serde(rename = "taskId", default)]
+    pub task_id: Option<Uuid>,
+
+    #[serde(rename = "assignedUsers", default)]
+    pub assigned_users: Vec<Uuid>,
+
+    #[serde(rename = "assignedUsersDetail", default)]
+    pub assigned_users_detail: HashMap<Uuid, AssignedUser>,
+}
+
+/// Reminder notification of a task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reminder {
+    pub id: Uuid,
+
+    /// For dailies: first day the reminder applies
+    #[serde(rename = "startDate", default, skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<DateTime<Utc>>,
+
+    /// When the reminder fires (for dailies only the time of day counts)
+    pub time: DateTime<Utc>,
+}
+
+/// Checklist item of a todo or daily
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    pub id: Uuid,
+
+    pub text: String,
+
+    #[serde(default)]
+    pub completed: bool,
+}
+
+/// Challenge a task was copied from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct ChallengeInfo {
+    /// Challenge the task belongs to, unset once the challenge is left
+    #[serde(default)]
+    pub id: Option<Uuid>,
+
+    #[serde(rename = "shortName", default)]
+    pub short_name: Option<String>,
+}
+
+impl GroupInfo {
+    /// Names of the assigned users, falling back to their IDs
+    pub fn assigned_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .assigned_users
+            .iter()
+            .map(|id| {
+                self.assigned_users_detail
+                    .get(id)
+                    .and_then(|user| user.assigned_username.clone())
+                    .unwrap_or_else(|| id.to_string())
+            })
+            .collect();
+        names.sort();
+        names
+    }
+}
+
 /// A task as represented in the Habitica API
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct HabiticaTask {
     /// Habitica's UUID for the task
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -33,6 +178,10 @@ pub struct HabiticaTask {
     /// Task text/title
     pub text: String,
 
+    /// User-defined short name, holding the Taskwarrior UUID of linked tasks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+
     /// Task notes/description
     #[serde(default)]
     pub notes: String,
@@ -44,6 +193,10 @@ pub struct HabiticaTask {
     /// Priority (0.1=trivial, 1=easy, 1.5=medium, 2=hard)
     pub priority: f64,
 
+    /// Character attribute the task trains
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attribute: Option<HabiticaAttribute>,
+
     /// Whether the task is completed
     #[serde(default)]
     pub completed: bool,
@@ -56,17 +209,148 @@ pub struct HabiticaTask {
     #[serde(rename = "updatedAt", skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<DateTime<Utc>>,
 
+    /// When a todo was last completed
+    #[serde(
+        rename = "dateCompleted",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub date_completed: Option<DateTime<Utc>>,
+
     /// For dailies: whether the task is due today
     #[serde(rename = "isDue", default, skip_serializing)]
     pub is_due: bool,
+
+    /// For dailies: repeat frequency
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency: Option<Frequency>,
+
+    /// For dailies: repeat every X days/weeks/months/years
+    #[serde(rename = "everyX", default, skip_serializing_if = "Option::is_none")]
+    pub every_x: Option<u32>,
+
+    /// For dailies: active weekdays
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat: Option<WeekdayRepeat>,
+
+    /// For monthly dailies: days of the month
+    #[serde(rename = "daysOfMonth", default, skip_serializing_if = "Vec::is_empty")]
+    pub days_of_month: Vec<u32>,
+
+    /// For monthly dailies: zero-based weeks of the month
+    #[serde(
+        rename = "weeksOfMonth",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub weeks_of_month: Vec<u32>,
+
+    /// For dailies: consecutive days completed, managed by Habitica
+    #[serde(default, skip_serializing)]
+    pub streak: Option<u32>,
+
+    /// For dailies: first day the schedule applies
+    #[serde(rename = "startDate", default, skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<DateTime<Utc>>,
+
+    /// Reminder notifications, left untouched on Habitica when `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reminders: Option<Vec<Reminder>>,
+
+    /// Checklist items, left untouched on Habitica when `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checklist: Option<Vec<ChecklistItem>>,
+
+    /// For habits: whether the positive (+) button is shown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub up: Option<bool>,
+
+    /// For habits: whether the negative (-) button is shown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub down: Option<bool>,
+
+    /// For habits: positive scores since the counter was last reset
+    #[serde(rename = "counterUp", default, skip_serializing)]
+    pub counter_up: Option<u32>,
+
+    /// For habits: negative scores since the counter was last reset
+    #[serde(rename = "counterDown", default, skip_serializing)]
+    pub counter_down: Option<u32>,
+
+    /// For rewards: gold price (other types use it for the task's score)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+
+    /// Group plan the task belongs to, managed by Habitica
+    #[serde(default, skip_serializing)]
+    pub group: Option<GroupInfo>,
+
+    /// Challenge the task comes from, managed by Habitica
+    #[serde(default, skip_serializing)]
+    pub challenge: Option<ChallengeInfo>,
+
+    /// Whether Habitica created the task itself, like the onboarding todos
+    #[serde(rename = "byHabitica", default, skip_serializing)]
+    pub by_habitica: bool,
 }
 
 impl HabiticaTask {
+    /// Group plan the task belongs to, if any
+    pub fn group_id(&self) -> Option<Uuid> {
+        self.group.as_ref().and_then(|group| group.id)
+    }
+
+    /// Taskwarrior UUID stored in the alias, if any
+    pub fn alias_uuid(&self) -> Option<Uuid> {
+        self.alias.as_deref().and_then(|alias| alias.parse().ok())
+    }
+
+    /// Challenge the task belongs to, if any
+    pub fn challenge_id(&self) -> Option<Uuid> {
+        self.challenge.as_ref().and_then(|challenge| challenge.id)
+    }
+
+    /// Name of the challenge the task belongs to, falling back to its ID
+    pub fn challenge_name(&self) -> Option<String> {
+        let challenge = self.challenge.as_ref()?;
+        let id = challenge.id?;
+        Some(
+            challenge
+                .short_name
+                .clone()
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| id.to_string()),
+        )
+    }
+
+    /// Comma-separated assignees of a group task, `None` if unassigned
+    pub fn assigned(&self) -> Option<String> {
+        let names = self.group.as_ref()?.assigned_names();
+        (!names.is_empty()).then(|| names.join(","))
+    }
+
+    /// Time of the first reminder
+    pub fn first_reminder(&self) -> Option<DateTime<Utc>> {
+        self.reminders
+            .as_ref()?
+            .first()
+            .map(|reminder| reminder.time)
+    }
+
     /// Get the effective status based on completion and daily due status
-    pub fn effective_status(&self) -> HabiticaTaskStatus {
+    pub fn effective_status(&self, day_start: DayStart) -> HabiticaTaskStatus {
+        self.status_at(day_start, Utc::now())
+    }
+
+    fn status_at(&self, day_start: DayStart, now: DateTime<Utc>) -> HabiticaTaskStatus {
         if self.task_type == HabiticaTaskType::Daily {
+            // Habitica's flags describe its current day, so a daily dated
+            // after it (synced before the day rolled over) is still to do
+            let upcoming = self
+                .date
+                .is_some_and(|date| day_start.local_date(date) > day_start.day_of(now));
             // Dailies are pending only if they're not completed AND are due
-            if !self.completed && self.is_due {
+            if upcoming || (!self.completed && self.is_due) {
                 HabiticaTaskStatus::Pending
             } else {
                 HabiticaTaskStatus::Completed
@@ -111,6 +395,48 @@ pub struct UserStats {
     pub to_next_level: Option<i32>,
     pub gp: f64,
     pub lvl: i32,
+    /// Allocated constitution points, which soften damage from missed dailies
+    #[serde(default)]
+    pub con: f64,
+    /// Character class (warrior, wizard, healer or rogue)
+    #[serde(default)]
+    pub class: Option<String>,
+}
+
+/// The account a set of credentials logs in to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    /// Login name, shown as `@username`
+    pub username: String,
+    /// Display name
+    pub name: String,
+    pub level: i32,
+    /// Character class, unset before level 10
+    pub class: Option<String>,
+}
+
+/// When the user's Habitica day rolls over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+pub struct DayStart {
+    /// Hour of the day (0-12) cron runs at
+    #[serde(rename = "dayStart", default)]
+    pub hour: u32,
+
+    /// Minutes to add to local time to get UTC, as in JavaScript
+    #[serde(rename = "timezoneOffset", default)]
+    pub timezone_offset: i32,
+}
+
+impl DayStart {
+    /// Calendar date of a moment in the user's timezone
+    pub fn local_date(&self, at: DateTime<Utc>) -> NaiveDate {
+        (at - Duration::minutes(i64::from(self.timezone_offset))).date_naive()
+    }
+
+    /// Habitica day a moment falls on, which starts at the custom day start
+    pub fn day_of(&self, at: DateTime<Utc>) -> NaiveDate {
+        self.local_date(at - Duration::hours(i64::from(self.hour)))
+    }
 }
 
 /// Item drop information
@@ -128,6 +454,27 @@ pub struct ItemDropTemp {
 #[derive(Debug, Clone, Deserialize)]
 pub struct ItemDropData {
     pub dialog: Option<String>,
+    #[serde(rename = "type", default)]
+    pub item_type: Option<String>,
+    #[serde(default)]
+    pub key: Option<String>,
+}
+
+impl ItemDropData {
+    /// Describe the drop, preferring the catalog's item name over the dialog
+    pub fn describe(&self, catalog: Option<&ContentCatalog>) -> Option<String> {
+        let name = match (catalog, &self.item_type, &self.key) {
+            (Some(catalog), Some(item_type), Some(key)) => catalog.item_name(item_type, key),
+            _ => None,
+        };
+
+        match (name, &self.dialog, &self.key) {
+            (Some(name), _, _) => Some(format!("You found a {}!", name)),
+            (None, Some(dialog), _) => Some(dialog.clone()),
+            (None, None, Some(key)) => Some(format!("You found a {}!", key)),
+            (None, None, None) => None,
+        }
+    }
 }
 
 impl ItemDrop {
@@ -135,7 +482,51 @@ impl ItemDrop {
         self.tmp
             .as_ref()
             .and_then(|t| t.drop.as_ref())
-            .and_then(|d| d.dialog.clone())
+            .and_then(|d| d.describe(None))
+    }
+}
+
+/// A webhook registered on the user's account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Webhook {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Uuid>,
+
+    /// Endpoint the events are posted to
+    pub url: String,
+
+    #[serde(default)]
+    pub label: String,
+
+    /// Event family, e.g. `taskActivity`
+    #[serde(rename = "type", default)]
+    pub webhook_type: String,
+
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Type-specific settings, like which task events are sent
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub options: serde_json::Value,
+}
+
+impl Webhook {
+    /// Enabled `taskActivity` webhook sent for every task change
+    pub fn task_activity(url: &str, label: &str) -> Self {
+        Webhook {
+            id: None,
+            url: url.to_string(),
+            label: label.to_string(),
+            webhook_type: "taskActivity".to_string(),
+            enabled: true,
+            options: serde_json::json!({
+                "created": true,
+                "updated": true,
+                "deleted": true,
+                "scored": true,
+                "checklistScored": false,
+            }),
+        }
     }
 }
 
@@ -152,18 +543,79 @@ pub struct ResponseWithStats<T> {
 }
 
 impl<T> ResponseWithStats<T> {
-    pub fn item_drop_message(&self) -> Option<String> {
-        self.tmp
-            .as_ref()
-            .and_then(|t| t.drop.as_ref())
-            .and_then(|d| d.dialog.clone())
+    /// Item dropped by this request, if any
+    pub fn item_drop(&self) -> Option<&ItemDropData> {
+        self.tmp.as_ref().and_then(|t| t.drop.as_ref())
+    }
+
+    pub fn item_drop_message(&self, catalog: Option<&ContentCatalog>) -> Option<String> {
+        self.item_drop().and_then(|d| d.describe(catalog))
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_drop_description() {
+        let drop = ItemDropData {
+            dialog: Some("You've found a Wolf Egg!".to_string()),
+            item_type: Some("Egg".to_string()),
+            key: Some("Wolf".to_string()),
+        };
+        let mut catalog = ContentCatalog::default();
+        catalog.items.insert(
+            "eggs".to_string(),
+            HashMap::from([("Wolf".to_string(), "Wolf Egg".to_string())]),
+        );
+
+        assert_eq!(
+            drop.describe(Some(&catalog)).as_deref(),
+            Some("You found a Wolf Egg!")
+        );
+        assert_eq!(
+            drop.describe(None).as_deref(),
+            Some("You've found a Wolf Egg!")
+        );
+
+        let bare = ItemDropData {
+            dialog: None,
+            ..drop
+        };
+        assert_eq!(bare.describe(None).as_deref(), Some("You found a Wolf!"));
+    }
+
+    #[test]
+    fn test_group_task_assignees() {
+        let json = r#"{
+            "text": "Dishes",
+            "type": "todo",
+            "priority": 1,
+            "group": {
+                "id": "6a4c1ea5-4bd0-4dc7-90a3-9e6d9a7a1b24",
+                "assignedUsers": [
+                    "0b3e3c0e-2b55-4f3b-9a63-0a3d2b9c4f11",
+                    "9f1d1c55-7c7a-4d8e-8d8e-3f0a5b2b6c22"
+                ],
+                "assignedUsersDetail": {
+                    "0b3e3c0e-2b55-4f3b-9a63-0a3d2b9c4f11": { "assignedUsername": "sam" }
+                }
+            }
+        }"#;
+        let task: HabiticaTask = serde_json::from_str(json).expect("Failed to parse");
+
+        assert!(task.group_id().is_some());
+        assert_eq!(
+            task.assigned().as_deref(),
+            Some("9f1d1c55-7c7a-4d8e-8d8e-3f0a5b2b6c22,sam")
+        );
+
+        let personal = HabiticaTask::default();
+        assert_eq!(personal.assigned(), None);
+    }
+
     #[test]
     fn test_habitica_task_effective_status() {
         let mut task = HabiticaTask {
@@ -176,23 +628,71 @@ mod tests {
             date: None,
             updated_at: None,
             is_due: false,
+            ..Default::default()
         };
 
         // Todo not completed should be pending
-        assert_eq!(task.effective_status(), HabiticaTaskStatus::Pending);
+        assert_eq!(
+            task.effective_status(DayStart::default()),
+            HabiticaTaskStatus::Pending
+        );
 
         // Todo completed should be completed
         task.completed = true;
-        assert_eq!(task.effective_status(), HabiticaTaskStatus::Completed);
+        assert_eq!(
+            task.effective_status(DayStart::default()),
+            HabiticaTaskStatus::Completed
+        );
 
         // Daily not due should be completed even if not completed
         task.task_type = HabiticaTaskType::Daily;
         task.completed = false;
         task.is_due = false;
-        assert_eq!(task.effective_status(), HabiticaTaskStatus::Completed);
+        assert_eq!(
+            task.effective_status(DayStart::default()),
+            HabiticaTaskStatus::Completed
+        );
 
         // Daily due and not completed should be pending
         task.is_due = true;
-        assert_eq!(task.effective_status(), HabiticaTaskStatus::Pending);
+        assert_eq!(
+            task.effective_status(DayStart::default()),
+            HabiticaTaskStatus::Pending
+        );
+    }
+
+    #[test]
+    fn test_daily_before_day_start() {
+        // UTC+2 with the day starting at 4am
+        let day_start: DayStart =
+            serde_json::from_str(r#"{"dayStart": 4, "timezoneOffset": -120}"#).unwrap();
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+
+        // Completed yesterday, now due today (local midnight, 22:00 UTC)
+        let task = HabiticaTask {
+            task_type: HabiticaTaskType::Daily,
+            completed: true,
+            is_due: true,
+            date: Some(at("2024-03-09T22:00:00Z")),
+            ..Default::default()
+        };
+
+        // 3am local: Habitica's day is still the 9th
+        let early = at("2024-03-10T01:00:00Z");
+        assert_eq!(
+            day_start.day_of(early),
+            NaiveDate::from_ymd_opt(2024, 3, 9).unwrap()
+        );
+        assert_eq!(
+            task.status_at(day_start, early),
+            HabiticaTaskStatus::Pending
+        );
+
+        // 5am local: the completion is today's
+        let later = at("2024-03-10T03:00:00Z");
+        assert_eq!(
+            task.status_at(day_start, later),
+            HabiticaTaskStatus::Completed
+        );
     }
 }
@@ -0,0 +1,81 @@
+# Sync-Ausgabe
+sync-start = Synchronisiere Aufgaben zwischen Taskwarrior und Habitica...
+sync-complete = Synchronisierung abgeschlossen!
+sync-task = Aufgabe: { $text }
+sync-habitica-task = Habitica-Aufgabe:    { $text }
+sync-taskwarrior-task = Taskwarrior-Aufgabe: { $text }
+sync-status-created-taskwarrior = Status: In Taskwarrior erstellt.
+sync-status-created-habitica = Status: In Habitica erstellt.
+sync-status-deleted-habitica = Status: In Habitica gelöscht.
+sync-status-deleted-taskwarrior = Status: In Taskwarrior gelöscht.
+sync-action-delete-habitica = Aktion: Lösche in Habitica.
+sync-status-expired = Status: Das Ablaufdatum (until) ist überschritten.
+sync-action-expire = Aktion: Lösche in Habitica. Setze den Status in Taskwarrior auf Gelöscht. Entferne die Habitica-ID.
+sync-status-filtered = Status: Passt nicht mehr zu habitica.filter.
+sync-action-remove = Aktion: Lösche in Habitica. Entferne die Habitica-ID.
+sync-status-superseded = Status: Durch eine neuere Instanz der wiederkehrenden Aufgabe ersetzt.
+sync-action-hand-on = Aktion: Übergebe die Habitica-Aufgabe an die neue Instanz. Entferne die Habitica-ID.
+sync-status-both = Status: Existiert in Habitica und Taskwarrior.
+sync-action-push = Aktion: Übertrage zu Habitica und setze die Habitica-ID in Taskwarrior.
+sync-action-import = Aktion: Importiere in Taskwarrior.
+sync-action-keep-completed = Aktion: In Taskwarrior bereits erledigt. Status bleibt Erledigt. Entferne die Habitica-ID.
+sync-action-mark-deleted = Aktion: Setze den Status in Taskwarrior auf Gelöscht. Entferne die Habitica-ID.
+sync-action-equal = Aktion: Aufgaben sind gleich. Nichts zu tun.
+sync-action-use-habitica = Aktion: Habitica-Aufgabe wurde zuletzt geändert. Aktualisiere in Taskwarrior.
+sync-action-use-taskwarrior = Aktion: Taskwarrior-Aufgabe wurde zuletzt geändert. Aktualisiere in Habitica.
+sync-status-conflict = Status: In Habitica und Taskwarrior geändert.
+sync-action-merge = Aktion: Mit dem externen Merge-Werkzeug zusammengeführt. Aktualisiere beide Seiten.
+sync-action-combine = Aktion: Übernehme die Änderungen beider Seiten feldweise. Aktualisiere beide Seiten.
+sync-action-skip = Aktion: Übersprungen. Nachfrage beim nächsten Sync.
+sync-action-defer = Aktion: Zurückgestellt. Mit `task2habitica resolve` eine Seite wählen.
+sync-note-challenge = Hinweis: Teil der Herausforderung { $challenge }, daher wird aus Taskwarrior nur der Status übernommen.
+sync-resumed = Unterbrochene Synchronisierung fortgesetzt: { $count } bereits mit Habitica synchronisierte Aufgaben importiert
+sync-progress-planning = Vergleiche Aufgaben...
+sync-reordered = { $count } Habitica-To-Dos nach Dringlichkeit sortiert
+sync-party-posted = Im Gruppenchat gepostet: { $message }
+sync-party-failed = Posten im Gruppenchat fehlgeschlagen: { $error }
+
+# Gasthaus
+inn-resting = Du ruhst im Gasthaus: verpasste Tagesaufgaben verursachen keinen Schaden.
+inn-not-resting = Du ruhst nicht im Gasthaus: verpasste Tagesaufgaben verursachen Schaden.
+
+# Werteänderungen
+stats-level-up-label = STUFENAUFSTIEG!
+stats-level-up = { stats-level-up-label } ({ $old } -> { $new })
+stats-level-lost = STUFE VERLOREN! ({ $old } -> { $new })
+stats-hp = LP
+stats-mp = MP
+stats-exp = EP
+stats-gold = Gold
+stats-streak = { $days } Tage in Folge bei "{ $task }"!
+stats-level = Stufe { $level }
+stats-level-class = Stufe { $level } { $class }
+stats-changed = Geändert um { $time }: { $changes }
+
+# Bericht
+report-subject = task2habitica-Bericht vom { $date }
+report-sent = Bericht an { $to } gesendet
+report-completed = In den letzten 24 Stunden erledigt: { $count }
+report-stats = Werte seit dem letzten Bericht:
+report-stats-none = Keine Änderungen
+report-waiting = Wartet auf Sync: { $count }
+report-push = Noch nicht bei Habitica: { $task }
+report-import = Noch nicht in Taskwarrior: { $task }
+report-deleted-habitica = Bei Habitica gelöscht: { $task }
+report-deleted-taskwarrior = In Taskwarrior gelöscht: { $task }
+report-expired = Abgelaufen: { $task }
+report-filtered = Passt nicht mehr zum Filter: { $task }
+report-superseded = Durch eine neuere Instanz ersetzt: { $task }
+report-changed-habitica = Bei Habitica geändert: { $task }
+report-changed-taskwarrior = In Taskwarrior geändert: { $task }
+report-changed-both = Auf beiden Seiten geändert: { $task }
+
+# Fehler
+error = Fehler: { $message }
+error-taskwarrior-not-found = Taskwarrior wurde nicht gefunden oder ist nicht ausführbar. Bitte installiere Taskwarrior 3.4.2 oder neuer.
+error-taskwarrior-too-old = Taskwarrior-Version { $version } ist zu alt. Version 3.4.2 oder neuer wird benötigt.
+error-invalid-credentials = Fehlende oder ungültige Habitica-Zugangsdaten. Setze die Umgebungsvariablen HABITICA_USER_ID und HABITICA_API_KEY oder habitica.user_id und habitica.api_key in der .taskrc
+error-habitica-api = Habitica-API-Fehler: { $details }
+error-http = HTTP-Anfrage fehlgeschlagen: { $details }
+error-config = Konfigurationsfehler: { $details }
+error-task-not-found = Aufgabe nicht gefunden: { $details }
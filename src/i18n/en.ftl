@@ -0,0 +1,81 @@
+# Sync narration
+sync-start = Syncing tasks between Taskwarrior and Habitica...
+sync-complete = Sync complete!
+sync-task = Task: { $text }
+sync-habitica-task = Habitica Task:    { $text }
+sync-taskwarrior-task = Taskwarrior Task: { $text }
+sync-status-created-taskwarrior = Status: Created in Taskwarrior.
+sync-status-created-habitica = Status: Created on Habitica.
+sync-status-deleted-habitica = Status: Deleted on Habitica.
+sync-status-deleted-taskwarrior = Status: Deleted in Taskwarrior.
+sync-action-delete-habitica = Action: Deleting on Habitica.
+sync-status-expired = Status: Its until date has passed.
+sync-action-expire = Action: Deleting on Habitica. Setting status to Deleted in Taskwarrior. Unsetting Habitica ID.
+sync-status-filtered = Status: No longer matches habitica.filter.
+sync-action-remove = Action: Deleting on Habitica. Unsetting Habitica ID.
+sync-status-superseded = Status: Replaced by a newer instance of the recurring task.
+sync-action-hand-on = Action: Handing its Habitica task on to the new instance. Unsetting Habitica ID.
+sync-status-both = Status: Exists on both Habitica and Taskwarrior.
+sync-action-push = Action: Pushing to Habitica and updating Habitica ID in Taskwarrior.
+sync-action-import = Action: Importing into Taskwarrior.
+sync-action-keep-completed = Action: Already completed in Taskwarrior. Leaving status as Completed. Unsetting Habitica ID.
+sync-action-mark-deleted = Action: Setting status to Deleted in Taskwarrior. Unsetting Habitica ID.
+sync-action-equal = Action: Tasks are equal. Doing nothing.
+sync-action-use-habitica = Action: Habitica task is most recently modified. Updating in Taskwarrior.
+sync-action-use-taskwarrior = Action: Taskwarrior task is most recently modified. Updating on Habitica.
+sync-status-conflict = Status: Changed on both Habitica and Taskwarrior.
+sync-action-merge = Action: Merged with the external merge tool. Updating both sides.
+sync-action-combine = Action: Keeping each side's changes field by field. Updating both sides.
+sync-action-skip = Action: Skipped. Asking again on the next sync.
+sync-action-defer = Action: Deferred. Run `task2habitica resolve` to pick a side.
+sync-note-challenge = Note: Part of the challenge { $challenge }, so only its status syncs from Taskwarrior.
+sync-resumed = Resumed an interrupted sync: imported { $count } tasks already synced to Habitica
+sync-progress-planning = Comparing tasks...
+sync-reordered = Reordered { $count } Habitica todos by urgency
+sync-party-posted = Posted to party chat: { $message }
+sync-party-failed = Failed to post to party chat: { $error }
+
+# Inn
+inn-resting = Resting in the inn: missed dailies won't damage you.
+inn-not-resting = Not resting in the inn: missed dailies will damage you.
+
+# Stat changes
+stats-level-up-label = LEVEL UP!
+stats-level-up = { stats-level-up-label } ({ $old } -> { $new })
+stats-level-lost = LEVEL LOST! ({ $old } -> { $new })
+stats-hp = HP
+stats-mp = MP
+stats-exp = Exp
+stats-gold = Gold
+stats-streak = { $days }-day streak on "{ $task }"!
+stats-level = Level { $level }
+stats-level-class = Level { $level } { $class }
+stats-changed = Changed at { $time }: { $changes }
+
+# Report
+report-subject = task2habitica report for { $date }
+report-sent = Sent report to { $to }
+report-completed = Completed in the last 24 hours: { $count }
+report-stats = Stats since the last report:
+report-stats-none = No changes
+report-waiting = Waiting for sync: { $count }
+report-push = Not on Habitica yet: { $task }
+report-import = Not in Taskwarrior yet: { $task }
+report-deleted-habitica = Deleted on Habitica: { $task }
+report-deleted-taskwarrior = Deleted in Taskwarrior: { $task }
+report-expired = Expired: { $task }
+report-filtered = No longer matches the filter: { $task }
+report-superseded = Replaced by a newer instance: { $task }
+report-changed-habitica = Changed on Habitica: { $task }
+report-changed-taskwarrior = Changed in Taskwarrior: { $task }
+report-changed-both = Changed on both sides: { $task }
+
+# Errors
+error = Error: { $message }
+error-taskwarrior-not-found = Taskwarrior not found or not executable. Please install Taskwarrior 3.4.2 or higher.
+error-taskwarrior-too-old = Taskwarrior version { $version } is too old. Version 3.4.2 or higher is required.
+error-invalid-credentials = Missing or malformed Habitica credentials. Set HABITICA_USER_ID and HABITICA_API_KEY environment variables, or habitica.user_id and habitica.api_key in .taskrc
+error-habitica-api = Habitica API error: { $details }
+error-http = HTTP request failed: { $details }
+error-config = Configuration error: { $details }
+error-task-not-found = Task not found: { $details }
@@ -0,0 +1,152 @@
+use std::{env, sync::OnceLock};
+
+use fluent_bundle::{concurrent::FluentBundle, FluentResource};
+pub use fluent_bundle::{FluentArgs, FluentValue};
+use unic_langid::LanguageIdentifier;
+
+/// Translations bundled with the binary, by language code
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("en.ftl")),
+    ("de", include_str!("de.ftl")),
+];
+
+/// Language used when no translation matches
+const FALLBACK_LANGUAGE: &str = "en";
+
+/// Translations for the selected language, backed by English
+struct Localizer {
+    bundles: Vec<FluentBundle<FluentResource>>,
+}
+
+impl Localizer {
+    fn new(language: &str) -> Self {
+        let mut codes = vec![language];
+        if language != FALLBACK_LANGUAGE {
+            codes.push(FALLBACK_LANGUAGE);
+        }
+        Localizer {
+            bundles: codes.into_iter().filter_map(build_bundle).collect(),
+        }
+    }
+
+    fn translate(&self, id: &str, args: Option<&FluentArgs>) -> String {
+        for bundle in &self.bundles {
+            let Some(pattern) = bundle.get_message(id).and_then(|msg| msg.value()) else {
+                continue;
+            };
+            let mut errors = Vec::new();
+            return bundle
+                .format_pattern(pattern, args, &mut errors)
+                .into_owned();
+        }
+        id.to_string()
+    }
+}
+
+fn build_bundle(code: &str) -> Option<FluentBundle<FluentResource>> {
+    let (_, source) = LOCALES.iter().find(|(lang, _)| *lang == code)?;
+    let langid: LanguageIdentifier = code.parse().ok()?;
+    let resource = FluentResource::try_new((*source).to_string()).ok()?;
+
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // Unicode isolation marks show up as garbage in most terminals
+    bundle.set_use_isolating(false);
+    bundle.add_resource(resource).ok()?;
+    Some(bundle)
+}
+
+static LOCALIZER: OnceLock<Localizer> = OnceLock::new();
+
+/// Select the output language
+///
+/// Uses the configured locale, falling back to `LC_ALL`, `LC_MESSAGES` and
+/// `LANG`. Only the first call has an effect; without one, output is English.
+pub fn init(configured: Option<&str>) {
+    let locale = configured.map(String::from).or_else(|| {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .find_map(|var| env::var(var).ok().filter(|v| !v.is_empty()))
+    });
+    let language = locale
+        .as_deref()
+        .map_or(FALLBACK_LANGUAGE, language_code)
+        .to_string();
+
+    let _ = LOCALIZER.set(Localizer::new(&language));
+}
+
+/// Language code of a locale like `de_DE.UTF-8`
+fn language_code(locale: &str) -> &str {
+    locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .filter(|code| !code.is_empty())
+        .unwrap_or(FALLBACK_LANGUAGE)
+}
+
+/// Translate a message, see [`tr!`](crate::tr)
+pub fn translate(id: &str, args: Option<&FluentArgs>) -> String {
+    LOCALIZER
+        .get_or_init(|| Localizer::new(FALLBACK_LANGUAGE))
+        .translate(id, args)
+}
+
+/// Translate a message id, with optional `name = value` arguments
+#[macro_export]
+macro_rules! tr {
+    ($id:expr) => {
+        $crate::i18n::translate($id, None)
+    };
+    ($id:expr, $($key:ident = $value:expr),+ $(,)?) => {{
+        let mut args = $crate::i18n::FluentArgs::new();
+        $(args.set(stringify!($key), $crate::i18n::FluentValue::from($value));)+
+        $crate::i18n::translate($id, Some(&args))
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_code() {
+        assert_eq!(language_code("de_DE.UTF-8"), "de");
+        assert_eq!(language_code("en-US"), "en");
+        assert_eq!(language_code("C"), "C");
+    }
+
+    #[test]
+    fn test_translate_with_fallback() {
+        let german = Localizer::new("de");
+        let mut args = FluentArgs::new();
+        args.set("text", "Einkaufen");
+        assert_eq!(
+            german.translate("sync-task", Some(&args)),
+            "Aufgabe: Einkaufen"
+        );
+
+        let unknown = Localizer::new("xx");
+        assert_eq!(unknown.translate("sync-complete", None), "Sync complete!");
+        assert_eq!(
+            unknown.translate("no-such-message", None),
+            "no-such-message"
+        );
+    }
+
+    #[test]
+    fn test_all_locales_have_every_message() {
+        let english_ids: Vec<&str> = include_str!("en.ftl")
+            .lines()
+            .filter(|line| line.starts_with(|c: char| c.is_ascii_lowercase()))
+            .filter_map(|line| line.split_once(" =").map(|(id, _)| id))
+            .collect();
+        assert!(english_ids.contains(&"sync-complete"));
+
+        for (code, _) in LOCALES {
+            let bundle = build_bundle(code).expect("translations must parse");
+            for id in &english_ids {
+                assert!(bundle.has_message(id), "{} lacks {}", code, id);
+            }
+        }
+    }
+}
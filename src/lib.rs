@@ -1,7 +1,13 @@
+#[cfg(feature = "cli")]
 pub mod commands;
 pub mod config;
 pub mod error;
 pub mod habitica;
+pub mod i18n;
+#[cfg(feature = "cli")]
+pub mod notify;
+#[cfg(feature = "cli")]
+pub mod output;
 pub mod sync;
 pub mod taskwarrior;
 
@@ -1,7 +1,22 @@
-use std::{env, process};
+use std::{
+    path::{Path, PathBuf},
+    process,
+    sync::Mutex,
+    time::Duration,
+};
 
-use clap::{Parser, Subcommand};
-use task2habitica::{commands, Config, Error};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use task2habitica::{
+    commands::{self, stats::MIN_WATCH_SECONDS, ExportFormat, SyncFormat, SyncMode, SyncOptions},
+    config::ColorChoice,
+    habitica::ScoreDirection,
+    i18n,
+    output::{self, Level},
+    taskwarrior::HookGuard,
+    Config, Error,
+};
+use tracing_subscriber::fmt::format::FmtSpan;
+use uuid::Uuid;
 
 /// Sync Taskwarrior tasks with Habitica
 #[derive(Parser)]
@@ -11,39 +26,426 @@ use task2habitica::{commands, Config, Error};
 struct Cli {
     #[command(subcommand)]
     command: Commands,
-    #[arg(short, long, global = true)]
-    verbose: bool,
+    /// More detail, `-vv` also logs requests like `--log-level debug`
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+    /// Only print warnings and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Stable, timestamped line output for cron logs
+    #[arg(long, global = true)]
+    plain: bool,
+    /// Color what changed in sync and hook output (default:
+    /// habitica.color, or auto)
+    #[arg(long, global = true, value_enum, value_name = "WHEN")]
+    color: Option<Color>,
+    /// Log requests, Taskwarrior calls and sync decisions with their timing
+    /// to stderr or the log file (error, warn, info, debug or trace)
+    #[arg(long, global = true, value_name = "LEVEL")]
+    log_level: Option<tracing::Level>,
+    /// Also write every message with a timestamp to this file, rotated once
+    /// it reaches 1 MiB (default: habitica.log.file)
+    #[arg(long, global = true, value_name = "PATH")]
+    log_file: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Add the UDAs to the taskrc, install the hooks and ask for credentials
+    Init {
+        /// Leave the hooks out, for syncing with `daemon --poll` only
+        #[arg(long)]
+        no_hooks: bool,
+    },
+    /// Walk through the credentials, note directory and sync preferences,
+    /// then offer to add the UDAs and install the hooks
+    Setup,
+    /// Install the on-add, on-modify and on-exit hooks, running this binary
+    InstallHooks,
+    /// Remove the hooks installed by task2habitica
+    UninstallHooks,
     Add,
     Modify,
     Exit,
-    Sync,
+    /// Push the changes spooled by the hooks in queue mode
+    Flush,
+    Sync {
+        /// Only push unlinked Taskwarrior tasks, skip reconciling linked ones
+        #[arg(long, conflicts_with = "full")]
+        only_new: bool,
+        /// Reconcile every linked task, not just those changed since the
+        /// last sync
+        #[arg(long)]
+        full: bool,
+        /// Uncheck dailies without asking, even with streak protection on
+        #[arg(long)]
+        force: bool,
+        /// Show tasks changed on both sides field by field and ask which
+        /// side to keep
+        #[arg(long)]
+        interactive: bool,
+        /// Link unlinked tasks that exist on both sides, matched by
+        /// description and due date, instead of copying them
+        #[arg(long, conflicts_with = "only_new")]
+        link_existing: bool,
+        /// Print what happened as text, or as one JSON object per line for
+        /// scripts
+        #[arg(
+            long,
+            value_enum,
+            default_value = "text",
+            conflicts_with = "interactive"
+        )]
+        format: OutputFormat,
+    },
+    /// Reconcile a single task and print a one-line result
+    Touch {
+        /// Taskwarrior ID or UUID
+        id: String,
+    },
+    /// Pick a side for each conflict deferred by syncs
+    Resolve,
+    /// Run a pomodoro timer, then score the pomodoro habit and annotate the
+    /// task
+    Pomodoro {
+        /// Taskwarrior ID or UUID
+        id: String,
+        /// Timer length in minutes (default: habitica.pomodoro.minutes)
+        #[arg(long)]
+        minutes: Option<u32>,
+    },
+    /// Delete Habitica todos of tasks finished long ago and unlink them
+    Prune {
+        /// Minimum age since completion or deletion, e.g. 90d, 12w or 36h
+        #[arg(long)]
+        older_than: String,
+    },
+    /// Delete the Habitica tasks written by task2habitica and unlink all
+    /// tasks, to stop using it
+    Purge {
+        /// Really delete, instead of only counting what would go
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Restore lost links to Habitica tasks by alias or exact description
+    Relink,
+    /// Pair a Taskwarrior task with a Habitica task by hand
+    Link {
+        /// Taskwarrior ID or UUID
+        id: String,
+        /// Habitica task ID
+        habitica_id: Uuid,
+        /// Sync the pair right away
+        #[arg(long)]
+        sync: bool,
+    },
+    /// Delete completed Habitica todos that are also completed in Taskwarrior
+    ClearCompleted {
+        /// Only todos completed longer ago than this, e.g. 30d, 4w or 36h
+        #[arg(long)]
+        older_than: Option<String>,
+    },
+    /// Unlink todos completed long ago and leave them out of future syncs
+    Archive {
+        /// Minimum age since completion, e.g. 180d, 26w or 36h
+        #[arg(long)]
+        older_than: String,
+    },
+    /// Write all Habitica tasks as Taskwarrior import JSON, or the linked
+    /// pairs as JSON or CSV
+    Export {
+        /// Output file, stdout for json and csv when left out
+        file: Option<PathBuf>,
+        /// taskwarrior for import JSON, json or csv for one record per
+        /// linked pair
+        #[arg(long, value_enum, default_value = "taskwarrior")]
+        format: ExportOutput,
+    },
+    /// Write Habitica dailies and dated todos to an iCalendar file
+    Ical {
+        /// Output .ics file
+        file: PathBuf,
+    },
+    /// Create tasks from a CSV or JSON file on both sides, already linked,
+    /// or without a file, pull all unlinked Habitica tasks into Taskwarrior
+    Import {
+        /// CSV or JSON file with description, due, difficulty and type
+        file: Option<PathBuf>,
+        /// Only pull Habitica tasks of this type
+        #[arg(long = "type", value_enum, conflicts_with = "file")]
+        task_type: Option<HabiticaType>,
+        /// Project for the pulled tasks, instead of habitica.sync.project
+        #[arg(long, conflicts_with = "file")]
+        project: Option<String>,
+        /// Tag to add to the pulled tasks, can be repeated
+        #[arg(long = "tag", conflicts_with = "file")]
+        tags: Vec<String>,
+    },
+    /// Print a digest of completed tasks, stat gains and pending sync changes
+    Report {
+        /// Email the digest instead of printing it
+        #[arg(long)]
+        email: bool,
+    },
+    /// Score the Habitica task linked to a task and show the stat changes
+    Score {
+        /// Taskwarrior ID or UUID
+        id: String,
+        /// Score the task up or down
+        #[arg(value_enum, default_value = "up")]
+        direction: Direction,
+    },
+    /// Buy Habitica rewards
+    Reward {
+        #[command(subcommand)]
+        action: RewardAction,
+    },
+    /// Manage Habitica webhooks
+    Webhook {
+        #[command(subcommand)]
+        action: WebhookAction,
+    },
+    /// Check the Habitica credentials
+    Auth {
+        #[command(subcommand)]
+        action: AuthAction,
+    },
+    /// Show the character's level, class, HP, MP, experience and gold
+    Stats {
+        /// Fetch them again every this many seconds, 10 or more, until
+        /// interrupted
+        #[arg(
+            long,
+            value_name = "SECONDS",
+            num_args = 0..=1,
+            default_missing_value = "60",
+            value_parser = clap::value_parser!(u64).range(MIN_WATCH_SECONDS..)
+        )]
+        watch: Option<u64>,
+    },
+    /// Show the last sync, linked tasks and anything waiting to be synced,
+    /// without contacting Habitica
+    Status,
+    /// List what is due today on both sides, flagging unlinked items
+    Today,
+    /// Estimate the HP lost at cron to today's unfinished dailies
+    Damage,
+    /// Show the actions recent syncs took, from the sync journal
+    Log {
+        /// Number of entries to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Only entries about this task (Taskwarrior UUID or Habitica ID)
+        #[arg(long)]
+        task: Option<Uuid>,
+        /// List the fields each action changed
+        #[arg(long)]
+        details: bool,
+    },
+    /// Start or stop resting in the inn
+    Inn {
+        #[arg(value_enum)]
+        state: InnState,
+    },
+    /// Sync periodically, serving a D-Bus interface when built with `dbus`
+    Daemon {
+        /// Minutes between syncs
+        #[arg(long, default_value_t = commands::daemon::DEFAULT_INTERVAL_MINUTES)]
+        interval: u64,
+        /// Also sync within this many seconds of a Taskwarrior change, for
+        /// use without hooks
+        #[arg(long, value_name = "SECONDS", num_args = 0..=1, default_missing_value = "60")]
+        poll: Option<u64>,
+    },
+    /// Serve sync, list, link and score operations over JSON-RPC
+    Rpc {
+        /// Unix socket to listen on (default: a socket in data.location)
+        #[arg(long, conflicts_with = "listen")]
+        socket: Option<PathBuf>,
+        /// Listen on a TCP address instead, e.g. 127.0.0.1:7487; requests
+        /// must carry habitica.rpc.token
+        #[arg(long)]
+        listen: Option<String>,
+    },
 }
 
-/// Check if we're running inside a sync operation
-fn is_sync_running() -> bool {
-    env::var("TASK2HABITICA_RUNNING").is_ok()
+#[derive(Subcommand)]
+enum RewardAction {
+    /// Buy a reward and report the gold spent
+    Buy {
+        /// Reward name or Habitica ID
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum WebhookAction {
+    /// Register a webhook sent on every task change
+    Add {
+        /// Endpoint receiving the events
+        url: String,
+        /// Label shown on Habitica
+        #[arg(long, default_value = "task2habitica")]
+        label: String,
+    },
+    /// List registered webhooks
+    List,
+    /// Remove a webhook
+    Remove {
+        /// Webhook ID, as shown by `webhook list`
+        id: Uuid,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuthAction {
+    /// Log in and show the account, level and rate limit
+    Verify,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum InnState {
+    On,
+    Off,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Color {
+    Auto,
+    Always,
+    Never,
+}
+
+impl Color {
+    const fn choice(self) -> ColorChoice {
+        match self {
+            Color::Auto => ColorChoice::Auto,
+            Color::Always => ColorChoice::Always,
+            Color::Never => ColorChoice::Never,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Direction {
+    Up,
+    Down,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum HabiticaType {
+    Todos,
+    Dailys,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportOutput {
+    Taskwarrior,
+    Json,
+    Csv,
 }
 
-/// Set environment variable to indicate sync is running
-fn set_sync_env() {
-    env::set_var("TASK2HABITICA_RUNNING", "1");
+/// Check if another task2habitica process is writing to Taskwarrior
+fn hooks_paused(config: &Config) -> bool {
+    HookGuard::is_held(&config.hook_guard_path())
+}
+
+/// Keep the hooks quiet until the returned guard is dropped
+fn pause_hooks(config: &Config) -> Result<HookGuard, Error> {
+    HookGuard::acquire(&config.hook_guard_path())
+}
+
+/// Copy messages to the log file and log spans as they close, so each one
+/// shows how long it took
+///
+/// A log file that can't be opened is only warned about, so hooks keep
+/// working.
+fn init_logging(log_file: Option<&Path>, level: Option<tracing::Level>) {
+    let file = log_file.and_then(|path| match output::log_to(path) {
+        Ok(file) => Some(file),
+        Err(err) => {
+            output::print(
+                Level::Warn,
+                format_args!("Could not open log file {}: {}", path.display(), err),
+            );
+            None
+        }
+    });
+
+    let Some(level) = level else {
+        return;
+    };
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_span_events(FmtSpan::CLOSE);
+    match file {
+        Some(file) => subscriber
+            .with_ansi(false)
+            .with_writer(Mutex::new(file))
+            .init(),
+        None => subscriber.with_writer(std::io::stderr).init(),
+    }
 }
 
 fn run() -> Result<(), Error> {
     let cli = Cli::parse();
+    output::set_plain(cli.plain);
+    output::set_quiet(cli.quiet);
+
+    let log_level = cli
+        .log_level
+        .or_else(|| (cli.verbose > 1).then_some(tracing::Level::DEBUG));
 
-    // Load configuration
-    let config = Config::load(cli.verbose)?;
+    // Setup runs before the configuration is complete
+    if matches!(
+        cli.command,
+        Commands::Init { .. } | Commands::Setup | Commands::InstallHooks | Commands::UninstallHooks
+    ) {
+        init_logging(cli.log_file.as_deref(), log_level);
+        return match cli.command {
+            Commands::Init { no_hooks } => commands::handle_init(!no_hooks),
+            Commands::Setup => commands::handle_setup(),
+            Commands::InstallHooks => commands::handle_install_hooks(),
+            _ => commands::handle_uninstall_hooks(),
+        };
+    }
+
+    // Load configuration, whose errors make it to a log file given here
+    let config = Config::load(cli.verbose > 0);
+    let log_file = cli.log_file.clone().or_else(|| {
+        config
+            .as_ref()
+            .ok()
+            .and_then(|config| config.log_file.clone())
+    });
+    init_logging(log_file.as_deref(), log_level);
+    let config = config?;
+    output::set_color(cli.color.map_or(config.color, Color::choice));
+    // Plain output stays English so log parsers can rely on it
+    i18n::init(if cli.plain {
+        Some("en")
+    } else {
+        config.locale.as_deref()
+    });
 
     // Handle commands
     match cli.command {
+        Commands::Init { .. }
+        | Commands::Setup
+        | Commands::InstallHooks
+        | Commands::UninstallHooks => {
+            unreachable!("handled before loading the configuration")
+        }
+
         Commands::Add => {
             // Skip if sync is running
-            if is_sync_running() {
+            if hooks_paused(&config) {
                 // Just pass through the input
                 use std::io::{self, BufRead};
                 let stdin = io::stdin();
@@ -58,7 +460,7 @@ fn run() -> Result<(), Error> {
 
         Commands::Modify => {
             // Skip if sync is running
-            if is_sync_running() {
+            if hooks_paused(&config) {
                 // Just pass through the new task
                 use std::io::{self, BufRead};
                 let stdin = io::stdin();
@@ -76,10 +478,201 @@ fn run() -> Result<(), Error> {
             commands::handle_exit(&config)?;
         }
 
-        Commands::Sync => {
-            // Set environment variable to prevent hooks from running during sync
-            set_sync_env();
-            commands::handle_sync(&config)?;
+        Commands::Flush => {
+            // A running sync pushes the same tasks, the next flush takes over
+            if hooks_paused(&config) {
+                return Ok(());
+            }
+            let _guard = pause_hooks(&config)?;
+            commands::handle_flush(&config)?;
+        }
+
+        Commands::Sync {
+            only_new,
+            full,
+            force,
+            interactive,
+            link_existing,
+            format,
+        } => {
+            let mode = match (only_new, full) {
+                (true, _) => SyncMode::OnlyNew,
+                (_, true) => SyncMode::Full,
+                _ => SyncMode::Incremental,
+            };
+            // Keep hooks of other task processes quiet during sync
+            let _guard = pause_hooks(&config)?;
+            let options = SyncOptions {
+                force,
+                interactive,
+                link_existing,
+            };
+            let format = match format {
+                OutputFormat::Text => SyncFormat::Text,
+                OutputFormat::Json => SyncFormat::Json,
+            };
+            commands::handle_sync(&config, mode, options, format)?;
+        }
+
+        Commands::Touch { id } => {
+            // Same as sync, limited to one task
+            let _guard = pause_hooks(&config)?;
+            commands::handle_touch(&config, &id)?;
+        }
+
+        Commands::Resolve => {
+            let _guard = pause_hooks(&config)?;
+            commands::handle_resolve(&config)?;
+        }
+
+        Commands::Pomodoro { id, minutes } => {
+            // The annotation is imported with hooks off, and pausing them
+            // for the whole timer would hold up other task processes
+            commands::handle_pomodoro(&config, &id, minutes)?;
+        }
+
+        Commands::Prune { older_than } => {
+            // Unlinking imports into Taskwarrior, so keep the hooks quiet
+            let _guard = pause_hooks(&config)?;
+            commands::handle_prune(&config, &older_than)?;
+        }
+
+        Commands::Purge { yes } => {
+            // Unlinking imports into Taskwarrior, so keep the hooks quiet
+            let _guard = pause_hooks(&config)?;
+            commands::handle_purge(&config, yes)?;
+        }
+
+        Commands::Relink => {
+            // Relinking imports into Taskwarrior, so keep the hooks quiet
+            let _guard = pause_hooks(&config)?;
+            commands::handle_relink(&config)?;
+        }
+
+        Commands::Link {
+            id,
+            habitica_id,
+            sync,
+        } => {
+            let _guard = pause_hooks(&config)?;
+            commands::handle_link(&config, &id, habitica_id, sync)?;
+        }
+
+        Commands::ClearCompleted { older_than } => {
+            // Unlinking imports into Taskwarrior, so keep the hooks quiet
+            let _guard = pause_hooks(&config)?;
+            commands::handle_clear_completed(&config, older_than.as_deref())?;
+        }
+
+        Commands::Archive { older_than } => {
+            // Same as prune, without touching Habitica
+            let _guard = pause_hooks(&config)?;
+            commands::handle_archive(&config, &older_than)?;
+        }
+
+        Commands::Export { file, format } => {
+            let format = match format {
+                ExportOutput::Taskwarrior => ExportFormat::Taskwarrior,
+                ExportOutput::Json => ExportFormat::Json,
+                ExportOutput::Csv => ExportFormat::Csv,
+            };
+            commands::handle_export(&config, file.as_deref(), format)?;
+        }
+
+        Commands::Ical { file } => {
+            commands::handle_ical(&config, &file)?;
+        }
+
+        Commands::Import {
+            file,
+            task_type,
+            project,
+            tags,
+        } => {
+            // Tasks are linked before import, so the add hook must not push them
+            let _guard = pause_hooks(&config)?;
+            if let Some(file) = file {
+                commands::handle_import(&config, &file)?;
+            } else {
+                let types: &[&str] = match task_type {
+                    Some(HabiticaType::Todos) => &["todos"],
+                    Some(HabiticaType::Dailys) => &["dailys"],
+                    None => &["todos", "dailys"],
+                };
+                commands::handle_import_habitica(&config, types, project.as_deref(), &tags)?;
+            }
+        }
+
+        Commands::Report { email } => {
+            commands::handle_report(&config, email)?;
+        }
+
+        Commands::Score { id, direction } => {
+            let direction = match direction {
+                Direction::Up => ScoreDirection::Up,
+                Direction::Down => ScoreDirection::Down,
+            };
+            commands::handle_score(&config, &id, direction)?;
+        }
+
+        Commands::Reward {
+            action: RewardAction::Buy { name },
+        } => {
+            commands::handle_reward_buy(&config, &name)?;
+        }
+
+        Commands::Webhook { action } => match action {
+            WebhookAction::Add { url, label } => {
+                commands::handle_webhook_add(&config, &url, &label)?;
+            }
+            WebhookAction::List => commands::handle_webhook_list(&config)?,
+            WebhookAction::Remove { id } => commands::handle_webhook_remove(&config, id)?,
+        },
+
+        Commands::Auth {
+            action: AuthAction::Verify,
+        } => {
+            commands::handle_auth_verify(&config)?;
+        }
+
+        Commands::Stats { watch } => {
+            commands::handle_stats(&config, watch)?;
+        }
+
+        Commands::Status => {
+            commands::handle_status(&config)?;
+        }
+
+        Commands::Today => {
+            commands::handle_today(&config)?;
+        }
+
+        Commands::Damage => {
+            commands::handle_damage(&config)?;
+        }
+
+        Commands::Log {
+            limit,
+            task,
+            details,
+        } => {
+            commands::handle_log(&config, limit, task, details)?;
+        }
+
+        Commands::Inn { state } => {
+            commands::handle_inn(&config, matches!(state, InnState::On))?;
+        }
+
+        Commands::Daemon { interval, poll } => {
+            // Hooks are paused for each sync run only
+            commands::handle_daemon(&config, interval, poll.map(Duration::from_secs))?;
+        }
+
+        Commands::Rpc { socket, listen } => {
+            // Requests writing to Taskwarrior pause the hooks while they
+            // run, the server itself stays out of the way of other task
+            // processes
+            commands::handle_rpc(&config, socket.as_deref(), listen.as_deref())?;
         }
     }
 
@@ -89,7 +682,7 @@ fn run() -> Result<(), Error> {
 fn main() {
     if let Err(err) = run() {
         // Print error message
-        eprintln!("Error: {}", err);
+        output::print(Level::Error, format_args!("{}", err.localized()));
 
         // Exit with error code
         process::exit(1);
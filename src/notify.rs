@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use reqwest::blocking::Client;
+use serde_json::json;
+
+use crate::{config::Config, error::Result, say_err, sync::SyncSummary};
+
+/// Something worth telling the user about
+#[derive(Debug)]
+pub enum Notification<'a> {
+    /// A sync run finished
+    SyncCompleted(&'a SyncSummary),
+    /// The user gained a level (message from the stats diff)
+    LevelUp(&'a str),
+    /// A sync run failed
+    SyncFailed(&'a str),
+}
+
+impl Notification<'_> {
+    /// Machine-readable event name for webhooks
+    pub const fn event(&self) -> &'static str {
+        match self {
+            Notification::SyncCompleted(_) => "sync_completed",
+            Notification::LevelUp(_) => "level_up",
+            Notification::SyncFailed(_) => "sync_failed",
+        }
+    }
+
+    /// Short headline
+    pub const fn title(&self) -> &'static str {
+        match self {
+            Notification::SyncCompleted(_) => "Habitica sync complete",
+            Notification::LevelUp(_) => "Level up!",
+            Notification::SyncFailed(_) => "Habitica sync failed",
+        }
+    }
+
+    /// Human-readable body
+    pub fn message(&self) -> String {
+        match self {
+            Notification::SyncCompleted(summary) => format!(
+                "{} pushed, {} imported, {} deleted, {} updated in Taskwarrior, {} updated on Habitica",
+                summary.pushed,
+                summary.imported,
+                summary.deleted,
+                summary.updated_taskwarrior,
+                summary.updated_habitica
+            ),
+            Notification::LevelUp(message) | Notification::SyncFailed(message) => {
+                (*message).to_string()
+            }
+        }
+    }
+}
+
+/// Sends notifications to an ntfy topic and/or a generic webhook
+pub struct Notifier {
+    client: Client,
+    ntfy_url: Option<String>,
+    webhook_url: Option<String>,
+}
+
+impl Notifier {
+    /// Create a notifier, `None` if no target is configured
+    pub fn new(config: &Config) -> Result<Option<Self>> {
+        if config.notify_ntfy_url.is_none() && config.notify_webhook_url.is_none() {
+            return Ok(None);
+        }
+
+        let client = Client::builder().timeout(Duration::from_secs(10)).build()?;
+
+        Ok(Some(Notifier {
+            client,
+            ntfy_url: config.notify_ntfy_url.clone(),
+            webhook_url: config.notify_webhook_url.clone(),
+        }))
+    }
+
+    /// Deliver a notification to every configured target
+    pub fn send(&self, notification: &Notification) -> Result<()> {
+        if let Some(url) = &self.ntfy_url {
+            self.client
+                .post(url)
+                .header("Title", notification.title())
+                .header("Tags", notification.event())
+                .body(notification.message())
+                .send()?
+                .error_for_status()?;
+        }
+
+        if let Some(url) = &self.webhook_url {
+            let mut payload = json!({
+                "event": notification.event(),
+                "title": notification.title(),
+                "message": notification.message(),
+            });
+            if let Notification::SyncCompleted(summary) = notification {
+                payload["summary"] = serde_json::to_value(summary)?;
+            }
+
+            self.client
+                .post(url)
+                .json(&payload)
+                .send()?
+                .error_for_status()?;
+        }
+
+        Ok(())
+    }
+
+    /// Deliver a notification, reporting delivery problems without failing
+    pub fn send_or_warn(&self, notification: &Notification) {
+        if let Err(err) = self.send(notification) {
+            say_err!("Failed to send notification: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notifier_disabled_without_targets() {
+        let config = Config::for_tests();
+        assert!(Notifier::new(&config).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_sync_completed_message() {
+        let summary = SyncSummary {
+            pushed: 2,
+            updated_habitica: 1,
+            ..Default::default()
+        };
+        let notification = Notification::SyncCompleted(&summary);
+
+        assert_eq!(notification.event(), "sync_completed");
+        assert_eq!(
+            notification.message(),
+            "2 pushed, 0 imported, 0 deleted, 0 updated in Taskwarrior, 1 updated on Habitica"
+        );
+    }
+}
@@ -0,0 +1,340 @@
+use std::{
+    env,
+    fmt::{self, Write as _},
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, IsTerminal, Write as _},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::config::ColorChoice;
+
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+static STDOUT_RESERVED: AtomicBool = AtomicBool::new(false);
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// File every message is copied to, see [`log_to`]
+static LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+
+/// Size from which the log file is rotated when opened
+pub const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+/// Rotated log files kept, as `<file>.1` (newest) to `<file>.3`
+const KEPT_LOGS: u32 = 3;
+
+/// Held while a question is on the terminal, so sync workers ask one at a time
+static PROMPT: Mutex<()> = Mutex::new(());
+
+/// Switch all messages to the plain format
+///
+/// Plain output is meant for cron logs: every non-empty line is printed as
+/// `<UTC timestamp> <LEVEL> <text>` without indentation, blank lines or
+/// progress updates.
+pub fn set_plain(plain: bool) {
+    PLAIN.store(plain, Ordering::Relaxed);
+}
+
+/// Check whether plain output is enabled
+pub fn is_plain() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+/// Keep stdout for machine-readable output
+///
+/// Messages then go to stderr, so only what [`emit`] prints ends up on
+/// stdout.
+pub fn reserve_stdout(reserved: bool) {
+    STDOUT_RESERVED.store(reserved, Ordering::Relaxed);
+}
+
+/// Print a JSON value on a line of its own to stdout
+pub fn emit(value: &serde_json::Value) {
+    println!("{}", value);
+}
+
+/// Turn colors on or off, plain output is never colored
+pub fn set_color(choice: ColorChoice) {
+    let color = !is_plain()
+        && match choice {
+            ColorChoice::Auto => io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        };
+    COLOR.store(color, Ordering::Relaxed);
+}
+
+/// Kind of change a colored message reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    /// Green
+    Created,
+    /// Yellow
+    Updated,
+    /// Red
+    Deleted,
+    /// Cyan, for stat gains
+    Gain,
+}
+
+impl Tone {
+    const fn ansi_code(self) -> &'static str {
+        match self {
+            Tone::Created => "32",
+            Tone::Updated => "33",
+            Tone::Deleted => "31",
+            Tone::Gain => "36",
+        }
+    }
+}
+
+/// Color text for the kind of change it reports, if colors are on
+pub fn paint(text: &str, tone: Tone) -> String {
+    if COLOR.load(Ordering::Relaxed) {
+        format!("\x1b[{}m{}\x1b[0m", tone.ansi_code(), text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Remove the color codes [`paint`] adds
+fn strip_colors(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut in_code = false;
+    for c in text.chars() {
+        match (in_code, c) {
+            (false, '\x1b') => in_code = true,
+            (false, _) => stripped.push(c),
+            (true, 'm') => in_code = false,
+            (true, _) => {}
+        }
+    }
+    stripped
+}
+
+/// Leave out informational messages, only warnings and errors are printed
+///
+/// The log file still gets everything.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Copy every message to a log file as plain lines
+///
+/// The file is rotated first if it has grown past [`MAX_LOG_SIZE`]. Returns a
+/// second handle for other writers, such as the request log.
+pub fn log_to(path: &Path) -> io::Result<File> {
+    rotate_log(path, MAX_LOG_SIZE)?;
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let other = file.try_clone()?;
+    *LOG_FILE
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(file);
+    Ok(other)
+}
+
+/// Move a log file of at least `max_size` bytes to `<file>.1`, shifting older
+/// ones up and dropping the oldest
+fn rotate_log(path: &Path, max_size: u64) -> io::Result<()> {
+    if fs::metadata(path).map_or(true, |metadata| metadata.len() < max_size) {
+        return Ok(());
+    }
+    let rotated = |n: u32| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    };
+    for n in (1..KEPT_LOGS).rev() {
+        if rotated(n).exists() {
+            fs::rename(rotated(n), rotated(n + 1))?;
+        }
+    }
+    fs::rename(path, rotated(1))
+}
+
+/// Append a message to the log file, if there is one
+fn write_log(level: Level, args: fmt::Arguments) {
+    let mut log_file = LOG_FILE
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if let Some(file) = log_file.as_mut() {
+        // Logging must never make a command fail
+        let text = strip_colors(&args.to_string());
+        let _ = file.write_all(format_plain(Utc::now(), level, &text).as_bytes());
+    }
+}
+
+/// Severity shown in plain output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+}
+
+/// Format a message as plain lines, one per non-empty line of `text`
+pub fn format_plain(now: DateTime<Utc>, level: Level, text: &str) -> String {
+    let timestamp = now.format("%Y-%m-%dT%H:%M:%SZ");
+    let mut out = String::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let _ = writeln!(out, "{} {} {}", timestamp, level.as_str(), line);
+    }
+    out
+}
+
+/// Print a message, see [`say!`](crate::say) and [`say_err!`](crate::say_err)
+pub fn print(level: Level, args: fmt::Arguments) {
+    write_log(level, args);
+    if level == Level::Info && QUIET.load(Ordering::Relaxed) {
+        return;
+    }
+    let to_stdout = level == Level::Info && !STDOUT_RESERVED.load(Ordering::Relaxed);
+    if is_plain() {
+        let text = format_plain(Utc::now(), level, &args.to_string());
+        if to_stdout {
+            print!("{}", text);
+        } else {
+            eprint!("{}", text);
+        }
+    } else if to_stdout {
+        println!("{}", args);
+    } else {
+        eprintln!("{}", args);
+    }
+}
+
+/// Ask a yes/no question on the terminal, defaulting to no
+///
+/// Goes through `/dev/tty` so it also works from Taskwarrior hooks, whose
+/// stdin carries the task JSON. Without a terminal, or with plain output,
+/// the answer is no.
+pub fn confirm(question: &str) -> bool {
+    read_answer(&format!("{} [y/N] ", question)).is_some_and(|answer| is_yes(&answer))
+}
+
+/// Ask for a line of text on the terminal
+///
+/// Reads from `/dev/tty` like [`confirm`]. Gives `None` without a terminal,
+/// with plain output, or when the answer is empty.
+pub fn ask(question: &str) -> Option<String> {
+    let answer = read_answer(&format!("{}: ", question))?;
+    let answer = answer.trim();
+    (!answer.is_empty()).then(|| answer.to_string())
+}
+
+/// Print a prompt on the terminal and read the answer
+fn read_answer(prompt: &str) -> Option<String> {
+    if is_plain() {
+        return None;
+    }
+    let _prompt = PROMPT
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    write!(tty, "{}", prompt).and_then(|()| tty.flush()).ok()?;
+    let mut answer = String::new();
+    BufReader::new(tty).read_line(&mut answer).ok()?;
+    Some(answer)
+}
+
+/// Check whether an answer to [`confirm`] means yes
+fn is_yes(answer: &str) -> bool {
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Print a message to stdout, honouring plain output
+#[macro_export]
+macro_rules! say {
+    () => {
+        $crate::output::print($crate::output::Level::Info, format_args!(""))
+    };
+    ($($arg:tt)*) => {
+        $crate::output::print($crate::output::Level::Info, format_args!($($arg)*))
+    };
+}
+
+/// Print a warning to stderr, honouring plain output
+#[macro_export]
+macro_rules! say_err {
+    ($($arg:tt)*) => {
+        $crate::output::print($crate::output::Level::Warn, format_args!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn test_format_plain() {
+        let now = Utc.with_ymd_and_hms(2026, 1, 18, 18, 46, 24).unwrap();
+        assert_eq!(
+            format_plain(now, Level::Info, "Task: Buy milk\n    Action: Importing\n\n"),
+            "2026-01-18T18:46:24Z INFO Task: Buy milk\n2026-01-18T18:46:24Z INFO Action: Importing\n"
+        );
+        assert_eq!(format_plain(now, Level::Warn, ""), "");
+    }
+
+    #[test]
+    fn test_strip_colors() {
+        assert_eq!(
+            strip_colors("    \x1b[32mAction: Pushing\x1b[0m (1/3)"),
+            "    Action: Pushing (1/3)"
+        );
+        assert_eq!(strip_colors("Exp:+5"), "Exp:+5");
+    }
+
+    #[test]
+    fn test_rotate_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("task2habitica.log");
+        let rotated = |n: u32| dir.path().join(format!("task2habitica.log.{}", n));
+
+        rotate_log(&path, 4).unwrap();
+        fs::write(&path, "abc").unwrap();
+        rotate_log(&path, 4).unwrap();
+        assert!(path.exists());
+
+        for text in ["first", "second", "third", "fourth"] {
+            fs::write(&path, text).unwrap();
+            rotate_log(&path, 4).unwrap();
+        }
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(rotated(1)).unwrap(), "fourth");
+        assert_eq!(fs::read_to_string(rotated(3)).unwrap(), "second");
+        assert!(!rotated(4).exists());
+    }
+
+    #[test]
+    fn test_is_yes() {
+        assert!(is_yes("y\n"));
+        assert!(is_yes(" Yes "));
+        assert!(!is_yes("\n"));
+        assert!(!is_yes("nope"));
+    }
+}
@@ -0,0 +1,65 @@
+use std::{collections::BTreeSet, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{error::Result, habitica::HabiticaTask};
+
+/// Habitica tasks whose Taskwarrior link was archived
+///
+/// Archived tasks stay on Habitica but are left out of every later sync, so
+/// they are neither reconciled nor imported again.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Archive {
+    ids: BTreeSet<Uuid>,
+}
+
+impl Archive {
+    /// Load the archive, empty if it was never saved
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Archive::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Save the archive
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Remember a Habitica task as archived
+    pub fn insert(&mut self, h_id: Uuid) {
+        self.ids.insert(h_id);
+    }
+
+    /// Check whether a Habitica task was archived
+    pub fn contains(&self, h_task: &HabiticaTask) -> bool {
+        h_task.id.is_some_and(|id| self.ids.contains(&id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains() {
+        let archived = HabiticaTask {
+            id: Some(Uuid::new_v4()),
+            ..Default::default()
+        };
+        let active = HabiticaTask {
+            id: Some(Uuid::new_v4()),
+            ..Default::default()
+        };
+
+        let mut archive = Archive::default();
+        archive.insert(archived.id.unwrap_or_default());
+
+        assert!(archive.contains(&archived));
+        assert!(!archive.contains(&active));
+        assert!(!archive.contains(&HabiticaTask::default()));
+    }
+}
@@ -0,0 +1,632 @@
+use chrono::{DateTime, Utc};
+use tracing::instrument;
+
+use crate::{
+    config::{Config, ConflictStrategy},
+    error::{Error, Result},
+    habitica::{
+        AsyncHabiticaClient, DayStart, HabiticaTask, ScoreDirection, StatsCache, UserStats,
+    },
+    sync::{
+        archive::Archive,
+        conflicts::ConflictQueue,
+        converter,
+        engine::{
+            expire, resume_staged, split_by_filter, split_by_scope, unlink_deleted, PlanInputs,
+            PlanOptions,
+        },
+        journal::{self, JournalEntry},
+        merge::{self, MergeBase},
+        progress,
+        prompt::{self, Prompter},
+        resolver::{
+            check_dependencies, combine_task, preferred_side, pull_task, resolve_with_base,
+        },
+        tombstones::{DeletedOn, Tombstones},
+        watermark,
+        writes::{self, check_streak_loss, Reply, Request, Writes},
+        ResolutionAction, SyncEvent, SyncOperation, SyncPlan, SyncSummary,
+    },
+    taskwarrior::{NotesManager, Task, TaskBackend},
+};
+
+/// Async counterpart of [`SyncEngine`](super::SyncEngine)
+///
+/// Habitica requests are awaited on the caller's runtime. The Taskwarrior
+/// backend is still called synchronously. What gets written is decided by
+/// the same code as for [`SyncEngine`](super::SyncEngine), but nobody is
+/// asked about conflicts and no merge tool is run, so the `ask` and
+/// `external` strategies are refused and unchecking a protected streak
+/// needs [`allow_streak_loss`](Self::allow_streak_loss).
+pub struct AsyncSyncEngine<'a> {
+    config: &'a Config,
+    backend: &'a (dyn TaskBackend + Sync),
+    h_client: &'a AsyncHabiticaClient,
+    notes_manager: NotesManager<'a>,
+    options: PlanOptions,
+    allow_streak_loss: bool,
+    prompter: &'a dyn Prompter,
+}
+
+impl<'a> AsyncSyncEngine<'a> {
+    pub const fn new(
+        config: &'a Config,
+        backend: &'a (dyn TaskBackend + Sync),
+        h_client: &'a AsyncHabiticaClient,
+    ) -> Self {
+        AsyncSyncEngine {
+            config,
+            backend,
+            h_client,
+            notes_manager: NotesManager::new(config),
+            options: PlanOptions {
+                changed_since: None,
+                link_existing: false,
+            },
+            allow_streak_loss: false,
+            prompter: prompt::DEFAULT,
+        }
+    }
+
+    /// Only reconcile linked tasks modified on either side since `since`
+    #[must_use]
+    pub const fn changed_since(mut self, since: Option<DateTime<Utc>>) -> Self {
+        self.options.changed_since = since;
+        self
+    }
+
+    /// Link unlinked tasks that look the same on both sides instead of
+    /// pushing and importing copies of them
+    #[must_use]
+    pub const fn link_existing(mut self, link: bool) -> Self {
+        self.options.link_existing = link;
+        self
+    }
+
+    /// Uncheck dailies even with streak protection on
+    #[must_use]
+    pub const fn allow_streak_loss(mut self, allow: bool) -> Self {
+        self.allow_streak_loss = allow;
+        self
+    }
+
+    /// Send warnings through `prompter` instead of the default one
+    ///
+    /// Its questions aren't used, nobody is asked from inside a runtime.
+    #[must_use]
+    pub const fn prompter(mut self, prompter: &'a dyn Prompter) -> Self {
+        self.prompter = prompter;
+        self
+    }
+
+    /// Fetch both sides and compute what needs to happen
+    #[instrument(skip_all, err)]
+    pub async fn plan(&self) -> Result<SyncPlan> {
+        if matches!(
+            self.config.conflict_strategy,
+            ConflictStrategy::Ask | ConflictStrategy::External
+        ) {
+            return Err(Error::config(
+                "The async engine can't ask about or merge conflicts, pick another \
+                 habitica.conflict.strategy",
+            ));
+        }
+
+        let snapshot = self.backend.snapshot()?;
+        let mut h_tasks = self.h_client.get_all_tasks().await?;
+        for group_id in self.config.group_plans.values() {
+            h_tasks.extend(self.h_client.get_group_tasks(*group_id).await?);
+        }
+        let archive = Archive::load(&self.config.archive_path())?;
+        h_tasks.retain(|h_task| !archive.contains(h_task));
+
+        let snapshot = split_by_scope(self.config, self.backend, snapshot, &mut h_tasks)?;
+        let (snapshot, filtered) =
+            split_by_filter(self.config, self.backend, snapshot, &mut h_tasks)?;
+        let tombstones = Tombstones::load(&self.config.tombstones_path())?;
+        let inputs = PlanInputs::sort(
+            self.config,
+            self.options,
+            &tombstones,
+            snapshot.unlinked,
+            snapshot.linked,
+            h_tasks,
+        );
+
+        // A broken base only means more conflicts are decided by the strategy
+        let merge_base = MergeBase::load(&self.config.merge_base_path()).unwrap_or_default();
+        let mut plan = inputs.build(|tw_task, h_task| {
+            let base = h_task.id.and_then(|h_id| merge_base.get(h_id));
+            let action = resolve_with_base(self.config, base, tw_task, h_task, None, &|| {
+                preferred_side(self.config, tw_task, h_task)
+            });
+            check_dependencies(self.backend, self.prompter, tw_task, h_task, action)
+        });
+        plan.operations
+            .extend(filtered.into_iter().map(SyncOperation::Filtered));
+        Ok(plan)
+    }
+
+    /// Apply a plan, reporting each step through `on_event`
+    ///
+    /// Like [`SyncEngine::apply`](super::SyncEngine::apply), the updated
+    /// tasks are staged in the progress file and imported in one go at the
+    /// end, also after a failure.
+    #[instrument(skip_all, fields(operations = plan.operations.len()), err)]
+    pub async fn apply(
+        &self,
+        plan: SyncPlan,
+        on_event: &mut (dyn FnMut(&SyncEvent) + Send),
+    ) -> Result<SyncSummary> {
+        let mut summary = SyncSummary::default();
+        let mut current_stats = self.h_client.get_user_stats().await?;
+        let day_start = self.h_client.get_day_start().await?;
+        let mut tombstones = Tombstones::load(&self.config.tombstones_path())?;
+        let mut merge_base = MergeBase::load(&self.config.merge_base_path()).unwrap_or_default();
+        let mut conflicts = None;
+        let mut entries = Vec::new();
+        let mut on_event = |event: &SyncEvent| {
+            entries.extend(JournalEntry::from_event(event, "sync", Utc::now()));
+            on_event(event);
+        };
+
+        let progress_path = self.config.progress_path();
+        let mut imports = Vec::new();
+        let mut stage = |task: Task| -> Result<()> {
+            progress::record(&progress_path, &task)?;
+            imports.push(task);
+            Ok(())
+        };
+        let operations = plan.operations;
+        let applied: Result<()> = async {
+            for operation in operations {
+                match operation {
+                    SyncOperation::PushNew(tw_task) => {
+                        let mut stats_cache = Some(StatsCache::new(current_stats.clone()));
+                        let writes =
+                            writes::push(self.config, self.backend, &self.notes_manager, &tw_task)?;
+                        let updated_task = self.send(writes, &mut stats_cache).await?;
+                        merge_base.record(&updated_task);
+
+                        let stat_messages = take_stat_messages(stats_cache, &mut current_stats);
+                        summary.pushed += 1;
+                        on_event(&SyncEvent::Pushed {
+                            task: &updated_task,
+                            stat_messages,
+                        });
+                        stage(updated_task)?;
+                    }
+
+                    SyncOperation::ImportFromHabitica(h_task) => {
+                        let tw_task = self.pull(&h_task, None, day_start)?;
+                        merge_base.record(&tw_task);
+                        stage(tw_task)?;
+
+                        summary.imported += 1;
+                        on_event(&SyncEvent::Imported { h_task: &h_task });
+                    }
+
+                    SyncOperation::DeletedOnHabitica(tw_task) => {
+                        let (updated, kept_completed) = unlink_deleted(&tw_task);
+                        merge_base.record(&updated);
+                        stage(updated)?;
+                        if let Some(h_id) = tw_task.habitica_uuid {
+                            tombstones.record(&tw_task, h_id, DeletedOn::Habitica, Utc::now());
+                        }
+
+                        summary.deleted += 1;
+                        on_event(&SyncEvent::DeletedOnHabitica {
+                            task: &tw_task,
+                            kept_completed,
+                        });
+                    }
+
+                    SyncOperation::DeletedInTaskwarrior(h_task) => {
+                        if let Some(h_id) = h_task.id {
+                            self.h_client.delete_task(h_id).await?;
+                        }
+
+                        summary.deleted += 1;
+                        on_event(&SyncEvent::DeletedInTaskwarrior { h_task: &h_task });
+                    }
+
+                    // Deletes the Habitica task, unless a challenge holds on to it
+                    SyncOperation::Expired(tw_task) => {
+                        let writes = writes::modify(
+                            self.config,
+                            self.backend,
+                            &self.notes_manager,
+                            &tw_task,
+                            &expire(&tw_task),
+                        )?;
+                        let expired = self.send(writes, &mut None).await?;
+                        merge_base.record(&expired);
+                        stage(expired)?;
+
+                        summary.expired += 1;
+                        on_event(&SyncEvent::Expired { task: &tw_task });
+                    }
+
+                    SyncOperation::Filtered(tw_task) => {
+                        let unlinked = self
+                            .send(writes::remove(&tw_task, &tw_task), &mut None)
+                            .await?;
+                        stage(unlinked)?;
+
+                        summary.filtered += 1;
+                        on_event(&SyncEvent::Filtered { task: &tw_task });
+                    }
+
+                    SyncOperation::Superseded(tw_task) => {
+                        let mut unlinked = tw_task.clone();
+                        unlinked.habitica_uuid = None;
+                        stage(unlinked)?;
+
+                        summary.superseded += 1;
+                        on_event(&SyncEvent::Superseded { task: &tw_task });
+                    }
+
+                    SyncOperation::Reconcile {
+                        tw_task,
+                        h_task,
+                        action,
+                    } => match action {
+                        ResolutionAction::NoChange => {
+                            merge_base.record(&tw_task);
+                            summary.unchanged += 1;
+                            on_event(&SyncEvent::Unchanged {
+                                tw_task: &tw_task,
+                                h_task: &h_task,
+                            });
+                        }
+
+                        ResolutionAction::UseHabitica => {
+                            let pulled = self.pull(&h_task, Some(&tw_task), day_start)?;
+                            let updated_tw =
+                                h_task.id.and_then(|h_id| merge_base.get(h_id)).map_or_else(
+                                    || pulled.clone(),
+                                    |base| merge::keep_local_edits(base, &tw_task, &pulled),
+                                );
+                            let changes = converter::diff_tasks(self.config, &tw_task, &updated_tw);
+                            stage(updated_tw)?;
+                            // Habitica's version is the base, so kept edits get
+                            // pushed by the next sync
+                            merge_base.record(&pulled);
+
+                            summary.updated_taskwarrior += 1;
+                            on_event(&SyncEvent::UpdatedTaskwarrior {
+                                tw_task: &tw_task,
+                                h_task: &h_task,
+                                changes,
+                            });
+                        }
+
+                        ResolutionAction::UseTaskwarrior => {
+                            let mut stats_cache = Some(StatsCache::new(current_stats.clone()));
+                            let old_tw = self.pull(&h_task, Some(&tw_task), day_start)?;
+                            let updated_tw =
+                                self.modify(&old_tw, &tw_task, &mut stats_cache).await?;
+                            merge_base.record(&updated_tw);
+                            stage(updated_tw)?;
+
+                            let stat_messages = take_stat_messages(stats_cache, &mut current_stats);
+                            summary.updated_habitica += 1;
+                            on_event(&SyncEvent::UpdatedHabitica {
+                                tw_task: &tw_task,
+                                h_task: &h_task,
+                                changes: converter::diff_tasks(self.config, &old_tw, &tw_task),
+                                stat_messages,
+                            });
+                        }
+
+                        ResolutionAction::Combine(decisions) => {
+                            let mut stats_cache = Some(StatsCache::new(current_stats.clone()));
+                            let (old_tw, combined) = combine_task(
+                                self.config,
+                                &self.notes_manager,
+                                &tw_task,
+                                &h_task,
+                                decisions,
+                                day_start,
+                            )?;
+                            let updated_tw =
+                                self.modify(&old_tw, &combined, &mut stats_cache).await?;
+                            merge_base.record(&updated_tw);
+                            stage(updated_tw)?;
+
+                            let stat_messages = take_stat_messages(stats_cache, &mut current_stats);
+                            summary.merged += 1;
+                            on_event(&SyncEvent::Combined {
+                                tw_task: &tw_task,
+                                h_task: &h_task,
+                                stat_messages,
+                            });
+                        }
+
+                        // Not recorded as synced, so the next sync looks again
+                        ResolutionAction::Skip => {
+                            summary.skipped += 1;
+                            on_event(&SyncEvent::Skipped {
+                                tw_task: &tw_task,
+                                h_task: &h_task,
+                            });
+                        }
+
+                        ResolutionAction::Defer => {
+                            if conflicts.is_none() {
+                                conflicts =
+                                    Some(ConflictQueue::load(&self.config.conflicts_path())?);
+                            }
+                            if let Some(conflicts) = conflicts.as_mut() {
+                                conflicts.defer(&tw_task, Utc::now());
+                            }
+                            summary.deferred += 1;
+                            on_event(&SyncEvent::Deferred {
+                                tw_task: &tw_task,
+                                h_task: &h_task,
+                            });
+                        }
+
+                        ResolutionAction::Merge => {
+                            return Err(Error::config(format!(
+                                "\"{}\" needs the merge tool, which the async engine doesn't run",
+                                tw_task.description
+                            )));
+                        }
+                    },
+                }
+            }
+            Ok(())
+        }
+        .await;
+        journal::attach_imports(&mut entries, &imports);
+        let journaled = journal::append(&self.config.journal_path(), &entries);
+        self.backend.import_many(&imports)?;
+        progress::clear(&progress_path)?;
+        merge_base.save(&self.config.merge_base_path())?;
+        tombstones.save(&self.config.tombstones_path(), Utc::now())?;
+        if let Some(conflicts) = &conflicts {
+            conflicts.save(&self.config.conflicts_path())?;
+        }
+        journaled?;
+        applied.map(|()| summary)
+    }
+
+    /// Finish an interrupted sync by importing the tasks it had staged
+    ///
+    /// Returns how many tasks were imported.
+    pub fn resume(&self) -> Result<usize> {
+        resume_staged(self.config, self.backend, |staged| {
+            let path = self.config.merge_base_path();
+            let mut merge_base = MergeBase::load(&path).unwrap_or_default();
+            for task in staged {
+                merge_base.record(task);
+            }
+            merge_base.save(&path)
+        })
+    }
+
+    /// Plan and apply in one go, after finishing an interrupted sync
+    ///
+    /// Like `sync`, a run that skipped nothing moves the watermark of
+    /// incremental syncs to its start.
+    pub async fn run(&self, on_event: &mut (dyn FnMut(&SyncEvent) + Send)) -> Result<SyncSummary> {
+        let started = Utc::now();
+        self.resume()?;
+        let plan = self.plan().await?;
+        let summary = self.apply(plan, on_event).await?;
+        if summary.skipped == 0 {
+            watermark::save(&self.config.watermark_path(), started)?;
+        }
+        Ok(summary)
+    }
+
+    /// Convert a Habitica task for Taskwarrior, importing its note
+    fn pull(
+        &self,
+        h_task: &HabiticaTask,
+        existing_tw: Option<&Task>,
+        day_start: DayStart,
+    ) -> Result<Task> {
+        pull_task(
+            self.config,
+            &self.notes_manager,
+            h_task,
+            existing_tw,
+            day_start,
+        )
+    }
+
+    /// Push Taskwarrior changes to an existing Habitica task, scoring status
+    /// changes
+    async fn modify(
+        &self,
+        old_tw: &Task,
+        new_tw: &Task,
+        stats_cache: &mut Option<StatsCache>,
+    ) -> Result<Task> {
+        let writes = writes::modify(
+            self.config,
+            self.backend,
+            &self.notes_manager,
+            old_tw,
+            new_tw,
+        )?;
+        self.send(writes, stats_cache).await
+    }
+
+    /// Send the writes to Habitica, giving back the task to import
+    ///
+    /// Unchecking a daily with streak protection on fails unless allowed,
+    /// as nobody can confirm it here.
+    async fn send(&self, mut writes: Writes, stats_cache: &mut Option<StatsCache>) -> Result<Task> {
+        for notice in &writes.notices {
+            self.prompter.warn(notice);
+        }
+        if writes.unchecks_daily {
+            check_streak_loss(self.config, self.allow_streak_loss, &writes.updated, || {
+                false
+            })?;
+        }
+
+        for request in writes.take_requests() {
+            let reply = match (&request, writes.h_id) {
+                (
+                    Request::Create {
+                        h_task,
+                        group_id: Some(group_id),
+                    },
+                    _,
+                ) => Reply::Written(
+                    Box::new(self.h_client.create_group_task(*group_id, h_task).await?),
+                    None,
+                    None,
+                ),
+                (
+                    Request::Create {
+                        h_task,
+                        group_id: None,
+                    },
+                    _,
+                ) => {
+                    let (created, stats, drop_msg) = self.h_client.create_task(h_task).await?;
+                    Reply::Written(Box::new(created), stats, drop_msg)
+                }
+                (Request::Update(h_task), Some(h_id)) => {
+                    let (returned, stats, drop_msg) =
+                        self.h_client.update_task(h_id, h_task).await?;
+                    Reply::Written(Box::new(returned), stats, drop_msg)
+                }
+                (Request::RestoreCompletion(h_task), Some(h_id)) => {
+                    self.h_client.update_task(h_id, h_task).await?;
+                    Reply::Done
+                }
+                (Request::Score(direction), Some(h_id)) => {
+                    let (stats, drop_msg) = self.h_client.score_task(h_id, *direction).await?;
+                    Reply::Scored(stats, drop_msg)
+                }
+                (Request::ScoreHabit(tag), _) => {
+                    let habit = match self.h_client.find_habit(tag).await? {
+                        Some(habit) => habit,
+                        None => {
+                            self.h_client
+                                .create_task(&writes::habit_for_tag(tag))
+                                .await?
+                                .0
+                        }
+                    };
+                    let habit_id = habit
+                        .id
+                        .ok_or_else(|| Error::HabiticaApiError("Habit has no ID".to_string()))?;
+                    let (stats, drop_msg) = self
+                        .h_client
+                        .score_task(habit_id, ScoreDirection::Up)
+                        .await?;
+                    Reply::Scored(stats, drop_msg)
+                }
+                (Request::Delete(h_id), _) => {
+                    self.h_client.delete_task(*h_id).await?;
+                    Reply::Done
+                }
+                // Nothing was created to act on
+                (_, None) => continue,
+            };
+            writes.record(&request, reply, stats_cache);
+        }
+        Ok(writes.updated)
+    }
+}
+
+/// Stat messages of a step, moving the current stats along
+fn take_stat_messages(
+    stats_cache: Option<StatsCache>,
+    current_stats: &mut UserStats,
+) -> Vec<String> {
+    let Some(stats_cache) = stats_cache else {
+        return Vec::new();
+    };
+    if let Some(new_stats) = stats_cache.current.clone() {
+        *current_stats = new_stats;
+    }
+    stats_cache.get_diff_messages()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::taskwarrior::TaskStatus;
+
+    fn test_tw_task(h_id: Option<Uuid>) -> Task {
+        Task {
+            uuid: Uuid::new_v4(),
+            description: "Test task".to_string(),
+            status: TaskStatus::Pending,
+            modified: Some(Utc::now() - chrono::Duration::hours(1)),
+            due: None,
+            annotations: None,
+            habitica_uuid: h_id,
+            habitica_difficulty: None,
+            habitica_task_type: None,
+            habitica_cost: None,
+            habitica_assigned: None,
+            habitica_attribute: None,
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    /// Resolve a pair the way [`AsyncSyncEngine::plan`] does
+    fn resolve(
+        config: &Config,
+        base: Option<&Task>,
+        tw_task: &Task,
+        h_task: &HabiticaTask,
+    ) -> ResolutionAction {
+        resolve_with_base(config, base, tw_task, h_task, None, &|| {
+            preferred_side(config, tw_task, h_task)
+        })
+    }
+
+    #[test]
+    fn test_plan_without_remote_tasks_pushes_everything() {
+        let config = Config::for_tests();
+        let plan = SyncPlan::build(
+            vec![test_tw_task(None)],
+            Vec::new(),
+            Vec::new(),
+            |tw_task, h_task| resolve(&config, None, tw_task, h_task),
+        );
+        assert!(matches!(plan.operations[..], [SyncOperation::PushNew(_)]));
+    }
+
+    #[test]
+    fn test_plan_goes_by_the_merge_base() {
+        let mut config = Config::for_tests();
+        config.conflict_strategy = ConflictStrategy::TaskwarriorWins;
+        let h_id = Uuid::new_v4();
+        let base = test_tw_task(Some(h_id));
+        let h_task = HabiticaTask {
+            id: Some(h_id),
+            text: "Renamed on Habitica".to_string(),
+            updated_at: Some(Utc::now()),
+            ..Default::default()
+        };
+
+        // Only Habitica changed since the last sync, whatever the strategy
+        assert_eq!(
+            resolve(&config, Some(&base), &base, &h_task),
+            ResolutionAction::UseHabitica
+        );
+
+        // Both sides changed, the task waits for `resolve`
+        config.conflict_strategy = ConflictStrategy::Defer;
+        let mut tw_task = base.clone();
+        tw_task.description = "Renamed in Taskwarrior".to_string();
+        assert_eq!(
+            resolve(&config, Some(&base), &tw_task, &h_task),
+            ResolutionAction::Defer
+        );
+    }
+}
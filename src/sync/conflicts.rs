@@ -0,0 +1,123 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{error::Result, taskwarrior::Task};
+
+/// Conflict a sync left for `resolve`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeferredConflict {
+    pub habitica_id: Option<Uuid>,
+    pub description: String,
+    /// When a sync first deferred it
+    pub deferred: DateTime<Utc>,
+}
+
+/// Tasks changed on both sides that wait for `resolve`, keyed by
+/// Taskwarrior UUID
+///
+/// Syncs with the `defer` conflict strategy leave such tasks alone and add
+/// them here. A task stays until `resolve` picks a side for it, however
+/// many syncs defer it again meanwhile.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConflictQueue {
+    entries: BTreeMap<Uuid, DeferredConflict>,
+}
+
+impl ConflictQueue {
+    /// Load the queue, empty if it was never saved
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(ConflictQueue::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Save the queue
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Queue a task, keeping when it was first deferred
+    pub fn defer(&mut self, task: &Task, now: DateTime<Utc>) {
+        self.entries
+            .entry(task.uuid)
+            .and_modify(|conflict| conflict.description = task.description.clone())
+            .or_insert_with(|| DeferredConflict {
+                habitica_id: task.habitica_uuid,
+                description: task.description.clone(),
+                deferred: now,
+            });
+    }
+
+    /// Take a task off the queue
+    pub fn remove(&mut self, uuid: Uuid) {
+        self.entries.remove(&uuid);
+    }
+
+    /// Queued tasks, by Taskwarrior UUID
+    pub fn entries(&self) -> impl Iterator<Item = (Uuid, &DeferredConflict)> {
+        self.entries
+            .iter()
+            .map(|(uuid, conflict)| (*uuid, conflict))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_conflict_queue() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("habitica_conflicts.json");
+        let now = Utc::now();
+
+        let mut task = Task {
+            uuid: Uuid::new_v4(),
+            description: "Water plants".to_string(),
+            habitica_uuid: Some(Uuid::new_v4()),
+            ..Default::default()
+        };
+        let other = Task {
+            uuid: Uuid::new_v4(),
+            description: "Call mom".to_string(),
+            ..Default::default()
+        };
+
+        let mut queue = ConflictQueue::load(&path).unwrap();
+        assert!(queue.is_empty());
+        queue.defer(&task, now);
+        queue.defer(&other, now);
+        task.description = "Water the plants".to_string();
+        queue.defer(&task, now + Duration::hours(1));
+        queue.save(&path).unwrap();
+
+        let mut queue = ConflictQueue::load(&path).unwrap();
+        assert_eq!(queue.len(), 2);
+        let (_, conflict) = queue
+            .entries()
+            .find(|(uuid, _)| *uuid == task.uuid)
+            .unwrap();
+        assert_eq!(conflict.description, "Water the plants");
+        assert_eq!(conflict.deferred, now);
+        assert_eq!(conflict.habitica_id, task.habitica_uuid);
+
+        queue.remove(other.uuid);
+        assert_eq!(queue.len(), 1);
+    }
+}
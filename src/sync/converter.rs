@@ -1,21 +1,50 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
 use crate::{
+    config::Config,
     error::Result,
-    habitica::{HabiticaTask, HabiticaTaskStatus, HabiticaTaskType},
-    taskwarrior::{Task, TaskDifficulty, TaskStatus, TaskType},
+    habitica::{
+        ChecklistItem, DayStart, HabiticaAttribute, HabiticaTask, HabiticaTaskStatus,
+        HabiticaTaskType, Reminder,
+    },
+    sync::{recurrence, sanitize},
+    taskwarrior::{
+        date_format,
+        task::{CHALLENGE_UDA, HABIT_COUNTER_UDAS, STREAK_UDA},
+        Task, TaskAttribute, TaskDifficulty, TaskStatus, TaskType,
+    },
 };
 
+/// Difficulty of a task
+///
+/// An explicit `habitica_difficulty` wins. Otherwise the hardest difficulty
+/// configured for one of the task's tags applies, then the default.
+pub fn difficulty_of(config: &Config, tw_task: &Task) -> TaskDifficulty {
+    tw_task.habitica_difficulty.unwrap_or_else(|| {
+        tw_task
+            .tags()
+            .into_iter()
+            .filter_map(|tag| config.difficulty_tags.get(tag).copied())
+            .max()
+            .unwrap_or_default()
+    })
+}
+
 /// Convert a Taskwarrior task to a Habitica task
 pub fn taskwarrior_to_habitica(
+    config: &Config,
     tw_task: &Task,
     note_content: Option<&str>,
 ) -> Result<Option<HabiticaTask>> {
-    // Don't sync recurring or deleted tasks to Habitica
-    if !tw_task.status.should_sync_to_habitica() {
+    // Don't sync recurring, deleted or local-only tasks to Habitica
+    if !tw_task.status.should_sync_to_habitica() || config.keeps_local(tw_task) {
         return Ok(None);
     }
 
-    // Convert status
+    // Convert status (rewards and habits never complete on Habitica)
     let (completed, _status) = match tw_task.status {
+        _ if tw_task.task_type().is_repeatable() => (false, HabiticaTaskStatus::Pending),
         TaskStatus::Pending | TaskStatus::Waiting => (false, HabiticaTaskStatus::Pending),
         TaskStatus::Completed => (true, HabiticaTaskStatus::Completed),
         TaskStatus::Deleted | TaskStatus::Recurring => return Ok(None),
@@ -25,31 +54,56 @@ pub fn taskwarrior_to_habitica(
     let task_type = match tw_task.task_type() {
         TaskType::Todo => HabiticaTaskType::Todo,
         TaskType::Daily => HabiticaTaskType::Daily,
-        _ => HabiticaTaskType::Todo, // Default to todo for habits/rewards
+        TaskType::Reward => HabiticaTaskType::Reward,
+        TaskType::Habit => HabiticaTaskType::Habit,
     };
+    let value = (task_type == HabiticaTaskType::Reward).then(|| tw_task.cost());
 
-    Ok(Some(HabiticaTask {
+    let mut h_task = HabiticaTask {
         id: tw_task.habitica_uuid,
         text: tw_task.description.clone(),
+        alias: Some(tw_task.uuid.to_string()),
         notes: note_content.unwrap_or("").to_string(),
         task_type,
-        priority: tw_task.difficulty().to_habitica_priority(),
+        priority: difficulty_of(config, tw_task).to_habitica_priority(),
+        attribute: tw_task.habitica_attribute.map(attribute_to_habitica),
         completed,
         date: tw_task.due,
         updated_at: tw_task.modified,
+        date_completed: completed.then(|| tw_task.date_attribute("end")).flatten(),
         is_due: false, // This will be set by Habitica
-    }))
+        value,
+        reminders: reminders_of(config, tw_task, task_type),
+        ..Default::default()
+    };
+
+    // Dailies repeat like the Taskwarrior recurrence, when Habitica can
+    // express it; otherwise their schedule is left alone
+    if task_type == HabiticaTaskType::Daily {
+        h_task.start_date = tw_task.date_attribute("scheduled");
+        if let Ok(Some(schedule)) = recurrence::schedule(tw_task) {
+            schedule.apply(&mut h_task);
+        }
+    }
+
+    Ok(Some(h_task))
 }
 
 /// Convert a Habitica task to a Taskwarrior task
+///
+/// `day_start` tells which day a daily's completion on Habitica belongs to.
 pub fn habitica_to_taskwarrior(
+    config: &Config,
     h_task: &HabiticaTask,
     existing_tw_task: Option<&Task>,
+    day_start: DayStart,
 ) -> Result<Task> {
-    // Convert status
-    let status = match h_task.effective_status() {
-        HabiticaTaskStatus::Pending => TaskStatus::Pending,
-        HabiticaTaskStatus::Completed => TaskStatus::Completed,
+    // Convert status (rewards and habits never complete on Habitica, keep the
+    // local status)
+    let status = match (h_task.effective_status(day_start), existing_tw_task) {
+        (_, Some(existing)) if is_repeatable(h_task) => existing.status,
+        (HabiticaTaskStatus::Pending, _) => TaskStatus::Pending,
+        (HabiticaTaskStatus::Completed, _) => TaskStatus::Completed,
     };
 
     // Convert difficulty
@@ -74,9 +128,9 @@ pub fn habitica_to_taskwarrior(
         (uuid::Uuid::new_v4(), serde_json::Map::new(), None)
     };
 
-    Ok(Task {
+    let mut tw_task = Task {
         uuid,
-        description: h_task.text.clone(),
+        description: sanitize::description(config, &h_task.text),
         status,
         modified: h_task.updated_at,
         due: h_task.date,
@@ -84,15 +138,37 @@ pub fn habitica_to_taskwarrior(
         habitica_uuid: h_task.id,
         habitica_difficulty: Some(difficulty),
         habitica_task_type: Some(task_type),
+        habitica_cost: reward_cost(h_task),
+        habitica_assigned: h_task.assigned(),
+        habitica_attribute: h_task.attribute.map(attribute_from_habitica),
         extra,
-    })
+    };
+    if !is_repeatable(h_task) {
+        set_end(&mut tw_task, h_task);
+    }
+    set_reminder_date(config, &mut tw_task, h_task);
+    set_start_date(&mut tw_task, h_task);
+    set_challenge(&mut tw_task, h_task);
+    if task_type == TaskType::Daily {
+        recurrence::set_recur(&mut tw_task, h_task);
+    }
+    set_habit_counters(&mut tw_task, h_task);
+    if let Some(streak) = h_task.streak {
+        set_streak(&mut tw_task, streak);
+    }
+    Ok(tw_task)
 }
 
 /// Update a Taskwarrior task with data from a Habitica task
 /// Preserves Taskwarrior-specific fields like UUID, annotations, etc.
-pub fn update_taskwarrior_from_habitica(tw_task: &mut Task, h_task: &HabiticaTask) -> Result<()> {
+pub fn update_taskwarrior_from_habitica(
+    config: &Config,
+    tw_task: &mut Task,
+    h_task: &HabiticaTask,
+    day_start: DayStart,
+) -> Result<()> {
     // Update fields from Habitica
-    tw_task.description = h_task.text.clone();
+    tw_task.description = sanitize::description(config, &h_task.text);
     tw_task.due = h_task.date;
     tw_task.modified = h_task.updated_at;
     tw_task.habitica_uuid = h_task.id;
@@ -105,21 +181,223 @@ pub fn update_taskwarrior_from_habitica(tw_task: &mut Task, h_task: &HabiticaTas
         HabiticaTaskType::Reward => TaskType::Reward,
     };
     tw_task.habitica_task_type = Some(task_type);
+    tw_task.habitica_cost = reward_cost(h_task);
+    tw_task.habitica_assigned = h_task.assigned();
+    tw_task.habitica_attribute = h_task.attribute.map(attribute_from_habitica);
+
+    set_habit_counters(tw_task, h_task);
+    if let Some(streak) = h_task.streak {
+        set_streak(tw_task, streak);
+    }
+    set_reminder_date(config, tw_task, h_task);
+    set_start_date(tw_task, h_task);
+    set_challenge(tw_task, h_task);
+    if task_type == TaskType::Daily {
+        recurrence::set_recur(tw_task, h_task);
+    }
+
+    // Rewards and habits never complete on Habitica, so the local status stands
+    if task_type.is_repeatable() {
+        return Ok(());
+    }
 
     // Update status, but preserve Waiting status from Taskwarrior
-    tw_task.status = match (h_task.effective_status(), tw_task.status) {
+    tw_task.status = match (h_task.effective_status(day_start), tw_task.status) {
         (HabiticaTaskStatus::Pending, TaskStatus::Waiting) => TaskStatus::Waiting,
         (HabiticaTaskStatus::Pending, _) => TaskStatus::Pending,
         (HabiticaTaskStatus::Completed, _) => TaskStatus::Completed,
     };
+    set_end(tw_task, h_task);
 
     Ok(())
 }
 
+/// Take the completion date from Habitica, clearing it for pending tasks
+fn set_end(tw_task: &mut Task, h_task: &HabiticaTask) {
+    match (tw_task.status, h_task.date_completed) {
+        (TaskStatus::Completed, Some(done)) => {
+            tw_task.extra.insert(
+                "end".to_string(),
+                serde_json::Value::String(date_format::format(&done)),
+            );
+        }
+        (TaskStatus::Completed, None) => {}
+        _ => {
+            tw_task.extra.remove("end");
+        }
+    }
+}
+
+/// Habitica reminders of a todo from its configured Taskwarrior date
+///
+/// Todos get a single reminder, reusing the task UUID as the reminder ID so
+/// repeated conversions agree. `None` leaves the reminders on Habitica alone:
+/// for other task types, whose reminders are times of day rather than dates,
+/// and with the mapping turned off.
+fn reminders_of(
+    config: &Config,
+    tw_task: &Task,
+    task_type: HabiticaTaskType,
+) -> Option<Vec<Reminder>> {
+    let attribute = config.reminders.attribute()?;
+    if task_type != HabiticaTaskType::Todo {
+        return None;
+    }
+    let reminder = tw_task.date_attribute(attribute).map(|time| Reminder {
+        id: tw_task.uuid,
+        start_date: Some(time),
+        time,
+    });
+    Some(reminder.into_iter().collect())
+}
+
+/// Habitica checklist of a task from the tasks it depends on
+///
+/// Each dependency becomes an item with the dependency's UUID as its ID,
+/// ticked once it is completed. Deleted dependencies are left out. `None`
+/// for tasks without dependencies and for habits and rewards, which leaves
+/// the checklist on Habitica alone.
+pub fn checklist_of(tw_task: &Task, dependencies: &[Task]) -> Option<Vec<ChecklistItem>> {
+    let depends = tw_task.depends();
+    if depends.is_empty() || tw_task.task_type().is_repeatable() {
+        return None;
+    }
+    let items = depends
+        .iter()
+        .filter_map(|uuid| dependencies.iter().find(|task| task.uuid == *uuid))
+        .filter(|task| task.status.should_sync_to_habitica())
+        .map(|task| ChecklistItem {
+            id: task.uuid,
+            text: task.description.clone(),
+            completed: task.status.is_completed(),
+        })
+        .collect();
+    Some(items)
+}
+
+/// Check whether a Habitica checklist shows a task's dependencies as they
+/// are, always true for a checklist left alone
+pub fn checklist_matches(checklist: Option<&[ChecklistItem]>, h_task: &HabiticaTask) -> bool {
+    checklist.map_or(true, |checklist| {
+        h_task.checklist.as_deref().unwrap_or_default() == checklist
+    })
+}
+
+/// Take a todo's first Habitica reminder as its configured Taskwarrior date
+fn set_reminder_date(config: &Config, tw_task: &mut Task, h_task: &HabiticaTask) {
+    let Some(attribute) = config.reminders.attribute() else {
+        return;
+    };
+    if h_task.task_type != HabiticaTaskType::Todo || h_task.reminders.is_none() {
+        return;
+    }
+    match h_task.first_reminder() {
+        Some(time) => {
+            tw_task.extra.insert(
+                attribute.to_string(),
+                serde_json::Value::String(date_format::format(&time)),
+            );
+        }
+        None => {
+            tw_task.extra.remove(attribute);
+        }
+    }
+}
+
+/// Take a daily's Habitica start date as its Taskwarrior `scheduled` date
+///
+/// A `scheduled` date on the same day is kept, so its time of day survives.
+fn set_start_date(tw_task: &mut Task, h_task: &HabiticaTask) {
+    let Some(start) = h_task.start_date else {
+        return;
+    };
+    if h_task.task_type != HabiticaTaskType::Daily
+        || same_start(tw_task.date_attribute("scheduled"), start)
+    {
+        return;
+    }
+    tw_task.extra.insert(
+        "scheduled".to_string(),
+        serde_json::Value::String(date_format::format(&start)),
+    );
+}
+
+/// Check whether a `scheduled` date falls on a daily's start date
+///
+/// Habitica moves start dates to the beginning of the user's day, so anything
+/// less than a day apart counts as the same start.
+fn same_start(scheduled: Option<DateTime<Utc>>, start: DateTime<Utc>) -> bool {
+    scheduled.is_some_and(|scheduled| (scheduled - start).num_hours().abs() < 24)
+}
+
+/// Mark a task from a challenge, which can only be edited on Habitica
+fn set_challenge(tw_task: &mut Task, h_task: &HabiticaTask) {
+    match h_task.challenge_name() {
+        Some(name) => {
+            tw_task
+                .extra
+                .insert(CHALLENGE_UDA.to_string(), serde_json::Value::String(name));
+        }
+        None => {
+            tw_task.extra.remove(CHALLENGE_UDA);
+        }
+    }
+}
+
+/// Check whether a Habitica task is a reward or habit, which can be used
+/// any number of times and never completes
+const fn is_repeatable(h_task: &HabiticaTask) -> bool {
+    matches!(
+        h_task.task_type,
+        HabiticaTaskType::Reward | HabiticaTaskType::Habit
+    )
+}
+
+/// Copy a habit's score counters into the counter UDAs
+fn set_habit_counters(tw_task: &mut Task, h_task: &HabiticaTask) {
+    if h_task.task_type != HabiticaTaskType::Habit {
+        return;
+    }
+    let (up_uda, down_uda) = HABIT_COUNTER_UDAS;
+    for (uda, counter) in [(up_uda, h_task.counter_up), (down_uda, h_task.counter_down)] {
+        if let Some(counter) = counter {
+            tw_task.extra.insert(uda.to_string(), counter.into());
+        }
+    }
+}
+
+/// Mirror the streak of a daily
+pub(crate) fn set_streak(tw_task: &mut Task, streak: u32) {
+    tw_task.extra.insert(STREAK_UDA.to_string(), streak.into());
+}
+
+const fn attribute_to_habitica(attribute: TaskAttribute) -> HabiticaAttribute {
+    match attribute {
+        TaskAttribute::Str => HabiticaAttribute::Str,
+        TaskAttribute::Int => HabiticaAttribute::Int,
+        TaskAttribute::Con => HabiticaAttribute::Con,
+        TaskAttribute::Per => HabiticaAttribute::Per,
+    }
+}
+
+const fn attribute_from_habitica(attribute: HabiticaAttribute) -> TaskAttribute {
+    match attribute {
+        HabiticaAttribute::Str => TaskAttribute::Str,
+        HabiticaAttribute::Int => TaskAttribute::Int,
+        HabiticaAttribute::Con => TaskAttribute::Con,
+        HabiticaAttribute::Per => TaskAttribute::Per,
+    }
+}
+
+/// Gold price of a Habitica reward, `None` for other task types
+fn reward_cost(h_task: &HabiticaTask) -> Option<f64> {
+    (h_task.task_type == HabiticaTaskType::Reward).then_some(h_task.value)?
+}
+
 /// Check if two tasks are equivalent (ignoring modification time)
-pub fn tasks_are_equivalent(tw_task: &Task, h_task: &HabiticaTask) -> bool {
-    // Check basic fields
-    if tw_task.description != h_task.text {
+pub fn tasks_are_equivalent(config: &Config, tw_task: &Task, h_task: &HabiticaTask) -> bool {
+    // Check basic fields, comparing against the text as it would be imported
+    if tw_task.description != sanitize::description(config, &h_task.text) {
         return false;
     }
 
@@ -128,7 +406,7 @@ pub fn tasks_are_equivalent(tw_task: &Task, h_task: &HabiticaTask) -> bool {
     }
 
     // Check difficulty
-    if tw_task.difficulty().to_habitica_priority() != h_task.priority {
+    if difficulty_of(config, tw_task).to_habitica_priority() != h_task.priority {
         return false;
     }
 
@@ -143,6 +421,63 @@ pub fn tasks_are_equivalent(tw_task: &Task, h_task: &HabiticaTask) -> bool {
         return false;
     }
 
+    if tw_type == HabiticaTaskType::Daily {
+        if let Ok(Some(schedule)) = recurrence::schedule(tw_task) {
+            if !schedule.matches(h_task) {
+                return false;
+            }
+        }
+
+        // Dailies without a scheduled date leave Habitica's start date alone
+        if let Some(scheduled) = tw_task.date_attribute("scheduled") {
+            if !h_task
+                .start_date
+                .is_some_and(|start| same_start(Some(scheduled), start))
+            {
+                return false;
+            }
+        }
+    }
+
+    // Reminders, to the second as Taskwarrior stores dates
+    if let (Some(attribute), HabiticaTaskType::Todo, Some(_)) =
+        (config.reminders.attribute(), tw_type, &h_task.reminders)
+    {
+        let tw_reminder = tw_task
+            .date_attribute(attribute)
+            .map(|time| time.timestamp());
+        if tw_reminder != h_task.first_reminder().map(|time| time.timestamp()) {
+            return false;
+        }
+    }
+
+    // Streaks only change on Habitica, but have to reach Taskwarrior
+    if h_task.streak.is_some() && tw_task.streak() != h_task.streak {
+        return false;
+    }
+
+    // Tasks without an attribute leave Habitica's alone
+    if let Some(attribute) = tw_task.habitica_attribute {
+        if Some(attribute_to_habitica(attribute)) != h_task.attribute {
+            return false;
+        }
+    }
+
+    // Group task assignments are managed on Habitica
+    if tw_task.habitica_assigned != h_task.assigned() {
+        return false;
+    }
+
+    // Rewards have a price but no completion state
+    if tw_type == HabiticaTaskType::Reward {
+        return reward_cost(h_task) == Some(tw_task.cost()) && tw_task.habitica_uuid == h_task.id;
+    }
+
+    // Habits are scored through their counters, which reset on Habitica
+    if tw_type == HabiticaTaskType::Habit {
+        return tw_task.habitica_uuid == h_task.id;
+    }
+
     // Check status
     let tw_completed = tw_task.status.is_completed();
     if tw_completed != h_task.completed {
@@ -157,12 +492,92 @@ pub fn tasks_are_equivalent(tw_task: &Task, h_task: &HabiticaTask) -> bool {
     true
 }
 
+/// A field a sync changes, with its values before and after
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+/// Fields that differ between two versions of a task, as shown to users
+///
+/// Dates are shown by day, unless only their time differs. Unset values
+/// show as `-`.
+pub fn diff_tasks(config: &Config, old: &Task, new: &Task) -> Vec<FieldChange> {
+    let lower = |value: &dyn std::fmt::Debug| format!("{:?}", value).to_lowercase();
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+
+    let mut changes = Vec::new();
+    let mut change = |field: &'static str, old: String, new: String| {
+        if old != new {
+            changes.push(FieldChange { field, old, new });
+        }
+    };
+
+    change(
+        "description",
+        old.description.clone(),
+        new.description.clone(),
+    );
+    for field in ["due", "scheduled", "until"] {
+        let (old_date, new_date) = if field == "due" {
+            (old.due, new.due)
+        } else {
+            (old.date_attribute(field), new.date_attribute(field))
+        };
+        if old_date != new_date {
+            let (old_day, new_day) = (format_day(old_date), format_day(new_date));
+            if old_day == new_day {
+                change(field, format_time(old_date), format_time(new_date));
+            } else {
+                change(field, old_day, new_day);
+            }
+        }
+    }
+    change("status", lower(&old.status), lower(&new.status));
+    change(
+        "difficulty",
+        lower(&difficulty_of(config, old)),
+        lower(&difficulty_of(config, new)),
+    );
+    change(
+        "attribute",
+        optional(old.habitica_attribute.map(|attribute| lower(&attribute))),
+        optional(new.habitica_attribute.map(|attribute| lower(&attribute))),
+    );
+    if new.task_type() == TaskType::Reward {
+        change("cost", old.cost().to_string(), new.cost().to_string());
+    }
+    change(
+        "assigned",
+        optional(old.habitica_assigned.clone()),
+        optional(new.habitica_assigned.clone()),
+    );
+    changes
+}
+
+fn format_day(date: Option<DateTime<Utc>>) -> String {
+    date.map_or_else(
+        || "-".to_string(),
+        |date| date.format("%Y-%m-%d").to_string(),
+    )
+}
+
+fn format_time(date: Option<DateTime<Utc>>) -> String {
+    date.map_or_else(
+        || "-".to_string(),
+        |date| date.format("%Y-%m-%d %H:%M").to_string(),
+    )
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, clippy::bool_assert_comparison)]
 mod tests {
     use chrono::Utc;
 
     use super::*;
+    use crate::sync::sanitize::SanitizeMode;
 
     fn test_tw_task() -> Task {
         Task {
@@ -175,6 +590,9 @@ mod tests {
             habitica_uuid: Some(uuid::Uuid::new_v4()),
             habitica_difficulty: Some(TaskDifficulty::Easy),
             habitica_task_type: Some(TaskType::Todo),
+            habitica_cost: None,
+            habitica_assigned: None,
+            habitica_attribute: None,
             extra: serde_json::Map::new(),
         }
     }
@@ -190,13 +608,17 @@ mod tests {
             date: None,
             updated_at: Some(Utc::now()),
             is_due: false,
+            ..Default::default()
         }
     }
 
     #[test]
     fn test_taskwarrior_to_habitica_pending() {
+        let config = Config::for_tests();
         let tw_task = test_tw_task();
-        let h_task = taskwarrior_to_habitica(&tw_task, None).unwrap().unwrap();
+        let h_task = taskwarrior_to_habitica(&config, &tw_task, None)
+            .unwrap()
+            .unwrap();
 
         assert_eq!(h_task.text, tw_task.description);
         assert_eq!(h_task.completed, false);
@@ -205,46 +627,436 @@ mod tests {
 
     #[test]
     fn test_taskwarrior_to_habitica_completed() {
+        let config = Config::for_tests();
         let mut tw_task = test_tw_task();
         tw_task.status = TaskStatus::Completed;
 
-        let h_task = taskwarrior_to_habitica(&tw_task, None).unwrap().unwrap();
+        let h_task = taskwarrior_to_habitica(&config, &tw_task, None)
+            .unwrap()
+            .unwrap();
         assert_eq!(h_task.completed, true);
     }
 
     #[test]
     fn test_taskwarrior_to_habitica_deleted() {
+        let config = Config::for_tests();
         let mut tw_task = test_tw_task();
         tw_task.status = TaskStatus::Deleted;
 
-        let result = taskwarrior_to_habitica(&tw_task, None).unwrap();
+        let result = taskwarrior_to_habitica(&config, &tw_task, None).unwrap();
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_taskwarrior_to_habitica_exclude_tag() {
+        let mut config = Config::for_tests();
+        let mut tw_task = test_tw_task();
+        tw_task
+            .extra
+            .insert("tags".to_string(), serde_json::json!(["nohabitica"]));
+
+        let result = taskwarrior_to_habitica(&config, &tw_task, None).unwrap();
+        assert!(result.is_none());
+
+        config.exclude_tag = None;
+        let result = taskwarrior_to_habitica(&config, &tw_task, None).unwrap();
+        assert!(result.is_some());
+    }
+
+    #[test]
+    fn test_reminder_roundtrip() {
+        let mut config = Config::for_tests();
+        let mut tw_task = test_tw_task();
+        tw_task.extra.insert(
+            "scheduled".to_string(),
+            serde_json::Value::String("20260301T080000Z".to_string()),
+        );
+
+        let h_task = taskwarrior_to_habitica(&config, &tw_task, None)
+            .unwrap()
+            .unwrap();
+        let reminders = h_task.reminders.clone().unwrap();
+        assert_eq!(reminders.len(), 1);
+        assert_eq!(reminders[0].id, tw_task.uuid);
+        assert!(tasks_are_equivalent(&config, &tw_task, &h_task));
+
+        // A reminder moved on Habitica moves the scheduled date
+        let mut remote = h_task.clone();
+        remote.reminders.as_mut().unwrap()[0].time += chrono::Duration::hours(1);
+        assert!(!tasks_are_equivalent(&config, &tw_task, &remote));
+        let pulled =
+            habitica_to_taskwarrior(&config, &remote, Some(&tw_task), DayStart::default()).unwrap();
+        assert_eq!(
+            pulled.extra.get("scheduled").and_then(|v| v.as_str()),
+            Some("20260301T090000Z")
+        );
+
+        // Removing it clears the date
+        remote.reminders = Some(Vec::new());
+        let pulled =
+            habitica_to_taskwarrior(&config, &remote, Some(&tw_task), DayStart::default()).unwrap();
+        assert!(!pulled.extra.contains_key("scheduled"));
+
+        config.reminders = crate::config::ReminderMapping::Off;
+        let h_task = taskwarrior_to_habitica(&config, &tw_task, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(h_task.reminders, None);
+    }
+
+    #[test]
+    fn test_daily_start_date_roundtrip() {
+        let config = Config::for_tests();
+        let mut tw_task = test_tw_task();
+        tw_task.habitica_task_type = Some(TaskType::Daily);
+        tw_task.extra.insert(
+            "scheduled".to_string(),
+            serde_json::Value::String("20260301T080000Z".to_string()),
+        );
+
+        let h_task = taskwarrior_to_habitica(&config, &tw_task, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(h_task.start_date, tw_task.date_attribute("scheduled"));
+        assert_eq!(h_task.reminders, None);
+        assert!(tasks_are_equivalent(&config, &tw_task, &h_task));
+
+        // Habitica keeps the day, not the time
+        let mut remote = h_task.clone();
+        remote.start_date = DateTime::parse_from_rfc3339("2026-03-01T00:00:00Z")
+            .ok()
+            .map(|start| start.with_timezone(&Utc));
+        assert!(tasks_are_equivalent(&config, &tw_task, &remote));
+
+        // Moving the start on Habitica moves the scheduled date
+        remote.start_date = remote
+            .start_date
+            .map(|start| start + chrono::Duration::days(2));
+        assert!(!tasks_are_equivalent(&config, &tw_task, &remote));
+        let pulled =
+            habitica_to_taskwarrior(&config, &remote, Some(&tw_task), DayStart::default()).unwrap();
+        assert_eq!(
+            pulled.extra.get("scheduled").and_then(|v| v.as_str()),
+            Some("20260303T000000Z")
+        );
+    }
+
+    #[test]
+    fn test_habit_roundtrip() {
+        let config = Config::for_tests();
+        let mut tw_task = test_tw_task();
+        tw_task.habitica_task_type = Some(TaskType::Habit);
+        tw_task.status = TaskStatus::Completed;
+
+        let h_task = taskwarrior_to_habitica(&config, &tw_task, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(h_task.task_type, HabiticaTaskType::Habit);
+        assert_eq!(h_task.completed, false);
+
+        let mut remote = h_task;
+        remote.counter_up = Some(3);
+        remote.counter_down = Some(1);
+        let pulled =
+            habitica_to_taskwarrior(&config, &remote, Some(&tw_task), DayStart::default()).unwrap();
+        assert_eq!(pulled.task_type(), TaskType::Habit);
+        assert_eq!(pulled.status, TaskStatus::Completed);
+        assert_eq!(pulled.habit_counters(), (3, 1));
+        assert!(tasks_are_equivalent(&config, &tw_task, &remote));
+    }
+
     #[test]
     fn test_habitica_to_taskwarrior() {
+        let config = Config::for_tests();
         let h_task = test_h_task();
-        let tw_task = habitica_to_taskwarrior(&h_task, None).unwrap();
+        let tw_task = habitica_to_taskwarrior(&config, &h_task, None, DayStart::default()).unwrap();
 
         assert_eq!(tw_task.description, h_task.text);
         assert_eq!(tw_task.status, TaskStatus::Pending);
         assert_eq!(tw_task.habitica_uuid, h_task.id);
     }
 
+    #[test]
+    fn test_pull_preserves_foreign_udas() {
+        let config = Config::for_tests();
+        let mut tw_task = test_tw_task();
+        tw_task.extra.insert(
+            "githuburl".to_string(),
+            serde_json::Value::String("https://github.com/o/r/issues/1".to_string()),
+        );
+        tw_task.extra.insert(
+            "project".to_string(),
+            serde_json::Value::String("work".to_string()),
+        );
+        let mut h_task = test_h_task();
+        h_task.text = "Renamed on Habitica".to_string();
+
+        let pulled =
+            habitica_to_taskwarrior(&config, &h_task, Some(&tw_task), DayStart::default()).unwrap();
+        assert_eq!(pulled.extra, tw_task.extra);
+
+        let mut updated = tw_task.clone();
+        update_taskwarrior_from_habitica(&config, &mut updated, &h_task, DayStart::default())
+            .unwrap();
+        assert_eq!(updated.extra, tw_task.extra);
+    }
+
+    #[test]
+    fn test_reward_cost_roundtrip() {
+        let config = Config::for_tests();
+        let mut tw_task = test_tw_task();
+        tw_task.habitica_task_type = Some(TaskType::Reward);
+        tw_task.habitica_cost = Some(25.0);
+        tw_task.status = TaskStatus::Completed;
+
+        let h_task = taskwarrior_to_habitica(&config, &tw_task, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(h_task.task_type, HabiticaTaskType::Reward);
+        assert_eq!(h_task.value, Some(25.0));
+        assert!(!h_task.completed);
+        assert!(tasks_are_equivalent(&config, &tw_task, &h_task));
+
+        // A price change on Habitica flows back without touching the status
+        let mut repriced = h_task;
+        repriced.value = Some(40.0);
+        assert!(!tasks_are_equivalent(&config, &tw_task, &repriced));
+
+        let pulled =
+            habitica_to_taskwarrior(&config, &repriced, Some(&tw_task), DayStart::default())
+                .unwrap();
+        assert_eq!(pulled.habitica_cost, Some(40.0));
+        assert_eq!(pulled.status, TaskStatus::Completed);
+    }
+
+    #[test]
+    fn test_streak_is_pulled() {
+        let config = Config::for_tests();
+        let mut tw_task = test_tw_task();
+        tw_task.habitica_task_type = Some(TaskType::Daily);
+
+        let mut remote = taskwarrior_to_habitica(&config, &tw_task, None)
+            .unwrap()
+            .unwrap();
+        remote.is_due = true;
+        remote.streak = Some(5);
+        assert!(!tasks_are_equivalent(&config, &tw_task, &remote));
+
+        let pulled =
+            habitica_to_taskwarrior(&config, &remote, Some(&tw_task), DayStart::default()).unwrap();
+        assert_eq!(pulled.streak(), Some(5));
+        assert!(tasks_are_equivalent(&config, &pulled, &remote));
+    }
+
+    #[test]
+    fn test_attribute_roundtrip() {
+        let config = Config::for_tests();
+        let mut tw_task = test_tw_task();
+        tw_task.habitica_attribute = Some(TaskAttribute::Int);
+
+        let h_task = taskwarrior_to_habitica(&config, &tw_task, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(h_task.attribute, Some(HabiticaAttribute::Int));
+        assert!(tasks_are_equivalent(&config, &tw_task, &h_task));
+
+        // Switched on Habitica
+        let mut remote = h_task;
+        remote.attribute = Some(HabiticaAttribute::Per);
+        assert!(!tasks_are_equivalent(&config, &tw_task, &remote));
+        let pulled =
+            habitica_to_taskwarrior(&config, &remote, Some(&tw_task), DayStart::default()).unwrap();
+        assert_eq!(pulled.habitica_attribute, Some(TaskAttribute::Per));
+
+        // Unset locally, Habitica's attribute is left alone
+        tw_task.habitica_attribute = None;
+        assert!(tasks_are_equivalent(&config, &tw_task, &remote));
+    }
+
     #[test]
     fn test_tasks_are_equivalent() {
+        let config = Config::for_tests();
         let tw_task = test_tw_task();
-        let h_task = taskwarrior_to_habitica(&tw_task, None).unwrap().unwrap();
+        let h_task = taskwarrior_to_habitica(&config, &tw_task, None)
+            .unwrap()
+            .unwrap();
 
-        assert!(tasks_are_equivalent(&tw_task, &h_task));
+        assert!(tasks_are_equivalent(&config, &tw_task, &h_task));
     }
 
     #[test]
     fn test_tasks_not_equivalent_different_text() {
+        let config = Config::for_tests();
         let tw_task = test_tw_task();
-        let mut h_task = taskwarrior_to_habitica(&tw_task, None).unwrap().unwrap();
+        let mut h_task = taskwarrior_to_habitica(&config, &tw_task, None)
+            .unwrap()
+            .unwrap();
         h_task.text = "Different text".to_string();
 
-        assert!(!tasks_are_equivalent(&tw_task, &h_task));
+        assert!(!tasks_are_equivalent(&config, &tw_task, &h_task));
+    }
+
+    #[test]
+    fn test_difficulty_from_tags() {
+        let mut config = Config::for_tests();
+        config
+            .difficulty_tags
+            .insert("deepwork".to_string(), TaskDifficulty::Hard);
+        config
+            .difficulty_tags
+            .insert("chore".to_string(), TaskDifficulty::Trivial);
+
+        let mut tw_task = test_tw_task();
+        tw_task.habitica_difficulty = None;
+        tw_task
+            .extra
+            .insert("tags".to_string(), serde_json::json!(["chore", "deepwork"]));
+        assert_eq!(difficulty_of(&config, &tw_task), TaskDifficulty::Hard);
+
+        let h_task = taskwarrior_to_habitica(&config, &tw_task, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(h_task.priority, 2.0);
+        assert!(tasks_are_equivalent(&config, &tw_task, &h_task));
+
+        // An explicit difficulty wins over tags
+        tw_task.habitica_difficulty = Some(TaskDifficulty::Medium);
+        assert_eq!(difficulty_of(&config, &tw_task), TaskDifficulty::Medium);
+    }
+
+    #[test]
+    fn test_pull_sanitizes_text() {
+        let mut config = Config::for_tests();
+        config.sanitize.emoji = SanitizeMode::Strip;
+        config.sanitize.links = SanitizeMode::Convert;
+
+        let mut h_task = test_h_task();
+        h_task.text = ":books: Read [the docs](https://example.com)".to_string();
+
+        let tw_task = habitica_to_taskwarrior(&config, &h_task, None, DayStart::default()).unwrap();
+        assert_eq!(tw_task.description, "Read the docs");
+        assert!(tasks_are_equivalent(&config, &tw_task, &h_task));
+    }
+
+    #[test]
+    fn test_checklist_from_dependencies() {
+        let mut parent = test_tw_task();
+        let step = |description: &str, status| Task {
+            uuid: uuid::Uuid::new_v4(),
+            description: description.to_string(),
+            status,
+            ..Default::default()
+        };
+        let dependencies = vec![
+            step("Draft", TaskStatus::Completed),
+            step("Review", TaskStatus::Pending),
+            step("Dropped", TaskStatus::Deleted),
+        ];
+        assert_eq!(checklist_of(&parent, &dependencies), None);
+
+        let depends: Vec<String> = dependencies.iter().map(|t| t.uuid.to_string()).collect();
+        parent
+            .extra
+            .insert("depends".to_string(), serde_json::json!(depends));
+        let checklist = checklist_of(&parent, &dependencies).unwrap();
+        assert_eq!(
+            checklist,
+            vec![
+                ChecklistItem {
+                    id: dependencies[0].uuid,
+                    text: "Draft".to_string(),
+                    completed: true,
+                },
+                ChecklistItem {
+                    id: dependencies[1].uuid,
+                    text: "Review".to_string(),
+                    completed: false,
+                },
+            ]
+        );
+
+        // Ticking the second step on Taskwarrior makes Habitica's list stale
+        let mut h_task = test_h_task();
+        assert!(checklist_matches(None, &h_task));
+        assert!(!checklist_matches(Some(&checklist), &h_task));
+        h_task.checklist = Some(checklist.clone());
+        assert!(checklist_matches(Some(&checklist), &h_task));
+
+        let mut done = dependencies.clone();
+        done[1].status = TaskStatus::Completed;
+        let ticked = checklist_of(&parent, &done).unwrap();
+        assert!(!checklist_matches(Some(&ticked), &h_task));
+    }
+
+    #[test]
+    fn test_completion_date_roundtrip() {
+        let config = Config::for_tests();
+        let mut tw_task = test_tw_task();
+        tw_task.status = TaskStatus::Completed;
+        tw_task.extra.insert(
+            "end".to_string(),
+            serde_json::Value::String("20250101T120000Z".to_string()),
+        );
+        let end = tw_task.date_attribute("end");
+
+        let h_task = taskwarrior_to_habitica(&config, &tw_task, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(h_task.date_completed, end);
+
+        let mut pulled =
+            habitica_to_taskwarrior(&config, &h_task, None, DayStart::default()).unwrap();
+        assert_eq!(pulled.date_attribute("end"), end);
+
+        // Reopened on Habitica: the task is pending again, without an end
+        let mut reopened = h_task;
+        reopened.completed = false;
+        update_taskwarrior_from_habitica(&config, &mut pulled, &reopened, DayStart::default())
+            .unwrap();
+        assert_eq!(pulled.status, TaskStatus::Pending);
+        assert_eq!(pulled.date_attribute("end"), None);
+    }
+
+    #[test]
+    fn test_diff_tasks() {
+        use chrono::TimeZone;
+
+        let config = Config::for_tests();
+        let old = test_tw_task();
+        assert!(diff_tasks(&config, &old, &old).is_empty());
+
+        let mut new = old.clone();
+        new.due = Some(Utc.with_ymd_and_hms(2024, 5, 3, 0, 0, 0).unwrap());
+        new.status = TaskStatus::Completed;
+        new.habitica_difficulty = Some(TaskDifficulty::Hard);
+        let changes = diff_tasks(&config, &old, &new);
+        assert_eq!(
+            changes,
+            vec![
+                FieldChange {
+                    field: "due",
+                    old: "-".to_string(),
+                    new: "2024-05-03".to_string(),
+                },
+                FieldChange {
+                    field: "status",
+                    old: "pending".to_string(),
+                    new: "completed".to_string(),
+                },
+                FieldChange {
+                    field: "difficulty",
+                    old: "easy".to_string(),
+                    new: "hard".to_string(),
+                },
+            ]
+        );
+
+        // Moved within the same day
+        let mut later = new.clone();
+        later.due = Some(Utc.with_ymd_and_hms(2024, 5, 3, 18, 30, 0).unwrap());
+        let changes = diff_tasks(&config, &new, &later);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].old, "2024-05-03 00:00");
+        assert_eq!(changes[0].new, "2024-05-03 18:30");
     }
 }
@@ -0,0 +1,205 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    habitica::{HabiticaTask, HabiticaTaskType},
+    taskwarrior::{Task, TaskType},
+};
+
+/// Share of characters that may differ between two descriptions that are
+/// still taken for the same task
+const MAX_TYPOS: f64 = 0.15;
+
+/// Test for whether two tasks are the same, one per matching pass
+type Pass = dyn Fn(&Task, &HabiticaTask) -> bool;
+
+/// Pair unlinked Taskwarrior tasks with unlinked Habitica tasks that look
+/// like copies of them
+///
+/// Returns `(Taskwarrior UUID, Habitica ID)` pairs. Exact descriptions are
+/// paired first, then what's left is compared ignoring case, punctuation and
+/// spacing. Descriptions a few typos apart only count when both sides are due
+/// on the same day. Tasks must also be of the same type, and a match only
+/// counts when it is the only one on both sides, so same-named tasks are
+/// never guessed at.
+pub fn find_duplicates(tw_only: &[Task], h_tasks: &[HabiticaTask]) -> Vec<(Uuid, Uuid)> {
+    let mut tw_left: Vec<&Task> = tw_only
+        .iter()
+        .filter(|task| task.habitica_uuid.is_none() && task.status.is_pending())
+        .collect();
+    let mut h_left: Vec<&HabiticaTask> = h_tasks
+        .iter()
+        .filter(|h_task| h_task.id.is_some() && h_task.alias_uuid().is_none())
+        .collect();
+
+    let mut pairs = Vec::new();
+    let passes: [&Pass; 3] = [
+        &|task, h_task| task.description == h_task.text,
+        &|task, h_task| normalize(&task.description) == normalize(&h_task.text),
+        &|task, h_task| {
+            task.due.is_some()
+                && same_due(task.due, h_task.date)
+                && similar(&normalize(&task.description), &normalize(&h_task.text))
+        },
+    ];
+    for matches in passes {
+        let matches =
+            |task: &Task, h_task: &HabiticaTask| same_type(task, h_task) && matches(task, h_task);
+        let found: Vec<(Uuid, Uuid)> = tw_left
+            .iter()
+            .filter_map(|task| {
+                let [h_task] = h_left
+                    .iter()
+                    .filter(|h_task| matches(task, h_task))
+                    .collect::<Vec<_>>()[..]
+                else {
+                    return None;
+                };
+                let rivals = tw_left
+                    .iter()
+                    .filter(|other| matches(other, h_task))
+                    .count();
+                h_task
+                    .id
+                    .filter(|_| rivals == 1)
+                    .map(|h_id| (task.uuid, h_id))
+            })
+            .collect();
+
+        tw_left.retain(|task| !found.iter().any(|(uuid, _)| *uuid == task.uuid));
+        h_left.retain(|h_task| !found.iter().any(|(_, h_id)| Some(*h_id) == h_task.id));
+        pairs.extend(found);
+    }
+    pairs
+}
+
+/// Check whether the tasks are of the same type
+fn same_type(task: &Task, h_task: &HabiticaTask) -> bool {
+    matches!(
+        (task.task_type(), h_task.task_type),
+        (TaskType::Todo, HabiticaTaskType::Todo)
+            | (TaskType::Daily, HabiticaTaskType::Daily)
+            | (TaskType::Habit, HabiticaTaskType::Habit)
+            | (TaskType::Reward, HabiticaTaskType::Reward)
+    )
+}
+
+/// Check whether two due dates fall on the same day, or are both unset
+fn same_due(a: Option<DateTime<Utc>>, b: Option<DateTime<Utc>>) -> bool {
+    a.map(|due| due.date_naive()) == b.map(|due| due.date_naive())
+}
+
+/// Lowercase words without punctuation, separated by single spaces
+fn normalize(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Check whether two texts differ in only a few characters
+#[allow(clippy::cast_precision_loss)]
+fn similar(a: &str, b: &str) -> bool {
+    let longest = a.chars().count().max(b.chars().count());
+    longest > 0 && edit_distance(a, b) as f64 / longest as f64 <= MAX_TYPOS
+}
+
+/// Levenshtein distance between two texts, in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+
+    fn tw_task(description: &str, due: Option<DateTime<Utc>>) -> Task {
+        Task {
+            uuid: Uuid::new_v4(),
+            description: description.to_string(),
+            due,
+            ..Default::default()
+        }
+    }
+
+    fn h_task(text: &str, date: Option<DateTime<Utc>>) -> HabiticaTask {
+        HabiticaTask {
+            id: Some(Uuid::new_v4()),
+            text: text.to_string(),
+            date,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+        assert_eq!(normalize("  Call Mom!  (today)"), "call mom today");
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let due = Utc.with_ymd_and_hms(2026, 3, 1, 9, 0, 0).single();
+        let later = due.map(|due| due + Duration::days(1));
+
+        let exact = tw_task("Water plants", None);
+        let cased = tw_task("call mom!", None);
+        let typo = tw_task("Submit tax retrun", due);
+        let typo_undated = tw_task("Buy grocerys", None);
+        let moved = tw_task("Dentist appointment", due);
+        let typo_moved = tw_task("Renew pasport", due);
+        let twin = tw_task("Stretch", None);
+        let other_twin = tw_task("Stretch", None);
+
+        let h_exact = h_task("Water plants", None);
+        let h_cased = h_task("Call Mom", None);
+        let h_typo = h_task("Submit tax return", due.map(|due| due + Duration::hours(3)));
+        let h_moved = h_task("Dentist appointment", later);
+        let h_tasks = vec![
+            h_exact.clone(),
+            h_cased.clone(),
+            h_typo.clone(),
+            h_task("Buy groceries", None),
+            h_moved.clone(),
+            h_task("Renew passport", later),
+            h_task("Stretch", None),
+        ];
+
+        let pairs = find_duplicates(
+            &[
+                exact.clone(),
+                cased.clone(),
+                typo.clone(),
+                typo_undated,
+                moved.clone(),
+                typo_moved,
+                twin,
+                other_twin,
+            ],
+            &h_tasks,
+        );
+        let expected = [
+            (exact, h_exact),
+            (moved, h_moved),
+            (cased, h_cased),
+            (typo, h_typo),
+        ]
+        .map(|(task, h_task)| (task.uuid, h_task.id.unwrap_or_default()));
+        assert_eq!(pairs, expected);
+    }
+}
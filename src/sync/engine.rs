@@ -0,0 +1,1746 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Mutex, PoisonError,
+    },
+    thread, vec,
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::Result,
+    habitica::{HabiticaApi, HabiticaTask, HabiticaTaskType, StatsCache, UserStats},
+    sync::{
+        archive::Archive,
+        conflicts::ConflictQueue,
+        converter::{self, FieldChange},
+        duplicates::find_duplicates,
+        journal::{self, JournalEntry},
+        progress,
+        prompt::Prompter,
+        recurrence,
+        resolver::{is_excluded, skips_import},
+        tombstones::{DeletedOn, Tombstones},
+        ConflictResolver, ResolutionAction,
+    },
+    taskwarrior::{date_format, Task, TaskBackend, TaskSnapshot, TaskStatus},
+};
+
+/// A single step the sync engine intends to take
+#[derive(Debug, Clone)]
+pub enum SyncOperation {
+    /// Task only exists in Taskwarrior and gets created on Habitica
+    PushNew(Task),
+    /// Task only exists on Habitica and gets imported into Taskwarrior
+    ImportFromHabitica(HabiticaTask),
+    /// Linked Habitica task disappeared and the link gets cleared
+    DeletedOnHabitica(Task),
+    /// Linked Taskwarrior task was deleted before a sync carried it over,
+    /// and the Habitica task gets deleted too
+    DeletedInTaskwarrior(HabiticaTask),
+    /// Linked task passed its `until` date and gets deleted on Habitica
+    Expired(Task),
+    /// Linked task no longer matches `habitica.filter` and gets deleted on
+    /// Habitica
+    Filtered(Task),
+    /// Recurring instance handed its Habitica task on to a newer instance
+    Superseded(Task),
+    /// Task exists on both sides and the resolver picked a winner
+    Reconcile {
+        tw_task: Task,
+        h_task: HabiticaTask,
+        action: ResolutionAction,
+    },
+}
+
+/// Ordered list of operations computed by [`SyncEngine::plan`]
+#[derive(Debug, Clone, Default)]
+pub struct SyncPlan {
+    pub operations: Vec<SyncOperation>,
+}
+
+impl SyncPlan {
+    /// Pair up tasks from both sides and decide what to do with each
+    ///
+    /// Unlinked Taskwarrior tasks are paired with the Habitica task carrying
+    /// their UUID as alias, restoring links lost with the `habitica_uuid` UDA.
+    /// Tasks past their `until` date are expired instead of synced. Of a
+    /// recurring series only the current instance is synced, taking over the
+    /// Habitica task of the instance it replaces.
+    pub fn build(
+        tw_only: Vec<Task>,
+        tw_synced: Vec<Task>,
+        h_tasks: Vec<HabiticaTask>,
+        resolve: impl Fn(&Task, &HabiticaTask) -> ResolutionAction,
+    ) -> Self {
+        let mut operations: Vec<SyncOperation> = Vec::new();
+
+        let mut h_tasks_map: HashMap<Uuid, HabiticaTask> = h_tasks
+            .into_iter()
+            .filter_map(|t| t.id.map(|id| (id, t)))
+            .collect();
+
+        let mut tw_synced_map: HashMap<Uuid, Task> = tw_synced
+            .into_iter()
+            .filter_map(|t| t.habitica_uuid.map(|id| (id, t)))
+            .collect();
+
+        let aliases: HashMap<Uuid, Uuid> = h_tasks_map
+            .iter()
+            .filter(|(h_id, _)| !tw_synced_map.contains_key(h_id))
+            .filter_map(|(h_id, h_task)| h_task.alias_uuid().map(|alias| (alias, *h_id)))
+            .collect();
+        let now = Utc::now();
+        let current =
+            recurrence::current_instances(tw_only.iter().chain(tw_synced_map.values()), now);
+        let linked_series: HashMap<Uuid, Uuid> = tw_synced_map
+            .iter()
+            .filter(|(_, tw_task)| tw_task.status.is_pending())
+            .filter_map(|(h_id, tw_task)| tw_task.parent().map(|parent| (parent, *h_id)))
+            .collect();
+
+        let mut relinked = HashSet::new();
+        let mut rolled = HashSet::new();
+        for mut tw_task in tw_only {
+            if tw_task.is_expired(now) {
+                continue;
+            }
+            if let Some(parent) = tw_task.parent() {
+                if current.get(&parent) != Some(&tw_task.uuid) {
+                    continue;
+                }
+                let previous = linked_series
+                    .get(&parent)
+                    .filter(|h_id| h_tasks_map.contains_key(h_id))
+                    .and_then(|h_id| tw_synced_map.remove(h_id));
+                if let Some(previous) = previous {
+                    let h_id = previous.habitica_uuid;
+                    operations.push(SyncOperation::Superseded(previous));
+                    tw_task.habitica_uuid = h_id;
+                    if let Some(h_id) = h_id {
+                        rolled.insert(h_id);
+                        tw_synced_map.insert(h_id, tw_task);
+                    }
+                    continue;
+                }
+            }
+            match aliases.get(&tw_task.uuid) {
+                Some(&h_id) => {
+                    tw_task.habitica_uuid = Some(h_id);
+                    relinked.insert(h_id);
+                    tw_synced_map.insert(h_id, tw_task);
+                }
+                None => operations.push(SyncOperation::PushNew(tw_task)),
+            }
+        }
+
+        // Get all unique Habitica UUIDs
+        let mut all_h_uuids: Vec<Uuid> = h_tasks_map.keys().copied().collect();
+        all_h_uuids.extend(tw_synced_map.keys().copied());
+        all_h_uuids.sort();
+        all_h_uuids.dedup();
+
+        for h_uuid in all_h_uuids {
+            match (h_tasks_map.remove(&h_uuid), tw_synced_map.remove(&h_uuid)) {
+                // Rewards are only synced once linked from Taskwarrior
+                (Some(h_task), None) if h_task.task_type == HabiticaTaskType::Reward => {}
+                (Some(h_task), None) => operations.push(SyncOperation::ImportFromHabitica(h_task)),
+                (None, Some(tw_task)) => operations.push(SyncOperation::DeletedOnHabitica(tw_task)),
+                (Some(_), Some(tw_task)) if tw_task.is_expired(now) => {
+                    operations.push(SyncOperation::Expired(tw_task));
+                }
+                (Some(h_task), Some(tw_task)) => {
+                    // A rolled forward link pushes the new instance, and a
+                    // restored one has to be written back to Taskwarrior
+                    let action = match resolve(&tw_task, &h_task) {
+                        _ if rolled.contains(&h_uuid) => ResolutionAction::UseTaskwarrior,
+                        ResolutionAction::NoChange if relinked.contains(&h_uuid) => {
+                            ResolutionAction::UseHabitica
+                        }
+                        action => action,
+                    };
+                    operations.push(SyncOperation::Reconcile {
+                        tw_task,
+                        h_task,
+                        action,
+                    });
+                }
+                // Every UUID came from at least one of the maps
+                (None, None) => {}
+            }
+        }
+
+        SyncPlan { operations }
+    }
+
+    /// Check whether applying the plan would change anything
+    ///
+    /// Skipped and deferred conflicts leave both sides alone.
+    pub fn is_empty(&self) -> bool {
+        self.operations.iter().all(|op| {
+            matches!(
+                op,
+                SyncOperation::Reconcile {
+                    action: ResolutionAction::NoChange
+                        | ResolutionAction::Skip
+                        | ResolutionAction::Defer,
+                    ..
+                }
+            )
+        })
+    }
+}
+
+/// Notification emitted after each applied operation
+#[derive(Debug)]
+pub enum SyncEvent<'a> {
+    /// A Taskwarrior task was created on Habitica, given as linked to it
+    Pushed {
+        task: &'a Task,
+        stat_messages: Vec<String>,
+    },
+    /// A Habitica task was imported into Taskwarrior
+    Imported { h_task: &'a HabiticaTask },
+    /// A linked task was deleted on Habitica
+    DeletedOnHabitica {
+        task: &'a Task,
+        kept_completed: bool,
+    },
+    /// The Habitica task of a deleted Taskwarrior task was deleted
+    DeletedInTaskwarrior { h_task: &'a HabiticaTask },
+    /// A linked task passed its `until` date and was deleted on Habitica
+    Expired { task: &'a Task },
+    /// A linked task fell out of `habitica.filter` and was deleted on Habitica
+    Filtered { task: &'a Task },
+    /// A recurring instance was unlinked in favour of a newer one
+    Superseded { task: &'a Task },
+    /// Both sides are already equal
+    Unchanged {
+        tw_task: &'a Task,
+        h_task: &'a HabiticaTask,
+    },
+    /// The Habitica version was written to Taskwarrior
+    UpdatedTaskwarrior {
+        tw_task: &'a Task,
+        h_task: &'a HabiticaTask,
+        /// Fields changed in Taskwarrior
+        changes: Vec<FieldChange>,
+    },
+    /// The Taskwarrior version was written to Habitica
+    UpdatedHabitica {
+        tw_task: &'a Task,
+        h_task: &'a HabiticaTask,
+        /// Fields changed on Habitica
+        changes: Vec<FieldChange>,
+        stat_messages: Vec<String>,
+    },
+    /// The merge tool's result was written to both sides
+    Merged {
+        tw_task: &'a Task,
+        h_task: &'a HabiticaTask,
+        stat_messages: Vec<String>,
+    },
+    /// The changes of both sides were combined field by field
+    Combined {
+        tw_task: &'a Task,
+        h_task: &'a HabiticaTask,
+        stat_messages: Vec<String>,
+    },
+    /// A task changed on both sides was left alone until the next sync
+    Skipped {
+        tw_task: &'a Task,
+        h_task: &'a HabiticaTask,
+    },
+    /// A task changed on both sides was queued for `resolve`
+    Deferred {
+        tw_task: &'a Task,
+        h_task: &'a HabiticaTask,
+    },
+}
+
+/// New Taskwarrior tasks with their Habitica versions, created in one request
+type Batch = Vec<(Task, HabiticaTask)>;
+
+/// Counts of what a sync run did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SyncSummary {
+    pub pushed: usize,
+    pub imported: usize,
+    pub deleted: usize,
+    pub expired: usize,
+    pub filtered: usize,
+    pub superseded: usize,
+    pub updated_taskwarrior: usize,
+    pub updated_habitica: usize,
+    pub merged: usize,
+    pub skipped: usize,
+    pub deferred: usize,
+    pub unchanged: usize,
+}
+
+/// Drop unlinked Habitica tasks that shouldn't be imported
+///
+/// Tasks already linked stay, so they aren't mistaken for deleted ones.
+pub(crate) fn drop_unwanted_imports(
+    config: &Config,
+    tw_synced: &[Task],
+    h_tasks: &mut Vec<HabiticaTask>,
+) {
+    let linked: HashSet<Uuid> = tw_synced.iter().filter_map(|t| t.habitica_uuid).collect();
+    h_tasks.retain(|h_task| {
+        h_task.id.is_some_and(|id| linked.contains(&id)) || !skips_import(config, h_task)
+    });
+}
+
+/// Check whether a task is a member's copy of a shared group task
+pub(crate) fn is_shared_copy(config: &Config, h_task: &HabiticaTask) -> bool {
+    h_task.group.as_ref().is_some_and(|group| {
+        group.task_id.is_some()
+            && group
+                .id
+                .is_some_and(|id| config.project_for_group(id).is_some())
+    })
+}
+
+/// Point unlinked Habitica tasks at the Taskwarrior tasks they duplicate
+///
+/// The pair is then linked like a task whose alias holds its Taskwarrior
+/// UUID, so neither side gets a copy of the other.
+pub(crate) fn link_duplicates(tw_only: &[Task], tw_synced: &[Task], h_tasks: &mut [HabiticaTask]) {
+    let linked: HashSet<Uuid> = tw_synced.iter().filter_map(|t| t.habitica_uuid).collect();
+    let unlinked: Vec<HabiticaTask> = h_tasks
+        .iter()
+        .filter(|h_task| h_task.id.is_some_and(|id| !linked.contains(&id)))
+        .cloned()
+        .collect();
+
+    for (uuid, h_id) in find_duplicates(tw_only, &unlinked) {
+        if let Some(h_task) = h_tasks.iter_mut().find(|h_task| h_task.id == Some(h_id)) {
+            h_task.alias = Some(uuid.to_string());
+        }
+    }
+}
+
+/// Carry over deletions the other side doesn't know about yet
+///
+/// Habitica tasks whose Taskwarrior task was deleted are deleted instead of
+/// imported; challenge tasks, which can't be deleted, are just left out.
+/// Unlinked tasks whose Habitica copy was deleted, and that weren't modified
+/// since, are marked deleted instead of pushed.
+pub(crate) fn apply_tombstones(
+    tombstones: &Tombstones,
+    tw_only: &mut Vec<Task>,
+    tw_synced: &[Task],
+    h_tasks: &mut Vec<HabiticaTask>,
+) -> Vec<SyncOperation> {
+    let linked: HashSet<Uuid> = tw_synced.iter().filter_map(|t| t.habitica_uuid).collect();
+    let (gone, kept): (Vec<HabiticaTask>, Vec<HabiticaTask>) =
+        std::mem::take(h_tasks).into_iter().partition(|h_task| {
+            h_task
+                .id
+                .is_some_and(|id| !linked.contains(&id) && tombstones.deleted_in_taskwarrior(id))
+        });
+    *h_tasks = kept;
+    let mut operations: Vec<SyncOperation> = gone
+        .into_iter()
+        .filter(|h_task| h_task.challenge_id().is_none())
+        .map(SyncOperation::DeletedInTaskwarrior)
+        .collect();
+
+    let (gone, kept): (Vec<Task>, Vec<Task>) = std::mem::take(tw_only)
+        .into_iter()
+        .partition(|task| tombstones.deleted_on_habitica(task));
+    *tw_only = kept;
+    operations.extend(gone.into_iter().map(SyncOperation::DeletedOnHabitica));
+    operations
+}
+
+/// Leave out linked tasks modified on neither side since `since`
+///
+/// Both halves of such a pair go, so neither is taken for deleted or new.
+pub(crate) fn skip_unchanged(
+    since: DateTime<Utc>,
+    tw_synced: &mut Vec<Task>,
+    h_tasks: &mut Vec<HabiticaTask>,
+) {
+    let h_unchanged: HashSet<Uuid> = h_tasks
+        .iter()
+        .filter(|h_task| h_task.modified_or_now() <= since)
+        .filter_map(|h_task| h_task.id)
+        .collect();
+    let unchanged: HashSet<Uuid> = tw_synced
+        .iter()
+        .filter(|task| task.modified_or_now() <= since)
+        .filter_map(|task| task.habitica_uuid)
+        .filter(|h_id| h_unchanged.contains(h_id))
+        .collect();
+    tw_synced.retain(|task| !task.habitica_uuid.is_some_and(|id| unchanged.contains(&id)));
+    h_tasks.retain(|h_task| !h_task.id.is_some_and(|id| unchanged.contains(&id)));
+}
+
+/// Take the tasks outside the sync scope out of a snapshot
+///
+/// They are left alone, and so are the Habitica tasks linked to them or
+/// carrying their UUID as alias, which are thus neither imported nor taken
+/// for deleted.
+pub(crate) fn split_by_scope(
+    config: &Config,
+    backend: &dyn TaskBackend,
+    snapshot: TaskSnapshot,
+    h_tasks: &mut Vec<HabiticaTask>,
+) -> Result<TaskSnapshot> {
+    let Some(scope) = &config.sync_scope else {
+        return Ok(snapshot);
+    };
+    let in_scope: HashSet<Uuid> = backend
+        .export(&[&format!("({})", scope)])?
+        .into_iter()
+        .map(|task| task.uuid)
+        .collect();
+
+    let (unlinked, outside_unlinked): (Vec<Task>, Vec<Task>) = snapshot
+        .unlinked
+        .into_iter()
+        .partition(|task| in_scope.contains(&task.uuid));
+    let (linked, outside_linked): (Vec<Task>, Vec<Task>) = snapshot
+        .linked
+        .into_iter()
+        .partition(|task| in_scope.contains(&task.uuid));
+
+    let outside: HashSet<Uuid> = outside_unlinked
+        .iter()
+        .chain(&outside_linked)
+        .map(|task| task.uuid)
+        .collect();
+    let outside_h: HashSet<Uuid> = outside_linked
+        .iter()
+        .filter_map(|task| task.habitica_uuid)
+        .collect();
+    h_tasks.retain(|h_task| {
+        !h_task.id.is_some_and(|id| outside_h.contains(&id))
+            && !h_task
+                .alias_uuid()
+                .is_some_and(|alias| outside.contains(&alias))
+    });
+
+    Ok(TaskSnapshot { unlinked, linked })
+}
+
+/// Check whether a task is within the sync scope
+pub(crate) fn in_scope(config: &Config, backend: &dyn TaskBackend, task: &Task) -> Result<bool> {
+    let Some(scope) = &config.sync_scope else {
+        return Ok(true);
+    };
+    let uuid = task.uuid.to_string();
+    Ok(!backend
+        .export(&[&uuid, &format!("({})", scope)])?
+        .is_empty())
+}
+
+/// Take the tasks that don't match `habitica.filter` out of a snapshot
+///
+/// Unlinked ones just stay in Taskwarrior, along with Habitica tasks carrying
+/// their UUID as alias. Linked ones are returned to have their Habitica task,
+/// which is removed from `h_tasks`, deleted. Only pending tasks are gated, so
+/// completing a linked task still scores it.
+pub(crate) fn split_by_filter(
+    config: &Config,
+    backend: &dyn TaskBackend,
+    snapshot: TaskSnapshot,
+    h_tasks: &mut Vec<HabiticaTask>,
+) -> Result<(TaskSnapshot, Vec<Task>)> {
+    let Some(filter) = &config.push_filter else {
+        return Ok((snapshot, Vec::new()));
+    };
+    let matching: HashSet<Uuid> = backend
+        .export(&[
+            &format!("({})", filter),
+            "(status:pending or status:waiting)",
+        ])?
+        .into_iter()
+        .map(|task| task.uuid)
+        .collect();
+    let below = |task: &Task| task.status.is_pending() && !matching.contains(&task.uuid);
+
+    let (held, unlinked): (Vec<Task>, Vec<Task>) =
+        snapshot.unlinked.into_iter().partition(|task| below(task));
+    let h_ids: HashSet<Uuid> = h_tasks.iter().filter_map(|h_task| h_task.id).collect();
+    let (filtered, linked): (Vec<Task>, Vec<Task>) = snapshot
+        .linked
+        .into_iter()
+        .partition(|task| below(task) && task.habitica_uuid.is_some_and(|id| h_ids.contains(&id)));
+
+    let held: HashSet<Uuid> = held.iter().map(|task| task.uuid).collect();
+    let filtered_ids: HashSet<Uuid> = filtered
+        .iter()
+        .filter_map(|task| task.habitica_uuid)
+        .collect();
+    h_tasks.retain(|h_task| {
+        !h_task.id.is_some_and(|id| filtered_ids.contains(&id))
+            && !h_task
+                .alias_uuid()
+                .is_some_and(|alias| held.contains(&alias))
+    });
+
+    Ok((TaskSnapshot { unlinked, linked }, filtered))
+}
+
+/// Unlink a task whose Habitica counterpart was deleted
+///
+/// Completed tasks keep their status, anything else is marked deleted. The
+/// returned flag tells whether the completed status was kept.
+pub(crate) fn unlink_deleted(tw_task: &Task) -> (Task, bool) {
+    let kept_completed = tw_task.status == TaskStatus::Completed;
+    let mut updated = tw_task.clone();
+    if !kept_completed {
+        updated.status = TaskStatus::Deleted;
+    }
+    updated.habitica_uuid = None;
+    (updated, kept_completed)
+}
+
+/// Delete and unlink a task whose `until` date passed
+///
+/// Taskwarrior expires such tasks the same way, ending them at the `until`
+/// date.
+pub(crate) fn expire(tw_task: &Task) -> Task {
+    let mut expired = tw_task.clone();
+    expired.status = TaskStatus::Deleted;
+    expired.habitica_uuid = None;
+    if let Some(until) = tw_task.until() {
+        expired.extra.insert(
+            "end".to_string(),
+            serde_json::Value::String(date_format::format(&until)),
+        );
+    }
+    expired
+}
+
+/// Habitica side of an operation, sent by a sync worker
+enum Job {
+    Push(Task),
+    Expire(Task),
+    Filter(Task),
+    Update {
+        tw_task: Task,
+        h_task: Box<HabiticaTask>,
+    },
+}
+
+/// What a job wrote to Habitica
+struct Written {
+    /// Task to import
+    updated: Task,
+    stats_cache: Option<StatsCache>,
+    /// Fields an update changed on Habitica
+    changes: Vec<FieldChange>,
+}
+
+/// A job with what it wrote, or what went wrong
+type Outcome = (Job, Result<Written>);
+
+impl Job {
+    /// Send the writes, giving back the task to import
+    fn run(&self, resolver: &ConflictResolver, stats: &UserStats) -> Result<Written> {
+        let mut stats_cache = Some(StatsCache::new(stats.clone()));
+        let mut changes = Vec::new();
+        let updated = match self {
+            Job::Push(tw_task) => resolver.push_to_habitica(tw_task, &mut stats_cache)?,
+            // Deletes the Habitica task, unless a challenge holds on to it
+            Job::Expire(tw_task) => {
+                resolver.modify_on_habitica(tw_task, &expire(tw_task), &mut None)?
+            }
+            Job::Filter(tw_task) => resolver.remove_from_habitica(tw_task, tw_task)?,
+            Job::Update { tw_task, h_task } => {
+                let old_tw = resolver.pull_from_habitica(h_task, Some(tw_task))?;
+                changes = converter::diff_tasks(resolver.config(), &old_tw, tw_task);
+                resolver.modify_on_habitica(&old_tw, tw_task, &mut stats_cache)?
+            }
+        };
+        Ok(Written {
+            updated,
+            stats_cache,
+            changes,
+        })
+    }
+}
+
+/// Run jobs off the queue until it is empty or a job failed
+fn work(
+    resolver: &ConflictResolver,
+    queue: &Mutex<vec::IntoIter<Job>>,
+    failed: &AtomicBool,
+    stats: &UserStats,
+    done: &mpsc::Sender<Outcome>,
+) {
+    while !failed.load(Ordering::Relaxed) {
+        let next = queue.lock().unwrap_or_else(PoisonError::into_inner).next();
+        let Some(job) = next else {
+            break;
+        };
+        let result = job.run(resolver, stats);
+        if result.is_err() {
+            failed.store(true, Ordering::Relaxed);
+        }
+        if done.send((job, result)).is_err() {
+            break;
+        }
+    }
+}
+
+/// How a plan treats linked tasks and unlinked duplicates
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct PlanOptions {
+    /// Linked tasks modified on neither side since then are skipped
+    pub changed_since: Option<DateTime<Utc>>,
+    /// Link unlinked tasks found on both sides instead of copying them
+    pub link_existing: bool,
+}
+
+/// Tasks of both sides sorted for a plan, with the deletions carried over
+/// by tombstones
+pub(crate) struct PlanInputs {
+    tw_only: Vec<Task>,
+    tw_synced: Vec<Task>,
+    h_tasks: Vec<HabiticaTask>,
+    deletions: Vec<SyncOperation>,
+}
+
+impl PlanInputs {
+    /// Sort fetched tasks for a plan
+    ///
+    /// Excluded tasks, members' copies of shared group tasks and Habitica
+    /// tasks that shouldn't be imported are left out.
+    pub fn sort(
+        config: &Config,
+        options: PlanOptions,
+        tombstones: &Tombstones,
+        tw_only: Vec<Task>,
+        mut tw_synced: Vec<Task>,
+        h_tasks: Vec<HabiticaTask>,
+    ) -> Self {
+        let mut tw_only = tw_only
+            .into_iter()
+            .filter(|task| !is_excluded(config, task))
+            .collect();
+
+        // Members' copies of shared group tasks are synced through the group task
+        let mut h_tasks: Vec<HabiticaTask> = h_tasks
+            .into_iter()
+            .filter(|h_task| !is_shared_copy(config, h_task))
+            .collect();
+        drop_unwanted_imports(config, &tw_synced, &mut h_tasks);
+        if let Some(since) = options.changed_since {
+            skip_unchanged(since, &mut tw_synced, &mut h_tasks);
+        }
+        let deletions = apply_tombstones(tombstones, &mut tw_only, &tw_synced, &mut h_tasks);
+        if options.link_existing {
+            link_duplicates(&tw_only, &tw_synced, &mut h_tasks);
+        }
+        PlanInputs {
+            tw_only,
+            tw_synced,
+            h_tasks,
+            deletions,
+        }
+    }
+
+    /// Build the plan, resolving linked pairs with `resolve`
+    pub fn build(self, resolve: impl Fn(&Task, &HabiticaTask) -> ResolutionAction) -> SyncPlan {
+        let mut plan = SyncPlan::build(self.tw_only, self.tw_synced, self.h_tasks, resolve);
+        plan.operations.splice(0..0, self.deletions);
+        plan
+    }
+}
+
+/// Finish an interrupted sync by importing the tasks it had staged
+///
+/// `record` gets the imported tasks before the progress file is cleared.
+/// Returns how many tasks were imported.
+pub(crate) fn resume_staged(
+    config: &Config,
+    backend: &dyn TaskBackend,
+    record: impl FnOnce(&[Task]) -> Result<()>,
+) -> Result<usize> {
+    let path = config.progress_path();
+    let staged = progress::load(&path)?;
+    if staged.is_empty() {
+        return Ok(0);
+    }
+    backend.import_many(&staged)?;
+    record(&staged)?;
+    progress::clear(&path)?;
+    Ok(staged.len())
+}
+
+/// Orchestrates a full bidirectional sync between Taskwarrior and Habitica
+pub struct SyncEngine<'a> {
+    config: &'a Config,
+    backend: &'a dyn TaskBackend,
+    h_client: &'a dyn HabiticaApi,
+    resolver: ConflictResolver<'a>,
+    options: PlanOptions,
+    /// Tasks to import once the plan is applied
+    imports: RefCell<Vec<Task>>,
+    /// Loaded on first use
+    tombstones: RefCell<Option<Tombstones>>,
+    /// Loaded when a conflict is deferred
+    conflicts: RefCell<Option<ConflictQueue>>,
+    /// Command named as the source of journal entries
+    source: &'static str,
+}
+
+impl<'a> SyncEngine<'a> {
+    pub const fn new(
+        config: &'a Config,
+        backend: &'a dyn TaskBackend,
+        h_client: &'a dyn HabiticaApi,
+    ) -> Self {
+        SyncEngine {
+            config,
+            backend,
+            h_client,
+            resolver: ConflictResolver::new(config, backend, h_client),
+            options: PlanOptions {
+                changed_since: None,
+                link_existing: false,
+            },
+            imports: RefCell::new(Vec::new()),
+            tombstones: RefCell::new(None),
+            conflicts: RefCell::new(None),
+            source: "sync",
+        }
+    }
+
+    /// Name the command running the sync in the journal
+    #[must_use]
+    pub const fn source(mut self, source: &'static str) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Only reconcile linked tasks modified on either side since `since`
+    #[must_use]
+    pub const fn changed_since(mut self, since: Option<DateTime<Utc>>) -> Self {
+        self.options.changed_since = since;
+        self
+    }
+
+    /// Uncheck dailies without asking, even with streak protection on
+    #[must_use]
+    pub fn allow_streak_loss(mut self, allow: bool) -> Self {
+        self.resolver = self.resolver.allow_streak_loss(allow);
+        self
+    }
+
+    /// Ask which side to keep for tasks changed on both sides
+    #[must_use]
+    pub fn interactive(mut self, interactive: bool) -> Self {
+        self.resolver = self.resolver.interactive(interactive);
+        self
+    }
+
+    /// Tell and ask the user things through `prompter` instead of the
+    /// default one
+    #[must_use]
+    pub fn prompter(mut self, prompter: &'a dyn Prompter) -> Self {
+        self.resolver = self.resolver.prompter(prompter);
+        self
+    }
+
+    /// Link unlinked tasks that look the same on both sides instead of
+    /// pushing and importing copies of them
+    #[must_use]
+    pub const fn link_existing(mut self, link: bool) -> Self {
+        self.options.link_existing = link;
+        self
+    }
+
+    /// Fetch Habitica's side and compute what needs to happen to a snapshot
+    /// of the Taskwarrior side
+    #[instrument(skip_all, err)]
+    pub fn plan(&self, snapshot: TaskSnapshot) -> Result<SyncPlan> {
+        let mut h_tasks = self.h_client.get_all_tasks()?;
+        for group_id in self.config.group_plans.values() {
+            h_tasks.extend(self.h_client.get_group_tasks(*group_id)?);
+        }
+
+        let archive = Archive::load(&self.config.archive_path())?;
+        h_tasks.retain(|h_task| !archive.contains(h_task));
+
+        let snapshot = split_by_scope(self.config, self.backend, snapshot, &mut h_tasks)?;
+        let (snapshot, filtered) =
+            split_by_filter(self.config, self.backend, snapshot, &mut h_tasks)?;
+        let mut plan = self.plan_from(snapshot.unlinked, snapshot.linked, h_tasks);
+        plan.operations
+            .extend(filtered.into_iter().map(SyncOperation::Filtered));
+        Ok(plan)
+    }
+
+    /// Compute a plan that only pushes unlinked Taskwarrior tasks
+    ///
+    /// Nothing is fetched from Habitica and linked tasks are left alone.
+    pub fn plan_new(&self, snapshot: TaskSnapshot) -> Result<SyncPlan> {
+        let snapshot = split_by_scope(self.config, self.backend, snapshot, &mut Vec::new())?;
+        let (snapshot, _) = split_by_filter(self.config, self.backend, snapshot, &mut Vec::new())?;
+        Ok(self.plan_from(snapshot.unlinked, Vec::new(), Vec::new()))
+    }
+
+    /// Compute a plan that only touches a single Taskwarrior task
+    pub fn plan_task(&self, tw_task: Task) -> Result<SyncPlan> {
+        if !in_scope(self.config, self.backend, &tw_task)? {
+            return Ok(SyncPlan::default());
+        }
+        match tw_task.habitica_uuid {
+            Some(h_id) => {
+                let h_tasks = self.h_client.get_task(h_id)?.into_iter().collect();
+                Ok(self.plan_from(Vec::new(), vec![tw_task], h_tasks))
+            }
+            None if tw_task.status.is_pending() => {
+                Ok(self.plan_from(vec![tw_task], Vec::new(), Vec::new()))
+            }
+            None => Ok(SyncPlan::default()),
+        }
+    }
+
+    /// Compute a plan from already fetched tasks
+    pub fn plan_from(
+        &self,
+        tw_only: Vec<Task>,
+        tw_synced: Vec<Task>,
+        h_tasks: Vec<HabiticaTask>,
+    ) -> SyncPlan {
+        let inputs = self.with_tombstones(|tombstones| {
+            PlanInputs::sort(
+                self.config,
+                self.options,
+                tombstones,
+                tw_only,
+                tw_synced,
+                h_tasks,
+            )
+        });
+        inputs.build(|tw_task, h_task| self.resolver.resolve(tw_task, h_task))
+    }
+
+    /// Run `f` on the tombstones, loading them first if needed
+    fn with_tombstones<R>(&self, f: impl FnOnce(&mut Tombstones) -> R) -> R {
+        let mut tombstones = self.tombstones.borrow_mut();
+        let tombstones = tombstones.get_or_insert_with(|| {
+            // Unreadable tombstones only cost the deletions they'd carry over
+            Tombstones::load(&self.config.tombstones_path()).unwrap_or_default()
+        });
+        f(tombstones)
+    }
+
+    /// Apply a plan, reporting each step through `on_event`
+    ///
+    /// Habitica writes that don't depend on each other are sent by up to
+    /// `habitica.sync.workers` threads sharing the client's rate limit, while
+    /// this thread handles their results and applies the other operations.
+    /// The updated tasks are imported into Taskwarrior in one go at the end,
+    /// also when a step failed, so nothing already sent to Habitica is lost.
+    /// Every change is appended to the journal.
+    #[instrument(skip_all, fields(operations = plan.operations.len()), err)]
+    pub fn apply(
+        &self,
+        plan: SyncPlan,
+        on_event: &mut dyn FnMut(&SyncEvent),
+    ) -> Result<SyncSummary> {
+        let mut entries = Vec::new();
+        let result = self.apply_staged(plan, &mut |event| {
+            entries.extend(JournalEntry::from_event(event, self.source, Utc::now()));
+            on_event(event);
+        });
+
+        let imports = self.imports.take();
+        journal::attach_imports(&mut entries, &imports);
+        let journaled = journal::append(&self.config.journal_path(), &entries);
+        self.backend.import_many(&imports)?;
+        progress::clear(&self.config.progress_path())?;
+        self.resolver.save_merge_base()?;
+        if let Some(tombstones) = self.tombstones.borrow_mut().as_mut() {
+            tombstones.save(&self.config.tombstones_path(), Utc::now())?;
+        }
+        if let Some(conflicts) = self.conflicts.borrow().as_ref() {
+            conflicts.save(&self.config.conflicts_path())?;
+        }
+        journaled?;
+        result
+    }
+
+    /// Apply a plan, staging the Taskwarrior side for [`apply`](Self::apply)
+    fn apply_staged(
+        &self,
+        plan: SyncPlan,
+        on_event: &mut dyn FnMut(&SyncEvent),
+    ) -> Result<SyncSummary> {
+        let mut summary = SyncSummary::default();
+        let mut current_stats = self.h_client.get_user_stats()?;
+
+        // New todos are created in one request, everything else step by step
+        let (batch, operations) = self.split_batch(plan.operations)?;
+        self.push_batch(&batch, &mut summary, on_event)?;
+
+        let mut jobs = Vec::new();
+        let mut local = Vec::new();
+        for operation in operations {
+            // Writes that don't depend on other operations go to the workers
+            match operation {
+                SyncOperation::PushNew(tw_task) => jobs.push(Job::Push(tw_task)),
+                SyncOperation::Expired(tw_task) => jobs.push(Job::Expire(tw_task)),
+                SyncOperation::Filtered(tw_task) => jobs.push(Job::Filter(tw_task)),
+                SyncOperation::Reconcile {
+                    tw_task,
+                    h_task,
+                    action: ResolutionAction::UseTaskwarrior,
+                } => jobs.push(Job::Update {
+                    tw_task,
+                    h_task: Box::new(h_task),
+                }),
+                other => local.push(other),
+            }
+        }
+        if jobs.iter().any(|job| matches!(job, Job::Update { .. })) {
+            // Fetched once here rather than by every worker
+            self.resolver.day_start()?;
+        }
+
+        let workers = self.config.sync_workers.min(jobs.len());
+        let queue = Mutex::new(jobs.into_iter());
+        let failed = AtomicBool::new(false);
+        let initial_stats = current_stats.clone();
+        let mut failure = None;
+        thread::scope(|scope| {
+            let (done_tx, done_rx) = mpsc::channel();
+            for _ in 0..workers {
+                let resolver = self.resolver.for_worker();
+                let done_tx = done_tx.clone();
+                let (queue, failed, stats) = (&queue, &failed, &initial_stats);
+                scope.spawn(move || work(&resolver, queue, failed, stats, &done_tx));
+            }
+            drop(done_tx);
+
+            for operation in local {
+                if let Err(e) =
+                    self.apply_local(operation, &mut summary, &mut current_stats, on_event)
+                {
+                    failed.store(true, Ordering::Relaxed);
+                    failure = Some(e);
+                    break;
+                }
+            }
+
+            // Writes that reached Habitica are staged even after a failure
+            for (job, result) in done_rx {
+                let finished = result.and_then(|written| {
+                    self.finish(&job, written, &mut summary, &mut current_stats, on_event)
+                });
+                if let Err(e) = finished {
+                    failed.store(true, Ordering::Relaxed);
+                    failure.get_or_insert(e);
+                }
+            }
+        });
+
+        failure.map_or(Ok(summary), Err)
+    }
+
+    /// Queue a task for the import at the end of [`apply`](Self::apply),
+    /// noting it in the progress file in case the sync is interrupted
+    fn stage(&self, task: &Task) -> Result<()> {
+        progress::record(&self.config.progress_path(), task)?;
+        self.imports.borrow_mut().push(task.clone());
+        Ok(())
+    }
+
+    /// Apply an operation that needs no worker
+    fn apply_local(
+        &self,
+        operation: SyncOperation,
+        summary: &mut SyncSummary,
+        current_stats: &mut UserStats,
+        on_event: &mut dyn FnMut(&SyncEvent),
+    ) -> Result<()> {
+        match operation {
+            SyncOperation::ImportFromHabitica(h_task) => {
+                let tw_task = self.resolver.pull_from_habitica(&h_task, None)?;
+                self.stage(&tw_task)?;
+                self.resolver.record_synced(&tw_task);
+
+                summary.imported += 1;
+                on_event(&SyncEvent::Imported { h_task: &h_task });
+            }
+
+            SyncOperation::DeletedOnHabitica(tw_task) => {
+                let (updated, kept_completed) = unlink_deleted(&tw_task);
+                self.stage(&updated)?;
+                self.resolver.record_synced(&updated);
+                if let Some(h_id) = tw_task.habitica_uuid {
+                    self.with_tombstones(|tombstones| {
+                        tombstones.record(&tw_task, h_id, DeletedOn::Habitica, Utc::now());
+                    });
+                }
+
+                summary.deleted += 1;
+                on_event(&SyncEvent::DeletedOnHabitica {
+                    task: &tw_task,
+                    kept_completed,
+                });
+            }
+
+            SyncOperation::DeletedInTaskwarrior(h_task) => {
+                if let Some(h_id) = h_task.id {
+                    self.h_client.delete_task(h_id)?;
+                }
+
+                summary.deleted += 1;
+                on_event(&SyncEvent::DeletedInTaskwarrior { h_task: &h_task });
+            }
+
+            SyncOperation::Superseded(tw_task) => {
+                let mut unlinked = tw_task.clone();
+                unlinked.habitica_uuid = None;
+                self.stage(&unlinked)?;
+
+                summary.superseded += 1;
+                on_event(&SyncEvent::Superseded { task: &tw_task });
+            }
+
+            SyncOperation::Reconcile {
+                tw_task,
+                h_task,
+                action,
+            } => match action {
+                ResolutionAction::NoChange => {
+                    self.resolver.record_synced(&tw_task);
+                    summary.unchanged += 1;
+                    on_event(&SyncEvent::Unchanged {
+                        tw_task: &tw_task,
+                        h_task: &h_task,
+                    });
+                }
+
+                ResolutionAction::UseHabitica => {
+                    let pulled = self.resolver.pull_from_habitica(&h_task, Some(&tw_task))?;
+                    let updated_tw = self.resolver.keep_local_edits(&tw_task, &pulled);
+                    self.stage(&updated_tw)?;
+                    // Habitica's version is the base, so kept edits get
+                    // pushed by the next sync
+                    self.resolver.record_synced(&pulled);
+
+                    summary.updated_taskwarrior += 1;
+                    on_event(&SyncEvent::UpdatedTaskwarrior {
+                        tw_task: &tw_task,
+                        h_task: &h_task,
+                        changes: converter::diff_tasks(self.config, &tw_task, &updated_tw),
+                    });
+                }
+
+                ResolutionAction::Merge => {
+                    let merged = self.resolver.merge_with_tool(&tw_task, &h_task)?;
+                    let mut stats_cache = Some(StatsCache::new(current_stats.clone()));
+                    let old_tw = self.resolver.pull_from_habitica(&h_task, Some(&tw_task))?;
+                    let updated_tw =
+                        self.resolver
+                            .modify_on_habitica(&old_tw, &merged, &mut stats_cache)?;
+                    self.stage(&updated_tw)?;
+                    self.resolver.record_synced(&updated_tw);
+
+                    let stat_messages = Self::take_stat_messages(stats_cache, current_stats);
+                    summary.merged += 1;
+                    on_event(&SyncEvent::Merged {
+                        tw_task: &tw_task,
+                        h_task: &h_task,
+                        stat_messages,
+                    });
+                }
+
+                // Not recorded as synced, so the next sync asks again
+                ResolutionAction::Skip => {
+                    summary.skipped += 1;
+                    on_event(&SyncEvent::Skipped {
+                        tw_task: &tw_task,
+                        h_task: &h_task,
+                    });
+                }
+
+                ResolutionAction::Defer => {
+                    let mut conflicts = self.conflicts.borrow_mut();
+                    if conflicts.is_none() {
+                        *conflicts = Some(ConflictQueue::load(&self.config.conflicts_path())?);
+                    }
+                    if let Some(conflicts) = conflicts.as_mut() {
+                        conflicts.defer(&tw_task, Utc::now());
+                    }
+                    summary.deferred += 1;
+                    on_event(&SyncEvent::Deferred {
+                        tw_task: &tw_task,
+                        h_task: &h_task,
+                    });
+                }
+
+                ResolutionAction::Combine(decisions) => {
+                    let mut stats_cache = Some(StatsCache::new(current_stats.clone()));
+                    let (old_tw, combined) = self.resolver.combine(&tw_task, &h_task, decisions)?;
+                    let updated_tw =
+                        self.resolver
+                            .modify_on_habitica(&old_tw, &combined, &mut stats_cache)?;
+                    self.stage(&updated_tw)?;
+                    self.resolver.record_synced(&updated_tw);
+
+                    let stat_messages = Self::take_stat_messages(stats_cache, current_stats);
+                    summary.merged += 1;
+                    on_event(&SyncEvent::Combined {
+                        tw_task: &tw_task,
+                        h_task: &h_task,
+                        stat_messages,
+                    });
+                }
+
+                ResolutionAction::UseTaskwarrior => unreachable!("sent by the workers"),
+            },
+
+            SyncOperation::PushNew(_) | SyncOperation::Expired(_) | SyncOperation::Filtered(_) => {
+                unreachable!("sent by the workers")
+            }
+        }
+        Ok(())
+    }
+
+    /// Stage the result of a worker's Habitica writes and report it
+    fn finish(
+        &self,
+        job: &Job,
+        written: Written,
+        summary: &mut SyncSummary,
+        current_stats: &mut UserStats,
+        on_event: &mut dyn FnMut(&SyncEvent),
+    ) -> Result<()> {
+        let updated = &written.updated;
+        self.stage(updated)?;
+        let stat_messages = Self::take_stat_messages(written.stats_cache, current_stats);
+        match job {
+            Job::Push(_) => {
+                self.resolver.record_synced(updated);
+                summary.pushed += 1;
+                on_event(&SyncEvent::Pushed {
+                    task: updated,
+                    stat_messages,
+                });
+            }
+            Job::Expire(tw_task) => {
+                self.resolver.record_synced(updated);
+                summary.expired += 1;
+                on_event(&SyncEvent::Expired { task: tw_task });
+            }
+            Job::Filter(tw_task) => {
+                summary.filtered += 1;
+                on_event(&SyncEvent::Filtered { task: tw_task });
+            }
+            Job::Update { tw_task, h_task } => {
+                self.resolver.record_synced(updated);
+                summary.updated_habitica += 1;
+                on_event(&SyncEvent::UpdatedHabitica {
+                    tw_task,
+                    h_task,
+                    changes: written.changes,
+                    stat_messages,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Finish an interrupted sync by importing the tasks it had staged
+    ///
+    /// Their Habitica side was already written, so once they're imported
+    /// the next plan leaves them alone rather than pushing or scoring them
+    /// again. Returns how many tasks were imported.
+    pub fn resume(&self) -> Result<usize> {
+        resume_staged(self.config, self.backend, |staged| {
+            for task in staged {
+                self.resolver.record_synced(task);
+            }
+            self.resolver.save_merge_base()
+        })
+    }
+
+    /// Plan and apply in one go, after finishing an interrupted sync
+    pub fn run(&self, on_event: &mut dyn FnMut(&SyncEvent)) -> Result<SyncSummary> {
+        self.resume()?;
+        let plan = self.plan(self.backend.snapshot()?)?;
+        self.apply(plan, on_event)
+    }
+
+    /// Separate the new tasks that can be created in a single request
+    ///
+    /// A batch needs at least two tasks, otherwise everything stays in the
+    /// remaining operations.
+    fn split_batch(&self, operations: Vec<SyncOperation>) -> Result<(Batch, Vec<SyncOperation>)> {
+        let mut batch = Vec::new();
+        let mut rest = Vec::with_capacity(operations.len());
+        for operation in operations {
+            match operation {
+                SyncOperation::PushNew(tw_task) => match self.resolver.batch_candidate(&tw_task)? {
+                    Some(h_task) => batch.push((tw_task, h_task)),
+                    None => rest.push(SyncOperation::PushNew(tw_task)),
+                },
+                other => rest.push(other),
+            }
+        }
+
+        if batch.len() < 2 {
+            let single = batch
+                .into_iter()
+                .map(|(tw_task, _)| SyncOperation::PushNew(tw_task));
+            rest.splice(0..0, single);
+            return Ok((Vec::new(), rest));
+        }
+        Ok((batch, rest))
+    }
+
+    /// Create a batch of new tasks on Habitica and link them
+    fn push_batch(
+        &self,
+        batch: &[(Task, HabiticaTask)],
+        summary: &mut SyncSummary,
+        on_event: &mut dyn FnMut(&SyncEvent),
+    ) -> Result<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let h_tasks: Vec<HabiticaTask> = batch.iter().map(|(_, h_task)| h_task.clone()).collect();
+        let created = self.h_client.create_tasks(&h_tasks)?;
+
+        for ((tw_task, _), h_task) in batch.iter().zip(created) {
+            let mut updated_task = tw_task.clone();
+            updated_task.habitica_uuid = h_task.id;
+            self.stage(&updated_task)?;
+            self.resolver.record_synced(&updated_task);
+
+            summary.pushed += 1;
+            on_event(&SyncEvent::Pushed {
+                task: &updated_task,
+                stat_messages: Vec::new(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Carry the newest stats forward and collect the diff messages
+    ///
+    /// Workers start from the stats before the sync, so the diff is taken
+    /// against the stats carried forward so far.
+    fn take_stat_messages(
+        stats_cache: Option<StatsCache>,
+        current_stats: &mut UserStats,
+    ) -> Vec<String> {
+        let Some(mut cache) = stats_cache else {
+            return Vec::new();
+        };
+        cache.old = current_stats.clone();
+        if let Some(new_stats) = cache.current.clone() {
+            *current_stats = new_stats;
+        }
+        cache.get_diff_messages()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::*;
+    use crate::{
+        config::ConflictStrategy,
+        habitica::{GroupInfo, HabiticaClient},
+        taskwarrior::{TaskDifficulty, TaskType, TaskwarriorClient},
+    };
+
+    fn test_tw_task(h_id: Option<Uuid>) -> Task {
+        Task {
+            uuid: Uuid::new_v4(),
+            description: "Test task".to_string(),
+            status: TaskStatus::Pending,
+            modified: Some(Utc::now() - Duration::hours(1)),
+            due: None,
+            annotations: None,
+            habitica_uuid: h_id,
+            habitica_difficulty: Some(TaskDifficulty::Easy),
+            habitica_task_type: Some(TaskType::Todo),
+            habitica_cost: None,
+            habitica_assigned: None,
+            habitica_attribute: None,
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    fn test_h_task(id: Uuid, text: &str) -> HabiticaTask {
+        HabiticaTask {
+            id: Some(id),
+            text: text.to_string(),
+            notes: String::new(),
+            task_type: HabiticaTaskType::Todo,
+            priority: 1.0,
+            completed: false,
+            date: None,
+            updated_at: Some(Utc::now()),
+            is_due: false,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_plan_from_classifies_tasks() {
+        let config = Config::for_tests();
+        let backend = TaskwarriorClient::new();
+        let h_client = HabiticaClient::new(&config).unwrap();
+        let engine = SyncEngine::new(&config, &backend, &h_client);
+
+        let linked_id = Uuid::new_v4();
+        let gone_id = Uuid::new_v4();
+        let remote_id = Uuid::new_v4();
+
+        let plan = engine.plan_from(
+            vec![test_tw_task(None)],
+            vec![test_tw_task(Some(linked_id)), test_tw_task(Some(gone_id))],
+            vec![
+                test_h_task(linked_id, "Renamed on Habitica"),
+                test_h_task(remote_id, "Remote only"),
+            ],
+        );
+
+        assert_eq!(plan.operations.len(), 4);
+        assert!(matches!(plan.operations[0], SyncOperation::PushNew(_)));
+        assert!(plan.operations.iter().any(|op| matches!(
+            op,
+            SyncOperation::Reconcile {
+                action: ResolutionAction::UseHabitica,
+                ..
+            }
+        )));
+        assert!(plan
+            .operations
+            .iter()
+            .any(|op| matches!(op, SyncOperation::DeletedOnHabitica(t) if t.habitica_uuid == Some(gone_id))));
+        assert!(plan.operations.iter().any(
+            |op| matches!(op, SyncOperation::ImportFromHabitica(h) if h.id == Some(remote_id))
+        ));
+        assert!(!plan.is_empty());
+    }
+
+    #[test]
+    fn test_conflicts_are_deferred() {
+        let mut config = Config::for_tests();
+        config.conflict_strategy = ConflictStrategy::Defer;
+        let backend = TaskwarriorClient::new();
+        let h_client = HabiticaClient::new(&config).unwrap();
+        let engine = SyncEngine::new(&config, &backend, &h_client);
+
+        let linked_id = Uuid::new_v4();
+        let plan = engine.plan_from(
+            Vec::new(),
+            vec![test_tw_task(Some(linked_id))],
+            vec![test_h_task(linked_id, "Renamed on Habitica")],
+        );
+        assert!(matches!(
+            plan.operations[..],
+            [SyncOperation::Reconcile {
+                action: ResolutionAction::Defer,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_plan_without_changes_is_empty() {
+        let linked_id = Uuid::new_v4();
+        let reconcile = |action| SyncOperation::Reconcile {
+            tw_task: test_tw_task(Some(linked_id)),
+            h_task: test_h_task(linked_id, "Test task"),
+            action,
+        };
+
+        let mut plan = SyncPlan {
+            operations: vec![
+                reconcile(ResolutionAction::NoChange),
+                reconcile(ResolutionAction::Skip),
+                reconcile(ResolutionAction::Defer),
+            ],
+        };
+        assert!(plan.is_empty());
+
+        plan.operations
+            .push(reconcile(ResolutionAction::UseHabitica));
+        assert!(!plan.is_empty());
+    }
+
+    #[test]
+    fn test_shared_copies_are_skipped() {
+        let group_id = Uuid::new_v4();
+        let mut config = Config::for_tests();
+        config.group_plans.insert("home".to_string(), group_id);
+        let backend = TaskwarriorClient::new();
+        let h_client = HabiticaClient::new(&config).unwrap();
+        let engine = SyncEngine::new(&config, &backend, &h_client);
+
+        let mut group_task = test_h_task(Uuid::new_v4(), "Dishes");
+        group_task.group = Some(GroupInfo {
+            id: Some(group_id),
+            ..Default::default()
+        });
+        let mut copy = test_h_task(Uuid::new_v4(), "Dishes");
+        copy.group = Some(GroupInfo {
+            id: Some(group_id),
+            task_id: group_task.id,
+            ..Default::default()
+        });
+
+        let plan = engine.plan_from(Vec::new(), Vec::new(), vec![group_task.clone(), copy]);
+        assert_eq!(plan.operations.len(), 1);
+        assert!(matches!(
+            &plan.operations[0],
+            SyncOperation::ImportFromHabitica(h) if h.id == group_task.id
+        ));
+    }
+
+    #[test]
+    fn test_habitica_starter_tasks_are_not_imported() {
+        let config = Config::for_tests();
+        let backend = TaskwarriorClient::new();
+        let h_client = HabiticaClient::new(&config).unwrap();
+        let engine = SyncEngine::new(&config, &backend, &h_client);
+
+        let mut starter = test_h_task(Uuid::new_v4(), "Join Habitica");
+        starter.by_habitica = true;
+        // Already linked before the filter existed, so it keeps syncing
+        let mut linked = test_h_task(Uuid::new_v4(), "Set up a daily");
+        linked.by_habitica = true;
+
+        let plan = engine.plan_from(
+            Vec::new(),
+            vec![test_tw_task(linked.id)],
+            vec![starter, linked],
+        );
+        assert_eq!(plan.operations.len(), 1);
+        assert!(matches!(
+            plan.operations[0],
+            SyncOperation::Reconcile { .. }
+        ));
+    }
+
+    #[test]
+    fn test_new_todos_are_batched() {
+        let config = Config::for_tests();
+        let backend = TaskwarriorClient::new();
+        let h_client = HabiticaClient::new(&config).unwrap();
+        let engine = SyncEngine::new(&config, &backend, &h_client);
+
+        let mut daily = test_tw_task(None);
+        daily.habitica_task_type = Some(TaskType::Daily);
+        let mut done = test_tw_task(None);
+        done.status = TaskStatus::Completed;
+        let operations = vec![
+            SyncOperation::PushNew(test_tw_task(None)),
+            SyncOperation::PushNew(daily),
+            SyncOperation::PushNew(done),
+            SyncOperation::PushNew(test_tw_task(None)),
+        ];
+
+        let (batch, rest) = engine.split_batch(operations).unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(rest.len(), 2);
+
+        // A single todo isn't worth a batch
+        let (batch, rest) = engine
+            .split_batch(vec![SyncOperation::PushNew(test_tw_task(None))])
+            .unwrap();
+        assert!(batch.is_empty());
+        assert_eq!(rest.len(), 1);
+    }
+
+    #[test]
+    fn test_unlinked_rewards_are_not_imported() {
+        let mut reward = test_h_task(Uuid::new_v4(), "Movie night");
+        reward.task_type = HabiticaTaskType::Reward;
+
+        let plan = SyncPlan::build(Vec::new(), Vec::new(), vec![reward], |_, _| {
+            ResolutionAction::NoChange
+        });
+        assert!(plan.operations.is_empty());
+    }
+
+    /// Backend whose exports are the tasks matching `habitica.filter`
+    struct FilterBackend {
+        matching: Vec<Task>,
+    }
+
+    impl TaskBackend for FilterBackend {
+        fn export(&self, _filters: &[&str]) -> Result<Vec<Task>> {
+            Ok(self.matching.clone())
+        }
+
+        fn import(&self, _task: &Task) -> Result<String> {
+            Ok(String::new())
+        }
+
+        fn get_config(&self, _key: &str) -> Result<String> {
+            Ok(String::new())
+        }
+    }
+
+    #[test]
+    fn test_split_by_filter() {
+        let mut config = Config::for_tests();
+        config.push_filter = Some("urgency > 5".to_string());
+
+        let urgent = test_tw_task(None);
+        let held = test_tw_task(None);
+        let linked_id = Uuid::new_v4();
+        let below_id = Uuid::new_v4();
+        let done_id = Uuid::new_v4();
+        let linked = test_tw_task(Some(linked_id));
+        let below = test_tw_task(Some(below_id));
+        let mut done = test_tw_task(Some(done_id));
+        done.status = TaskStatus::Completed;
+
+        let backend = FilterBackend {
+            matching: vec![urgent.clone(), linked.clone()],
+        };
+        let mut lost_link = test_h_task(Uuid::new_v4(), "Held");
+        lost_link.alias = Some(held.uuid.to_string());
+        let mut h_tasks = vec![
+            test_h_task(linked_id, "Linked"),
+            test_h_task(below_id, "Below"),
+            test_h_task(done_id, "Done"),
+            lost_link,
+        ];
+
+        let (snapshot, filtered) = split_by_filter(
+            &config,
+            &backend,
+            TaskSnapshot {
+                unlinked: vec![urgent.clone(), held],
+                linked: vec![linked, below.clone(), done],
+            },
+            &mut h_tasks,
+        )
+        .unwrap();
+
+        assert_eq!(snapshot.unlinked.len(), 1);
+        assert_eq!(snapshot.unlinked[0].uuid, urgent.uuid);
+        assert_eq!(snapshot.linked.len(), 2);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].uuid, below.uuid);
+        let texts: Vec<&str> = h_tasks.iter().map(|h_task| h_task.text.as_str()).collect();
+        assert_eq!(texts, ["Linked", "Done"]);
+    }
+
+    #[test]
+    fn test_split_by_scope() {
+        let mut config = Config::for_tests();
+        config.sync_scope = Some("project:work".to_string());
+
+        let inside = test_tw_task(None);
+        let outside = test_tw_task(None);
+        let linked_id = Uuid::new_v4();
+        let outside_id = Uuid::new_v4();
+        let linked = test_tw_task(Some(linked_id));
+        let outside_linked = test_tw_task(Some(outside_id));
+
+        let backend = FilterBackend {
+            matching: vec![inside.clone(), linked.clone()],
+        };
+        let mut lost_link = test_h_task(Uuid::new_v4(), "Lost link");
+        lost_link.alias = Some(outside.uuid.to_string());
+        let mut h_tasks = vec![
+            test_h_task(linked_id, "Linked"),
+            test_h_task(outside_id, "Outside"),
+            test_h_task(Uuid::new_v4(), "New on Habitica"),
+            lost_link,
+        ];
+
+        let snapshot = split_by_scope(
+            &config,
+            &backend,
+            TaskSnapshot {
+                unlinked: vec![inside.clone(), outside],
+                linked: vec![linked.clone(), outside_linked],
+            },
+            &mut h_tasks,
+        )
+        .unwrap();
+
+        assert_eq!(snapshot.unlinked.len(), 1);
+        assert_eq!(snapshot.unlinked[0].uuid, inside.uuid);
+        assert_eq!(snapshot.linked.len(), 1);
+        assert_eq!(snapshot.linked[0].uuid, linked.uuid);
+        let texts: Vec<&str> = h_tasks.iter().map(|h_task| h_task.text.as_str()).collect();
+        assert_eq!(texts, ["Linked", "New on Habitica"]);
+    }
+
+    #[test]
+    fn test_skip_unchanged() {
+        let since = Utc::now() - Duration::minutes(30);
+        let old_id = Uuid::new_v4();
+        let touched_id = Uuid::new_v4();
+        let edited_id = Uuid::new_v4();
+        let old = test_tw_task(Some(old_id));
+        let touched = test_tw_task(Some(touched_id));
+        let mut edited = test_tw_task(Some(edited_id));
+        edited.modified = Some(Utc::now());
+
+        let mut untouched = test_h_task(old_id, "Old");
+        untouched.updated_at = Some(since - Duration::hours(1));
+        let mut stale = test_h_task(edited_id, "Edited");
+        stale.updated_at = untouched.updated_at;
+        let mut tw_synced = vec![old, touched, edited];
+        let mut h_tasks = vec![untouched, test_h_task(touched_id, "Touched"), stale];
+
+        skip_unchanged(since, &mut tw_synced, &mut h_tasks);
+
+        assert_eq!(tw_synced.len(), 2);
+        let texts: Vec<&str> = h_tasks.iter().map(|h_task| h_task.text.as_str()).collect();
+        assert_eq!(texts, ["Touched", "Edited"]);
+    }
+
+    #[test]
+    fn test_apply_tombstones() {
+        let now = Utc::now();
+        let purged_id = Uuid::new_v4();
+        let restored_id = Uuid::new_v4();
+        let purged = test_tw_task(Some(purged_id));
+        let restored = test_tw_task(Some(restored_id));
+        let stale = test_tw_task(None);
+        let mut revived = test_tw_task(None);
+
+        let mut tombstones = Tombstones::default();
+        tombstones.record(&purged, purged_id, DeletedOn::Taskwarrior, now);
+        tombstones.record(&restored, restored_id, DeletedOn::Taskwarrior, now);
+        tombstones.record(&stale, Uuid::new_v4(), DeletedOn::Habitica, now);
+        tombstones.record(&revived, Uuid::new_v4(), DeletedOn::Habitica, now);
+        revived.modified = Some(now + Duration::minutes(5));
+
+        // The restored task got its link back, so it is reconciled as usual
+        let tw_synced = vec![restored];
+        let mut tw_only = vec![stale.clone(), revived.clone()];
+        let mut h_tasks = vec![
+            test_h_task(purged_id, "Purged"),
+            test_h_task(restored_id, "Restored"),
+        ];
+
+        let operations = apply_tombstones(&tombstones, &mut tw_only, &tw_synced, &mut h_tasks);
+
+        assert_eq!(operations.len(), 2);
+        assert!(matches!(
+            &operations[0],
+            SyncOperation::DeletedInTaskwarrior(h_task) if h_task.id == Some(purged_id)
+        ));
+        assert!(matches!(
+            &operations[1],
+            SyncOperation::DeletedOnHabitica(task) if task.uuid == stale.uuid
+        ));
+        assert_eq!(h_tasks.len(), 1);
+        assert_eq!(h_tasks[0].id, Some(restored_id));
+        assert_eq!(tw_only.len(), 1);
+        assert_eq!(tw_only[0].uuid, revived.uuid);
+    }
+
+    #[test]
+    fn test_alias_restores_lost_link() {
+        let tw_task = test_tw_task(None);
+        let mut h_task = test_h_task(Uuid::new_v4(), "Test task");
+        h_task.alias = Some(tw_task.uuid.to_string());
+
+        let plan = SyncPlan::build(vec![tw_task], Vec::new(), vec![h_task.clone()], |_, _| {
+            ResolutionAction::NoChange
+        });
+        assert_eq!(plan.operations.len(), 1);
+        assert!(matches!(
+            &plan.operations[0],
+            SyncOperation::Reconcile {
+                tw_task,
+                action: ResolutionAction::UseHabitica,
+                ..
+            } if tw_task.habitica_uuid == h_task.id
+        ));
+    }
+
+    #[test]
+    fn test_expired_tasks_are_not_synced() {
+        let until = serde_json::Value::String("20200101T000000Z".to_string());
+        let mut unlinked = test_tw_task(None);
+        unlinked.extra.insert("until".to_string(), until.clone());
+        let h_task = test_h_task(Uuid::new_v4(), "Test task");
+        let mut linked = test_tw_task(h_task.id);
+        linked.extra.insert("until".to_string(), until);
+
+        let plan = SyncPlan::build(vec![unlinked], vec![linked], vec![h_task], |_, _| {
+            ResolutionAction::NoChange
+        });
+        assert_eq!(plan.operations.len(), 1);
+        let SyncOperation::Expired(tw_task) = &plan.operations[0] else {
+            panic!("expected an expired task");
+        };
+
+        let expired = expire(tw_task);
+        assert_eq!(expired.status, TaskStatus::Deleted);
+        assert_eq!(expired.habitica_uuid, None);
+        assert_eq!(expired.date_attribute("end"), tw_task.until());
+    }
+
+    #[test]
+    fn test_recurring_link_rolls_forward() {
+        let parent = serde_json::Value::String(Uuid::new_v4().to_string());
+        let h_task = test_h_task(Uuid::new_v4(), "Test task");
+        let mut previous = test_tw_task(h_task.id);
+        previous.due = Some(Utc::now() - Duration::days(7));
+        previous.extra.insert("parent".to_string(), parent.clone());
+        previous.extra.insert("imask".to_string(), 1.into());
+        let mut current = test_tw_task(None);
+        current.due = Some(Utc::now() - Duration::hours(1));
+        current.extra.insert("parent".to_string(), parent.clone());
+        current.extra.insert("imask".to_string(), 2.into());
+        let mut upcoming = test_tw_task(None);
+        upcoming.due = Some(Utc::now() + Duration::days(7));
+        upcoming.extra.insert("parent".to_string(), parent);
+        upcoming.extra.insert("imask".to_string(), 3.into());
+
+        let plan = SyncPlan::build(
+            vec![current.clone(), upcoming],
+            vec![previous.clone()],
+            vec![h_task.clone()],
+            |_, _| ResolutionAction::NoChange,
+        );
+        assert_eq!(plan.operations.len(), 2);
+        assert!(matches!(
+            &plan.operations[0],
+            SyncOperation::Superseded(t) if t.uuid == previous.uuid
+        ));
+        let SyncOperation::Reconcile {
+            tw_task,
+            action: ResolutionAction::UseTaskwarrior,
+            ..
+        } = &plan.operations[1]
+        else {
+            panic!("expected the current instance to be pushed");
+        };
+        assert_eq!(tw_task.uuid, current.uuid);
+        assert_eq!(tw_task.habitica_uuid, h_task.id);
+    }
+
+    #[test]
+    fn test_empty_plan() {
+        assert!(SyncPlan::default().is_empty());
+    }
+}
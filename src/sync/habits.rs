@@ -0,0 +1,179 @@
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    habitica::{HabiticaApi, ScoreDirection, StatsCache},
+    taskwarrior::{Task, TaskType},
+};
+
+/// Score a habit, given by name or ID, a number of times
+pub fn score_habit(
+    h_client: &dyn HabiticaApi,
+    name_or_id: &str,
+    times: u32,
+    stats_cache: &mut Option<StatsCache>,
+) -> Result<()> {
+    if times == 0 {
+        return Ok(());
+    }
+
+    let habit = h_client
+        .find_habit(name_or_id)?
+        .ok_or_else(|| Error::config(format!("Habit not found on Habitica: {}", name_or_id)))?;
+    let habit_id = habit
+        .id
+        .ok_or_else(|| Error::HabiticaApiError("Habit has no ID".to_string()))?;
+
+    for _ in 0..times {
+        let (new_stats, drop_msg) = h_client.score_task(habit_id, ScoreDirection::Up)?;
+        if let Some(cache) = stats_cache {
+            cache.update(new_stats, drop_msg);
+        }
+    }
+
+    Ok(())
+}
+
+/// Scores due for a linked habit whose counter UDAs went up
+///
+/// Counters going down (Habitica resets them) score nothing.
+pub fn counter_increments(old: &Task, new: &Task) -> Vec<(ScoreDirection, u32)> {
+    if new.task_type() != TaskType::Habit || new.habitica_uuid.is_none() {
+        return Vec::new();
+    }
+    let (old_up, old_down) = old.habit_counters();
+    let (new_up, new_down) = new.habit_counters();
+    [
+        (ScoreDirection::Up, new_up.saturating_sub(old_up)),
+        (ScoreDirection::Down, new_down.saturating_sub(old_down)),
+    ]
+    .into_iter()
+    .filter(|(_, times)| *times > 0)
+    .collect()
+}
+
+/// Scores due for the configured start habit after a modification
+///
+/// Starting a linked task scores the habit once, and with
+/// `habitica.start.on_stop` so does stopping it, which `task done` does for a
+/// started task too. Moving the start date of a running task scores nothing.
+pub fn start_scores(config: &Config, old: &Task, new: &Task) -> u32 {
+    if config.start_habit.is_none() || new.habitica_uuid.is_none() {
+        return 0;
+    }
+    match (
+        old.date_attribute("start").is_some(),
+        new.date_attribute("start").is_some(),
+    ) {
+        (false, true) => 1,
+        (true, false) if config.start_habit_on_stop => 1,
+        _ => 0,
+    }
+}
+
+/// Score a linked habit in each direction the given number of times
+pub fn score_counters(
+    h_client: &dyn HabiticaApi,
+    habit: &Task,
+    scores: &[(ScoreDirection, u32)],
+    stats_cache: &mut Option<StatsCache>,
+) -> Result<()> {
+    let Some(habit_id) = habit.habitica_uuid else {
+        return Ok(());
+    };
+    for (direction, times) in scores {
+        for _ in 0..*times {
+            let (new_stats, drop_msg) = h_client.score_task(habit_id, *direction)?;
+            if let Some(cache) = stats_cache {
+                cache.update(new_stats, drop_msg);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Tags of a task that are configured as habit tags
+pub fn habit_tags_of<'t>(task: &'t Task, habit_tags: &[String]) -> Vec<&'t str> {
+    task.tags()
+        .into_iter()
+        .filter(|tag| habit_tags.iter().any(|habit_tag| habit_tag == tag))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_habit_tags_of() {
+        let mut task = Task::default();
+        task.extra
+            .insert("tags".to_string(), json!(["exercise", "home", "reading"]));
+        let habit_tags = vec!["reading".to_string(), "exercise".to_string()];
+
+        assert_eq!(habit_tags_of(&task, &habit_tags), ["exercise", "reading"]);
+        assert!(habit_tags_of(&Task::default(), &habit_tags).is_empty());
+    }
+
+    #[test]
+    fn test_start_scores() {
+        let mut config = Config::for_tests();
+        let stopped = Task {
+            habitica_uuid: Some(uuid::Uuid::new_v4()),
+            ..Default::default()
+        };
+        let mut started = stopped.clone();
+        started
+            .extra
+            .insert("start".to_string(), json!("20260301T080000Z"));
+        assert_eq!(start_scores(&config, &stopped, &started), 0);
+
+        config.start_habit = Some("Deep work".to_string());
+        assert_eq!(start_scores(&config, &stopped, &started), 1);
+        assert_eq!(start_scores(&config, &started, &started), 0);
+        assert_eq!(start_scores(&config, &started, &stopped), 0);
+
+        config.start_habit_on_stop = true;
+        assert_eq!(start_scores(&config, &started, &stopped), 1);
+
+        // Only linked tasks score
+        let mut unlinked = started.clone();
+        unlinked.habitica_uuid = None;
+        assert_eq!(start_scores(&config, &stopped, &unlinked), 0);
+    }
+
+    #[test]
+    fn test_counter_increments() {
+        let mut old = Task {
+            habitica_uuid: Some(uuid::Uuid::new_v4()),
+            habitica_task_type: Some(TaskType::Habit),
+            ..Default::default()
+        };
+        old.extra
+            .insert("habitica_counter_up".to_string(), json!(2));
+        old.extra
+            .insert("habitica_counter_down".to_string(), json!(1));
+
+        let mut new = old.clone();
+        new.extra
+            .insert("habitica_counter_up".to_string(), json!(4));
+        new.extra
+            .insert("habitica_counter_down".to_string(), json!(0));
+
+        let scores = counter_increments(&old, &new);
+        assert_eq!(scores.len(), 1);
+        assert!(matches!(scores[0], (ScoreDirection::Up, 2)));
+
+        // Unset counters count as zero
+        new.extra.remove("habitica_counter_up");
+        new.extra
+            .insert("habitica_counter_down".to_string(), json!("3"));
+        let scores = counter_increments(&Task::default(), &new);
+        assert!(matches!(scores[..], [(ScoreDirection::Down, 3)]));
+
+        // Only linked habits are scored
+        new.habitica_task_type = Some(TaskType::Todo);
+        assert!(counter_increments(&old, &new).is_empty());
+    }
+}
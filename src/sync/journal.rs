@@ -0,0 +1,272 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{error::Result, sync::SyncEvent, taskwarrior::Task};
+
+/// What the sync engine did to a task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum JournalAction {
+    Pushed,
+    Imported,
+    DeletedOnHabitica,
+    DeletedInTaskwarrior,
+    Expired,
+    Filtered,
+    Superseded,
+    UpdatedTaskwarrior,
+    UpdatedHabitica,
+    Merged,
+    Combined,
+}
+
+impl JournalAction {
+    /// Name used in the journal and by `log`
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            JournalAction::Pushed => "pushed",
+            JournalAction::Imported => "imported",
+            JournalAction::DeletedOnHabitica => "deleted-on-habitica",
+            JournalAction::DeletedInTaskwarrior => "deleted-in-taskwarrior",
+            JournalAction::Expired => "expired",
+            JournalAction::Filtered => "filtered",
+            JournalAction::Superseded => "superseded",
+            JournalAction::UpdatedTaskwarrior => "updated-taskwarrior",
+            JournalAction::UpdatedHabitica => "updated-habitica",
+            JournalAction::Merged => "merged",
+            JournalAction::Combined => "combined",
+        }
+    }
+}
+
+/// One action of the sync engine
+///
+/// `before` and `after` are the Taskwarrior task around the action. Tasks
+/// that only exist on Habitica have no Taskwarrior side, so an import has no
+/// `before` and a deletion carried over to Habitica keeps the deleted
+/// Habitica task as `before`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub time: DateTime<Utc>,
+    /// Command that ran the sync, e.g. `sync` or `flush`
+    pub source: String,
+    pub action: JournalAction,
+    /// Taskwarrior UUID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub habitica_id: Option<Uuid>,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Value>,
+}
+
+impl JournalEntry {
+    /// Entry for a sync event, `None` if nothing was changed
+    pub fn from_event(event: &SyncEvent, source: &str, time: DateTime<Utc>) -> Option<Self> {
+        let (action, task) = match event {
+            SyncEvent::Imported { h_task } => {
+                return Some(JournalEntry {
+                    time,
+                    source: source.to_string(),
+                    action: JournalAction::Imported,
+                    task: None,
+                    habitica_id: h_task.id,
+                    description: h_task.text.clone(),
+                    before: None,
+                    after: None,
+                });
+            }
+            SyncEvent::DeletedInTaskwarrior { h_task } => {
+                return Some(JournalEntry {
+                    time,
+                    source: source.to_string(),
+                    action: JournalAction::DeletedInTaskwarrior,
+                    task: None,
+                    habitica_id: h_task.id,
+                    description: h_task.text.clone(),
+                    before: serde_json::to_value(h_task).ok(),
+                    after: None,
+                });
+            }
+            SyncEvent::Pushed { task, .. } => (JournalAction::Pushed, *task),
+            SyncEvent::DeletedOnHabitica { task, .. } => (JournalAction::DeletedOnHabitica, *task),
+            SyncEvent::Expired { task } => (JournalAction::Expired, *task),
+            SyncEvent::Filtered { task } => (JournalAction::Filtered, *task),
+            SyncEvent::Superseded { task } => (JournalAction::Superseded, *task),
+            SyncEvent::UpdatedTaskwarrior { tw_task, .. } => {
+                (JournalAction::UpdatedTaskwarrior, *tw_task)
+            }
+            SyncEvent::UpdatedHabitica { tw_task, .. } => {
+                (JournalAction::UpdatedHabitica, *tw_task)
+            }
+            SyncEvent::Merged { tw_task, .. } => (JournalAction::Merged, *tw_task),
+            SyncEvent::Combined { tw_task, .. } => (JournalAction::Combined, *tw_task),
+            SyncEvent::Unchanged { .. }
+            | SyncEvent::Skipped { .. }
+            | SyncEvent::Deferred { .. } => return None,
+        };
+        Some(JournalEntry {
+            time,
+            source: source.to_string(),
+            action,
+            task: Some(task.uuid),
+            habitica_id: task.habitica_uuid,
+            description: task.description.clone(),
+            before: serde_json::to_value(task).ok(),
+            after: None,
+        })
+    }
+
+    /// Check whether the entry is about a task, by either side's ID
+    pub fn concerns(&self, id: Uuid) -> bool {
+        self.task == Some(id) || self.habitica_id == Some(id)
+    }
+
+    /// Fields that differ between `before` and `after`, with both values
+    pub fn changes(&self) -> Vec<(String, Option<Value>, Option<Value>)> {
+        let (Some(Value::Object(before)), Some(Value::Object(after))) = (&self.before, &self.after)
+        else {
+            return Vec::new();
+        };
+
+        let mut fields: Vec<&String> = before.keys().chain(after.keys()).collect();
+        fields.sort();
+        fields.dedup();
+        fields
+            .into_iter()
+            .filter(|field| before.get(*field) != after.get(*field))
+            .map(|field| {
+                (
+                    field.clone(),
+                    before.get(field).cloned(),
+                    after.get(field).cloned(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Fill in `after` with the Taskwarrior tasks as they were imported
+///
+/// Imports land after the plan is applied, so entries only learn the
+/// result once the engine is done. A task imported twice ends up as its
+/// last version.
+pub fn attach_imports(entries: &mut [JournalEntry], imports: &[Task]) {
+    for entry in entries {
+        let imported = imports.iter().rev().find(|task| match entry.task {
+            Some(uuid) => task.uuid == uuid,
+            None => {
+                entry.action == JournalAction::Imported && task.habitica_uuid == entry.habitica_id
+            }
+        });
+        if let Some(task) = imported {
+            entry.task = Some(task.uuid);
+            // Pushed tasks only get their Habitica ID on the way
+            entry.habitica_id = entry.habitica_id.or(task.habitica_uuid);
+            entry.after = serde_json::to_value(task).ok();
+        }
+    }
+}
+
+/// Append entries to the journal file
+pub fn append(path: &Path, entries: &[JournalEntry]) -> Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let mut lines = String::new();
+    for entry in entries {
+        lines.push_str(&serde_json::to_string(entry)?);
+        lines.push('\n');
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(lines.as_bytes())?;
+    Ok(())
+}
+
+/// Read the whole journal, oldest entry first
+pub fn read(path: &Path) -> Result<Vec<JournalEntry>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect::<serde_json::Result<Vec<JournalEntry>>>()?;
+    Ok(entries)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::habitica::HabiticaTask;
+
+    #[test]
+    fn test_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("habitica_journal.jsonl");
+        let now = Utc::now();
+
+        let tw_task = Task {
+            uuid: Uuid::new_v4(),
+            description: "Water plants".to_string(),
+            ..Default::default()
+        };
+        let h_task = HabiticaTask {
+            id: Some(Uuid::new_v4()),
+            text: "Call mom".to_string(),
+            ..Default::default()
+        };
+
+        let events = [
+            SyncEvent::Pushed {
+                task: &tw_task,
+                stat_messages: Vec::new(),
+            },
+            SyncEvent::Imported { h_task: &h_task },
+            SyncEvent::Unchanged {
+                tw_task: &tw_task,
+                h_task: &h_task,
+            },
+        ];
+        let mut entries: Vec<JournalEntry> = events
+            .iter()
+            .filter_map(|event| JournalEntry::from_event(event, "sync", now))
+            .collect();
+        assert_eq!(entries.len(), 2);
+
+        let mut pushed = tw_task.clone();
+        pushed.habitica_uuid = Some(Uuid::new_v4());
+        let imported = Task {
+            uuid: Uuid::new_v4(),
+            description: "Call mom".to_string(),
+            habitica_uuid: h_task.id,
+            ..Default::default()
+        };
+        attach_imports(&mut entries, &[pushed.clone(), imported.clone()]);
+        assert_eq!(entries[1].task, Some(imported.uuid));
+
+        let changes = entries[0].changes();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].0, "habitica_uuid");
+
+        append(&path, &entries[..1]).unwrap();
+        append(&path, &entries[1..]).unwrap();
+        let read_back = read(&path).unwrap();
+        assert_eq!(read_back, entries);
+        assert!(read_back[0].concerns(pushed.habitica_uuid.unwrap()));
+        assert!(!read_back[1].concerns(tw_task.uuid));
+    }
+}
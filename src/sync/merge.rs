@@ -0,0 +1,577 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    habitica::HabiticaTask,
+    sync::{converter, sanitize, ResolutionAction},
+    taskwarrior::{NotesManager, Task, TaskDifficulty},
+};
+
+/// Last synced version of each linked task, the common ancestor for merges
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MergeBase {
+    tasks: HashMap<Uuid, Task>,
+    /// When each task was last recorded, missing for tasks recorded by
+    /// earlier versions
+    #[serde(default)]
+    synced_at: HashMap<Uuid, DateTime<Utc>>,
+}
+
+impl MergeBase {
+    /// Load the merge base, empty if it was never saved
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(MergeBase::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Save the merge base
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Last synced version of the task linked to a Habitica task
+    pub fn get(&self, h_id: Uuid) -> Option<&Task> {
+        self.tasks.get(&h_id)
+    }
+
+    /// When the task linked to a Habitica task was last synced
+    pub fn synced_at(&self, h_id: Uuid) -> Option<DateTime<Utc>> {
+        self.synced_at.get(&h_id).copied()
+    }
+
+    /// Remember a task as synced now, or forget it once unlinked
+    pub fn record(&mut self, task: &Task) {
+        self.record_at(task, Utc::now());
+    }
+
+    /// Remember a task as synced at `now`, or forget it once unlinked
+    pub fn record_at(&mut self, task: &Task, now: DateTime<Utc>) {
+        if let Some(h_id) = task.habitica_uuid {
+            self.tasks.insert(h_id, task.clone());
+            self.synced_at.insert(h_id, now);
+        } else {
+            self.tasks.retain(|_, base| base.uuid != task.uuid);
+            let tasks = &self.tasks;
+            self.synced_at.retain(|h_id, _| tasks.contains_key(h_id));
+        }
+    }
+}
+
+/// Decide which side changed since the last sync
+///
+/// Only a task changed on both sides, or one without a known base, needs
+/// the merge tool.
+pub fn resolve_three_way(
+    config: &Config,
+    base: Option<&Task>,
+    tw_task: &Task,
+    h_task: &HabiticaTask,
+) -> ResolutionAction {
+    if converter::tasks_are_equivalent(config, tw_task, h_task) {
+        return ResolutionAction::NoChange;
+    }
+
+    base.and_then(|base| changed_side(config, base, tw_task, h_task))
+        .unwrap_or(ResolutionAction::Merge)
+}
+
+/// Pick the side that changed a task since the base, `None` if both did
+pub fn changed_side(
+    config: &Config,
+    base: &Task,
+    tw_task: &Task,
+    h_task: &HabiticaTask,
+) -> Option<ResolutionAction> {
+    if base == tw_task {
+        Some(ResolutionAction::UseHabitica)
+    } else if converter::tasks_are_equivalent(config, base, h_task) {
+        Some(ResolutionAction::UseTaskwarrior)
+    } else {
+        None
+    }
+}
+
+/// Side whose value of a field is kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Taskwarrior,
+    Habitica,
+}
+
+/// Which side wins each field of a task changed on both sides
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldDecisions {
+    pub description: Side,
+    pub due: Side,
+    pub difficulty: Side,
+    pub status: Side,
+    pub notes: Side,
+}
+
+impl FieldDecisions {
+    /// The side winning every field, if one does
+    pub fn unanimous(&self) -> Option<Side> {
+        let sides = [
+            self.description,
+            self.due,
+            self.difficulty,
+            self.status,
+            self.notes,
+        ];
+        sides
+            .iter()
+            .all(|side| *side == self.description)
+            .then_some(self.description)
+    }
+}
+
+/// Decide each field of a task changed on both sides on its own
+///
+/// A field changed on one side since the base keeps that change. One changed
+/// on both sides, or on neither, goes to the side `winner` picks, which is
+/// called at most once. `h_notes` are the Habitica notes without an appended
+/// issue link.
+pub fn decide_fields(
+    config: &Config,
+    base: &Task,
+    tw_task: &Task,
+    h_task: &HabiticaTask,
+    h_notes: &str,
+    winner: impl FnOnce() -> Side,
+) -> FieldDecisions {
+    let base_difficulty = converter::difficulty_of(config, base);
+    let prefix = &config.task_note_prefix;
+    let base_note = base.note_annotation(prefix);
+    let h_note = NotesManager::new(config).preview(h_notes);
+
+    // Whether each field changed in Taskwarrior and on Habitica
+    let changes = [
+        (
+            tw_task.description != base.description,
+            sanitize::description(config, &h_task.text) != base.description,
+        ),
+        (tw_task.due != base.due, h_task.date != base.due),
+        (
+            converter::difficulty_of(config, tw_task) != base_difficulty,
+            base_difficulty.to_habitica_priority() != h_task.priority,
+        ),
+        (
+            tw_task.status != base.status,
+            h_task.completed != base.status.is_completed(),
+        ),
+        (
+            tw_task.note_annotation(prefix) != base_note,
+            h_note.as_deref() != base_note,
+        ),
+    ];
+    let tie_break = changes.iter().any(|(tw, h)| tw == h).then(winner);
+    let [description, due, difficulty, status, notes] = changes.map(|changed| match changed {
+        (true, false) => Side::Taskwarrior,
+        (false, true) => Side::Habitica,
+        // Only reached when the winner was picked
+        _ => tie_break.unwrap_or(Side::Taskwarrior),
+    });
+
+    FieldDecisions {
+        description,
+        due,
+        difficulty,
+        status,
+        notes,
+    }
+}
+
+/// Fields that differ between both versions of a task, each with its
+/// Taskwarrior and Habitica value
+pub fn field_diff(
+    config: &Config,
+    tw_task: &Task,
+    h_task: &HabiticaTask,
+    h_notes: &str,
+) -> Vec<(&'static str, String, String)> {
+    let date = |date: Option<DateTime<Utc>>| {
+        date.map_or_else(
+            || "-".to_string(),
+            |date| date.format("%Y-%m-%d %H:%M").to_string(),
+        )
+    };
+    let state = |completed: bool| if completed { "completed" } else { "open" }.to_string();
+    let difficulty = |difficulty: TaskDifficulty| format!("{:?}", difficulty).to_lowercase();
+    let prefix = &config.task_note_prefix;
+    let note = |preview: Option<&str>| {
+        preview
+            .and_then(|preview| preview.strip_prefix(prefix.as_str()))
+            .map_or_else(|| "-".to_string(), |line| line.trim().to_string())
+    };
+    let h_note = NotesManager::new(config).preview(h_notes);
+
+    let fields = [
+        (
+            "description",
+            tw_task.description.clone(),
+            sanitize::description(config, &h_task.text),
+        ),
+        ("due", date(tw_task.due), date(h_task.date)),
+        (
+            "difficulty",
+            difficulty(converter::difficulty_of(config, tw_task)),
+            difficulty(TaskDifficulty::from_habitica_priority(h_task.priority)),
+        ),
+        (
+            "status",
+            state(tw_task.status.is_completed()),
+            state(h_task.completed),
+        ),
+        (
+            "notes",
+            note(tw_task.note_annotation(prefix)),
+            note(h_note.as_deref()),
+        ),
+    ];
+    fields
+        .into_iter()
+        .filter(|(_, tw_value, h_value)| tw_value != h_value)
+        .collect()
+}
+
+/// Combine a task with the fields decided for Habitica from its pulled
+/// version
+pub fn combine(tw_task: &Task, pulled: &Task, decisions: FieldDecisions) -> Task {
+    let mut combined = tw_task.clone();
+    if decisions.description == Side::Habitica {
+        combined.description.clone_from(&pulled.description);
+    }
+    if decisions.due == Side::Habitica {
+        combined.due = pulled.due;
+    }
+    if decisions.difficulty == Side::Habitica {
+        combined.habitica_difficulty = pulled.habitica_difficulty;
+    }
+    if decisions.status == Side::Habitica {
+        combined.status = pulled.status;
+        copy_attribute(&mut combined, pulled, "end");
+    }
+    if decisions.notes == Side::Habitica {
+        combined.annotations.clone_from(&pulled.annotations);
+    }
+    combined
+}
+
+/// Date attributes a pull may change, besides `end`
+const PULLED_DATES: &[&str] = &["scheduled", "wait", "start"];
+
+/// Combine a task pulled from Habitica with the Taskwarrior edits it lacks
+///
+/// Fields Habitica left as they were in the base keep their Taskwarrior
+/// value, so a pull only overwrites what was actually changed on Habitica.
+pub fn keep_local_edits(base: &Task, tw_task: &Task, pulled: &Task) -> Task {
+    let mut merged = pulled.clone();
+    if pulled.description == base.description {
+        merged.description.clone_from(&tw_task.description);
+    }
+    if pulled.due == base.due {
+        merged.due = tw_task.due;
+    }
+    if pulled.habitica_difficulty == base.habitica_difficulty {
+        merged.habitica_difficulty = tw_task.habitica_difficulty;
+    }
+    if pulled.habitica_attribute == base.habitica_attribute {
+        merged.habitica_attribute = tw_task.habitica_attribute;
+    }
+    if pulled.habitica_cost == base.habitica_cost {
+        merged.habitica_cost = tw_task.habitica_cost;
+    }
+    if pulled.status == base.status {
+        merged.status = tw_task.status;
+        copy_attribute(&mut merged, tw_task, "end");
+    }
+    for key in PULLED_DATES {
+        if pulled.extra.get(*key) == base.extra.get(*key) {
+            copy_attribute(&mut merged, tw_task, key);
+        }
+    }
+    merged
+}
+
+/// Set an attribute of `task` to its value in `from`, removing it if unset
+fn copy_attribute(task: &mut Task, from: &Task, key: &str) {
+    match from.extra.get(key) {
+        Some(value) => task.extra.insert(key.to_string(), value.clone()),
+        None => task.extra.remove(key),
+    };
+}
+
+/// Run the configured merge tool on a conflicting task
+///
+/// Like `git mergetool`, the command runs through `sh -c` with the JSON
+/// files in `$BASE`, `$LOCAL` (Taskwarrior) and `$REMOTE` (Habitica). It
+/// writes the result to `$MERGED`, which starts out as the Taskwarrior
+/// version. Without a known base, `$BASE` contains `null`.
+pub fn run_merge_tool(
+    command: &str,
+    base: Option<&Task>,
+    tw_task: &Task,
+    h_version: &Task,
+) -> Result<Task> {
+    let dir = env::temp_dir().join(format!("task2habitica-merge-{}", tw_task.uuid));
+    fs::create_dir_all(&dir)?;
+    let result = merge_in(&dir, command, base, tw_task, h_version);
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+fn merge_in(
+    dir: &Path,
+    command: &str,
+    base: Option<&Task>,
+    tw_task: &Task,
+    h_version: &Task,
+) -> Result<Task> {
+    let base_path = write_json(dir, "base.json", &base)?;
+    let local_path = write_json(dir, "taskwarrior.json", tw_task)?;
+    let remote_path = write_json(dir, "habitica.json", h_version)?;
+    let merged_path = write_json(dir, "merged.json", tw_task)?;
+
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("BASE", &base_path)
+        .env("LOCAL", &local_path)
+        .env("REMOTE", &remote_path)
+        .env("MERGED", &merged_path)
+        .status()
+        .map_err(|e| Error::custom(format!("Failed to run merge tool: {}", e)))?;
+    if !status.success() {
+        return Err(Error::SyncConflict(format!(
+            "Merge tool failed for \"{}\" ({})",
+            tw_task.description, status
+        )));
+    }
+
+    let mut merged: Task = serde_json::from_str(&fs::read_to_string(&merged_path)?)?;
+    // The tool decides the content, not which tasks are linked
+    merged.uuid = tw_task.uuid;
+    merged.habitica_uuid = tw_task.habitica_uuid;
+    Ok(merged)
+}
+
+fn write_json(dir: &Path, name: &str, value: &impl Serialize) -> Result<PathBuf> {
+    let path = dir.join(name);
+    fs::write(&path, serde_json::to_string_pretty(value)?)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::habitica::HabiticaTaskType;
+
+    fn linked_task(description: &str) -> Task {
+        Task {
+            uuid: Uuid::new_v4(),
+            description: description.to_string(),
+            habitica_uuid: Some(Uuid::new_v4()),
+            ..Default::default()
+        }
+    }
+
+    fn h_task_for(task: &Task, text: &str) -> HabiticaTask {
+        HabiticaTask {
+            id: task.habitica_uuid,
+            text: text.to_string(),
+            task_type: HabiticaTaskType::Todo,
+            priority: 1.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_resolve_three_way() {
+        let config = Config::for_tests();
+        let base = linked_task("Write report");
+        let mut tw_task = base.clone();
+
+        // Only Habitica changed
+        let h_task = h_task_for(&base, "Write the report");
+        assert_eq!(
+            resolve_three_way(&config, Some(&base), &tw_task, &h_task),
+            ResolutionAction::UseHabitica
+        );
+
+        // Both changed
+        tw_task.description = "Write quarterly report".to_string();
+        assert_eq!(
+            resolve_three_way(&config, Some(&base), &tw_task, &h_task),
+            ResolutionAction::Merge
+        );
+        assert_eq!(
+            resolve_three_way(&config, None, &tw_task, &h_task),
+            ResolutionAction::Merge
+        );
+    }
+
+    #[test]
+    fn test_changed_side() {
+        let config = Config::for_tests();
+        let base = linked_task("Write report");
+        let mut tw_task = base.clone();
+
+        let h_task = h_task_for(&base, "Write report");
+        tw_task.description = "Write quarterly report".to_string();
+        assert_eq!(
+            changed_side(&config, &base, &tw_task, &h_task),
+            Some(ResolutionAction::UseTaskwarrior)
+        );
+
+        let h_task = h_task_for(&base, "Write the report");
+        assert_eq!(changed_side(&config, &base, &tw_task, &h_task), None);
+        assert_eq!(
+            changed_side(&config, &base, &base, &h_task),
+            Some(ResolutionAction::UseHabitica)
+        );
+    }
+
+    #[test]
+    fn test_decide_fields() {
+        let config = Config::for_tests();
+        let base = linked_task("Write report");
+        let mut tw_task = base.clone();
+        tw_task.due = Some(chrono::Utc::now());
+        let h_task = h_task_for(&base, "Write the report");
+
+        let decisions = decide_fields(&config, &base, &tw_task, &h_task, "", || Side::Taskwarrior);
+        assert_eq!(decisions.description, Side::Habitica);
+        assert_eq!(decisions.due, Side::Taskwarrior);
+        assert_eq!(decisions.status, Side::Taskwarrior);
+        assert_eq!(decisions.unanimous(), None);
+
+        // Changed on both sides, the newest side wins
+        tw_task.description = "Write quarterly report".to_string();
+        let decisions = decide_fields(&config, &base, &tw_task, &h_task, "", || Side::Taskwarrior);
+        assert_eq!(decisions.description, Side::Taskwarrior);
+        assert_eq!(decisions.unanimous(), Some(Side::Taskwarrior));
+    }
+
+    #[test]
+    fn test_field_diff() {
+        let config = Config::for_tests();
+        let tw_task = linked_task("Write report");
+        let mut h_task = h_task_for(&tw_task, "Write the report");
+        h_task.completed = true;
+
+        let diff = field_diff(&config, &tw_task, &h_task, "");
+        assert_eq!(
+            diff,
+            [
+                (
+                    "description",
+                    "Write report".to_string(),
+                    "Write the report".to_string()
+                ),
+                ("status", "open".to_string(), "completed".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_combine() {
+        let base = linked_task("Write report");
+        let mut tw_task = base.clone();
+        tw_task.due = Some(chrono::Utc::now());
+        let mut pulled = base.clone();
+        pulled.description = "Write the report".to_string();
+
+        let decisions = FieldDecisions {
+            description: Side::Habitica,
+            due: Side::Taskwarrior,
+            difficulty: Side::Taskwarrior,
+            status: Side::Taskwarrior,
+            notes: Side::Taskwarrior,
+        };
+        let combined = combine(&tw_task, &pulled, decisions);
+        assert_eq!(combined.description, "Write the report");
+        assert_eq!(combined.due, tw_task.due);
+    }
+
+    #[test]
+    fn test_keep_local_edits() {
+        let base = linked_task("Write report");
+        let mut tw_task = base.clone();
+        tw_task.due = Some(chrono::Utc::now());
+        tw_task
+            .extra
+            .insert("scheduled".to_string(), "20260301T080000Z".into());
+        let mut pulled = base.clone();
+        pulled.description = "Write the report".to_string();
+
+        let merged = keep_local_edits(&base, &tw_task, &pulled);
+        assert_eq!(merged.description, "Write the report");
+        assert_eq!(merged.due, tw_task.due);
+        assert_eq!(
+            merged.extra.get("scheduled"),
+            tw_task.extra.get("scheduled")
+        );
+
+        // A field changed on Habitica wins, even if edited locally too
+        tw_task.description = "Write quarterly report".to_string();
+        let merged = keep_local_edits(&base, &tw_task, &pulled);
+        assert_eq!(merged.description, "Write the report");
+    }
+
+    #[test]
+    fn test_merge_base_record() {
+        let mut merge_base = MergeBase::default();
+        let mut task = linked_task("Write report");
+        let h_id = task.habitica_uuid.unwrap();
+
+        let now = Utc::now();
+        merge_base.record_at(&task, now);
+        assert_eq!(merge_base.get(h_id), Some(&task));
+        assert_eq!(merge_base.synced_at(h_id), Some(now));
+
+        task.habitica_uuid = None;
+        merge_base.record(&task);
+        assert!(merge_base.get(h_id).is_none());
+        assert!(merge_base.synced_at(h_id).is_none());
+    }
+
+    #[test]
+    fn test_merge_base_without_sync_times() {
+        let task = linked_task("Write report");
+        let h_id = task.habitica_uuid.unwrap();
+        let json = serde_json::json!({ "tasks": { h_id.to_string(): task } });
+
+        let merge_base: MergeBase = serde_json::from_value(json).unwrap();
+        assert_eq!(merge_base.get(h_id), Some(&task));
+        assert!(merge_base.synced_at(h_id).is_none());
+    }
+
+    #[test]
+    fn test_run_merge_tool() {
+        let tw_task = linked_task("Write report");
+        let mut h_version = tw_task.clone();
+        h_version.description = "Write the report".to_string();
+
+        // Take Habitica's side, as `git checkout --theirs` would
+        let merged =
+            run_merge_tool("cp \"$REMOTE\" \"$MERGED\"", None, &tw_task, &h_version).unwrap();
+        assert_eq!(merged.description, "Write the report");
+        assert_eq!(merged.uuid, tw_task.uuid);
+
+        assert!(run_merge_tool("exit 1", None, &tw_task, &h_version).is_err());
+    }
+}
@@ -1,8 +1,32 @@
+pub mod archive;
+#[cfg(feature = "async")]
+pub mod async_engine;
+pub mod conflicts;
 pub mod converter;
+pub mod duplicates;
+pub mod engine;
+pub mod habits;
+pub mod journal;
+pub mod merge;
+pub mod order;
+pub mod party;
+pub mod progress;
+pub mod prompt;
+pub mod recurrence;
 pub mod resolver;
+pub mod sanitize;
+pub mod spool;
+pub mod timewarrior;
+pub mod tombstones;
+pub mod watermark;
+pub mod writes;
 
+#[cfg(feature = "async")]
+pub use async_engine::AsyncSyncEngine;
 pub use converter::{
     habitica_to_taskwarrior, tasks_are_equivalent, taskwarrior_to_habitica,
     update_taskwarrior_from_habitica,
 };
+pub use engine::{SyncEngine, SyncEvent, SyncOperation, SyncPlan, SyncSummary};
+pub use prompt::{Prompter, Unattended};
 pub use resolver::{ConflictResolver, ResolutionAction};
@@ -0,0 +1,100 @@
+use std::collections::HashSet;
+
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::Result,
+    habitica::HabiticaApi,
+    taskwarrior::{Task, TaskBackend, TaskType},
+};
+
+/// Moves that put the ranked tasks at the top of the list, in rank order
+///
+/// Each move is a task and the position it goes to, applied one after the
+/// other. Tasks already in place are not moved, and tasks missing from the
+/// list are skipped.
+pub fn reorder_moves(current: &[Uuid], ranked: &[Uuid]) -> Vec<(Uuid, usize)> {
+    let listed: HashSet<&Uuid> = current.iter().collect();
+    let mut order = current.to_vec();
+    let mut moves = Vec::new();
+
+    for (position, id) in ranked.iter().filter(|id| listed.contains(id)).enumerate() {
+        if order[position] == *id {
+            continue;
+        }
+        if let Some(from) = order.iter().position(|other| other == id) {
+            order.remove(from);
+            order.insert(position, *id);
+            moves.push((*id, position));
+        }
+    }
+    moves
+}
+
+/// Habitica IDs of pending Taskwarrior todos, most urgent first
+fn ranked_todos(mut tasks: Vec<Task>) -> Vec<Uuid> {
+    tasks.retain(|task| task.status.is_pending() && task.task_type() == TaskType::Todo);
+    tasks.sort_by(|a, b| urgency(b).total_cmp(&urgency(a)));
+    tasks.iter().filter_map(|task| task.habitica_uuid).collect()
+}
+
+fn urgency(task: &Task) -> f64 {
+    task.extra
+        .get("urgency")
+        .and_then(serde_json::Value::as_f64)
+        .unwrap_or(0.0)
+}
+
+/// Order Habitica todos like Taskwarrior ranks them by urgency
+///
+/// Linked todos move to the top of the list, followed by the ones that only
+/// exist on Habitica. Returns the number of tasks moved.
+pub fn mirror_urgency(
+    config: &Config,
+    backend: &dyn TaskBackend,
+    h_client: &dyn HabiticaApi,
+) -> Result<usize> {
+    if !config.sort_todos {
+        return Ok(0);
+    }
+
+    let ranked = ranked_todos(backend.get_tasks_with_habitica()?);
+    let current: Vec<Uuid> = h_client
+        .get_tasks(Some("todos"))?
+        .iter()
+        .filter_map(|h_task| h_task.id)
+        .collect();
+
+    let moves = reorder_moves(&current, &ranked);
+    for (id, position) in &moves {
+        h_client.move_task(*id, *position)?;
+    }
+    Ok(moves.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reorder_moves() {
+        let ids: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+        let (a, b, c, d) = (ids[0], ids[1], ids[2], ids[3]);
+
+        // Already in order
+        assert!(reorder_moves(&[a, b, c, d], &[a, b]).is_empty());
+
+        // `d` only exists on Habitica and ends up below the ranked tasks
+        assert_eq!(
+            reorder_moves(&[d, c, a, b], &[a, b, c]),
+            vec![(a, 0), (b, 1), (c, 2)]
+        );
+
+        // Tasks not in the list are skipped
+        assert_eq!(
+            reorder_moves(&[b, a], &[Uuid::new_v4(), a, b]),
+            vec![(a, 0)]
+        );
+    }
+}
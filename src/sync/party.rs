@@ -0,0 +1,85 @@
+use std::{fs, path::Path};
+
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Config, error::Result, habitica::HabiticaApi, taskwarrior::TaskBackend};
+
+/// Day on which the milestone was last announced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct MilestoneRecord {
+    date: NaiveDate,
+}
+
+impl MilestoneRecord {
+    fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+    }
+
+    fn save(self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string(&self)?)?;
+        Ok(())
+    }
+}
+
+/// Check whether today's count reaches a milestone not yet announced today
+pub fn milestone_reached(
+    completed_today: usize,
+    milestone: u32,
+    last_posted: Option<NaiveDate>,
+    today: NaiveDate,
+) -> bool {
+    milestone > 0
+        && completed_today >= milestone as usize
+        && last_posted.map_or(true, |date| date < today)
+}
+
+/// Post to the party chat once the daily milestone is reached
+///
+/// Returns the posted message, if any. At most one message is posted per day.
+pub fn post_party_milestone(
+    config: &Config,
+    backend: &dyn TaskBackend,
+    h_client: &dyn HabiticaApi,
+) -> Result<Option<String>> {
+    let Some(milestone) = config.party_milestone else {
+        return Ok(None);
+    };
+
+    let record_path = config.party_milestone_path();
+    let today = Local::now().date_naive();
+    let last_posted = MilestoneRecord::load(&record_path)?.map(|record| record.date);
+
+    let completed_today = backend
+        .export(&["status:completed", "end.after:today"])?
+        .len();
+    if !milestone_reached(completed_today, milestone, last_posted, today) {
+        return Ok(None);
+    }
+
+    let message = format!("Completed {} tasks today!", completed_today);
+    h_client.post_party_chat(&message)?;
+    MilestoneRecord { date: today }.save(&record_path)?;
+
+    Ok(Some(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_milestone_reached_once_per_day() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 2).expect("valid date");
+        let yesterday = today.pred_opt().expect("valid date");
+
+        assert!(milestone_reached(5, 5, None, today));
+        assert!(milestone_reached(6, 5, Some(yesterday), today));
+        assert!(!milestone_reached(4, 5, None, today));
+        assert!(!milestone_reached(7, 5, Some(today), today));
+        assert!(!milestone_reached(7, 0, None, today));
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use crate::{error::Result, taskwarrior::Task};
+
+/// Append a task staged for import to the progress file
+///
+/// Staged tasks are only imported once the whole plan is applied. Written
+/// down as they come, they survive a sync that dies halfway, so the next
+/// run can import them before planning instead of redoing the Habitica
+/// writes they stand for.
+pub fn record(path: &Path, task: &Task) -> Result<()> {
+    let mut line = serde_json::to_string(task)?;
+    line.push('\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Read the tasks staged by a sync that didn't get to import them
+///
+/// A line cut short by the interruption is skipped.
+pub fn load(path: &Path) -> Result<Vec<Task>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Forget the progress once the staged tasks are imported
+pub fn clear(path: &Path) -> Result<()> {
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::fs::OpenOptions;
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn test_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("habitica_progress.jsonl");
+        assert!(load(&path).unwrap().is_empty());
+
+        let task = Task {
+            uuid: Uuid::new_v4(),
+            description: "Water plants".to_string(),
+            habitica_uuid: Some(Uuid::new_v4()),
+            ..Default::default()
+        };
+        record(&path, &task).unwrap();
+        // Killed in the middle of the next line
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(br#"{"uuid":"#)
+            .unwrap();
+
+        let staged = load(&path).unwrap();
+        assert_eq!(staged.len(), 1);
+        assert_eq!(staged[0].habitica_uuid, task.habitica_uuid);
+
+        clear(&path).unwrap();
+        assert!(!path.exists());
+        clear(&path).unwrap();
+    }
+}
@@ -0,0 +1,72 @@
+/// Where a sync sends what it has to tell or ask the user
+///
+/// Conflicts, notices about tasks and confirmations all go through it, so a
+/// library user can show them in their own interface.
+pub trait Prompter: Sync {
+    /// Tell the user something
+    fn say(&self, message: &str);
+
+    /// Warn the user about a task being synced
+    fn warn(&self, message: &str);
+
+    /// Ask a yes/no question, no when nobody answers
+    fn confirm(&self, question: &str) -> bool;
+
+    /// Ask for a line of text, `None` when nobody answers
+    fn ask(&self, question: &str) -> Option<String>;
+}
+
+/// Nobody to talk to: messages go to `tracing` and every question gets no
+/// answer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Unattended;
+
+impl Prompter for Unattended {
+    fn say(&self, message: &str) {
+        tracing::info!("{}", message);
+    }
+
+    fn warn(&self, message: &str) {
+        tracing::warn!("{}", message);
+    }
+
+    fn confirm(&self, _question: &str) -> bool {
+        false
+    }
+
+    fn ask(&self, _question: &str) -> Option<String> {
+        None
+    }
+}
+
+/// The terminal, through [`output`](crate::output)
+#[cfg(feature = "cli")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Terminal;
+
+#[cfg(feature = "cli")]
+impl Prompter for Terminal {
+    fn say(&self, message: &str) {
+        crate::say!("{}", message);
+    }
+
+    fn warn(&self, message: &str) {
+        crate::say_err!("{}", message);
+    }
+
+    fn confirm(&self, question: &str) -> bool {
+        crate::output::confirm(question)
+    }
+
+    fn ask(&self, question: &str) -> Option<String> {
+        crate::output::ask(question)
+    }
+}
+
+/// Prompter of a sync not given another one
+#[cfg(feature = "cli")]
+pub(crate) const DEFAULT: &dyn Prompter = &Terminal;
+
+/// Prompter of a sync not given another one
+#[cfg(not(feature = "cli"))]
+pub(crate) const DEFAULT: &dyn Prompter = &Unattended;
@@ -0,0 +1,477 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Local, Utc, Weekday};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    error::{Error, Result},
+    habitica::{Frequency, HabiticaTask, WeekdayRepeat},
+    taskwarrior::Task,
+};
+
+/// Habitica repeat schedule of a daily
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    pub frequency: Frequency,
+    pub every_x: u32,
+    pub repeat: Option<WeekdayRepeat>,
+    pub days_of_month: Vec<u32>,
+    pub weeks_of_month: Vec<u32>,
+}
+
+impl Schedule {
+    const fn every(frequency: Frequency, every_x: u32) -> Self {
+        Schedule {
+            frequency,
+            every_x,
+            repeat: None,
+            days_of_month: Vec::new(),
+            weeks_of_month: Vec::new(),
+        }
+    }
+
+    /// Check whether a Habitica daily already follows the schedule
+    pub fn matches(&self, h_task: &HabiticaTask) -> bool {
+        h_task.frequency == Some(self.frequency)
+            && h_task.every_x.unwrap_or(1) == self.every_x
+            && self
+                .repeat
+                .map_or(true, |repeat| h_task.repeat == Some(repeat))
+            && h_task.days_of_month == self.days_of_month
+            && h_task.weeks_of_month == self.weeks_of_month
+    }
+
+    /// Write the schedule into a Habitica daily
+    pub fn apply(self, h_task: &mut HabiticaTask) {
+        h_task.frequency = Some(self.frequency);
+        h_task.every_x = Some(self.every_x);
+        h_task.repeat = self.repeat;
+        h_task.days_of_month = self.days_of_month;
+        h_task.weeks_of_month = self.weeks_of_month;
+    }
+}
+
+/// Habitica schedule for a Taskwarrior daily's `recur` value
+///
+/// Day and week intervals map directly. Weekly dailies repeat on the due
+/// date's weekday. Monthly dailies repeat on the due date's day of the month,
+/// or with `habitica_monthly:weekday` on its weekday of the month (e.g. the
+/// 2nd Tuesday). `None` if the task doesn't recur; an error if Habitica
+/// can't represent the pattern, in which case the daily keeps its schedule.
+pub fn schedule(tw_task: &Task) -> Result<Option<Schedule>> {
+    let Some(recur) = tw_task.extra.get("recur").and_then(Value::as_str) else {
+        return Ok(None);
+    };
+    let unsupported = |reason: &str| {
+        Error::custom(format!(
+            "Recurrence '{}' of \"{}\" can't be represented on Habitica: {}",
+            recur, tw_task.description, reason
+        ))
+    };
+
+    let (count, unit) = parse_recur(recur).ok_or_else(|| unsupported("unknown period"))?;
+    if count == 0 {
+        return Err(unsupported("zero interval"));
+    }
+    let due = tw_task
+        .due
+        .map(|due| due.with_timezone(&Local).date_naive());
+
+    let schedule = match unit {
+        Unit::Day => Schedule::every(Frequency::Daily, count),
+        Unit::Weekdays => Schedule {
+            repeat: Some(WeekdayRepeat {
+                s: false,
+                su: false,
+                ..Default::default()
+            }),
+            ..Schedule::every(Frequency::Weekly, 1)
+        },
+        Unit::Week => {
+            let due = due.ok_or_else(|| unsupported("weekly dailies need a due date"))?;
+            Schedule {
+                repeat: Some(only_on(due.weekday())),
+                ..Schedule::every(Frequency::Weekly, count)
+            }
+        }
+        Unit::Month => {
+            let due = due.ok_or_else(|| unsupported("monthly dailies need a due date"))?;
+            let by_weekday = tw_task
+                .extra
+                .get("habitica_monthly")
+                .and_then(Value::as_str)
+                == Some("weekday");
+            if by_weekday {
+                Schedule {
+                    repeat: Some(only_on(due.weekday())),
+                    weeks_of_month: vec![(due.day() - 1) / 7],
+                    ..Schedule::every(Frequency::Monthly, count)
+                }
+            } else {
+                Schedule {
+                    days_of_month: vec![due.day()],
+                    ..Schedule::every(Frequency::Monthly, count)
+                }
+            }
+        }
+        Unit::Year => Schedule::every(Frequency::Yearly, count),
+    };
+    Ok(Some(schedule))
+}
+
+/// Taskwarrior `recur` value for a Habitica daily's schedule
+///
+/// The flag tells whether a monthly daily repeats on a weekday of the month,
+/// which Taskwarrior keeps in `habitica_monthly:weekday`. `None` if the task
+/// has no schedule or Taskwarrior can't express it, like weekly dailies
+/// active on some but not all weekdays.
+pub fn recur(h_task: &HabiticaTask) -> Option<(String, bool)> {
+    let every = h_task.every_x.unwrap_or(1);
+    if every == 0 {
+        return None;
+    }
+    let period = |single: &str, unit: &str| {
+        if every == 1 {
+            single.to_string()
+        } else {
+            format!("{}{}", every, unit)
+        }
+    };
+    let days = h_task.repeat.unwrap_or_default().days();
+    let active_days = days.iter().filter(|active| **active).count();
+
+    match h_task.frequency? {
+        Frequency::Daily => Some((period("daily", "d"), false)),
+        Frequency::Weekly => match active_days {
+            1 => Some((period("weekly", "weeks"), false)),
+            5 if every == 1 && days == [true, true, true, true, true, false, false] => {
+                Some(("weekdays".to_string(), false))
+            }
+            7 if every == 1 => Some(("daily".to_string(), false)),
+            _ => None,
+        },
+        Frequency::Monthly => match (h_task.days_of_month.len(), h_task.weeks_of_month.len()) {
+            (1, 0) => Some((period("monthly", "months"), false)),
+            (0, 1) if active_days == 1 => Some((period("monthly", "months"), true)),
+            _ => None,
+        },
+        Frequency::Yearly => Some((period("yearly", "years"), false)),
+    }
+}
+
+/// Give a pulled daily the Taskwarrior recurrence of its Habitica schedule
+///
+/// A `recur` that already maps to the schedule is kept, so e.g. `1w` doesn't
+/// turn into `weekly`. Taskwarrior only repeats tasks with a due date, so
+/// undated dailies are left alone, as are schedules it can't express.
+pub fn set_recur(tw_task: &mut Task, h_task: &HabiticaTask) {
+    if tw_task.due.is_none() {
+        return;
+    }
+    if let Ok(Some(schedule)) = schedule(tw_task) {
+        if schedule.matches(h_task) {
+            return;
+        }
+    }
+    let Some((recur, by_weekday)) = recur(h_task) else {
+        return;
+    };
+
+    tw_task
+        .extra
+        .insert("recur".to_string(), Value::String(recur));
+    if by_weekday {
+        tw_task.extra.insert(
+            "habitica_monthly".to_string(),
+            Value::String("weekday".to_string()),
+        );
+    } else {
+        tw_task.extra.remove("habitica_monthly");
+    }
+}
+
+/// Period of a Taskwarrior recurrence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Day,
+    Weekdays,
+    Week,
+    Month,
+    Year,
+}
+
+/// Current instance of each recurring series, keyed by parent UUID
+///
+/// That's the latest pending instance already due, or the next one when none
+/// is due yet. Taskwarrior generates instances ahead of time, and only the
+/// current one belongs on Habitica.
+pub fn current_instances<'a>(
+    tasks: impl IntoIterator<Item = &'a Task>,
+    now: DateTime<Utc>,
+) -> HashMap<Uuid, Uuid> {
+    let mut series: HashMap<Uuid, Vec<&Task>> = HashMap::new();
+    for task in tasks {
+        if let (Some(parent), true) = (task.parent(), task.status.is_pending()) {
+            series.entry(parent).or_default().push(task);
+        }
+    }
+
+    series
+        .into_iter()
+        .filter_map(|(parent, instances)| {
+            let current = instances
+                .iter()
+                .filter(|task| task.due.map_or(true, |due| due <= now))
+                .max_by_key(|task| task.imask())
+                .or_else(|| instances.iter().min_by_key(|task| task.imask()))?;
+            Some((parent, current.uuid))
+        })
+        .collect()
+}
+
+/// Split a `recur` value like `2weeks` or `monthly` into count and unit
+fn parse_recur(recur: &str) -> Option<(u32, Unit)> {
+    let recur = recur.trim().to_lowercase();
+    let named = match recur.as_str() {
+        "daily" | "day" => Some((1, Unit::Day)),
+        "weekdays" => Some((1, Unit::Weekdays)),
+        "weekly" | "week" => Some((1, Unit::Week)),
+        "biweekly" | "fortnight" => Some((2, Unit::Week)),
+        "monthly" | "month" => Some((1, Unit::Month)),
+        "bimonthly" => Some((2, Unit::Month)),
+        "quarterly" => Some((3, Unit::Month)),
+        "semiannual" => Some((6, Unit::Month)),
+        "annual" | "yearly" | "year" => Some((1, Unit::Year)),
+        "biannual" | "biyearly" => Some((2, Unit::Year)),
+        _ => None,
+    };
+    if named.is_some() {
+        return named;
+    }
+
+    let digits = recur.find(|c: char| !c.is_ascii_digit())?;
+    let (count, unit) = recur.split_at(digits);
+    let count: u32 = if count.is_empty() {
+        1
+    } else {
+        count.parse().ok()?
+    };
+    let unit = match unit {
+        "d" | "day" | "days" => Unit::Day,
+        "w" | "wk" | "wks" | "week" | "weeks" => Unit::Week,
+        "mo" | "mos" | "mth" | "mths" | "month" | "months" => Unit::Month,
+        "q" | "qtr" | "qtrs" | "quarter" | "quarters" => {
+            return Some((count.checked_mul(3)?, Unit::Month))
+        }
+        "y" | "yr" | "yrs" | "year" | "years" => Unit::Year,
+        _ => return None,
+    };
+    Some((count, unit))
+}
+
+/// Weekly repeat active on a single day
+const fn only_on(weekday: Weekday) -> WeekdayRepeat {
+    let mut repeat = WeekdayRepeat {
+        m: false,
+        t: false,
+        w: false,
+        th: false,
+        f: false,
+        s: false,
+        su: false,
+    };
+    match weekday {
+        Weekday::Mon => repeat.m = true,
+        Weekday::Tue => repeat.t = true,
+        Weekday::Wed => repeat.w = true,
+        Weekday::Thu => repeat.th = true,
+        Weekday::Fri => repeat.f = true,
+        Weekday::Sat => repeat.s = true,
+        Weekday::Sun => repeat.su = true,
+    }
+    repeat
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn recurring(recur: &str) -> Task {
+        let mut task = Task {
+            description: "Pay rent".to_string(),
+            // Tuesday, the 2nd Tuesday of the month at noon local time
+            due: Some(
+                Local
+                    .with_ymd_and_hms(2026, 1, 13, 12, 0, 0)
+                    .unwrap()
+                    .with_timezone(&Utc),
+            ),
+            ..Default::default()
+        };
+        task.extra
+            .insert("recur".to_string(), Value::String(recur.to_string()));
+        task
+    }
+
+    #[test]
+    fn test_parse_recur() {
+        assert_eq!(parse_recur("daily"), Some((1, Unit::Day)));
+        assert_eq!(parse_recur("3d"), Some((3, Unit::Day)));
+        assert_eq!(parse_recur("2weeks"), Some((2, Unit::Week)));
+        assert_eq!(parse_recur("quarterly"), Some((3, Unit::Month)));
+        assert_eq!(parse_recur("2q"), Some((6, Unit::Month)));
+        assert_eq!(parse_recur("4000000000q"), None);
+        assert_eq!(parse_recur("mo"), Some((1, Unit::Month)));
+        assert_eq!(parse_recur("12h"), None);
+        assert_eq!(parse_recur("P1M"), None);
+    }
+
+    #[test]
+    fn test_monthly_on_day() {
+        let schedule = schedule(&recurring("monthly")).unwrap().unwrap();
+        assert_eq!(schedule.frequency, Frequency::Monthly);
+        assert_eq!(schedule.days_of_month, vec![13]);
+        assert!(schedule.weeks_of_month.is_empty());
+    }
+
+    #[test]
+    fn test_monthly_on_weekday() {
+        let mut task = recurring("monthly");
+        task.extra.insert(
+            "habitica_monthly".to_string(),
+            Value::String("weekday".to_string()),
+        );
+
+        let schedule = schedule(&task).unwrap().unwrap();
+        assert_eq!(schedule.weeks_of_month, vec![1]);
+        assert_eq!(
+            schedule.repeat.unwrap().days(),
+            [false, true, false, false, false, false, false]
+        );
+    }
+
+    #[test]
+    fn test_recur_from_habitica() {
+        let daily = |frequency, every_x| HabiticaTask {
+            frequency: Some(frequency),
+            every_x: Some(every_x),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            recur(&daily(Frequency::Daily, 3)),
+            Some(("3d".to_string(), false))
+        );
+        assert_eq!(
+            recur(&daily(Frequency::Weekly, 1)),
+            Some(("daily".to_string(), false))
+        );
+        assert_eq!(
+            recur(&daily(Frequency::Yearly, 1)),
+            Some(("yearly".to_string(), false))
+        );
+
+        let mut weekly = daily(Frequency::Weekly, 2);
+        weekly.repeat = Some(only_on(Weekday::Fri));
+        assert_eq!(recur(&weekly), Some(("2weeks".to_string(), false)));
+        weekly.repeat = Some(WeekdayRepeat {
+            m: true,
+            ..only_on(Weekday::Fri)
+        });
+        assert_eq!(recur(&weekly), None);
+
+        let mut monthly = daily(Frequency::Monthly, 1);
+        monthly.repeat = Some(only_on(Weekday::Tue));
+        monthly.weeks_of_month = vec![1];
+        assert_eq!(recur(&monthly), Some(("monthly".to_string(), true)));
+    }
+
+    #[test]
+    fn test_schedule_roundtrip() {
+        for (recur, monthly) in [
+            ("3d", None),
+            ("weekdays", None),
+            ("2weeks", None),
+            ("quarterly", None),
+            ("monthly", Some("weekday")),
+            ("yearly", None),
+        ] {
+            let mut task = recurring(recur);
+            if let Some(monthly) = monthly {
+                task.extra.insert(
+                    "habitica_monthly".to_string(),
+                    Value::String(monthly.to_string()),
+                );
+            }
+            let mut h_task = HabiticaTask::default();
+            schedule(&task).unwrap().unwrap().apply(&mut h_task);
+
+            // Pulling onto a task without a recurrence restores an equivalent one
+            let mut pulled = recurring(recur);
+            pulled.extra.remove("recur");
+            set_recur(&mut pulled, &h_task);
+            assert!(
+                schedule(&pulled).unwrap().unwrap().matches(&h_task),
+                "{}",
+                recur
+            );
+
+            // An equivalent recurrence is kept as written
+            let mut unchanged = task.clone();
+            set_recur(&mut unchanged, &h_task);
+            assert_eq!(unchanged.extra.get("recur"), task.extra.get("recur"));
+        }
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert!(schedule(&recurring("12h")).is_err());
+        assert!(schedule(&recurring("0d")).is_err());
+
+        let mut undated = recurring("weekly");
+        undated.due = None;
+        assert!(schedule(&undated).is_err());
+
+        assert_eq!(schedule(&Task::default()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_current_instances() {
+        let now = Utc::now();
+        let parent = Uuid::new_v4();
+        let instance = |imask: u64, days: i64| {
+            let mut task = Task {
+                uuid: Uuid::new_v4(),
+                due: Some(now + chrono::Duration::days(days)),
+                ..Default::default()
+            };
+            task.extra
+                .insert("parent".to_string(), Value::String(parent.to_string()));
+            task.extra.insert("imask".to_string(), Value::from(imask));
+            task
+        };
+
+        // The latest instance already due wins over older and upcoming ones
+        let overdue = instance(1, -7);
+        let due = instance(2, 0);
+        let upcoming = instance(3, 7);
+        let current = current_instances([&overdue, &due, &upcoming], now);
+        assert_eq!(current.get(&parent), Some(&due.uuid));
+
+        // Without a due instance, the next one is current
+        let later = instance(4, 14);
+        let current = current_instances([&later, &upcoming], now);
+        assert_eq!(current.get(&parent), Some(&upcoming.uuid));
+
+        // Finished instances and standalone tasks don't count
+        let mut done = instance(5, -1);
+        done.status = crate::taskwarrior::TaskStatus::Completed;
+        let current = current_instances([&done, &upcoming, &Task::default()], now);
+        assert_eq!(current.len(), 1);
+        assert_eq!(current.get(&parent), Some(&upcoming.uuid));
+    }
+}
@@ -1,12 +1,31 @@
+use std::cell::{Cell, RefCell};
+
+use chrono::{DateTime, Duration, Utc};
+use tracing::instrument;
+use uuid::Uuid;
+
 use crate::{
-    config::Config,
-    error::Result,
-    habitica::{HabiticaClient, HabiticaTask, ScoreDirection, StatsCache},
-    sync::converter,
-    taskwarrior::{NotesManager, Task, TaskwarriorClient},
+    config::{BugwarriorMode, Config, ConflictStrategy},
+    error::{Error, Result},
+    habitica::{
+        ChecklistItem, DayStart, HabiticaApi, HabiticaTask, HabiticaTaskType, ScoreDirection,
+        StatsCache,
+    },
+    sync::{
+        converter,
+        merge::{self, FieldDecisions, MergeBase, Side},
+        prompt::{self, Prompter},
+        writes::{self, check_streak_loss, Reply, Request, Writes},
+    },
+    taskwarrior::{NotesManager, Task, TaskBackend, TaskStatus, TaskType},
+    tr,
 };
 
+/// Days of a daily streak that earn Habitica's streak achievement
+const STREAK_MILESTONE: u32 = 21;
+
 /// Result of resolving a conflict between Taskwarrior and Habitica
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ResolutionAction {
     /// Keep Taskwarrior version and push to Habitica
     UseTaskwarrior,
@@ -14,138 +33,683 @@ pub enum ResolutionAction {
     UseHabitica,
     /// Tasks are equivalent, no action needed
     NoChange,
+    /// Both sides changed, the external merge tool decides
+    Merge,
+    /// Both sides changed, each field keeps the side that changed it
+    Combine(FieldDecisions),
+    /// Both sides changed and the user chose to leave the task for now
+    Skip,
+    /// Both sides changed and the task waits for `resolve`
+    Defer,
+}
+
+/// Side whose changes win a conflict, per the conflict strategy
+///
+/// `ask` goes by modification time like `newest` here, for when nobody can
+/// be asked.
+pub fn preferred_side(config: &Config, tw_task: &Task, h_task: &HabiticaTask) -> Side {
+    match config.conflict_strategy {
+        ConflictStrategy::TaskwarriorWins => Side::Taskwarrior,
+        ConflictStrategy::HabiticaWins => Side::Habitica,
+        _ if h_task.modified_or_now() > tw_task.modified_or_now() => Side::Habitica,
+        _ => Side::Taskwarrior,
+    }
+}
+
+/// Keep challenge tasks pull-only
+///
+/// Challenge tasks can only be edited on Habitica, so Taskwarrior only wins
+/// when it completes or reopens the task.
+pub fn pull_only_challenge(
+    tw_task: &Task,
+    h_task: &HabiticaTask,
+    action: ResolutionAction,
+) -> ResolutionAction {
+    let status_changed = tw_task.status.is_completed() != h_task.completed;
+    match action {
+        ResolutionAction::UseTaskwarrior
+        | ResolutionAction::Merge
+        | ResolutionAction::Combine(_)
+            if h_task.challenge_id().is_some() && !status_changed =>
+        {
+            ResolutionAction::UseHabitica
+        }
+        other => other,
+    }
+}
+
+/// Check whether a task is configured to stay out of Habitica
+///
+/// That's tasks with the exclusion tag, and with `habitica.hold_waiting`,
+/// unlinked tasks that are still waiting; they are pushed by the first sync
+/// after their wait date.
+pub fn is_excluded(config: &Config, task: &Task) -> bool {
+    let held = config.hold_waiting && task.habitica_uuid.is_none() && task.is_waiting(Utc::now());
+    held || config.keeps_local(task)
+        || (config.bugwarrior == BugwarriorMode::Skip && task.is_bugwarrior_task())
+}
+
+/// Check whether a Habitica task stays out of Taskwarrior
+///
+/// That's tasks made by Habitica itself or a challenge, unless
+/// `habitica.import.allow` lets them in, and with `habitica.sync_group_tasks`
+/// off, tasks of groups not shared through a project.
+pub fn skips_import(config: &Config, h_task: &HabiticaTask) -> bool {
+    let allowed = |entry: &str| config.import_allow.iter().any(|allow| allow == entry);
+
+    let unshared_group = h_task
+        .group_id()
+        .is_some_and(|id| config.project_for_group(id).is_none());
+    if unshared_group && !config.sync_group_tasks {
+        return true;
+    }
+
+    if h_task.by_habitica && !allowed("habitica") {
+        return true;
+    }
+    h_task
+        .challenge_id()
+        .is_some_and(|id| !allowed("challenge") && !allowed(&id.to_string()))
+}
+
+/// Check whether a task was completed too long before linking to earn XP
+///
+/// Habitica can't create a task as completed without scoring it, so such
+/// tasks are linked but stay open there.
+pub fn completed_before_linking(config: &Config, task: &Task, now: DateTime<Utc>) -> bool {
+    task.status.is_completed()
+        && config.link_score_max_age.is_some_and(|max_age| {
+            let cutoff = now.checked_sub_signed(max_age);
+            task.date_attribute("end")
+                .zip(cutoff)
+                .is_some_and(|(end, cutoff)| end < cutoff)
+        })
+}
+
+/// Check whether a task changed beyond its completion status
+pub(crate) fn details_changed(
+    config: &Config,
+    old_tw: &Task,
+    new_h: &HabiticaTask,
+    note_content: Option<&str>,
+) -> Result<bool> {
+    let Some(mut old_h) = converter::taskwarrior_to_habitica(config, old_tw, note_content)? else {
+        return Ok(true);
+    };
+    old_h.completed = new_h.completed;
+    old_h.date_completed = new_h.date_completed;
+    old_h.updated_at = new_h.updated_at;
+    old_h.checklist = new_h.checklist.clone();
+    Ok(old_h != *new_h)
+}
+
+/// Feedback shown after buying a reward
+pub(crate) fn purchase_message(reward: &str, cost: f64, gold_left: f64) -> String {
+    format!(
+        "Bought {} for {} gold, {:.2} gold left",
+        reward, cost, gold_left
+    )
+}
+
+/// Announce a daily's streak when it reaches a multiple of 21 days, the
+/// length of Habitica's streak achievement
+pub(crate) fn streak_message(daily: &str, streak: u32) -> Option<String> {
+    (streak > 0 && streak % STREAK_MILESTONE == 0)
+        .then(|| tr!("stats-streak", days = streak, task = daily))
+}
+
+/// Remove an issue link previously appended to Habitica notes
+fn strip_issue_link<'n>(notes: &'n str, url: Option<&str>) -> &'n str {
+    url.and_then(|url| notes.strip_suffix(url))
+        .map_or(notes, str::trim_end)
+}
+
+/// Determine which version of a linked task should win, per the conflict
+/// strategy and the version recorded by the last sync
+///
+/// `ask`, when given, settles tasks changed on both sides since `base`.
+/// `winner` picks the side of fields changed on both, or of everything
+/// without a base.
+pub(crate) fn resolve_with_base(
+    config: &Config,
+    base: Option<&Task>,
+    tw_task: &Task,
+    h_task: &HabiticaTask,
+    ask: Option<&dyn Fn() -> ResolutionAction>,
+    winner: &dyn Fn() -> Side,
+) -> ResolutionAction {
+    // A linked task tagged to stay local comes off Habitica
+    if config.keeps_local(tw_task) {
+        return ResolutionAction::UseTaskwarrior;
+    }
+    // So does a deleted one, whatever changed on Habitica meanwhile
+    if tw_task.status == TaskStatus::Deleted {
+        return pull_only_challenge(tw_task, h_task, ResolutionAction::UseTaskwarrior);
+    }
+
+    // A task linked without scoring stays open on Habitica, which isn't
+    // a change to sync
+    let completed_h;
+    let h_task = if tw_task.status.is_completed()
+        && !h_task.completed
+        && completed_before_linking(config, tw_task, Utc::now())
+    {
+        completed_h = HabiticaTask {
+            completed: true,
+            ..h_task.clone()
+        };
+        &completed_h
+    } else {
+        h_task
+    };
+
+    if converter::tasks_are_equivalent(config, tw_task, h_task) {
+        return ResolutionAction::NoChange;
+    }
+    let diverged =
+        !base.is_some_and(|base| merge::changed_side(config, base, tw_task, h_task).is_some());
+    // The strategy only decides fields changed on both sides since the
+    // last sync, or everything when it isn't known
+    let action = match (ask, config.conflict_strategy, base) {
+        (Some(ask), _, _) if diverged => ask(),
+        (_, ConflictStrategy::Defer, _) if diverged => ResolutionAction::Defer,
+        (_, ConflictStrategy::External, base) => {
+            merge::resolve_three_way(config, base, tw_task, h_task)
+        }
+        (_, _, Some(base)) => merge::changed_side(config, base, tw_task, h_task)
+            .unwrap_or_else(|| decide_fields(config, base, tw_task, h_task, winner)),
+        (_, _, None) => match winner() {
+            Side::Taskwarrior => ResolutionAction::UseTaskwarrior,
+            Side::Habitica => ResolutionAction::UseHabitica,
+        },
+    };
+    pull_only_challenge(tw_task, h_task, action)
+}
+
+/// Decide the fields of a task changed on both sides since `base`
+///
+/// When one side wins every field, that side is used as a whole.
+fn decide_fields(
+    config: &Config,
+    base: &Task,
+    tw_task: &Task,
+    h_task: &HabiticaTask,
+    winner: &dyn Fn() -> Side,
+) -> ResolutionAction {
+    let h_notes = strip_issue_link(&h_task.notes, tw_task.bugwarrior_url());
+    let decisions = merge::decide_fields(config, base, tw_task, h_task, h_notes, winner);
+    match decisions.unanimous() {
+        Some(Side::Taskwarrior) => ResolutionAction::UseTaskwarrior,
+        Some(Side::Habitica) => ResolutionAction::UseHabitica,
+        None => ResolutionAction::Combine(decisions),
+    }
+}
+
+/// Update a pair otherwise in sync whose Habitica checklist is behind the
+/// task's dependencies
+///
+/// Completing a dependency doesn't touch the task itself, but its checklist
+/// item still has to be ticked. A failed lookup counts as current, leaving
+/// the checklist for the next sync.
+pub(crate) fn check_dependencies(
+    backend: &dyn TaskBackend,
+    prompter: &dyn Prompter,
+    tw_task: &Task,
+    h_task: &HabiticaTask,
+    action: ResolutionAction,
+) -> ResolutionAction {
+    if action != ResolutionAction::NoChange || h_task.challenge_id().is_some() {
+        return action;
+    }
+    match dependency_checklist(backend, tw_task) {
+        Ok(checklist) if !converter::checklist_matches(checklist.as_deref(), h_task) => {
+            ResolutionAction::UseTaskwarrior
+        }
+        Ok(_) => action,
+        Err(err) => {
+            prompter.warn(&format!(
+                "Failed to look up the dependencies of \"{}\": {}",
+                tw_task.description, err
+            ));
+            action
+        }
+    }
+}
+
+/// Habitica checklist listing the tasks a task depends on
+fn dependency_checklist(
+    backend: &dyn TaskBackend,
+    tw_task: &Task,
+) -> Result<Option<Vec<ChecklistItem>>> {
+    let depends: Vec<String> = tw_task.depends().iter().map(Uuid::to_string).collect();
+    if depends.is_empty() {
+        return Ok(None);
+    }
+    let filters: Vec<&str> = depends.iter().map(String::as_str).collect();
+    let dependencies = backend.export(&filters)?;
+    Ok(converter::checklist_of(tw_task, &dependencies))
+}
+
+/// Habitica version of a task, with its note and dependency checklist
+pub(crate) fn to_habitica(
+    config: &Config,
+    backend: &dyn TaskBackend,
+    tw_task: &Task,
+    note_content: Option<&str>,
+) -> Result<Option<HabiticaTask>> {
+    let Some(mut h_task) = converter::taskwarrior_to_habitica(config, tw_task, note_content)?
+    else {
+        return Ok(None);
+    };
+    h_task.checklist = dependency_checklist(backend, tw_task)?;
+    Ok(Some(h_task))
+}
+
+/// Build the Habitica notes for a task from its note file
+pub(crate) fn habitica_notes(
+    config: &Config,
+    notes_manager: &NotesManager,
+    task: &Task,
+) -> Result<Option<String>> {
+    let note_content = notes_manager.read_note(task)?;
+
+    match (config.bugwarrior, task.bugwarrior_url()) {
+        (BugwarriorMode::Link, Some(url)) => Ok(Some(match note_content {
+            Some(content) if !content.trim().is_empty() => {
+                format!("{}\n\n{}", content.trim_end(), url)
+            }
+            _ => url.to_string(),
+        })),
+        _ => Ok(note_content),
+    }
+}
+
+/// Convert a Habitica task for Taskwarrior, importing its note
+pub(crate) fn pull_task(
+    config: &Config,
+    notes_manager: &NotesManager,
+    h_task: &HabiticaTask,
+    existing_tw: Option<&Task>,
+    day_start: DayStart,
+) -> Result<Task> {
+    let mut tw_task = converter::habitica_to_taskwarrior(config, h_task, existing_tw, day_start)?;
+
+    // New group tasks land in the project shared with that group, others
+    // in the one of `habitica.sync.project`
+    if existing_tw.is_none() {
+        if let Some(project) = h_task
+            .group_id()
+            .and_then(|id| config.project_for_group(id))
+            .or(config.import_project.as_deref())
+        {
+            tw_task.extra.insert(
+                "project".to_string(),
+                serde_json::Value::String(project.to_string()),
+            );
+        }
+    }
+
+    // Import note from Habitica, minus any issue link we appended
+    let notes = strip_issue_link(&h_task.notes, existing_tw.and_then(Task::bugwarrior_url));
+    notes_manager.import_note_from_habitica(&mut tw_task, notes)?;
+
+    Ok(tw_task)
+}
+
+/// Pull a task changed on both sides and combine it field by field
+///
+/// Returns the pulled version along with the combined task. A local note
+/// decided for Taskwarrior is put back after the pull.
+pub(crate) fn combine_task(
+    config: &Config,
+    notes_manager: &NotesManager,
+    tw_task: &Task,
+    h_task: &HabiticaTask,
+    decisions: FieldDecisions,
+    day_start: DayStart,
+) -> Result<(Task, Task)> {
+    let local_note = notes_manager.read_note(tw_task)?;
+    let pulled = pull_task(config, notes_manager, h_task, Some(tw_task), day_start)?;
+    if decisions.notes == Side::Taskwarrior {
+        match local_note {
+            Some(content) => notes_manager.write_note(tw_task, &content)?,
+            None => notes_manager.delete_note(tw_task)?,
+        }
+    }
+    let combined = merge::combine(tw_task, &pulled, decisions);
+    Ok((pulled, combined))
+}
+
+/// Check whether pushing a task links it without scoring its completion
+pub(crate) fn links_unscored(config: &Config, tw_task: &Task) -> bool {
+    tw_task.habitica_uuid.is_none() && completed_before_linking(config, tw_task, Utc::now())
+}
+
+/// Direction to score a linked task whose status went from `old_tw` to
+/// `new_tw`, completing a reward buys it
+pub(crate) fn score_direction(
+    config: &Config,
+    old_tw: &Task,
+    new_tw: &Task,
+) -> Option<ScoreDirection> {
+    match (old_tw.status.is_completed(), new_tw.status.is_completed()) {
+        // Old completions earn no XP, see `push_to_habitica`
+        (false, true) if completed_before_linking(config, new_tw, Utc::now()) => None,
+        (false, true) => Some(ScoreDirection::Up),
+        (true, false) if !new_tw.task_type().is_repeatable() => Some(ScoreDirection::Down),
+        _ => None,
+    }
+}
+
+/// Streak of a daily after scoring it, which moves it by one
+pub(crate) fn scored_streak(task: &Task, direction: ScoreDirection) -> Option<u32> {
+    task.streak()
+        .filter(|_| task.task_type() == TaskType::Daily)
+        .map(|streak| match direction {
+            ScoreDirection::Up => streak + 1,
+            ScoreDirection::Down => streak.saturating_sub(1),
+        })
+}
+
+/// Check whether scoring resets a backdated completion date that has to be
+/// put back
+pub(crate) fn loses_completion_date(h_task: &HabiticaTask) -> bool {
+    let backdated = h_task
+        .date_completed
+        .is_some_and(|done| Utc::now() - done > Duration::minutes(5));
+    backdated && h_task.task_type != HabiticaTaskType::Reward
 }
 
 /// Resolve conflicts between Taskwarrior and Habitica tasks
 #[allow(dead_code)]
 pub struct ConflictResolver<'a> {
     config: &'a Config,
-    tw_client: &'a TaskwarriorClient,
-    h_client: &'a HabiticaClient,
+    backend: &'a dyn TaskBackend,
+    h_client: &'a dyn HabiticaApi,
     notes_manager: NotesManager<'a>,
+    merge_base: RefCell<Option<MergeBase>>,
+    /// Fetched on the first pull
+    day_start: Cell<Option<DayStart>>,
+    allow_streak_loss: bool,
+    interactive: bool,
+    prompter: &'a dyn Prompter,
 }
 
 impl<'a> ConflictResolver<'a> {
     pub const fn new(
         config: &'a Config,
-        tw_client: &'a TaskwarriorClient,
-        h_client: &'a HabiticaClient,
+        backend: &'a dyn TaskBackend,
+        h_client: &'a dyn HabiticaApi,
     ) -> Self {
         ConflictResolver {
             config,
-            tw_client,
+            backend,
             h_client,
             notes_manager: NotesManager::new(config),
+            merge_base: RefCell::new(None),
+            day_start: Cell::new(None),
+            allow_streak_loss: false,
+            interactive: false,
+            prompter: prompt::DEFAULT,
         }
     }
 
-    /// Determine which version of a task should win based on modification time
-    pub fn resolve(&self, tw_task: &Task, h_task: &HabiticaTask) -> ResolutionAction {
-        // First check if tasks are equivalent
-        if converter::tasks_are_equivalent(tw_task, h_task) {
-            return ResolutionAction::NoChange;
+    pub const fn config(&self) -> &'a Config {
+        self.config
+    }
+
+    /// Uncheck dailies without asking, even with streak protection on
+    #[must_use]
+    pub const fn allow_streak_loss(mut self, allow: bool) -> Self {
+        self.allow_streak_loss = allow;
+        self
+    }
+
+    /// Ask which side to keep for tasks changed on both sides
+    #[must_use]
+    pub const fn interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+
+    /// Tell and ask the user things through `prompter` instead of the
+    /// default one
+    #[must_use]
+    pub const fn prompter(mut self, prompter: &'a dyn Prompter) -> Self {
+        self.prompter = prompter;
+        self
+    }
+
+    /// Resolver for a sync worker, sharing the settings and the fetched day
+    /// start but not the merge base, which stays with this one
+    pub(crate) fn for_worker(&self) -> Self {
+        ConflictResolver {
+            config: self.config,
+            backend: self.backend,
+            h_client: self.h_client,
+            notes_manager: NotesManager::new(self.config),
+            merge_base: RefCell::new(None),
+            day_start: Cell::new(self.day_start.get()),
+            allow_streak_loss: self.allow_streak_loss,
+            interactive: self.interactive,
+            prompter: self.prompter,
         }
+    }
 
-        // Compare modification times
-        let tw_modified = tw_task.modified_or_now();
-        let h_modified = h_task.modified_or_now();
+    /// Determine which version of a task should win, per the conflict strategy
+    #[instrument(level = "debug", skip_all, fields(task = %tw_task.uuid), ret)]
+    pub fn resolve(&self, tw_task: &Task, h_task: &HabiticaTask) -> ResolutionAction {
+        let base = self
+            .with_merge_base(|merge_base| h_task.id.and_then(|h_id| merge_base.get(h_id)).cloned());
+        let ask = || self.ask_conflict(tw_task, h_task);
+        let action = resolve_with_base(
+            self.config,
+            base.as_ref(),
+            tw_task,
+            h_task,
+            self.interactive
+                .then_some(&ask as &dyn Fn() -> ResolutionAction),
+            &|| self.conflict_winner(tw_task, h_task),
+        );
+        check_dependencies(self.backend, self.prompter, tw_task, h_task, action)
+    }
 
-        if h_modified > tw_modified {
-            ResolutionAction::UseHabitica
-        } else {
-            ResolutionAction::UseTaskwarrior
+    /// Show how a task changed on both sides differs and ask which side to
+    /// keep
+    ///
+    /// Anything but a side, including no answer, skips the task.
+    fn ask_conflict(&self, tw_task: &Task, h_task: &HabiticaTask) -> ResolutionAction {
+        let h_notes = strip_issue_link(&h_task.notes, tw_task.bugwarrior_url());
+        self.prompter.say(&format!(
+            "\"{}\" changed on both sides:",
+            tw_task.description
+        ));
+        for (field, tw_value, h_value) in merge::field_diff(self.config, tw_task, h_task, h_notes) {
+            self.prompter.say(&format!(
+                "    {}: {} (Taskwarrior) / {} (Habitica)",
+                field, tw_value, h_value
+            ));
+        }
+        let answer = self
+            .prompter
+            .ask("Keep [t]askwarrior, [h]abitica or [s]kip");
+        match answer.map(|answer| answer.to_lowercase()).as_deref() {
+            Some("t" | "taskwarrior") => ResolutionAction::UseTaskwarrior,
+            Some("h" | "habitica") => ResolutionAction::UseHabitica,
+            _ => ResolutionAction::Skip,
         }
     }
 
-    /// Push a Taskwarrior task to Habitica and handle scoring if needed
-    pub fn push_to_habitica(
+    /// Side winning a conflict, asked on the terminal with the `ask` strategy
+    ///
+    /// Without an answer, the most recently modified side wins.
+    fn conflict_winner(&self, tw_task: &Task, h_task: &HabiticaTask) -> Side {
+        if self.config.conflict_strategy == ConflictStrategy::Ask {
+            let answer = self.prompter.ask(&format!(
+                "\"{}\" changed on both sides. Keep [t]askwarrior or [h]abitica",
+                tw_task.description
+            ));
+            match answer.map(|answer| answer.to_lowercase()).as_deref() {
+                Some("t" | "taskwarrior") => return Side::Taskwarrior,
+                Some("h" | "habitica") => return Side::Habitica,
+                _ => {}
+            }
+        }
+        preferred_side(self.config, tw_task, h_task)
+    }
+
+    /// Habitica version of a task, with its note and dependency checklist
+    fn to_habitica(
         &self,
         tw_task: &Task,
-        stats_cache: &mut Option<StatsCache>,
-    ) -> Result<Task> {
-        // Read note content
-        let note_content = self.notes_manager.read_note(tw_task)?;
-
-        // Convert to Habitica task
-        let h_task_opt = converter::taskwarrior_to_habitica(tw_task, note_content.as_deref())?;
+        note_content: Option<&str>,
+    ) -> Result<Option<HabiticaTask>> {
+        to_habitica(self.config, self.backend, tw_task, note_content)
+    }
 
-        let Some(h_task) = h_task_opt else {
-            // Task should not be synced to Habitica
-            return Ok(tw_task.clone());
-        };
+    /// Merge a task changed on both sides with the external merge tool
+    pub fn merge_with_tool(&self, tw_task: &Task, h_task: &HabiticaTask) -> Result<Task> {
+        let command = self.config.merge_command.as_deref().ok_or_else(|| {
+            Error::config("No habitica.conflict.merge_command configured for merging")
+        })?;
+        let h_version = self.pull_from_habitica(h_task, Some(tw_task))?;
+        let base = self
+            .with_merge_base(|merge_base| h_task.id.and_then(|h_id| merge_base.get(h_id)).cloned());
+        merge::run_merge_tool(command, base.as_ref(), tw_task, &h_version)
+    }
 
-        let mut updated_tw_task = tw_task.clone();
+    /// Combine a task changed on both sides field by field
+    ///
+    /// Returns the version pulled from Habitica along with the combined task.
+    /// A local note decided for Taskwarrior is put back after the pull.
+    pub fn combine(
+        &self,
+        tw_task: &Task,
+        h_task: &HabiticaTask,
+        decisions: FieldDecisions,
+    ) -> Result<(Task, Task)> {
+        combine_task(
+            self.config,
+            &self.notes_manager,
+            tw_task,
+            h_task,
+            decisions,
+            self.day_start()?,
+        )
+    }
 
-        // Create or update on Habitica
-        let (returned_h_task, new_stats, drop_msg) = if let Some(h_id) = h_task.id {
-            self.h_client.update_task(h_id, &h_task)?
-        } else {
-            self.h_client.create_task(&h_task)?
-        };
+    /// Keep the Taskwarrior edits a task pulled from Habitica would lose
+    ///
+    /// Without a recorded base the pulled task is taken as is.
+    pub fn keep_local_edits(&self, tw_task: &Task, pulled: &Task) -> Task {
+        let base = self.with_merge_base(|merge_base| {
+            pulled
+                .habitica_uuid
+                .and_then(|h_id| merge_base.get(h_id))
+                .cloned()
+        });
+        base.map_or_else(
+            || pulled.clone(),
+            |base| merge::keep_local_edits(&base, tw_task, pulled),
+        )
+    }
 
-        // Update the Habitica UUID in Taskwarrior task
-        updated_tw_task.habitica_uuid = returned_h_task.id;
+    /// Remember the synced version of a task as the base for later merges
+    /// and pulls
+    pub fn record_synced(&self, task: &Task) {
+        self.with_merge_base(|merge_base| merge_base.record(task));
+    }
 
-        // Update stats cache
-        if let Some(cache) = stats_cache {
-            cache.update(new_stats, drop_msg.clone());
+    /// Persist the merge base after a sync
+    pub fn save_merge_base(&self) -> Result<()> {
+        match &*self.merge_base.borrow() {
+            Some(merge_base) => merge_base.save(&self.config.merge_base_path()),
+            None => Ok(()),
         }
+    }
 
-        // If task is already completed, score it
-        if tw_task.status.is_completed() && returned_h_task.id.is_some() {
-            if let Some(h_id) = returned_h_task.id {
-                let (score_stats, score_drop) =
-                    self.h_client.score_task(h_id, ScoreDirection::Up)?;
-                if let Some(cache) = stats_cache {
-                    cache.update(score_stats, score_drop);
-                }
-            }
-        }
+    /// Run `f` on the merge base, loading it on first use
+    fn with_merge_base<R>(&self, f: impl FnOnce(&mut MergeBase) -> R) -> R {
+        let mut merge_base = self.merge_base.borrow_mut();
+        let merge_base = merge_base.get_or_insert_with(|| {
+            // A broken base only means more conflicts go to the merge tool
+            MergeBase::load(&self.config.merge_base_path()).unwrap_or_default()
+        });
+        f(merge_base)
+    }
 
-        Ok(updated_tw_task)
+    /// Check whether a task is configured to stay out of Habitica
+    pub fn is_excluded(&self, task: &Task) -> bool {
+        is_excluded(self.config, task)
     }
 
-    /// Update Taskwarrior from Habitica task
-    pub fn pull_from_habitica(
+    /// Build the Habitica notes for a task from its note file
+    fn habitica_notes(&self, task: &Task) -> Result<Option<String>> {
+        habitica_notes(self.config, &self.notes_manager, task)
+    }
+
+    /// Push a Taskwarrior task to Habitica and handle scoring if needed
+    pub fn push_to_habitica(
         &self,
-        h_task: &HabiticaTask,
-        existing_tw: Option<&Task>,
+        tw_task: &Task,
+        stats_cache: &mut Option<StatsCache>,
     ) -> Result<Task> {
-        // Convert to Taskwarrior task
-        let mut tw_task = converter::habitica_to_taskwarrior(h_task, existing_tw)?;
+        let writes = writes::push(self.config, self.backend, &self.notes_manager, tw_task)?;
+        self.send(writes, stats_cache)
+    }
 
-        // Import note from Habitica
-        self.notes_manager
-            .import_note_from_habitica(&mut tw_task, &h_task.notes)?;
+    /// Habitica version of a new task that can be created in a batch
+    ///
+    /// That's pending todos outside shared group projects, which need nothing
+    /// but the creation itself. `None` for anything else.
+    pub fn batch_candidate(&self, tw_task: &Task) -> Result<Option<HabiticaTask>> {
+        let in_group = tw_task
+            .project()
+            .and_then(|project| self.config.group_for_project(project))
+            .is_some();
+        if tw_task.habitica_uuid.is_some()
+            || !tw_task.status.is_pending()
+            || in_group
+            || self.is_excluded(tw_task)
+        {
+            return Ok(None);
+        }
 
-        Ok(tw_task)
+        let note_content = self.habitica_notes(tw_task)?;
+        let h_task = self.to_habitica(tw_task, note_content.as_deref())?;
+        Ok(h_task.filter(|h_task| h_task.task_type == HabiticaTaskType::Todo))
     }
 
-    /// Handle status transitions that require scoring
-    pub fn handle_status_change(
+    /// When the user's Habitica day rolls over, fetched once
+    pub(crate) fn day_start(&self) -> Result<DayStart> {
+        if let Some(day_start) = self.day_start.get() {
+            return Ok(day_start);
+        }
+        let day_start = self.h_client.get_day_start()?;
+        self.day_start.set(Some(day_start));
+        Ok(day_start)
+    }
+
+    /// Update Taskwarrior from Habitica task
+    pub fn pull_from_habitica(
         &self,
-        old_tw: &Task,
-        new_tw: &Task,
-        stats_cache: &mut Option<StatsCache>,
+        h_task: &HabiticaTask,
+        existing_tw: Option<&Task>,
     ) -> Result<Task> {
-        let old_status = old_tw.status;
-        let new_status = new_tw.status;
-
-        // Check if we need to score on Habitica
-        let score_direction = match (old_status.is_completed(), new_status.is_completed()) {
-            (false, true) => Some(ScoreDirection::Up), // Pending -> Completed
-            (true, false) => Some(ScoreDirection::Down), // Completed -> Pending
-            _ => None,
-        };
-
-        if let (Some(direction), Some(h_id)) = (score_direction, new_tw.habitica_uuid) {
-            let (new_stats, drop_msg) = self.h_client.score_task(h_id, direction)?;
-            if let Some(cache) = stats_cache {
-                cache.update(new_stats, drop_msg);
-            }
-        }
+        pull_task(
+            self.config,
+            &self.notes_manager,
+            h_task,
+            existing_tw,
+            self.day_start()?,
+        )
+    }
 
-        Ok(new_tw.clone())
+    /// Delete the Habitica task linked to `old_tw` and unlink `new_tw`
+    ///
+    /// Challenge tasks can't be deleted and stay on Habitica.
+    pub fn remove_from_habitica(&self, old_tw: &Task, new_tw: &Task) -> Result<Task> {
+        self.send(writes::remove(old_tw, new_tw), &mut None)
     }
 
     /// Modify a task on Habitica based on changes from Taskwarrior
@@ -155,46 +719,392 @@ impl<'a> ConflictResolver<'a> {
         new_tw: &Task,
         stats_cache: &mut Option<StatsCache>,
     ) -> Result<Task> {
-        // Check if task should be deleted from Habitica
-        if !new_tw.status.should_sync_to_habitica() && old_tw.habitica_uuid.is_some() {
-            if let Some(h_id) = old_tw.habitica_uuid {
-                self.h_client.delete_task(h_id)?;
-            }
-            let mut updated = new_tw.clone();
-            updated.habitica_uuid = None;
-            return Ok(updated);
-        }
+        let writes = writes::modify(
+            self.config,
+            self.backend,
+            &self.notes_manager,
+            old_tw,
+            new_tw,
+        )?;
+        self.send(writes, stats_cache)
+    }
 
-        // Check if task should be created on Habitica
-        if new_tw.status.should_sync_to_habitica() && !old_tw.status.should_sync_to_habitica() {
-            return self.push_to_habitica(new_tw, stats_cache);
+    /// Send writes to Habitica, giving back the task to import
+    ///
+    /// Unchecking a daily with streak protection on is confirmed first,
+    /// unless forced.
+    fn send(&self, mut writes: Writes, stats_cache: &mut Option<StatsCache>) -> Result<Task> {
+        for notice in &writes.notices {
+            self.prompter.warn(notice);
+        }
+        if writes.unchecks_daily {
+            check_streak_loss(self.config, self.allow_streak_loss, &writes.updated, || {
+                self.prompter.confirm("Uncheck it anyway?")
+            })?;
         }
 
-        // Check if we need to push changes
-        let note_content = self.notes_manager.read_note(new_tw)?;
-        let new_h_opt = converter::taskwarrior_to_habitica(new_tw, note_content.as_deref())?;
-
-        if let Some(new_h) = new_h_opt {
-            // Update details if changed
-            if let Some(h_id) = new_h.id {
-                let (_, new_stats, drop_msg) = self.h_client.update_task(h_id, &new_h)?;
-                if let Some(cache) = stats_cache {
-                    cache.update(new_stats, drop_msg);
+        for request in writes.take_requests() {
+            let reply = match (&request, writes.h_id) {
+                (
+                    Request::Create {
+                        h_task,
+                        group_id: Some(group_id),
+                    },
+                    _,
+                ) => Reply::Written(
+                    Box::new(self.h_client.create_group_task(*group_id, h_task)?),
+                    None,
+                    None,
+                ),
+                (
+                    Request::Create {
+                        h_task,
+                        group_id: None,
+                    },
+                    _,
+                ) => {
+                    let (created, stats, drop_msg) = self.h_client.create_task(h_task)?;
+                    Reply::Written(Box::new(created), stats, drop_msg)
                 }
-            }
-
-            // Handle status changes (scoring)
-            return self.handle_status_change(old_tw, new_tw, stats_cache);
+                (Request::Update(h_task), Some(h_id)) => {
+                    let (returned, stats, drop_msg) = self.h_client.update_task(h_id, h_task)?;
+                    Reply::Written(Box::new(returned), stats, drop_msg)
+                }
+                (Request::RestoreCompletion(h_task), Some(h_id)) => {
+                    self.h_client.update_task(h_id, h_task)?;
+                    Reply::Done
+                }
+                (Request::Score(direction), Some(h_id)) => {
+                    let (stats, drop_msg) = self.h_client.score_task(h_id, *direction)?;
+                    Reply::Scored(stats, drop_msg)
+                }
+                (Request::ScoreHabit(tag), _) => {
+                    let habit = match self.h_client.find_habit(tag)? {
+                        Some(habit) => habit,
+                        None => self.h_client.create_task(&writes::habit_for_tag(tag))?.0,
+                    };
+                    let habit_id = habit
+                        .id
+                        .ok_or_else(|| Error::HabiticaApiError("Habit has no ID".to_string()))?;
+                    let (stats, drop_msg) =
+                        self.h_client.score_task(habit_id, ScoreDirection::Up)?;
+                    Reply::Scored(stats, drop_msg)
+                }
+                (Request::Delete(h_id), _) => {
+                    self.h_client.delete_task(*h_id)?;
+                    Reply::Done
+                }
+                // Nothing was created to act on
+                (_, None) => continue,
+            };
+            writes.record(&request, reply, stats_cache);
         }
-
-        Ok(new_tw.clone())
+        Ok(writes.updated)
     }
 }
 
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
+    use std::sync::Mutex;
+
     use super::*;
+    use crate::{
+        habitica::HabiticaClient,
+        taskwarrior::{TaskStatus, TaskwarriorClient},
+    };
+
+    /// Keeps the warnings and answers no
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl Prompter for Recorder {
+        fn say(&self, _message: &str) {}
+
+        fn warn(&self, message: &str) {
+            self.0.lock().unwrap().push(message.to_string());
+        }
+
+        fn confirm(&self, _question: &str) -> bool {
+            false
+        }
+
+        fn ask(&self, _question: &str) -> Option<String> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_streak_message() {
+        assert_eq!(
+            streak_message("Stretch", 21).as_deref(),
+            Some("21-day streak on \"Stretch\"!")
+        );
+        assert!(streak_message("Stretch", 42).is_some());
+        assert_eq!(streak_message("Stretch", 20), None);
+        assert_eq!(streak_message("Stretch", 0), None);
+    }
+
+    #[test]
+    fn test_strip_issue_link() {
+        let url = "https://github.com/o/r/issues/1";
+        assert_eq!(
+            strip_issue_link("Some notes\n\nhttps://github.com/o/r/issues/1", Some(url)),
+            "Some notes"
+        );
+        assert_eq!(strip_issue_link(url, Some(url)), "");
+        assert_eq!(strip_issue_link("Some notes", None), "Some notes");
+    }
+
+    #[test]
+    fn test_purchase_message() {
+        assert_eq!(
+            purchase_message("Movie night", 25.0, 12.5),
+            "Bought Movie night for 25 gold, 12.50 gold left"
+        );
+    }
+
+    #[test]
+    fn test_completed_before_linking() {
+        let now = Utc::now();
+        let mut config = Config::for_tests();
+        let mut task = Task {
+            status: TaskStatus::Completed,
+            ..Default::default()
+        };
+        task.extra.insert(
+            "end".to_string(),
+            serde_json::Value::String("20250101T120000Z".to_string()),
+        );
+        assert!(!completed_before_linking(&config, &task, now));
+
+        config.link_score_max_age = Some(chrono::Duration::days(1));
+        assert!(completed_before_linking(&config, &task, now));
+
+        task.status = TaskStatus::Pending;
+        assert!(!completed_before_linking(&config, &task, now));
+    }
+
+    #[test]
+    fn test_old_completion_is_linked_without_scoring() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::for_tests();
+        config.data_location = dir.path().to_path_buf();
+        config.link_score_max_age = Some(chrono::Duration::days(1));
+
+        let h_id = Uuid::new_v4();
+        let mut server = mockito::Server::new();
+        let created = server
+            .mock("POST", "/v3/tasks/user")
+            .with_body(format!(
+                r#"{{"success":true,"data":{{"id":"{}","text":"Old work","type":"todo","priority":1}}}}"#,
+                h_id
+            ))
+            .create();
+        let scored = server
+            .mock("POST", mockito::Matcher::Regex("/score/".to_string()))
+            .expect(0)
+            .create();
+
+        let tw_client = TaskwarriorClient::new();
+        let h_client = HabiticaClient::with_base_url(&config, &server.url()).unwrap();
+        let recorder = Recorder::default();
+        let resolver = ConflictResolver::new(&config, &tw_client, &h_client).prompter(&recorder);
+        let mut task = Task {
+            description: "Old work".to_string(),
+            status: TaskStatus::Completed,
+            ..Default::default()
+        };
+        task.extra.insert(
+            "end".to_string(),
+            serde_json::Value::String("20250101T120000Z".to_string()),
+        );
+
+        let linked = resolver.push_to_habitica(&task, &mut None).unwrap();
+        assert_eq!(linked.habitica_uuid, Some(h_id));
+        created.assert();
+        scored.assert();
+        assert_eq!(
+            recorder.0.lock().unwrap()[..],
+            ["Linked \"Old work\" without scoring, it was completed long before linking"]
+        );
+
+        // Open on Habitica, the pair counts as in sync
+        let h_task = HabiticaTask {
+            id: Some(h_id),
+            text: "Old work".to_string(),
+            priority: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            resolver.resolve(&linked, &h_task),
+            ResolutionAction::NoChange
+        );
+    }
+
+    #[test]
+    fn test_bugwarrior_skip_excludes_task() {
+        let mut config = Config::for_tests();
+        let mut task = Task::default();
+        task.extra.insert(
+            "githuburl".to_string(),
+            serde_json::Value::String("https://github.com/o/r/issues/1".to_string()),
+        );
+
+        assert!(!is_excluded(&config, &task));
+        config.bugwarrior = BugwarriorMode::Skip;
+        assert!(is_excluded(&config, &task));
+    }
+
+    #[test]
+    fn test_hold_waiting_excludes_unlinked_task() {
+        let mut config = Config::for_tests();
+        let mut task = Task::default();
+        task.extra.insert(
+            "wait".to_string(),
+            serde_json::Value::String("29990101T000000Z".to_string()),
+        );
+
+        assert!(!is_excluded(&config, &task));
+        config.hold_waiting = true;
+        assert!(is_excluded(&config, &task));
+
+        // Already on Habitica, so it keeps syncing
+        task.habitica_uuid = Some(Uuid::new_v4());
+        assert!(!is_excluded(&config, &task));
+
+        // The wait date has passed
+        task.habitica_uuid = None;
+        task.extra.insert(
+            "wait".to_string(),
+            serde_json::Value::String("20200101T000000Z".to_string()),
+        );
+        assert!(!is_excluded(&config, &task));
+    }
+
+    #[test]
+    fn test_skips_import() {
+        let mut config = Config::for_tests();
+        let challenge_id = Uuid::new_v4();
+        let mut starter = HabiticaTask {
+            by_habitica: true,
+            ..Default::default()
+        };
+        let challenge_task = HabiticaTask {
+            challenge: Some(crate::habitica::ChallengeInfo {
+                id: Some(challenge_id),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert!(!skips_import(&config, &HabiticaTask::default()));
+        assert!(skips_import(&config, &starter));
+        assert!(skips_import(&config, &challenge_task));
+
+        config.import_allow = vec!["habitica".to_string(), challenge_id.to_string()];
+        assert!(!skips_import(&config, &starter));
+        assert!(!skips_import(&config, &challenge_task));
+
+        // Group tasks only stay out when asked to
+        let group_id = Uuid::new_v4();
+        let group_task = HabiticaTask {
+            group: Some(crate::habitica::GroupInfo {
+                id: Some(group_id),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(!skips_import(&config, &group_task));
+        config.sync_group_tasks = false;
+        assert!(skips_import(&config, &group_task));
+        config.group_plans.insert("home".to_string(), group_id);
+        assert!(!skips_import(&config, &group_task));
+
+        // A left challenge has no ID anymore and its tasks are the user's own
+        starter.by_habitica = false;
+        starter.challenge = Some(crate::habitica::ChallengeInfo::default());
+        config.import_allow.clear();
+        assert!(!skips_import(&config, &starter));
+    }
+
+    /// Resolve a pair the last sync didn't record, as the strategy says
+    fn resolve_without_base(
+        config: &Config,
+        tw_task: &Task,
+        h_task: &HabiticaTask,
+    ) -> ResolutionAction {
+        resolve_with_base(config, None, tw_task, h_task, None, &|| {
+            preferred_side(config, tw_task, h_task)
+        })
+    }
+
+    #[test]
+    fn test_resolve_without_base() {
+        let mut config = Config::for_tests();
+        let h_task = HabiticaTask {
+            id: Some(Uuid::new_v4()),
+            text: "Write report".to_string(),
+            updated_at: Some(Utc::now() - Duration::hours(1)),
+            ..Default::default()
+        };
+        let mut tw_task =
+            converter::habitica_to_taskwarrior(&config, &h_task, None, DayStart::default())
+                .unwrap();
+        tw_task.description = "Write quarterly report".to_string();
+        tw_task.modified = Some(Utc::now());
+
+        assert_eq!(
+            resolve_without_base(&config, &tw_task, &h_task),
+            ResolutionAction::UseTaskwarrior
+        );
+        config.conflict_strategy = ConflictStrategy::HabiticaWins;
+        assert_eq!(
+            resolve_without_base(&config, &tw_task, &h_task),
+            ResolutionAction::UseHabitica
+        );
+
+        // Deleting in Taskwarrior wins regardless
+        tw_task.status = TaskStatus::Deleted;
+        assert_eq!(
+            resolve_without_base(&config, &tw_task, &h_task),
+            ResolutionAction::UseTaskwarrior
+        );
+    }
+
+    #[test]
+    fn test_challenge_tasks_are_pull_only() {
+        let config = Config::for_tests();
+        let h_task = HabiticaTask {
+            id: Some(Uuid::new_v4()),
+            text: "Run 5k".to_string(),
+            challenge: Some(crate::habitica::ChallengeInfo {
+                id: Some(Uuid::new_v4()),
+                short_name: Some("Couch to 5k".to_string()),
+            }),
+            updated_at: Some(Utc::now() - Duration::hours(1)),
+            ..Default::default()
+        };
+        let mut tw_task =
+            converter::habitica_to_taskwarrior(&config, &h_task, None, DayStart::default())
+                .unwrap();
+        assert_eq!(tw_task.challenge(), Some("Couch to 5k"));
+
+        // A newer local edit doesn't go to Habitica
+        tw_task.description = "Run 10k".to_string();
+        tw_task.modified = Some(Utc::now());
+        assert!(matches!(
+            resolve_without_base(&config, &tw_task, &h_task),
+            ResolutionAction::UseHabitica
+        ));
+
+        // Completing it still does
+        tw_task.status = TaskStatus::Completed;
+        assert!(matches!(
+            resolve_without_base(&config, &tw_task, &h_task),
+            ResolutionAction::UseTaskwarrior
+        ));
+    }
 
     #[test]
     fn test_resolution_action() {
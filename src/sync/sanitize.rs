@@ -0,0 +1,299 @@
+use std::str::FromStr;
+
+use crate::{
+    config::Config,
+    error::{Error, Result},
+};
+
+/// Shortcodes converted to Unicode, covering the emoji most used in tasks
+const EMOJI: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("alarm_clock", "⏰"),
+    ("book", "📖"),
+    ("books", "📚"),
+    ("calendar", "📅"),
+    ("check", "✔️"),
+    ("clock", "🕐"),
+    ("coffee", "☕"),
+    ("computer", "💻"),
+    ("dog", "🐶"),
+    ("dragon", "🐉"),
+    ("email", "📧"),
+    ("fire", "🔥"),
+    ("heart", "❤️"),
+    ("heavy_check_mark", "✔️"),
+    ("house", "🏠"),
+    ("moneybag", "💰"),
+    ("muscle", "💪"),
+    ("pencil", "📝"),
+    ("pill", "💊"),
+    ("rocket", "🚀"),
+    ("running", "🏃"),
+    ("shopping_cart", "🛒"),
+    ("sleeping", "😴"),
+    ("smile", "😄"),
+    ("sparkles", "✨"),
+    ("star", "⭐"),
+    ("sunny", "☀️"),
+    ("tada", "🎉"),
+    ("trophy", "🏆"),
+    ("warning", "⚠️"),
+    ("white_check_mark", "✅"),
+    ("x", "❌"),
+];
+
+/// Named HTML entities decoded by [`SanitizeMode::Convert`]
+const ENTITIES: &[(&str, &str)] = &[
+    ("amp", "&"),
+    ("lt", "<"),
+    ("gt", ">"),
+    ("quot", "\""),
+    ("apos", "'"),
+    ("nbsp", " "),
+    ("hellip", "…"),
+    ("ndash", "–"),
+    ("mdash", "—"),
+];
+
+/// What happens to one kind of markup in imported Habitica text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SanitizeMode {
+    /// Leave the markup as is
+    #[default]
+    Keep,
+    /// Remove the markup entirely
+    Strip,
+    /// Replace the markup with its plain-text meaning
+    Convert,
+}
+
+impl FromStr for SanitizeMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "keep" => Ok(SanitizeMode::Keep),
+            "strip" => Ok(SanitizeMode::Strip),
+            "convert" => Ok(SanitizeMode::Convert),
+            other => Err(Error::config(format!(
+                "Invalid sanitize mode: {} (expected keep, strip or convert)",
+                other
+            ))),
+        }
+    }
+}
+
+/// How Habitica markup is cleaned up in Taskwarrior descriptions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SanitizeOptions {
+    /// Emoji shortcodes like `:smile:`
+    pub emoji: SanitizeMode,
+    /// Markdown links and images like `[text](url)`
+    pub links: SanitizeMode,
+    /// HTML entities like `&amp;`
+    pub entities: SanitizeMode,
+}
+
+impl SanitizeOptions {
+    /// Check whether every element is kept as is
+    pub fn is_noop(&self) -> bool {
+        *self == SanitizeOptions::default()
+    }
+}
+
+/// Description for a Habitica task text, cleaned up per the configuration
+pub fn description(config: &Config, text: &str) -> String {
+    sanitize(&config.sanitize, text)
+}
+
+/// Clean up Habitica markup in a text
+pub fn sanitize(options: &SanitizeOptions, text: &str) -> String {
+    if options.is_noop() {
+        return text.to_string();
+    }
+
+    let text = replace_links(text, options.links);
+    let text = replace_emoji(&text, options.emoji);
+    let text = replace_entities(&text, options.entities);
+
+    // Stripped markup leaves doubled or dangling spaces behind
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Replace `[text](url)` and `![alt](url)`
+fn replace_links(text: &str, mode: SanitizeMode) -> String {
+    if mode == SanitizeMode::Keep {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('[') {
+        let Some((label, after)) = parse_link(&rest[open..]) else {
+            out.push_str(&rest[..=open]);
+            rest = &rest[open + 1..];
+            continue;
+        };
+        let before = rest[..open].strip_suffix('!').unwrap_or(&rest[..open]);
+        out.push_str(before);
+        if mode == SanitizeMode::Convert {
+            out.push_str(label);
+        }
+        rest = after;
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Split `[label](url)rest` into the label and the rest
+fn parse_link(text: &str) -> Option<(&str, &str)> {
+    let close = text.find(']')?;
+    let label = &text[1..close];
+    let target = text[close + 1..].strip_prefix('(')?;
+    let end = target.find(')')?;
+    (!label.contains('[') && !target[..end].contains(char::is_whitespace))
+        .then_some((label, &target[end + 1..]))
+}
+
+/// Replace `:shortcode:` emoji
+fn replace_emoji(text: &str, mode: SanitizeMode) -> String {
+    if mode == SanitizeMode::Keep {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find(':') {
+        let after_open = &rest[open + 1..];
+        let code = after_open
+            .find(':')
+            .map(|close| &after_open[..close])
+            .filter(|code| is_shortcode(code));
+
+        let Some(code) = code else {
+            out.push_str(&rest[..=open]);
+            rest = after_open;
+            continue;
+        };
+
+        out.push_str(&rest[..open]);
+        if mode == SanitizeMode::Convert {
+            // Unknown codes stay readable
+            match EMOJI.iter().find(|(name, _)| *name == code) {
+                Some((_, emoji)) => out.push_str(emoji),
+                None => out.push_str(&rest[open..open + code.len() + 2]),
+            }
+        }
+        rest = &after_open[code.len() + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn is_shortcode(code: &str) -> bool {
+    !code.is_empty()
+        && code
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_+-".contains(c))
+}
+
+/// Replace `&name;`, `&#123;` and `&#x7B;` entities
+fn replace_entities(text: &str, mode: SanitizeMode) -> String {
+    if mode == SanitizeMode::Keep {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('&') {
+        let after_open = &rest[open + 1..];
+        let decoded = after_open
+            .find(';')
+            .filter(|close| *close <= 10)
+            .and_then(|close| Some((close, decode_entity(&after_open[..close])?)));
+
+        let Some((close, decoded)) = decoded else {
+            out.push_str(&rest[..=open]);
+            rest = after_open;
+            continue;
+        };
+
+        out.push_str(&rest[..open]);
+        if mode == SanitizeMode::Convert {
+            out.push(decoded);
+        }
+        rest = &after_open[close + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    if let Some(hex) = entity
+        .strip_prefix("#x")
+        .or_else(|| entity.strip_prefix("#X"))
+    {
+        return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+    }
+    if let Some(decimal) = entity.strip_prefix('#') {
+        return decimal.parse().ok().and_then(char::from_u32);
+    }
+    ENTITIES
+        .iter()
+        .find(|(name, _)| *name == entity)
+        .and_then(|(_, value)| value.chars().next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str =
+        "Read [the docs](https://habitica.com/docs) :books: &amp; relax :zzz_unknown:";
+
+    #[test]
+    fn test_keep_by_default() {
+        assert_eq!(sanitize(&SanitizeOptions::default(), TEXT), TEXT);
+    }
+
+    #[test]
+    fn test_convert() {
+        let options = SanitizeOptions {
+            emoji: SanitizeMode::Convert,
+            links: SanitizeMode::Convert,
+            entities: SanitizeMode::Convert,
+        };
+        assert_eq!(
+            sanitize(&options, TEXT),
+            "Read the docs 📚 & relax :zzz_unknown:"
+        );
+        assert_eq!(
+            sanitize(&options, "Fish &#x1F41F; &#38; chips"),
+            "Fish 🐟 & chips"
+        );
+    }
+
+    #[test]
+    fn test_strip() {
+        let options = SanitizeOptions {
+            emoji: SanitizeMode::Strip,
+            links: SanitizeMode::Strip,
+            entities: SanitizeMode::Strip,
+        };
+        assert_eq!(sanitize(&options, TEXT), "Read relax");
+    }
+
+    #[test]
+    fn test_plain_text_survives() {
+        let options = SanitizeOptions {
+            emoji: SanitizeMode::Strip,
+            links: SanitizeMode::Strip,
+            entities: SanitizeMode::Strip,
+        };
+        assert_eq!(
+            sanitize(&options, "Meeting at 10:30 [room 4] & lunch"),
+            "Meeting at 10:30 [room 4] & lunch"
+        );
+    }
+}
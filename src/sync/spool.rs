@@ -0,0 +1,199 @@
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{error::Result, taskwarrior::Task};
+
+/// Change recorded by a hook for the next `flush`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "hook", rename_all = "lowercase")]
+pub enum Intent {
+    /// A task was added
+    Add { task: Task },
+    /// A task was modified
+    Modify { old: Task, new: Task },
+}
+
+impl Intent {
+    /// UUID of the task the intent is about
+    pub const fn uuid(&self) -> Uuid {
+        match self {
+            Intent::Add { task } | Intent::Modify { new: task, .. } => task.uuid,
+        }
+    }
+}
+
+/// Pending change to a task, merged from all its intents
+#[derive(Debug, Clone)]
+pub struct Pending {
+    /// The task before the first modification, `None` for added tasks
+    pub old: Option<Task>,
+    /// The task as the last hook saw it
+    pub new: Task,
+}
+
+impl From<Pending> for Intent {
+    fn from(pending: Pending) -> Self {
+        match pending.old {
+            Some(old) => Intent::Modify {
+                old,
+                new: pending.new,
+            },
+            None => Intent::Add { task: pending.new },
+        }
+    }
+}
+
+/// Append an intent to the spool file
+pub fn append(path: &Path, intent: &Intent) -> Result<()> {
+    let mut line = serde_json::to_string(intent)?;
+    line.push('\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Read the spooled intents, leaving them for `flush`
+pub fn load(path: &Path) -> Result<Vec<Intent>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    parse(&fs::read_to_string(path)?)
+}
+
+/// Read and remove all spooled intents
+///
+/// The file is moved aside first, so hooks running meanwhile start a new one.
+pub fn take(path: &Path) -> Result<Vec<Intent>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let taken = path.with_extension("flushing");
+    fs::rename(path, &taken)?;
+    let intents = parse(&fs::read_to_string(&taken)?)?;
+    fs::remove_file(&taken)?;
+    Ok(intents)
+}
+
+fn parse(content: &str) -> Result<Vec<Intent>> {
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect::<serde_json::Result<Vec<Intent>>>()?)
+}
+
+/// Merge the intents of each task, in the order tasks were first touched
+///
+/// A task keeps the state before its first modification and the latest
+/// state, so a task added and then modified is still pushed as new.
+pub fn coalesce(intents: Vec<Intent>) -> Vec<Pending> {
+    let mut order = Vec::new();
+    let mut pending: HashMap<Uuid, Pending> = HashMap::new();
+    for intent in intents {
+        let uuid = intent.uuid();
+        let (old, new) = match intent {
+            Intent::Add { task } => (None, task),
+            Intent::Modify { old, new } => (Some(old), new),
+        };
+        if let Some(entry) = pending.get_mut(&uuid) {
+            entry.new = new;
+        } else {
+            order.push(uuid);
+            pending.insert(uuid, Pending { old, new });
+        }
+    }
+    order
+        .into_iter()
+        .filter_map(|uuid| pending.remove(&uuid))
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn task(description: &str) -> Task {
+        Task {
+            uuid: Uuid::new_v4(),
+            description: description.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_append_and_take() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("spool.jsonl");
+        assert!(take(&path).unwrap().is_empty());
+
+        let added = task("Write report");
+        append(
+            &path,
+            &Intent::Add {
+                task: added.clone(),
+            },
+        )
+        .unwrap();
+        append(
+            &path,
+            &Intent::Modify {
+                old: added.clone(),
+                new: added.clone(),
+            },
+        )
+        .unwrap();
+
+        let intents = take(&path).unwrap();
+        assert_eq!(intents.len(), 2);
+        assert!(matches!(&intents[0], Intent::Add { task } if task.uuid == added.uuid));
+        assert!(!path.exists());
+        assert!(take(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_coalesce() {
+        let added = task("Write report");
+        let mut renamed = added.clone();
+        renamed.description = "Write the report".to_string();
+        let modified = task("Stretch");
+        let mut done = modified.clone();
+        done.description = "Stretch more".to_string();
+        let mut later = done.clone();
+        later.description = "Stretch even more".to_string();
+
+        let pending = coalesce(vec![
+            Intent::Add {
+                task: added.clone(),
+            },
+            Intent::Modify {
+                old: modified.clone(),
+                new: done.clone(),
+            },
+            Intent::Modify {
+                old: added,
+                new: renamed,
+            },
+            Intent::Modify {
+                old: done,
+                new: later,
+            },
+        ]);
+
+        assert_eq!(pending.len(), 2);
+        assert!(pending[0].old.is_none());
+        assert_eq!(pending[0].new.description, "Write the report");
+        assert_eq!(
+            pending[1].old.as_ref().map(|t| t.description.as_str()),
+            Some("Stretch")
+        );
+        assert_eq!(pending[1].new.description, "Stretch even more");
+    }
+}
@@ -0,0 +1,105 @@
+use chrono::{DateTime, Utc};
+
+use crate::{
+    config::Config,
+    error::Result,
+    habitica::{HabiticaApi, StatsCache},
+    sync::habits::score_habit,
+    taskwarrior::Task,
+};
+
+/// Minutes tracked on a task if this modification stopped its timer
+///
+/// Timewarrior's own hook starts and stops tracking together with
+/// `task start`/`task stop`, so a vanishing `start` attribute marks the end
+/// of a tracked interval.
+pub fn stopped_minutes(old_task: &Task, new_task: &Task, now: DateTime<Utc>) -> Option<i64> {
+    let started = old_task.date_attribute("start")?;
+    if new_task.date_attribute("start").is_some() {
+        return None;
+    }
+    Some((now - started).num_minutes())
+}
+
+/// Number of habit scores earned for the tracked minutes
+pub fn scores_for(minutes: i64, minutes_per_score: u32) -> u32 {
+    if minutes <= 0 || minutes_per_score == 0 {
+        return 0;
+    }
+    u32::try_from(minutes / i64::from(minutes_per_score)).unwrap_or(u32::MAX)
+}
+
+/// Score the configured habit once per full block of tracked time
+///
+/// Returns the number of times the habit was scored.
+pub fn score_tracked_time(
+    config: &Config,
+    h_client: &dyn HabiticaApi,
+    minutes: i64,
+    stats_cache: &mut Option<StatsCache>,
+) -> Result<u32> {
+    let Some(habit_name) = &config.timew_habit else {
+        return Ok(0);
+    };
+
+    let scores = scores_for(minutes, config.timew_minutes_per_score);
+    if scores == 0 {
+        return Ok(0);
+    }
+
+    score_habit(h_client, habit_name, scores, stats_cache)?;
+    Ok(scores)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::taskwarrior::{date_format, TaskStatus};
+
+    fn test_task(start: Option<DateTime<Utc>>) -> Task {
+        let mut extra = serde_json::Map::new();
+        if let Some(start) = start {
+            extra.insert(
+                "start".to_string(),
+                Value::String(date_format::format(&start)),
+            );
+        }
+        Task {
+            uuid: uuid::Uuid::new_v4(),
+            description: "Deep work".to_string(),
+            status: TaskStatus::Pending,
+            modified: None,
+            due: None,
+            annotations: None,
+            habitica_uuid: None,
+            habitica_difficulty: None,
+            habitica_task_type: None,
+            habitica_cost: None,
+            habitica_assigned: None,
+            habitica_attribute: None,
+            extra,
+        }
+    }
+
+    #[test]
+    fn test_stopped_minutes() {
+        let now = Utc::now();
+        let running = test_task(Some(now - Duration::minutes(50)));
+        let stopped = test_task(None);
+
+        assert_eq!(stopped_minutes(&running, &stopped, now), Some(50));
+        assert_eq!(stopped_minutes(&stopped, &running, now), None);
+        assert_eq!(stopped_minutes(&running, &running, now), None);
+    }
+
+    #[test]
+    fn test_scores_for() {
+        assert_eq!(scores_for(50, 25), 2);
+        assert_eq!(scores_for(24, 25), 0);
+        assert_eq!(scores_for(-5, 25), 0);
+        assert_eq!(scores_for(60, 0), 0);
+    }
+}
@@ -0,0 +1,137 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{error::Result, taskwarrior::Task};
+
+/// Days a tombstone is kept before it is dropped
+const KEEP_DAYS: i64 = 90;
+
+/// Side a linked task was deleted on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeletedOn {
+    Taskwarrior,
+    Habitica,
+}
+
+/// Deletion of a linked task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tombstone {
+    pub habitica_id: Uuid,
+    pub side: DeletedOn,
+    pub deleted: DateTime<Utc>,
+}
+
+/// Deleted linked tasks, keyed by Taskwarrior UUID
+///
+/// Without them, a deletion the sync hasn't carried over looks like a task
+/// the other side never had: the Habitica copy of a task deleted and purged
+/// in Taskwarrior would be imported again, and a Taskwarrior copy restored
+/// from an old backup would be pushed again. Tombstones are dropped after
+/// 90 days.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Tombstones {
+    entries: BTreeMap<Uuid, Tombstone>,
+}
+
+impl Tombstones {
+    /// Load the tombstones, empty if they were never saved
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Tombstones::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Save the tombstones, dropping the ones older than 90 days
+    pub fn save(&mut self, path: &Path, now: DateTime<Utc>) -> Result<()> {
+        let cutoff = now - Duration::days(KEEP_DAYS);
+        self.entries
+            .retain(|_, tombstone| tombstone.deleted >= cutoff);
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Remember that a linked task was deleted on one side
+    pub fn record(&mut self, task: &Task, habitica_id: Uuid, side: DeletedOn, now: DateTime<Utc>) {
+        self.entries.insert(
+            task.uuid,
+            Tombstone {
+                habitica_id,
+                side,
+                deleted: now,
+            },
+        );
+    }
+
+    /// Check whether the Taskwarrior task linked to a Habitica task was
+    /// deleted
+    pub fn deleted_in_taskwarrior(&self, habitica_id: Uuid) -> bool {
+        self.entries.values().any(|tombstone| {
+            tombstone.side == DeletedOn::Taskwarrior && tombstone.habitica_id == habitica_id
+        })
+    }
+
+    /// Check whether a task's Habitica copy was deleted and the task hasn't
+    /// been modified since
+    pub fn deleted_on_habitica(&self, task: &Task) -> bool {
+        self.entries.get(&task.uuid).is_some_and(|tombstone| {
+            tombstone.side == DeletedOn::Habitica
+                && task
+                    .modified
+                    .map_or(true, |modified| modified <= tombstone.deleted)
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn task(modified: DateTime<Utc>) -> Task {
+        Task {
+            uuid: Uuid::new_v4(),
+            description: "Water plants".to_string(),
+            modified: Some(modified),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_tombstones() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("habitica_tombstones.json");
+        let now = Utc::now();
+
+        let deleted_here = task(now);
+        let deleted_there = task(now - Duration::hours(1));
+        let revived = task(now + Duration::hours(1));
+        let (here_id, there_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let mut tombstones = Tombstones::load(&path).unwrap();
+        tombstones.record(&deleted_here, here_id, DeletedOn::Taskwarrior, now);
+        tombstones.record(&deleted_there, there_id, DeletedOn::Habitica, now);
+        tombstones.record(&revived, Uuid::new_v4(), DeletedOn::Habitica, now);
+        tombstones.save(&path, now).unwrap();
+
+        let tombstones = Tombstones::load(&path).unwrap();
+        assert!(tombstones.deleted_in_taskwarrior(here_id));
+        assert!(!tombstones.deleted_in_taskwarrior(there_id));
+        assert!(tombstones.deleted_on_habitica(&deleted_there));
+        assert!(!tombstones.deleted_on_habitica(&deleted_here));
+        // Edited after the deletion, so it was brought back on purpose
+        assert!(!tombstones.deleted_on_habitica(&revived));
+
+        let mut tombstones = tombstones;
+        tombstones
+            .save(&path, now + Duration::days(KEEP_DAYS + 1))
+            .unwrap();
+        assert!(!Tombstones::load(&path)
+            .unwrap()
+            .deleted_in_taskwarrior(here_id));
+    }
+}
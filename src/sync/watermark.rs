@@ -0,0 +1,41 @@
+use std::{fs, path::Path};
+
+use chrono::{DateTime, Utc};
+
+use crate::error::{Error, Result};
+
+/// Load the time the last successful sync started, `None` if never saved
+pub fn load(path: &Path) -> Result<Option<DateTime<Utc>>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path)?;
+    DateTime::parse_from_rfc3339(content.trim())
+        .map(|time| Some(time.with_timezone(&Utc)))
+        .map_err(|e| Error::custom(format!("Invalid sync watermark {}: {}", path.display(), e)))
+}
+
+/// Save the time a successful sync started
+pub fn save(path: &Path, time: DateTime<Utc>) -> Result<()> {
+    fs::write(path, time.to_rfc3339())?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_and_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("habitica_last_sync");
+        assert_eq!(load(&path).unwrap(), None);
+
+        let time = DateTime::parse_from_rfc3339("2026-03-01T08:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        save(&path, time).unwrap();
+        assert_eq!(load(&path).unwrap(), Some(time));
+    }
+}
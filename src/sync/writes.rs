@@ -0,0 +1,434 @@
+use std::mem;
+
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    habitica::{HabiticaTask, HabiticaTaskType, ScoreDirection, StatsCache, UserStats},
+    sync::{
+        converter, habits, recurrence,
+        resolver::{
+            details_changed, habitica_notes, is_excluded, links_unscored, loses_completion_date,
+            purchase_message, score_direction, scored_streak, streak_message, to_habitica,
+        },
+    },
+    taskwarrior::{NotesManager, Task, TaskBackend, TaskType},
+};
+
+/// A Habitica request decided by the sync, sent by either engine
+///
+/// Requests without an ID act on the task the writes are for, which a
+/// [`Request::Create`] earlier in the list may only bring into being.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    /// Create the task, on a group plan when given
+    Create {
+        h_task: HabiticaTask,
+        group_id: Option<Uuid>,
+    },
+    /// Write the task, taking back its assignments and streak
+    Update(HabiticaTask),
+    /// Write the task again after scoring reset its completion date
+    RestoreCompletion(HabiticaTask),
+    /// Score the task
+    Score(ScoreDirection),
+    /// Score the habit named after a habit tag, creating it when missing
+    ScoreHabit(String),
+    /// Delete a Habitica task
+    Delete(Uuid),
+}
+
+/// Habitica's answer to a [`Request`]
+#[derive(Debug)]
+pub enum Reply {
+    /// The task as written by a create or update
+    Written(Box<HabiticaTask>, Option<UserStats>, Option<String>),
+    /// Stats after scoring
+    Scored(Option<UserStats>, Option<String>),
+    /// Nothing to take over
+    Done,
+}
+
+/// What pushing a change of a Taskwarrior task takes on Habitica
+///
+/// Decided without talking to Habitica: the engines send the requests in
+/// order and hand each reply back to [`Writes::record`], which updates the
+/// task to import.
+#[derive(Debug)]
+pub struct Writes {
+    /// Habitica task the requests act on, `None` until created
+    pub h_id: Option<Uuid>,
+    pub requests: Vec<Request>,
+    /// Task to import once the requests are sent
+    pub updated: Task,
+    /// Warnings for the user
+    pub notices: Vec<String>,
+    /// The requests uncheck this daily, resetting its streak
+    pub unchecks_daily: bool,
+}
+
+impl Writes {
+    /// Nothing to write, the task is imported as is
+    fn none(task: &Task) -> Self {
+        Writes {
+            h_id: task.habitica_uuid,
+            requests: Vec::new(),
+            updated: task.clone(),
+            notices: Vec::new(),
+            unchecks_daily: false,
+        }
+    }
+
+    /// Take the requests to send, leaving the rest for [`record`](Self::record)
+    pub fn take_requests(&mut self) -> Vec<Request> {
+        mem::take(&mut self.requests)
+    }
+
+    /// Take over Habitica's reply to a request
+    pub fn record(
+        &mut self,
+        request: &Request,
+        reply: Reply,
+        stats_cache: &mut Option<StatsCache>,
+    ) {
+        let (stats, drop_msg) = match (request, reply) {
+            (Request::Create { .. }, Reply::Written(h_task, stats, drop_msg)) => {
+                self.h_id = h_task.id;
+                self.updated.habitica_uuid = h_task.id;
+                (stats, drop_msg)
+            }
+            // Assignments and streaks only change on Habitica
+            (Request::Update(_), Reply::Written(h_task, stats, drop_msg)) => {
+                self.updated.habitica_assigned = h_task.assigned();
+                if let Some(streak) = h_task.streak {
+                    converter::set_streak(&mut self.updated, streak);
+                }
+                (stats, drop_msg)
+            }
+            (Request::Score(direction), Reply::Scored(stats, drop_msg)) => {
+                let task = &mut self.updated;
+                let purchase_msg = stats
+                    .as_ref()
+                    .filter(|_| task.task_type() == TaskType::Reward)
+                    .map(|stats| purchase_message(&task.description, task.cost(), stats.gp));
+                let streak = scored_streak(task, *direction);
+                if let Some(streak) = streak {
+                    converter::set_streak(task, streak);
+                }
+                let streak_msg = streak
+                    .filter(|_| matches!(direction, ScoreDirection::Up))
+                    .and_then(|streak| streak_message(&task.description, streak));
+                if let Some(cache) = stats_cache {
+                    cache.update(None, purchase_msg);
+                    cache.update(None, streak_msg);
+                }
+                (stats, drop_msg)
+            }
+            (_, Reply::Written(_, stats, drop_msg) | Reply::Scored(stats, drop_msg)) => {
+                (stats, drop_msg)
+            }
+            (_, Reply::Done) => (None, None),
+        };
+        if let Some(cache) = stats_cache {
+            cache.update(stats, drop_msg);
+        }
+    }
+}
+
+/// Habit created for a habit tag that has none on Habitica yet
+pub fn habit_for_tag(tag: &str) -> HabiticaTask {
+    HabiticaTask {
+        text: tag.to_string(),
+        task_type: HabiticaTaskType::Habit,
+        priority: 1.0,
+        ..Default::default()
+    }
+}
+
+/// Refuse to uncheck a daily with streak protection on, unless the loss is
+/// allowed or `confirm` agrees
+pub fn check_streak_loss(
+    config: &Config,
+    allow_streak_loss: bool,
+    daily: &Task,
+    confirm: impl FnOnce() -> bool,
+) -> Result<()> {
+    if !config.streak_protection || allow_streak_loss || confirm() {
+        return Ok(());
+    }
+    Err(Error::custom(format!(
+        "Kept the streak of \"{}\", use sync --force or confirm to uncheck it",
+        daily.description
+    )))
+}
+
+/// Writes creating a Taskwarrior task on Habitica, or updating it when
+/// linked, and scoring it if already completed
+pub fn push(
+    config: &Config,
+    backend: &dyn TaskBackend,
+    notes_manager: &NotesManager,
+    tw_task: &Task,
+) -> Result<Writes> {
+    let mut writes = Writes::none(tw_task);
+    if is_excluded(config, tw_task) {
+        return Ok(writes);
+    }
+
+    // Linked all the same, just without retroactive XP
+    let unscored = links_unscored(config, tw_task);
+    let note_content = habitica_notes(config, notes_manager, tw_task)?;
+    let Some(h_task) = to_habitica(config, backend, tw_task, note_content.as_deref())? else {
+        // Task should not be synced to Habitica
+        return Ok(writes);
+    };
+
+    if h_task.task_type == HabiticaTaskType::Daily {
+        if let Err(err) = recurrence::schedule(tw_task) {
+            writes
+                .notices
+                .push(format!("{}, keeping the daily's Habitica schedule", err));
+        }
+    }
+
+    let group_id = tw_task
+        .project()
+        .and_then(|project| config.group_for_project(project));
+    writes.requests.push(match h_task.id {
+        Some(_) => Request::Update(h_task.clone()),
+        None => Request::Create {
+            h_task: h_task.clone(),
+            group_id,
+        },
+    });
+
+    if unscored {
+        writes.notices.push(format!(
+            "Linked \"{}\" without scoring, it was completed long before linking",
+            tw_task.description
+        ));
+    } else if tw_task.status.is_completed() {
+        writes.requests.push(Request::Score(ScoreDirection::Up));
+        if loses_completion_date(&h_task) {
+            writes.requests.push(Request::RestoreCompletion(h_task));
+        }
+        score_habit_tags(config, tw_task, &mut writes);
+    }
+    Ok(writes)
+}
+
+/// Writes deleting the Habitica task linked to `old_tw` and unlinking
+/// `new_tw`
+///
+/// Challenge tasks can't be deleted and stay on Habitica.
+pub fn remove(old_tw: &Task, new_tw: &Task) -> Writes {
+    let mut writes = Writes::none(new_tw);
+    writes.updated.habitica_uuid = None;
+    let challenge = old_tw.challenge().or_else(|| new_tw.challenge());
+    match (old_tw.habitica_uuid, challenge) {
+        (Some(_), Some(challenge)) => writes.notices.push(format!(
+            "\"{}\" belongs to the challenge {} and stays on Habitica until you leave it",
+            new_tw.description, challenge
+        )),
+        (Some(h_id), None) => writes.requests.push(Request::Delete(h_id)),
+        (None, _) => {}
+    }
+    writes
+}
+
+/// Writes bringing Habitica up to date with a Taskwarrior task that went
+/// from `old_tw` to `new_tw`, scoring status changes
+pub fn modify(
+    config: &Config,
+    backend: &dyn TaskBackend,
+    notes_manager: &NotesManager,
+    old_tw: &Task,
+    new_tw: &Task,
+) -> Result<Writes> {
+    // Adding the exclusion tag to a linked task deletes it below
+    let keeps_local = config.keeps_local(new_tw);
+    if is_excluded(config, new_tw) && !keeps_local {
+        return Ok(Writes::none(new_tw));
+    }
+
+    let challenge = old_tw.challenge().or_else(|| new_tw.challenge());
+
+    // Check if task should be deleted from Habitica
+    let dropped = !new_tw.status.should_sync_to_habitica() || keeps_local;
+    if dropped && old_tw.habitica_uuid.is_some() {
+        return Ok(remove(old_tw, new_tw));
+    }
+    if keeps_local {
+        return Ok(Writes::none(new_tw));
+    }
+
+    // Check if task should be created on Habitica, also when it loses the
+    // exclusion tag
+    let held_back = !old_tw.status.should_sync_to_habitica() || config.keeps_local(old_tw);
+    if new_tw.status.should_sync_to_habitica() && held_back {
+        return push(config, backend, notes_manager, new_tw);
+    }
+
+    let mut writes = Writes::none(new_tw);
+    let note_content = habitica_notes(config, notes_manager, new_tw)?;
+    let Some(new_h) = to_habitica(config, backend, new_tw, note_content.as_deref())? else {
+        return Ok(writes);
+    };
+
+    // Update details if changed, which challenge tasks don't allow
+    if let Some(challenge) = challenge {
+        if details_changed(config, old_tw, &new_h, note_content.as_deref())? {
+            writes.notices.push(format!(
+                "\"{}\" belongs to the challenge {}, edits stay in Taskwarrior",
+                new_tw.description, challenge
+            ));
+        }
+    } else if new_h.id.is_some() {
+        writes.requests.push(Request::Update(new_h.clone()));
+    }
+
+    // Status changes are scored, completing a reward buys it
+    if let (Some(direction), Some(_)) = (score_direction(config, old_tw, new_tw), writes.h_id) {
+        if matches!(direction, ScoreDirection::Down) && new_tw.task_type() == TaskType::Daily {
+            writes.unchecks_daily = true;
+            writes.notices.push(format!(
+                "Unchecking the daily \"{}\" resets its streak on Habitica",
+                new_tw.description
+            ));
+        }
+        writes.requests.push(Request::Score(direction));
+        if matches!(direction, ScoreDirection::Up) {
+            score_habit_tags(config, new_tw, &mut writes);
+        }
+    }
+
+    let completed = !old_tw.status.is_completed() && new_tw.status.is_completed();
+    if completed && new_h.id.is_some() && challenge.is_none() && loses_completion_date(&new_h) {
+        writes.requests.push(Request::RestoreCompletion(new_h));
+    }
+    Ok(writes)
+}
+
+/// Score the habit named after each habit tag of a completed task
+fn score_habit_tags(config: &Config, task: &Task, writes: &mut Writes) {
+    writes.requests.extend(
+        habits::habit_tags_of(task, &config.habit_tags)
+            .into_iter()
+            .map(|tag| Request::ScoreHabit(tag.to_string())),
+    );
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+
+    use super::*;
+    use crate::taskwarrior::{TaskStatus, TaskwarriorClient};
+
+    fn daily(streak: u32) -> Task {
+        let mut task = Task {
+            description: "Stretch".to_string(),
+            status: TaskStatus::Completed,
+            habitica_uuid: Some(Uuid::new_v4()),
+            habitica_task_type: Some(TaskType::Daily),
+            ..Default::default()
+        };
+        converter::set_streak(&mut task, streak);
+        task
+    }
+
+    #[test]
+    fn test_push_scores_completed_tasks_and_habit_tags() {
+        let mut config = Config::for_tests();
+        config.habit_tags = vec!["reading".to_string()];
+        let backend = TaskwarriorClient::new();
+        let notes_manager = NotesManager::new(&config);
+        let mut task = Task {
+            description: "Finish the book".to_string(),
+            status: TaskStatus::Completed,
+            ..Default::default()
+        };
+        task.extra.insert("tags".to_string(), json!(["reading"]));
+
+        let writes = push(&config, &backend, &notes_manager, &task).unwrap();
+        assert!(matches!(
+            writes.requests[..],
+            [
+                Request::Create { .. },
+                Request::Score(ScoreDirection::Up),
+                Request::ScoreHabit(ref tag),
+            ] if tag == "reading"
+        ));
+
+        // Completed long before linking, it's created without scoring
+        config.link_score_max_age = Some(Duration::days(1));
+        let notes_manager = NotesManager::new(&config);
+        task.extra.insert(
+            "end".to_string(),
+            json!(crate::taskwarrior::date_format::format(
+                &(Utc::now() - Duration::days(3))
+            )),
+        );
+        let writes = push(&config, &backend, &notes_manager, &task).unwrap();
+        assert!(matches!(writes.requests[..], [Request::Create { .. }]));
+        assert_eq!(writes.notices.len(), 1);
+    }
+
+    #[test]
+    fn test_modify_unchecking_a_daily() {
+        let config = Config::for_tests();
+        let backend = TaskwarriorClient::new();
+        let notes_manager = NotesManager::new(&config);
+        let old = daily(5);
+        let new = Task {
+            status: TaskStatus::Pending,
+            ..old.clone()
+        };
+
+        let mut writes = modify(&config, &backend, &notes_manager, &old, &new).unwrap();
+        assert!(writes.unchecks_daily);
+        let requests = writes.take_requests();
+        assert!(matches!(
+            requests[..],
+            [Request::Update(_), Request::Score(ScoreDirection::Down)]
+        ));
+
+        let returned = HabiticaTask {
+            streak: Some(5),
+            ..Default::default()
+        };
+        writes.record(
+            &requests[0],
+            Reply::Written(Box::new(returned), None, None),
+            &mut None,
+        );
+        writes.record(&requests[1], Reply::Scored(None, None), &mut None);
+        assert_eq!(writes.updated.streak(), Some(4));
+    }
+
+    #[test]
+    fn test_remove_deletes_and_unlinks() {
+        let task = daily(0);
+        let writes = remove(&task, &task);
+        assert_eq!(writes.updated.habitica_uuid, None);
+        assert_eq!(
+            writes.requests,
+            [Request::Delete(task.habitica_uuid.unwrap())]
+        );
+    }
+
+    #[test]
+    fn test_check_streak_loss() {
+        let mut config = Config::for_tests();
+        let task = daily(5);
+        assert!(check_streak_loss(&config, false, &task, || false).is_ok());
+
+        config.streak_protection = true;
+        assert!(check_streak_loss(&config, false, &task, || false).is_err());
+        assert!(check_streak_loss(&config, false, &task, || true).is_ok());
+        assert!(check_streak_loss(&config, true, &task, || false).is_ok());
+    }
+}
@@ -0,0 +1,258 @@
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    taskwarrior::{
+        client::TaskwarriorClient,
+        task::{Task, TaskStatus},
+    },
+};
+
+/// Taskwarrior side of a sync, split from a single export
+#[derive(Debug, Clone, Default)]
+pub struct TaskSnapshot {
+    /// Pending tasks without a Habitica link
+    pub unlinked: Vec<Task>,
+    /// Tasks linked to Habitica, whatever their status
+    pub linked: Vec<Task>,
+}
+
+impl TaskSnapshot {
+    /// Split a full export into the task lists the sync works with
+    pub fn partition(tasks: Vec<Task>) -> Self {
+        let (linked, unlinked): (Vec<Task>, Vec<Task>) = tasks
+            .into_iter()
+            .partition(|task| task.habitica_uuid.is_some());
+        TaskSnapshot {
+            unlinked: unlinked
+                .into_iter()
+                .filter(|task| task.status == TaskStatus::Pending)
+                .collect(),
+            linked,
+        }
+    }
+}
+
+/// Storage backend holding the Taskwarrior side of the sync
+///
+/// The sync logic only talks to Taskwarrior through this trait, so the
+/// `task` subprocess client can be swapped for a direct database reader or
+/// an in-memory double in tests. Implementations are shared with the sync
+/// engine's workers.
+pub trait TaskBackend: Sync {
+    /// Export tasks matching the given Taskwarrior filters
+    fn export(&self, filters: &[&str]) -> Result<Vec<Task>>;
+
+    /// Import a task, creating or updating it by UUID
+    fn import(&self, task: &Task) -> Result<String>;
+
+    /// Import several tasks, in order, creating or updating them by UUID
+    ///
+    /// Imports them one by one unless the backend can do better.
+    fn import_many(&self, tasks: &[Task]) -> Result<()> {
+        for task in tasks {
+            self.import(task)?;
+        }
+        Ok(())
+    }
+
+    /// Get a configuration value
+    fn get_config(&self, key: &str) -> Result<String>;
+
+    /// Get a single task by its numeric ID or UUID
+    fn get_task(&self, id: &str) -> Result<Task> {
+        let mut tasks = self.export(&[id])?;
+        if tasks.len() != 1 {
+            return Err(Error::TaskNotFound(id.to_string()));
+        }
+        Ok(tasks.remove(0))
+    }
+
+    /// Export all tasks at once and split them for the sync
+    ///
+    /// A single export sees one state of the database, where separate
+    /// exports could each catch a different side of an edit made meanwhile.
+    fn snapshot(&self) -> Result<TaskSnapshot> {
+        Ok(TaskSnapshot::partition(self.export(&[])?))
+    }
+
+    /// Get all tasks that have Habitica UUIDs
+    fn get_tasks_with_habitica(&self) -> Result<Vec<Task>> {
+        self.export(&["habitica_uuid.any:"])
+    }
+}
+
+/// Backend for a sync, per `habitica.taskchampion`
+///
+/// Reading the database directly needs the `taskchampion` feature.
+pub fn sync_backend(config: &Config) -> Result<Box<dyn TaskBackend>> {
+    if !config.read_taskchampion {
+        return Ok(Box::new(TaskwarriorClient::new()));
+    }
+    #[cfg(feature = "taskchampion")]
+    {
+        Ok(Box::new(super::TaskChampionBackend::new(
+            config.taskchampion_path(),
+        )))
+    }
+    #[cfg(not(feature = "taskchampion"))]
+    {
+        Err(Error::config(
+            "habitica.taskchampion needs task2habitica built with the taskchampion feature",
+        ))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::sync::Mutex;
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    /// In-memory backend that ignores filters
+    #[derive(Default)]
+    struct MemoryBackend {
+        tasks: Mutex<Vec<Task>>,
+    }
+
+    impl TaskBackend for MemoryBackend {
+        fn export(&self, _filters: &[&str]) -> Result<Vec<Task>> {
+            Ok(self.tasks.lock().unwrap().clone())
+        }
+
+        fn import(&self, task: &Task) -> Result<String> {
+            let mut tasks = self.tasks.lock().unwrap();
+            tasks.retain(|t| t.uuid != task.uuid);
+            tasks.push(task.clone());
+            Ok(String::new())
+        }
+
+        fn get_config(&self, _key: &str) -> Result<String> {
+            Ok(String::new())
+        }
+    }
+
+    fn test_task() -> Task {
+        Task {
+            uuid: Uuid::new_v4(),
+            description: "Test task".to_string(),
+            status: TaskStatus::Pending,
+            modified: None,
+            due: None,
+            annotations: None,
+            habitica_uuid: None,
+            habitica_difficulty: None,
+            habitica_task_type: None,
+            habitica_cost: None,
+            habitica_assigned: None,
+            habitica_attribute: None,
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    #[test]
+    fn test_import_many_keeps_the_last_version() {
+        let backend = MemoryBackend::default();
+        let task = test_task();
+        let mut renamed = task.clone();
+        renamed.description = "Renamed".to_string();
+        backend.import_many(&[task, renamed]).unwrap();
+
+        let tasks = backend.export(&[]).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].description, "Renamed");
+    }
+
+    #[test]
+    fn test_snapshot_partitions_export() {
+        let backend = MemoryBackend::default();
+        let unlinked = test_task();
+        let mut linked = test_task();
+        linked.habitica_uuid = Some(Uuid::new_v4());
+        linked.status = TaskStatus::Completed;
+        let mut done = test_task();
+        done.status = TaskStatus::Completed;
+        for task in [&unlinked, &linked, &done] {
+            backend.import(task).unwrap();
+        }
+
+        let snapshot = backend.snapshot().unwrap();
+        assert_eq!(snapshot.unlinked.len(), 1);
+        assert_eq!(snapshot.unlinked[0].uuid, unlinked.uuid);
+        assert_eq!(snapshot.linked.len(), 1);
+        assert_eq!(snapshot.linked[0].uuid, linked.uuid);
+    }
+
+    /// Write a stand-in for `task` to `dir`
+    ///
+    /// It logs its arguments to `calls`, keeps what it is fed by `import`
+    /// in `imported`, answers `_get` with the key and `export` with the
+    /// contents of `export.json`.
+    #[cfg(unix)]
+    fn stub_task(dir: &std::path::Path) -> std::path::PathBuf {
+        use std::{fs, os::unix::fs::PermissionsExt};
+
+        let program = dir.join("task");
+        fs::write(
+            &program,
+            "#!/bin/sh\n\
+             dir=$(dirname \"$0\")\n\
+             echo \"$*\" >> \"$dir/calls\"\n\
+             case \"$2\" in\n\
+             import) cat > \"$dir/imported\" ;;\n\
+             _get) echo \" $3 \" ;;\n\
+             *) cat \"$dir/export.json\" ;;\n\
+             esac\n",
+        )
+        .unwrap();
+        fs::set_permissions(&program, fs::Permissions::from_mode(0o755)).unwrap();
+        program
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_taskwarrior_client_as_backend() {
+        use std::fs;
+
+        use crate::taskwarrior::TaskwarriorClient;
+
+        let dir = tempfile::tempdir().unwrap();
+        let client = TaskwarriorClient::with_program(&stub_task(dir.path()));
+        let backend: &dyn TaskBackend = &client;
+        let unlinked = test_task();
+        let mut linked = test_task();
+        linked.habitica_uuid = Some(Uuid::new_v4());
+        fs::write(
+            dir.path().join("export.json"),
+            serde_json::to_string(&[&unlinked, &linked]).unwrap(),
+        )
+        .unwrap();
+
+        let snapshot = backend.snapshot().unwrap();
+        assert_eq!(snapshot.unlinked[0].uuid, unlinked.uuid);
+        assert_eq!(snapshot.linked[0].uuid, linked.uuid);
+        assert_eq!(
+            backend.get_config("rc.data.location").unwrap(),
+            "rc.data.location"
+        );
+
+        // One import for all tasks, with the hooks off
+        backend.import_many(&[unlinked, linked]).unwrap();
+        let imported: Vec<Task> =
+            serde_json::from_str(&fs::read_to_string(dir.path().join("imported")).unwrap())
+                .unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("calls")).unwrap(),
+            "rc.hooks=off export\nrc.hooks=off _get rc.data.location\nrc.hooks=off import -\n"
+        );
+
+        fs::write(dir.path().join("export.json"), "not json").unwrap();
+        assert!(matches!(
+            backend.export(&[]),
+            Err(Error::TaskwarriorParseFailed(_))
+        ));
+    }
+}
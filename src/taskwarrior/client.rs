@@ -1,54 +1,66 @@
-use std::process::Command;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use tracing::instrument;
 
 use crate::{
     error::{Error, Result},
-    taskwarrior::task::Task,
+    taskwarrior::{backend::TaskBackend, task::Task},
 };
 
 /// Client for interacting with Taskwarrior
-pub struct TaskwarriorClient;
+pub struct TaskwarriorClient {
+    /// Executable run instead of `task` from the `PATH`
+    program: Option<PathBuf>,
+}
 
 impl TaskwarriorClient {
     pub const fn new() -> Self {
-        TaskwarriorClient
+        TaskwarriorClient { program: None }
     }
 
-    /// Export tasks matching the given filters
-    pub fn export(&self, filters: &[&str]) -> Result<Vec<Task>> {
-        let mut args = vec!["rc.hooks=off"];
-        args.extend(filters);
-        args.push("export");
+    /// Create a client running `program` instead of `task`, for tests
+    pub fn with_program(program: &Path) -> Self {
+        TaskwarriorClient {
+            program: Some(program.to_path_buf()),
+        }
+    }
 
-        let output = Command::new("task").args(&args).output().map_err(|e| {
-            Error::TaskwarriorCommandFailed(format!("Failed to execute task export: {}", e))
-        })?;
+    /// Command running Taskwarrior
+    fn command(&self) -> Command {
+        Command::new(self.program.as_deref().unwrap_or_else(|| Path::new("task")))
+    }
+
+    /// Write a configuration value to the taskrc
+    #[instrument(level = "debug", skip(self, value), err)]
+    pub fn set_config(&self, key: &str, value: &str) -> Result<()> {
+        let output = self
+            .command()
+            .args(["rc.hooks=off", "rc.confirmation=off", "config", key, value])
+            .output()
+            .map_err(|e| {
+                Error::TaskwarriorCommandFailed(format!("Failed to execute task config: {}", e))
+            })?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
             return Err(Error::TaskwarriorCommandFailed(format!(
-                "task export failed: {}",
-                stderr
+                "task config {} failed: {}",
+                key, stderr
             )));
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-
-        // Handle empty output
-        if stdout.trim().is_empty() || stdout.trim() == "[]" {
-            return Ok(Vec::new());
-        }
-
-        serde_json::from_str(&stdout).map_err(|e| {
-            Error::TaskwarriorParseFailed(format!("Failed to parse task export JSON: {}", e))
-        })
+        Ok(())
     }
 
-    /// Import a task into Taskwarrior
-    pub fn import(&self, task: &Task) -> Result<String> {
-        let task_json = serde_json::to_string(task)?;
-
-        let output = Command::new("task")
-            .args(["import", "-"])
+    /// Feed a task or an array of tasks to `task import`
+    fn run_import(&self, json: &str) -> Result<String> {
+        // Our own writes must not run the hooks, which would push them back
+        let output = self
+            .command()
+            .args(["rc.hooks=off", "import", "-"])
             .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
@@ -56,7 +68,7 @@ impl TaskwarriorClient {
             .and_then(|mut child| {
                 use std::io::Write;
                 if let Some(mut stdin) = child.stdin.take() {
-                    stdin.write_all(task_json.as_bytes())?;
+                    stdin.write_all(json.as_bytes())?;
                 }
                 child.wait_with_output()
             })
@@ -74,10 +86,61 @@ impl TaskwarriorClient {
 
         Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
     }
+}
+
+impl TaskBackend for TaskwarriorClient {
+    /// Export tasks matching the given filters
+    #[instrument(level = "debug", skip(self), err)]
+    fn export(&self, filters: &[&str]) -> Result<Vec<Task>> {
+        let mut args = vec!["rc.hooks=off"];
+        args.extend(filters);
+        args.push("export");
+
+        let output = self.command().args(&args).output().map_err(|e| {
+            Error::TaskwarriorCommandFailed(format!("Failed to execute task export: {}", e))
+        })?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(Error::TaskwarriorCommandFailed(format!(
+                "task export failed: {}",
+                stderr
+            )));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        // Handle empty output
+        if stdout.trim().is_empty() || stdout.trim() == "[]" {
+            return Ok(Vec::new());
+        }
+
+        serde_json::from_str(&stdout).map_err(|e| {
+            Error::TaskwarriorParseFailed(format!("Failed to parse task export JSON: {}", e))
+        })
+    }
+
+    /// Import a task into Taskwarrior, with hooks off
+    #[instrument(level = "debug", skip_all, fields(uuid = %task.uuid), err)]
+    fn import(&self, task: &Task) -> Result<String> {
+        self.run_import(&serde_json::to_string(task)?)
+    }
+
+    /// Import several tasks with a single `task import`
+    #[instrument(level = "debug", skip_all, fields(count = tasks.len()), err)]
+    fn import_many(&self, tasks: &[Task]) -> Result<()> {
+        if tasks.is_empty() {
+            return Ok(());
+        }
+        self.run_import(&serde_json::to_string(tasks)?)?;
+        Ok(())
+    }
 
     /// Get a configuration value from Taskwarrior
-    pub fn get_config(&self, key: &str) -> Result<String> {
-        let output = Command::new("task")
+    #[instrument(level = "debug", skip(self), err)]
+    fn get_config(&self, key: &str) -> Result<String> {
+        let output = self
+            .command()
             .args(["rc.hooks=off", "_get", key])
             .output()
             .map_err(|e| {
@@ -93,16 +156,6 @@ impl TaskwarriorClient {
 
         Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
     }
-
-    /// Get all pending tasks without Habitica UUIDs
-    pub fn get_pending_without_habitica(&self) -> Result<Vec<Task>> {
-        self.export(&["status:pending", "habitica_uuid.none:"])
-    }
-
-    /// Get all tasks that have Habitica UUIDs
-    pub fn get_tasks_with_habitica(&self) -> Result<Vec<Task>> {
-        self.export(&["habitica_uuid.any:"])
-    }
 }
 
 impl Default for TaskwarriorClient {
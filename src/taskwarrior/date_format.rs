@@ -1,9 +1,38 @@
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use serde::{Deserialize, Deserializer};
 
 /// Taskwarrior date format: YYYYMMDDTHHMMSSZ
 const TW_DATE_FORMAT: &str = "%Y%m%dT%H%M%S";
 
+/// Parse a Taskwarrior date string
+pub fn parse(s: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(s.trim_end_matches('Z'), TW_DATE_FORMAT)
+        .ok()
+        .map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc))
+}
+
+/// Parse a user-supplied date: Taskwarrior format, RFC 3339, or a local
+/// `YYYY-MM-DD` date
+pub fn parse_input(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    if let Some(date) = parse(s) {
+        return Some(date);
+    }
+    if let Ok(date) = DateTime::parse_from_rfc3339(s) {
+        return Some(date.with_timezone(&Utc));
+    }
+    let day = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+    Local
+        .from_local_datetime(&day.and_hms_opt(0, 0, 0)?)
+        .earliest()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Format a date the way Taskwarrior exports it
+pub fn format(date: &DateTime<Utc>) -> String {
+    date.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
 /// Deserialize a Taskwarrior date string
 pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
 where
@@ -18,6 +47,9 @@ where
 }
 
 /// Deserialize an optional Taskwarrior date string
+///
+/// RFC 3339 dates, as written when a [`Task`](super::Task) is serialized,
+/// are accepted as well.
 pub fn deserialize_opt<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
 where
     D: Deserializer<'de>,
@@ -25,6 +57,9 @@ where
     let opt: Option<String> = Option::deserialize(deserializer)?;
     match opt {
         Some(s) => {
+            if let Ok(date) = DateTime::parse_from_rfc3339(&s) {
+                return Ok(Some(date.with_timezone(&Utc)));
+            }
             let s = s.trim_end_matches('Z');
             NaiveDateTime::parse_from_str(s, TW_DATE_FORMAT)
                 .map(|dt| Some(DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc)))
@@ -46,6 +81,21 @@ mod tests {
         date: DateTime<Utc>,
     }
 
+    #[test]
+    fn test_parse_and_format_roundtrip() {
+        let date = parse("20260118T184624Z").expect("Failed to parse");
+        assert_eq!(format(&date), "20260118T184624Z");
+        assert!(parse("yesterday").is_none());
+    }
+
+    #[test]
+    fn test_parse_input() {
+        assert!(parse_input("20260118T184624Z").is_some());
+        assert!(parse_input("2026-01-18T18:46:24+02:00").is_some());
+        assert!(parse_input("2026-01-18").is_some());
+        assert!(parse_input("next week").is_none());
+    }
+
     #[test]
     fn test_taskwarrior_date_format() {
         let json = r#"{"date":"20260118T184624Z"}"#;
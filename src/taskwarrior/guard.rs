@@ -0,0 +1,160 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::error::Result;
+
+/// Number of the next guard this process acquires
+static NEXT_GUARD: AtomicU64 = AtomicU64::new(0);
+
+/// Marker telling the hooks that task2habitica is writing to Taskwarrior
+///
+/// Imports already run with `rc.hooks=off`, but a `task` process started
+/// elsewhere while a sync runs would still fire the hooks and push changes
+/// the sync is about to make itself. The marker is a directory holding one
+/// file per guard, named after the owner's PID, so a guard only ever removes
+/// its own file and the hooks stay paused until the last holder is done.
+/// Files left behind by crashed processes are ignored and cleaned up.
+pub struct HookGuard {
+    marker: PathBuf,
+}
+
+impl HookGuard {
+    /// Add this guard's file to the marker
+    pub fn acquire(path: &Path) -> Result<Self> {
+        // Earlier versions kept a single file
+        if path.is_file() {
+            fs::remove_file(path)?;
+        }
+        fs::create_dir_all(path)?;
+        for (pid, stale) in holders(path) {
+            if pid != process::id() && !is_alive(pid) {
+                let _ = fs::remove_file(stale);
+            }
+        }
+
+        let marker = path.join(format!(
+            "{}.{}",
+            process::id(),
+            NEXT_GUARD.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&marker, "")?;
+        Ok(HookGuard { marker })
+    }
+
+    /// Check whether a live process holds a guard
+    pub fn is_held(path: &Path) -> bool {
+        holders(path)
+            .into_iter()
+            .any(|(pid, _)| pid == process::id() || is_alive(pid))
+    }
+}
+
+impl Drop for HookGuard {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.marker);
+    }
+}
+
+/// Guard files in the marker with the PID holding each
+fn holders(path: &Path) -> Vec<(u32, PathBuf)> {
+    let Ok(entries) = fs::read_dir(path) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let pid = path
+                .file_name()?
+                .to_str()?
+                .split('.')
+                .next()?
+                .parse()
+                .ok()?;
+            Some((pid, path))
+        })
+        .collect()
+}
+
+/// Check whether a process is still running
+#[cfg(unix)]
+fn is_alive(pid: u32) -> bool {
+    process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Check whether a process is still running
+///
+/// Without a way to ask, a marker always counts as held.
+#[cfg(not(unix))]
+const fn is_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guard_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("habitica_hooks.lock");
+        assert!(!HookGuard::is_held(&path));
+
+        let guard = HookGuard::acquire(&path).unwrap();
+        assert!(HookGuard::is_held(&path));
+
+        drop(guard);
+        assert!(!HookGuard::is_held(&path));
+    }
+
+    #[test]
+    fn test_guards_release_only_their_own_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("habitica_hooks.lock");
+
+        let first = HookGuard::acquire(&path).unwrap();
+        let second = HookGuard::acquire(&path).unwrap();
+        drop(first);
+        assert!(HookGuard::is_held(&path));
+
+        drop(second);
+        assert!(!HookGuard::is_held(&path));
+    }
+
+    #[test]
+    fn test_replaces_marker_file_of_earlier_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("habitica_hooks.lock");
+        fs::write(&path, "4294967").unwrap();
+
+        let _guard = HookGuard::acquire(&path).unwrap();
+        assert!(path.is_dir());
+        assert!(HookGuard::is_held(&path));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_stale_marker_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("habitica_hooks.lock");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("not-a-pid"), "").unwrap();
+        assert!(!HookGuard::is_held(&path));
+
+        // PIDs never go this high on Linux or macOS
+        fs::write(path.join("4294967.0"), "").unwrap();
+        assert!(!HookGuard::is_held(&path));
+
+        // Acquiring cleans it up
+        drop(HookGuard::acquire(&path).unwrap());
+        assert!(!path.join("4294967.0").exists());
+    }
+}
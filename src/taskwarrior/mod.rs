@@ -1,8 +1,16 @@
+pub mod backend;
 pub mod client;
 pub mod date_format;
+pub mod guard;
 pub mod notes;
 pub mod task;
+#[cfg(feature = "taskchampion")]
+pub mod taskchampion;
 
+pub use backend::{TaskBackend, TaskSnapshot};
 pub use client::TaskwarriorClient;
+pub use guard::HookGuard;
 pub use notes::NotesManager;
-pub use task::{Annotation, Task, TaskDifficulty, TaskStatus, TaskType};
+pub use task::{Annotation, Task, TaskAttribute, TaskDifficulty, TaskStatus, TaskType};
+#[cfg(feature = "taskchampion")]
+pub use taskchampion::TaskChampionBackend;
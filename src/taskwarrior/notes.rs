@@ -5,7 +5,10 @@ use chrono::Utc;
 use crate::{
     config::Config,
     error::Result,
-    taskwarrior::task::{Annotation, Task},
+    taskwarrior::{
+        date_format,
+        task::{Annotation, Task},
+    },
 };
 
 /// Manages task notes stored as separate files
@@ -72,6 +75,12 @@ impl<'a> NotesManager<'a> {
         }
     }
 
+    /// Annotation previewing a note, `None` for an empty note
+    pub fn preview(&self, note_content: &str) -> Option<String> {
+        let first_line = note_content.lines().next().unwrap_or("").trim();
+        (!first_line.is_empty()).then(|| format!("{} {}", self.config.task_note_prefix, first_line))
+    }
+
     /// Update task annotations based on note content
     /// Returns a new task with updated annotations
     pub fn sync_note_to_annotation(
@@ -82,21 +91,20 @@ impl<'a> NotesManager<'a> {
         // Remove existing note annotations
         let mut annotations = task.filter_note_annotations(&self.config.task_note_prefix);
 
-        match note_content {
-            Some(content) if !content.trim().is_empty() => {
-                // Add new note annotation with first line as preview
-                let first_line = content.lines().next().unwrap_or("").trim();
-                if !first_line.is_empty() {
-                    let note_annotation = Annotation {
-                        entry: Utc::now().format("%Y%m%dT%H%M%SZ").to_string(),
-                        description: format!("{} {}", self.config.task_note_prefix, first_line),
-                    };
-                    annotations.insert(0, note_annotation);
-                }
-            }
-            _ => {
-                // No note or empty note - just keep non-note annotations
-            }
+        // Add new note annotation with first line as preview
+        if let Some(description) = note_content.and_then(|c| self.preview(&c)) {
+            // An unchanged preview keeps its timestamp, so repeated syncs
+            // don't rewrite the annotation
+            let entry = task
+                .annotations
+                .iter()
+                .flatten()
+                .find(|anno| anno.description == description)
+                .map_or_else(
+                    || date_format::format(&Utc::now()),
+                    |anno| anno.entry.clone(),
+                );
+            annotations.insert(0, Annotation { entry, description });
         }
 
         task.annotations = if annotations.is_empty() {
@@ -116,8 +124,11 @@ impl<'a> NotesManager<'a> {
             self.delete_note(task)?;
             self.sync_note_to_annotation(task, None)?;
         } else {
-            // Write note file
-            self.write_note(task, note_content)?;
+            // Write note file, leaving it untouched if unchanged so it doesn't
+            // look recently edited to the modify hook
+            if self.read_note(task)?.as_deref() != Some(note_content) {
+                self.write_note(task, note_content)?;
+            }
             self.sync_note_to_annotation(task, Some(note_content.to_string()))?;
         }
         Ok(())
@@ -130,17 +141,10 @@ mod tests {
     use uuid::Uuid;
 
     use super::*;
+    use crate::habitica::DayStart;
 
     fn test_config() -> Config {
-        Config {
-            habitica_user_id: String::new(),
-            habitica_api_key: String::new(),
-            task_note_dir: std::env::temp_dir().join("test_notes"),
-            task_note_prefix: "[tasknote]".to_string(),
-            task_note_extension: ".txt".to_string(),
-            data_location: std::env::temp_dir(),
-            verbose: false,
-        }
+        Config::for_tests()
     }
 
     fn test_task() -> Task {
@@ -154,6 +158,9 @@ mod tests {
             habitica_uuid: None,
             habitica_difficulty: None,
             habitica_task_type: None,
+            habitica_cost: None,
+            habitica_assigned: None,
+            habitica_attribute: None,
             extra: serde_json::Map::new(),
         }
     }
@@ -184,4 +191,72 @@ mod tests {
         // Cleanup
         manager.delete_note(&task).unwrap();
     }
+
+    #[test]
+    fn test_repeated_import_keeps_annotations() {
+        let config = test_config();
+        let manager = NotesManager::new(&config);
+        let mut task = test_task();
+        task.annotations = Some(vec![Annotation {
+            entry: "20250101T120000Z".to_string(),
+            description: "Called the bank".to_string(),
+        }]);
+
+        manager
+            .import_note_from_habitica(&mut task, "Bring passport\nand forms")
+            .unwrap();
+        let first = task.annotations.clone().unwrap();
+        assert_eq!(first.len(), 2);
+        assert!(first[0].description.ends_with("Bring passport"));
+
+        // Pulling the same note again changes nothing
+        manager
+            .import_note_from_habitica(&mut task, "Bring passport\nand forms")
+            .unwrap();
+        assert_eq!(task.annotations.as_ref(), Some(&first));
+
+        // Duplicate previews collapse into one, keeping its timestamp
+        let mut annotations = first.clone();
+        annotations.push(first[0].clone());
+        task.annotations = Some(annotations);
+        manager
+            .import_note_from_habitica(&mut task, "Bring passport\nand forms")
+            .unwrap();
+        assert_eq!(task.annotations.as_ref(), Some(&first));
+
+        manager.import_note_from_habitica(&mut task, "").unwrap();
+        assert_eq!(task.annotations.unwrap(), first[1..].to_vec());
+    }
+
+    #[test]
+    fn test_pull_round_trip_keeps_annotations() {
+        use crate::{habitica::HabiticaTask, sync::converter};
+
+        let config = test_config();
+        let manager = NotesManager::new(&config);
+        let h_task = HabiticaTask {
+            id: Some(Uuid::new_v4()),
+            text: "Renew passport".to_string(),
+            notes: "Photos first".to_string(),
+            ..Default::default()
+        };
+
+        let mut first =
+            converter::habitica_to_taskwarrior(&config, &h_task, None, DayStart::default())
+                .unwrap();
+        manager
+            .import_note_from_habitica(&mut first, &h_task.notes)
+            .unwrap();
+
+        let mut second =
+            converter::habitica_to_taskwarrior(&config, &h_task, Some(&first), DayStart::default())
+                .unwrap();
+        manager
+            .import_note_from_habitica(&mut second, &h_task.notes)
+            .unwrap();
+        assert_eq!(second.annotations, first.annotations);
+        assert_eq!(second.annotations.unwrap().len(), 1);
+
+        manager.delete_note(&first).unwrap();
+    }
 }
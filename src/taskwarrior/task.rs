@@ -1,12 +1,49 @@
+use std::str::FromStr;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
 
+use crate::error::{Error, Result};
+
+/// Gold price of a reward without a `habitica_cost`, matching Habitica's
+/// default
+pub const DEFAULT_REWARD_COST: f64 = 10.0;
+
+/// UDAs counting the positive and negative scores of a habit
+pub const HABIT_COUNTER_UDAS: (&str, &str) = ("habitica_counter_up", "habitica_counter_down");
+
+/// UDA mirroring the streak of a daily, read-only in Taskwarrior
+pub const STREAK_UDA: &str = "habitica_streak";
+
+/// UDA naming the Habitica challenge of a task, which makes it pull-only
+pub const CHALLENGE_UDA: &str = "habitica_challenge";
+
+/// Issue URL UDAs set by bugwarrior services
+const BUGWARRIOR_URL_UDAS: &[&str] = &[
+    "githuburl",
+    "gitlaburl",
+    "jiraurl",
+    "bitbucketurl",
+    "bugzillaurl",
+    "redmineurl",
+    "trellourl",
+    "gerriturl",
+    "phabricatorurl",
+    "pagureurl",
+    "tracurl",
+    "youtrackurl",
+    "taigaurl",
+    "gmailurl",
+    "azuredevopsurl",
+];
+
 /// Status of a Taskwarrior task
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum TaskStatus {
+    #[default]
     Pending,
     Waiting,
     Completed,
@@ -34,8 +71,8 @@ impl TaskStatus {
     }
 }
 
-/// Task difficulty level
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+/// Task difficulty level, ordered from easiest to hardest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum TaskDifficulty {
     Trivial,
@@ -70,6 +107,30 @@ impl TaskDifficulty {
     }
 }
 
+impl FromStr for TaskDifficulty {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "trivial" => Ok(TaskDifficulty::Trivial),
+            "easy" => Ok(TaskDifficulty::Easy),
+            "medium" => Ok(TaskDifficulty::Medium),
+            "hard" => Ok(TaskDifficulty::Hard),
+            other => Err(Error::custom(format!("Invalid difficulty: {}", other))),
+        }
+    }
+}
+
+/// Character attribute a task trains on Habitica
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskAttribute {
+    Str,
+    Int,
+    Con,
+    Per,
+}
+
 /// Task type (Habitica classification)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
@@ -83,6 +144,28 @@ pub enum TaskType {
     Reward,
 }
 
+impl TaskType {
+    /// Check whether the task can be used any number of times instead of
+    /// being completed once, like rewards and habits
+    pub const fn is_repeatable(self) -> bool {
+        matches!(self, TaskType::Reward | TaskType::Habit)
+    }
+}
+
+impl FromStr for TaskType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "todo" => Ok(TaskType::Todo),
+            "daily" => Ok(TaskType::Daily),
+            "habit" => Ok(TaskType::Habit),
+            "reward" => Ok(TaskType::Reward),
+            other => Err(Error::custom(format!("Invalid task type: {}", other))),
+        }
+    }
+}
+
 /// Annotation on a task
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Annotation {
@@ -91,7 +174,7 @@ pub struct Annotation {
 }
 
 /// A Taskwarrior task with all its fields
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Task {
     pub uuid: Uuid,
     pub description: String,
@@ -124,6 +207,15 @@ pub struct Task {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub habitica_task_type: Option<TaskType>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub habitica_cost: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub habitica_assigned: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub habitica_attribute: Option<TaskAttribute>,
+
     // Store any additional fields we don't explicitly handle
     #[serde(flatten)]
     pub extra: serde_json::Map<String, Value>,
@@ -135,6 +227,93 @@ impl Task {
         self.modified.unwrap_or_else(Utc::now)
     }
 
+    /// Get a date attribute that is not modelled explicitly (e.g. `start`)
+    pub fn date_attribute(&self, key: &str) -> Option<DateTime<Utc>> {
+        self.extra
+            .get(key)
+            .and_then(Value::as_str)
+            .and_then(super::date_format::parse)
+    }
+
+    /// Check if the task is hidden until a wait date after `now`
+    pub fn is_waiting(&self, now: DateTime<Utc>) -> bool {
+        self.status == TaskStatus::Waiting
+            || self.date_attribute("wait").is_some_and(|wait| wait > now)
+    }
+
+    /// Date after which Taskwarrior deletes the task
+    pub fn until(&self) -> Option<DateTime<Utc>> {
+        self.date_attribute("until")
+    }
+
+    /// Check if the task is still pending although its `until` date passed
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.status.is_pending() && self.until().is_some_and(|until| until <= now)
+    }
+
+    /// Recurring parent this task is an instance of
+    pub fn parent(&self) -> Option<Uuid> {
+        self.extra
+            .get("parent")
+            .and_then(Value::as_str)
+            .and_then(|parent| parent.parse().ok())
+    }
+
+    /// Tasks this task depends on, in the order Taskwarrior lists them
+    ///
+    /// Taskwarrior 2.6 exports them as an array, older versions as a
+    /// comma-separated string.
+    pub fn depends(&self) -> Vec<Uuid> {
+        match self.extra.get("depends") {
+            Some(Value::Array(depends)) => depends
+                .iter()
+                .filter_map(Value::as_str)
+                .filter_map(|uuid| uuid.parse().ok())
+                .collect(),
+            Some(Value::String(depends)) => depends
+                .split(',')
+                .filter_map(|uuid| uuid.trim().parse().ok())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Position of a recurring instance in its series
+    pub fn imask(&self) -> Option<u64> {
+        self.extra.get("imask").and_then(|imask| {
+            imask
+                .as_u64()
+                .or_else(|| imask.as_str().and_then(|imask| imask.parse().ok()))
+        })
+    }
+
+    /// Project of the task
+    pub fn project(&self) -> Option<&str> {
+        self.extra.get("project").and_then(Value::as_str)
+    }
+
+    /// Tags of the task
+    pub fn tags(&self) -> Vec<&str> {
+        self.extra
+            .get("tags")
+            .and_then(Value::as_array)
+            .map(|tags| tags.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    /// Issue URL if the task was created by bugwarrior
+    pub fn bugwarrior_url(&self) -> Option<&str> {
+        BUGWARRIOR_URL_UDAS
+            .iter()
+            .find_map(|key| self.extra.get(*key).and_then(Value::as_str))
+            .filter(|url| !url.is_empty())
+    }
+
+    /// Check if the task was created by bugwarrior
+    pub fn is_bugwarrior_task(&self) -> bool {
+        self.bugwarrior_url().is_some()
+    }
+
     /// Get task difficulty with default
     pub fn difficulty(&self) -> TaskDifficulty {
         self.habitica_difficulty.unwrap_or_default()
@@ -145,6 +324,38 @@ impl Task {
         self.habitica_task_type.unwrap_or_default()
     }
 
+    /// Get the reward price in gold with default
+    pub fn cost(&self) -> f64 {
+        self.habitica_cost.unwrap_or(DEFAULT_REWARD_COST)
+    }
+
+    /// Challenge the linked Habitica task belongs to, set by the sync
+    pub fn challenge(&self) -> Option<&str> {
+        self.extra
+            .get(CHALLENGE_UDA)
+            .and_then(Value::as_str)
+            .filter(|name| !name.is_empty())
+    }
+
+    /// Positive and negative score counters of a habit, zero if unset
+    pub fn habit_counters(&self) -> (u32, u32) {
+        let counter = |key: &str| self.count_attribute(key).unwrap_or(0);
+        (counter(HABIT_COUNTER_UDAS.0), counter(HABIT_COUNTER_UDAS.1))
+    }
+
+    /// Streak of a daily as last synced from Habitica
+    pub fn streak(&self) -> Option<u32> {
+        self.count_attribute(STREAK_UDA)
+    }
+
+    /// Get a numeric UDA holding a count
+    fn count_attribute(&self, key: &str) -> Option<u32> {
+        self.extra.get(key).and_then(|value| match value {
+            Value::String(s) => s.trim().parse().ok(),
+            other => other.as_u64().and_then(|n| u32::try_from(n).ok()),
+        })
+    }
+
     /// Check if task has a note (based on note prefix in annotations)
     pub fn has_note_annotation(&self, note_prefix: &str) -> bool {
         self.annotations.as_ref().is_some_and(|annos| {
@@ -154,6 +365,15 @@ impl Task {
         })
     }
 
+    /// Description of the annotation previewing the task's note
+    pub fn note_annotation(&self, note_prefix: &str) -> Option<&str> {
+        self.annotations
+            .iter()
+            .flatten()
+            .map(|anno| anno.description.trim())
+            .find(|description| description.starts_with(note_prefix))
+    }
+
     /// Filter annotations to only keep non-note annotations
     pub fn filter_note_annotations(&self, note_prefix: &str) -> Vec<Annotation> {
         self.annotations
@@ -179,6 +399,9 @@ impl PartialEq for Task {
             && self.habitica_uuid == other.habitica_uuid
             && self.habitica_difficulty == other.habitica_difficulty
             && self.habitica_task_type == other.habitica_task_type
+            && self.habitica_cost == other.habitica_cost
+            && self.habitica_assigned == other.habitica_assigned
+            && self.habitica_attribute == other.habitica_attribute
     }
 }
 
@@ -211,6 +434,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_difficulty_and_type() {
+        assert_eq!(
+            "Hard".parse::<TaskDifficulty>().ok(),
+            Some(TaskDifficulty::Hard)
+        );
+        assert_eq!("daily".parse::<TaskType>().ok(), Some(TaskType::Daily));
+        assert!("epic".parse::<TaskDifficulty>().is_err());
+    }
+
+    #[test]
+    fn test_foreign_udas_survive_roundtrip() {
+        let json = r#"{"uuid":"c2a4b1e0-1c8e-4a44-9d0e-5f7c4c5a1d2e","description":"Fix bug","status":"pending","githuburl":"https://github.com/o/r/issues/1","githubnumber":1,"habitica_uuid":"0f7c2b0e-7d61-4f0b-9a5b-2a7a3e1e9c10"}"#;
+        let task: Task = serde_json::from_str(json).expect("Failed to parse");
+
+        assert_eq!(
+            task.bugwarrior_url(),
+            Some("https://github.com/o/r/issues/1")
+        );
+        assert!(task.is_bugwarrior_task());
+
+        let reserialized = serde_json::to_value(&task).expect("Failed to serialize");
+        assert_eq!(reserialized["githuburl"], "https://github.com/o/r/issues/1");
+        assert_eq!(reserialized["githubnumber"], 1);
+    }
+
+    #[test]
+    fn test_depends_formats() {
+        let first = "c2a4b1e0-1c8e-4a44-9d0e-5f7c4c5a1d2e";
+        let second = "0f7c2b0e-7d61-4f0b-9a5b-2a7a3e1e9c10";
+        let expected: Vec<Uuid> = [first, second]
+            .iter()
+            .map(|uuid| uuid.parse().expect("Failed to parse"))
+            .collect();
+
+        let mut task = Task::default();
+        assert!(task.depends().is_empty());
+
+        task.extra
+            .insert("depends".to_string(), serde_json::json!([first, second]));
+        assert_eq!(task.depends(), expected);
+
+        task.extra.insert(
+            "depends".to_string(),
+            Value::String(format!("{},{}", first, second)),
+        );
+        assert_eq!(task.depends(), expected);
+    }
+
     #[test]
     fn test_task_status_sync() {
         assert!(TaskStatus::Pending.should_sync_to_habitica());
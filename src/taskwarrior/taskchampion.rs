@@ -0,0 +1,230 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use chrono::DateTime;
+use rusqlite::{Connection, OpenFlags};
+use serde_json::{Map, Value};
+use tracing::instrument;
+
+use crate::{
+    error::{Error, Result},
+    taskwarrior::{
+        backend::{TaskBackend, TaskSnapshot},
+        client::TaskwarriorClient,
+        date_format,
+        task::Task,
+    },
+};
+
+/// Attributes stored as Unix timestamps
+const DATE_ATTRIBUTES: &[&str] = &[
+    "entry",
+    "modified",
+    "start",
+    "end",
+    "due",
+    "wait",
+    "scheduled",
+    "until",
+];
+
+/// UDAs that `task export` writes as numbers
+const NUMERIC_UDAS: &[&str] = &["habitica_cost"];
+
+/// Backend reading exports straight from Taskwarrior 3's database file
+///
+/// Spawning `task export` is the slow part of a sync, so the task lists it
+/// needs are read from `taskchampion.sqlite3` instead. Filtered exports and
+/// all writes still go through the `task` command, which keeps hooks and
+/// Taskwarrior's own bookkeeping in charge of changes.
+pub struct TaskChampionBackend {
+    db_path: PathBuf,
+    client: TaskwarriorClient,
+}
+
+impl TaskChampionBackend {
+    pub const fn new(db_path: PathBuf) -> Self {
+        TaskChampionBackend {
+            db_path,
+            client: TaskwarriorClient::new(),
+        }
+    }
+
+    /// Read every task in the database
+    #[instrument(level = "debug", skip(self), fields(path = %self.db_path.display()), err)]
+    pub fn read_all(&self) -> Result<Vec<Task>> {
+        let db_error = |e: rusqlite::Error| {
+            Error::TaskwarriorCommandFailed(format!("TaskChampion database: {}", e))
+        };
+
+        let conn = Connection::open_with_flags(&self.db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(db_error)?;
+        let mut stmt = conn
+            .prepare("SELECT uuid, data FROM tasks")
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(db_error)?;
+
+        rows.map(|row| {
+            let (uuid, data) = row.map_err(db_error)?;
+            let data: HashMap<String, String> = serde_json::from_str(&data).map_err(|e| {
+                Error::TaskwarriorParseFailed(format!("Invalid data of task {}: {}", uuid, e))
+            })?;
+            task_from_map(&uuid, data)
+        })
+        .collect()
+    }
+}
+
+impl TaskBackend for TaskChampionBackend {
+    fn export(&self, filters: &[&str]) -> Result<Vec<Task>> {
+        self.client.export(filters)
+    }
+
+    fn import(&self, task: &Task) -> Result<String> {
+        self.client.import(task)
+    }
+
+    fn import_many(&self, tasks: &[Task]) -> Result<()> {
+        self.client.import_many(tasks)
+    }
+
+    fn get_config(&self, key: &str) -> Result<String> {
+        self.client.get_config(key)
+    }
+
+    fn snapshot(&self) -> Result<TaskSnapshot> {
+        Ok(TaskSnapshot::partition(self.read_all()?))
+    }
+
+    fn get_tasks_with_habitica(&self) -> Result<Vec<Task>> {
+        let mut tasks = self.read_all()?;
+        tasks.retain(|task| task.habitica_uuid.is_some());
+        Ok(tasks)
+    }
+}
+
+/// Build a task from its key-value representation in the database
+///
+/// The result matches what `task export` prints: dates in Taskwarrior's
+/// format, and tags, dependencies and annotations gathered from their
+/// `tag_`, `dep_` and `annotation_` keys.
+fn task_from_map(uuid: &str, data: HashMap<String, String>) -> Result<Task> {
+    let mut task = Map::new();
+    let mut tags = Vec::new();
+    let mut depends = Vec::new();
+    let mut annotations = Vec::new();
+
+    for (key, value) in data {
+        if let Some(tag) = key.strip_prefix("tag_") {
+            tags.push(tag.to_string());
+        } else if let Some(dep) = key.strip_prefix("dep_") {
+            depends.push(dep.to_string());
+        } else if let Some(entry) = key.strip_prefix("annotation_") {
+            if let Some(entry) = timestamp(entry) {
+                annotations.push((entry, value));
+            }
+        } else if DATE_ATTRIBUTES.contains(&key.as_str()) {
+            if let Some(date) = timestamp(&value) {
+                task.insert(key, Value::String(date));
+            }
+        } else if NUMERIC_UDAS.contains(&key.as_str()) {
+            if let Some(number) = value.trim().parse::<f64>().ok().map(Value::from) {
+                task.insert(key, number);
+            }
+        } else {
+            task.insert(key, Value::String(value));
+        }
+    }
+
+    task.insert("uuid".to_string(), Value::String(uuid.to_string()));
+    task.entry("description".to_string())
+        .or_insert_with(|| Value::String(String::new()));
+    if !tags.is_empty() {
+        tags.sort();
+        task.insert("tags".to_string(), tags.into());
+    }
+    if !depends.is_empty() {
+        depends.sort();
+        task.insert("depends".to_string(), depends.into());
+    }
+    if !annotations.is_empty() {
+        annotations.sort();
+        let annotations = annotations
+            .into_iter()
+            .map(|(entry, description)| {
+                serde_json::json!({ "entry": entry, "description": description })
+            })
+            .collect::<Vec<_>>();
+        task.insert("annotations".to_string(), annotations.into());
+    }
+
+    serde_json::from_value(Value::Object(task))
+        .map_err(|e| Error::TaskwarriorParseFailed(format!("Failed to read task {}: {}", uuid, e)))
+}
+
+/// Taskwarrior date of a Unix timestamp as stored in the database
+fn timestamp(value: &str) -> Option<String> {
+    let seconds = value.trim().parse().ok()?;
+    DateTime::from_timestamp(seconds, 0).map(|date| date_format::format(&date))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::taskwarrior::TaskStatus;
+
+    fn data(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_task_from_map() {
+        let uuid = "c2a4b1e0-1c8e-4a44-9d0e-5f7c4c5a1d2e";
+        let dep = "0f7c2b0e-7d61-4f0b-9a5b-2a7a3e1e9c10";
+        let task = task_from_map(
+            uuid,
+            data(&[
+                ("description", "Write report"),
+                ("status", "pending"),
+                ("due", "1772352000"),
+                ("tag_work", ""),
+                ("tag_deepwork", ""),
+                (&format!("dep_{}", dep), ""),
+                ("annotation_1772265600", "Outline done"),
+                ("habitica_uuid", dep),
+                ("habitica_cost", "25"),
+                ("project", "home"),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(task.uuid.to_string(), uuid);
+        assert_eq!(task.status, TaskStatus::Pending);
+        assert_eq!(
+            task.due.map(|due| date_format::format(&due)).as_deref(),
+            Some("20260301T080000Z")
+        );
+        assert_eq!(task.tags(), ["deepwork", "work"]);
+        assert_eq!(task.depends(), [dep.parse::<uuid::Uuid>().unwrap()]);
+        assert_eq!(task.habitica_uuid, Some(dep.parse().unwrap()));
+        assert_eq!(task.habitica_cost, Some(25.0));
+        assert_eq!(task.project(), Some("home"));
+
+        let annotations = task.annotations.unwrap();
+        assert_eq!(annotations[0].entry, "20260228T080000Z");
+        assert_eq!(annotations[0].description, "Outline done");
+    }
+
+    #[test]
+    fn test_task_from_map_rejects_unknown_status() {
+        let uuid = "c2a4b1e0-1c8e-4a44-9d0e-5f7c4c5a1d2e";
+        assert!(task_from_map(uuid, data(&[("status", "someday")])).is_err());
+    }
+}